@@ -0,0 +1,636 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_core::error::BackendError;
+use dragonfly_client_core::{Error as ClientError, Result as ClientResult};
+use opendal::{layers::TimeoutLayer, Operator};
+use percent_encoding::percent_decode_str;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::io::StreamReader;
+use tracing::{debug, error, instrument, warn};
+use url::Url;
+
+/// FILE_SCHEME is the scheme of the local filesystem.
+pub const FILE_SCHEME: &str = "file";
+
+/// LOCALHOST is the only host allowed in the file url, e.g. file://localhost/path/to/file.
+const LOCALHOST: &str = "localhost";
+
+/// File is a struct that implements the Backend trait for the local filesystem, including
+/// the mounted network filesystems such as NFS and shared volumes.
+pub struct File {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
+    /// scheme is the scheme of the local filesystem.
+    scheme: String,
+
+    /// allowed_roots are the canonicalized directories that the file backend can access.
+    allowed_roots: Vec<PathBuf>,
+}
+
+/// File implements the Backend trait.
+impl File {
+    /// new returns a new file backend.
+    pub fn new(config: Arc<Config>) -> Self {
+        // Canonicalize the allowed roots to compare with the canonicalized paths of the
+        // file urls.
+        let allowed_roots = config
+            .backend
+            .file
+            .allowed_roots
+            .iter()
+            .map(|root| {
+                std::fs::canonicalize(root).unwrap_or_else(|err| {
+                    warn!("canonicalize allowed root {:?} failed: {}", root, err);
+                    root.clone()
+                })
+            })
+            .collect();
+
+        Self {
+            config,
+            scheme: FILE_SCHEME.to_string(),
+            allowed_roots,
+        }
+    }
+
+    /// operator initializes the operator of the local filesystem.
+    pub fn operator(&self, timeout: Duration) -> ClientResult<Operator> {
+        Ok(Operator::new(opendal::services::Fs::default().root("/"))?
+            .finish()
+            .layer(TimeoutLayer::new().with_timeout(timeout)))
+    }
+
+    /// path returns the canonicalized absolute path of the file url. The host of the url
+    /// must be empty or localhost, and the canonicalized path must be in one of the
+    /// allowed roots.
+    pub async fn path(&self, url: &Url) -> ClientResult<String> {
+        match url.host_str() {
+            None | Some("") | Some(LOCALHOST) => {}
+            Some(_) => return Err(ClientError::InvalidURI(url.to_string())),
+        }
+
+        let decoded_path = percent_decode_str(url.path())
+            .decode_utf8_lossy()
+            .to_string();
+        let canonical_path = canonicalize(Path::new(&decoded_path)).await?;
+        if !self
+            .allowed_roots
+            .iter()
+            .any(|root| canonical_path.starts_with(root))
+        {
+            error!("path {:?} is not in the allowed roots", canonical_path);
+            return Err(ClientError::Unauthorized);
+        }
+
+        // Keep the trailing slash, because the operator lists the path ending with
+        // slash as a directory.
+        let mut path = canonical_path.to_string_lossy().to_string();
+        if decoded_path.ends_with('/') && !path.ends_with('/') {
+            path.push('/');
+        }
+
+        Ok(path)
+    }
+}
+
+/// canonicalize returns the canonicalized absolute path, which resolves the symbolic links
+/// and the `..` components. The path may not exist, e.g. the destination of the put request,
+/// so the nearest existing ancestor is canonicalized and the remaining components are appended.
+async fn canonicalize(path: &Path) -> ClientResult<PathBuf> {
+    let mut ancestor = path.to_path_buf();
+    let mut components = Vec::new();
+    loop {
+        match tokio::fs::canonicalize(&ancestor).await {
+            Ok(canonical_path) => {
+                return Ok(components
+                    .into_iter()
+                    .rev()
+                    .fold(canonical_path, |path, component| path.join(component)));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                // The component `..` of the nonexistent path can not be resolved.
+                match (ancestor.file_name(), ancestor.parent()) {
+                    (Some(file_name), Some(parent)) => {
+                        components.push(file_name.to_os_string());
+                        ancestor = parent.to_path_buf();
+                    }
+                    _ => return Err(ClientError::InvalidURI(path.display().to_string())),
+                }
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Implement the Backend trait for File.
+#[tonic::async_trait]
+impl super::Backend for File {
+    /// scheme returns the scheme of the file backend.
+    fn scheme(&self) -> String {
+        self.scheme.clone()
+    }
+
+    /// stat gets the metadata from the backend.
+    #[instrument(skip_all)]
+    async fn stat(&self, request: super::StatRequest) -> ClientResult<super::StatResponse> {
+        debug!(
+            "stat request {} {}: {:?}",
+            request.task_id, request.url, request.http_header
+        );
+
+        // Parse the URL.
+        let url = Url::parse(request.url.as_ref())
+            .map_err(|_| ClientError::InvalidURI(request.url.clone()))?;
+        let decoded_path = self.path(&url).await?;
+
+        // Initialize the operator of the local filesystem.
+        let operator = self.operator(request.timeout)?;
+
+        // Get the entries if url point to a directory.
+        let entries = if url.path().ends_with('/') {
+            let listed_entries = operator
+                .list_with(&decoded_path)
+                .recursive(true)
+                .await // Do the list op here.
+                .map_err(|err| {
+                    error!(
+                        "list request failed {} {}: {}",
+                        request.task_id, request.url, err
+                    );
+
+                    ClientError::BackendError(Box::new(BackendError {
+                        message: err.to_string(),
+                        status_code: None,
+                        header: None,
+                    }))
+                })?;
+
+            // The lister of the local filesystem does not return the content length,
+            // so stat each file to get it.
+            let mut entries = Vec::with_capacity(listed_entries.len());
+            for entry in listed_entries {
+                let is_dir = entry.metadata().is_dir();
+                let content_length = if is_dir {
+                    0
+                } else {
+                    operator
+                        .stat(entry.path())
+                        .await
+                        .map_err(|err| {
+                            error!(
+                                "stat entry failed {} {}: {}",
+                                request.task_id,
+                                entry.path(),
+                                err
+                            );
+
+                            ClientError::BackendError(Box::new(BackendError {
+                                message: err.to_string(),
+                                status_code: None,
+                                header: None,
+                            }))
+                        })?
+                        .content_length() as usize
+                };
+
+                let mut url = url.clone();
+                url.set_path(entry.path());
+                entries.push(super::DirEntry {
+                    url: url.to_string(),
+                    content_length,
+                    is_dir,
+                });
+            }
+
+            entries
+        } else {
+            Vec::new()
+        };
+
+        // Stat the path to get the metadata of the file.
+        let response = operator.stat_with(&decoded_path).await.map_err(|err| {
+            error!(
+                "stat request failed {} {}: {}",
+                request.task_id, request.url, err
+            );
+
+            ClientError::BackendError(Box::new(BackendError {
+                message: err.to_string(),
+                status_code: None,
+                header: None,
+            }))
+        })?;
+
+        debug!(
+            "stat response {} {}: {}",
+            request.task_id,
+            request.url,
+            response.content_length()
+        );
+
+        Ok(super::StatResponse {
+            success: true,
+            content_length: Some(response.content_length()),
+            http_header: Some(super::make_metadata_header(&response)),
+            http_status_code: None,
+            error_message: None,
+            entries,
+        })
+    }
+
+    /// get gets the content from the backend.
+    #[instrument(skip_all)]
+    async fn get(
+        &self,
+        request: super::GetRequest,
+    ) -> ClientResult<super::GetResponse<super::Body>> {
+        debug!(
+            "get request {} {}: {:?}",
+            request.piece_id, request.url, request.http_header
+        );
+
+        // Parse the URL.
+        let url = Url::parse(request.url.as_ref())
+            .map_err(|_| ClientError::InvalidURI(request.url.clone()))?;
+        let decoded_path = self.path(&url).await?;
+
+        // Initialize the reader of the file.
+        let operator_reader = self
+            .operator(request.timeout)?
+            .reader(decoded_path.as_ref())
+            .await
+            .map_err(|err| {
+                error!(
+                    "get request failed {} {}: {}",
+                    request.piece_id, request.url, err
+                );
+
+                ClientError::BackendError(Box::new(BackendError {
+                    message: err.to_string(),
+                    status_code: None,
+                    header: None,
+                }))
+            })?;
+
+        let stream = match request.range {
+            Some(range) => operator_reader
+                .into_bytes_stream(range.start..range.start + range.length)
+                .await
+                .map_err(|err| {
+                    error!(
+                        "get request failed {} {}: {}",
+                        request.piece_id, request.url, err
+                    );
+
+                    ClientError::BackendError(Box::new(BackendError {
+                        message: err.to_string(),
+                        status_code: None,
+                        header: None,
+                    }))
+                })?,
+            None => operator_reader.into_bytes_stream(..).await.map_err(|err| {
+                error!(
+                    "get request failed {} {}: {}",
+                    request.piece_id, request.url, err
+                );
+
+                ClientError::BackendError(Box::new(BackendError {
+                    message: err.to_string(),
+                    status_code: None,
+                    header: None,
+                }))
+            })?,
+        };
+
+        Ok(crate::GetResponse {
+            success: true,
+            http_header: None,
+            http_status_code: Some(reqwest::StatusCode::OK),
+            reader: Box::new(StreamReader::new(stream)),
+            error_message: None,
+        })
+    }
+
+    /// put puts the content of the local file to the destination path of the backend.
+    #[instrument(skip_all)]
+    async fn put(&self, request: super::PutRequest) -> ClientResult<super::PutResponse> {
+        debug!("put request {:?} {}", request.path, request.url);
+
+        // Parse the URL.
+        let url = Url::parse(request.url.as_ref())
+            .map_err(|_| ClientError::InvalidURI(request.url.clone()))?;
+        let decoded_path = self.path(&url).await?;
+
+        // Initialize the operator of the local filesystem.
+        let operator = self.operator(request.timeout)?;
+
+        // Initialize the writer of the destination file, the parent directories
+        // will be created automatically.
+        let mut writer = operator
+            .writer_with(&decoded_path)
+            .chunk(self.config.backend.put_chunk_size.as_u64() as usize)
+            .await
+            .map_err(|err| {
+                error!(
+                    "put request failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+
+                ClientError::BackendError(Box::new(BackendError {
+                    message: err.to_string(),
+                    status_code: None,
+                    header: None,
+                }))
+            })?;
+
+        let source_path = request.path.to_string_lossy().to_string();
+        let reader = operator
+            .reader_with(&source_path)
+            .concurrent(self.config.backend.put_concurrent_chunk_count as usize)
+            .chunk(self.config.backend.put_chunk_size.as_u64() as usize)
+            .await?;
+
+        let content_length = operator
+            .stat(&source_path)
+            .await
+            .inspect_err(|err| {
+                error!(
+                    "stat local file failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+            })?
+            .content_length();
+
+        let mut offset: u64 = 0;
+        while offset < content_length {
+            let end = std::cmp::min(
+                offset + self.config.backend.put_chunk_size.as_u64(),
+                content_length,
+            );
+
+            let buf = reader.read(offset..end).await.inspect_err(|err| {
+                error!(
+                    "read local file failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+            })?;
+
+            writer.write(buf).await.inspect_err(|err| {
+                error!(
+                    "put request failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+            })?;
+
+            offset = end;
+        }
+
+        writer.close().await.inspect_err(|err| {
+            error!(
+                "close put request failed {:?} {}: {}",
+                request.path, request.url, err
+            );
+        })?;
+
+        Ok(crate::PutResponse {
+            success: true,
+            http_header: None,
+            http_status_code: Some(reqwest::StatusCode::OK),
+            content_length: Some(content_length),
+            error_message: None,
+        })
+    }
+
+    /// exists checks whether the file exists in the backend.
+    #[instrument(skip_all)]
+    async fn exists(&self, request: super::ExistsRequest) -> ClientResult<bool> {
+        debug!(
+            "exists request {} {}: {:?}",
+            request.task_id, request.url, request.http_header
+        );
+
+        // Parse the URL.
+        let url = Url::parse(request.url.as_ref())
+            .map_err(|_| ClientError::InvalidURI(request.url.clone()))?;
+        let decoded_path = self.path(&url).await?;
+
+        // Initialize the operator of the local filesystem.
+        let operator = self.operator(request.timeout)?;
+        Ok(operator.exists(&decoded_path).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Backend, ExistsRequest, GetRequest, PutRequest, StatRequest};
+    use dragonfly_api::common::v2::Range;
+    use tempfile::tempdir;
+
+    fn file_url(path: &std::path::Path) -> String {
+        Url::from_file_path(path).unwrap().to_string()
+    }
+
+    fn new_file(root: &std::path::Path) -> File {
+        let mut config = Config::default();
+        config.backend.file.allowed_roots = vec![root.to_path_buf()];
+        File::new(Arc::new(config))
+    }
+
+    #[tokio::test]
+    async fn should_return_error_when_host_not_valid() {
+        let file = new_file(std::path::Path::new("/path"));
+        let url = Url::parse("file://example.com/path/to/file").unwrap();
+        assert!(matches!(
+            file.path(&url).await.unwrap_err(),
+            ClientError::InvalidURI(..)
+        ));
+
+        let url = Url::parse("file://localhost/path/to/file").unwrap();
+        assert_eq!(file.path(&url).await.unwrap(), "/path/to/file");
+    }
+
+    #[tokio::test]
+    async fn should_return_error_when_path_not_in_allowed_roots() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        let outside = dir.path().join("outside.txt");
+        std::fs::write(&outside, b"secret").unwrap();
+
+        let file = new_file(&root);
+        let url = Url::from_file_path(&outside).unwrap();
+        assert!(matches!(
+            file.path(&url).await.unwrap_err(),
+            ClientError::Unauthorized
+        ));
+
+        #[cfg(unix)]
+        {
+            let link = root.join("link");
+            std::os::unix::fs::symlink(&outside, &link).unwrap();
+            let url = Url::from_file_path(&link).unwrap();
+            assert!(matches!(
+                file.path(&url).await.unwrap_err(),
+                ClientError::Unauthorized
+            ));
+        }
+
+        let url = Url::from_file_path(root.join("nested").join("new.txt")).unwrap();
+        assert_eq!(
+            file.path(&url).await.unwrap(),
+            root.canonicalize()
+                .unwrap()
+                .join("nested")
+                .join("new.txt")
+                .to_string_lossy()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_stat_and_get_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file with space.txt");
+        std::fs::write(&path, b"hello dragonfly").unwrap();
+
+        let file = new_file(dir.path());
+        let response = file
+            .stat(StatRequest {
+                task_id: "test".to_string(),
+                url: file_url(&path),
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.content_length, Some(15));
+        assert!(response.entries.is_empty());
+
+        let mut response = file
+            .get(GetRequest {
+                task_id: "test".to_string(),
+                piece_id: "test".to_string(),
+                url: file_url(&path),
+                range: Some(Range {
+                    start: 6,
+                    length: 9,
+                }),
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "dragonfly");
+    }
+
+    #[tokio::test]
+    async fn should_list_directory() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"a").unwrap();
+        std::fs::write(dir.path().join("sub").join("b.txt"), b"bb").unwrap();
+
+        let file = new_file(dir.path());
+        let response = file
+            .stat(StatRequest {
+                task_id: "test".to_string(),
+                url: format!("{}/", file_url(dir.path())),
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+
+        let files: Vec<_> = response
+            .entries
+            .iter()
+            .filter(|entry| !entry.is_dir)
+            .collect();
+        assert_eq!(files.len(), 2);
+        assert!(files
+            .iter()
+            .any(|entry| entry.url.ends_with("/sub/b.txt") && entry.content_length == 2));
+    }
+
+    #[tokio::test]
+    async fn should_put_and_check_exists() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::write(&source, vec![1u8; 1024]).unwrap();
+        let destination = dir.path().join("nested").join("destination");
+
+        let file = new_file(dir.path());
+        let exists = file
+            .exists(ExistsRequest {
+                task_id: "test".to_string(),
+                url: file_url(&destination),
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(!exists);
+
+        let response = file
+            .put(PutRequest {
+                task_id: "test".to_string(),
+                url: file_url(&destination),
+                path: source.clone(),
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.content_length, Some(1024));
+        assert_eq!(std::fs::read(&destination).unwrap(), vec![1u8; 1024]);
+
+        let exists = file
+            .exists(ExistsRequest {
+                task_id: "test".to_string(),
+                url: file_url(&destination),
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(exists);
+    }
+}
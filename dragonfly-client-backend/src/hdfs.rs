@@ -14,11 +14,14 @@
  * limitations under the License.
  */
 
+use crate::retry::RetryPolicy;
 use dragonfly_api::common;
+use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::error::BackendError;
 use dragonfly_client_core::{Error as ClientError, Result as ClientResult};
-use opendal::{layers::TimeoutLayer, Operator};
+use opendal::{layers::HttpClientLayer, layers::TimeoutLayer, Operator};
 use percent_encoding::percent_decode_str;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio_util::io::StreamReader;
 use tracing::{debug, error, instrument};
@@ -30,20 +33,47 @@ pub const HDFS_SCHEME: &str = "hdfs";
 /// DEFAULT_NAMENODE_PORT is the default port of the HDFS namenode.
 const DEFAULT_NAMENODE_PORT: u16 = 9870;
 
+/// ATOMIC_WRITE_DIR is the temporary directory in HDFS to write the chunks concurrently,
+/// the chunks are concatenated and renamed to the destination path after all chunks are written.
+const ATOMIC_WRITE_DIR: &str = "/.dragonfly_tmp/";
+
 /// Hdfs is a struct that implements the Backend trait.
 #[derive(Default)]
 pub struct Hdfs {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
     /// scheme is the scheme of the HDFS.
     scheme: String,
+
+    /// retry_policy is the retry policy of the HDFS request.
+    retry_policy: RetryPolicy,
+
+    /// client is the reqwest client of the operator, it requests the namenode via the
+    /// upstream proxy if configured.
+    client: reqwest::Client,
 }
 
 /// Hdfs implements the Backend trait.
 impl Hdfs {
     /// new returns a new HDFS backend.
-    pub fn new() -> Self {
-        Self {
-            scheme: HDFS_SCHEME.to_string(),
+    pub fn new(config: Arc<Config>) -> ClientResult<Self> {
+        let retry_policy = RetryPolicy::new(config.backend.retry.clone());
+
+        // Initialize the reqwest client, the namenode is requested via the upstream proxy
+        // if configured.
+        let mut client_builder = reqwest::Client::builder();
+        if let Some(upstream_proxy) = config.network.upstream_proxy.as_ref() {
+            client_builder = client_builder.proxy(upstream_proxy.reqwest_proxy()?);
         }
+        let client = client_builder.build()?;
+
+        Ok(Self {
+            config,
+            scheme: HDFS_SCHEME.to_string(),
+            retry_policy,
+            client,
+        })
     }
 
     /// operator initializes the operator with the parsed URL and HDFS config.
@@ -64,7 +94,8 @@ impl Hdfs {
         let mut builder = opendal::services::Webhdfs::default();
         builder = builder
             .root("/")
-            .endpoint(&format!("http://{}:{}", host, port));
+            .endpoint(&format!("http://{}:{}", host, port))
+            .atomic_write_dir(ATOMIC_WRITE_DIR);
 
         // If HDFS config is not None, set the config for builder.
         if let Some(config) = config {
@@ -75,7 +106,11 @@ impl Hdfs {
 
         Ok(Operator::new(builder)?
             .finish()
-            .layer(TimeoutLayer::new().with_timeout(timeout)))
+            .layer(TimeoutLayer::new().with_timeout(timeout))
+            .layer(HttpClientLayer::new(
+                self.retry_policy
+                    .opendal_http_client(HDFS_SCHEME, self.client.clone()),
+            )))
     }
 }
 
@@ -163,7 +198,7 @@ impl super::Backend for Hdfs {
         Ok(super::StatResponse {
             success: true,
             content_length: Some(response.content_length()),
-            http_header: None,
+            http_header: Some(super::make_metadata_header(&response)),
             http_status_code: None,
             error_message: None,
             entries,
@@ -245,10 +280,101 @@ impl super::Backend for Hdfs {
         })
     }
 
-    /// put puts the content to the backend.
+    /// put puts the content of the local file to the destination path of the HDFS.
     #[instrument(skip_all)]
-    async fn put(&self, _request: super::PutRequest) -> ClientResult<super::PutResponse> {
-        unimplemented!()
+    async fn put(&self, request: super::PutRequest) -> ClientResult<super::PutResponse> {
+        debug!("put request {:?} {}", request.path, request.url);
+
+        // Parse the URL.
+        let url = Url::parse(request.url.as_ref())
+            .map_err(|_| ClientError::InvalidURI(request.url.clone()))?;
+        let decoded_path = percent_decode_str(url.path())
+            .decode_utf8_lossy()
+            .to_string();
+
+        // Initialize the writer of the HDFS, the chunks are written to the atomic write
+        // directory concurrently and concatenated to the destination path when closing.
+        let mut hdfs_writer = self
+            .operator(url.clone(), request.hdfs, request.timeout)?
+            .writer_with(&decoded_path)
+            .concurrent(self.config.backend.put_concurrent_chunk_count as usize)
+            .chunk(self.config.backend.put_chunk_size.as_u64() as usize)
+            .await
+            .map_err(|err| {
+                error!(
+                    "put request failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+
+                ClientError::BackendError(Box::new(BackendError {
+                    message: err.to_string(),
+                    status_code: None,
+                    header: None,
+                }))
+            })?;
+
+        // Initialize the fs operator to read the local file.
+        let fs_operator = Operator::new(opendal::services::Fs::default().root("/"))
+            .inspect_err(|err| {
+                error!("initialize fs operator failed: {}", err);
+            })?
+            .finish();
+
+        let fs_reader = fs_operator
+            .reader_with(&request.path.to_string_lossy())
+            .concurrent(self.config.backend.put_concurrent_chunk_count as usize)
+            .chunk(self.config.backend.put_chunk_size.as_u64() as usize)
+            .await?;
+
+        let content_length = fs_operator
+            .stat(&request.path.to_string_lossy())
+            .await
+            .inspect_err(|err| {
+                error!(
+                    "stat local file failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+            })?
+            .content_length();
+
+        let mut offset: u64 = 0;
+        while offset < content_length {
+            let end = std::cmp::min(
+                offset + self.config.backend.put_chunk_size.as_u64(),
+                content_length,
+            );
+
+            let buf = fs_reader.read(offset..end).await.inspect_err(|err| {
+                error!(
+                    "read local file failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+            })?;
+
+            hdfs_writer.write(buf).await.inspect_err(|err| {
+                error!(
+                    "put request failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+            })?;
+
+            offset = end;
+        }
+
+        hdfs_writer.close().await.inspect_err(|err| {
+            error!(
+                "close put request failed {:?} {}: {}",
+                request.path, request.url, err
+            );
+        })?;
+
+        Ok(crate::PutResponse {
+            success: true,
+            http_header: None,
+            http_status_code: Some(reqwest::StatusCode::OK),
+            content_length: Some(content_length),
+            error_message: None,
+        })
     }
 
     /// exists checks whether the file exists in the backend.
@@ -279,7 +405,11 @@ mod tests {
     #[tokio::test]
     async fn should_get_operator() {
         let url: Url = Url::parse("hdfs://127.0.0.1:9870/file").unwrap();
-        let operator = Hdfs::new().operator(url, None, Duration::from_secs(10));
+        let operator = Hdfs::new(Arc::new(Config::default())).unwrap().operator(
+            url,
+            None,
+            Duration::from_secs(10),
+        );
 
         assert!(
             operator.is_ok(),
@@ -291,7 +421,11 @@ mod tests {
     #[test]
     fn should_return_error_when_url_not_valid() {
         let url: Url = Url::parse("hdfs:/127.0.0.1:9870/file").unwrap();
-        let result = Hdfs::new().operator(url, None, Duration::from_secs(10));
+        let result = Hdfs::new(Arc::new(Config::default())).unwrap().operator(
+            url,
+            None,
+            Duration::from_secs(10),
+        );
 
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), ClientError::InvalidURI(..)));
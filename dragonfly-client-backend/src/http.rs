@@ -14,19 +14,21 @@
  * limitations under the License.
  */
 
+use crate::retry::{RetryMiddleware, RetryPolicy};
 use dashmap::{mapref::entry::Entry, DashMap};
 use dragonfly_api::common::v2::Range;
+use dragonfly_client_config::dfdaemon::{Backend as BackendConfig, UpstreamProxy};
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
     Error, Result,
 };
 use dragonfly_client_util::tls::NoVerifier;
 use futures::TryStreamExt;
-use http::header::{HeaderName, HeaderValue, RANGE, USER_AGENT};
+use http::header::{HeaderName, HeaderValue, CONTENT_LENGTH, RANGE, USER_AGENT};
 use lru::LruCache;
+use opendal::Operator;
 use reqwest::header::HeaderMap;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
-use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use reqwest_tracing::TracingMiddleware;
 use rustls_pki_types::CertificateDer;
 use std::collections::HashMap;
@@ -80,6 +82,25 @@ pub struct HTTP {
 
     /// cache_temporary_redirect_ttl is the TTL for cached 307 redirects.
     cache_temporary_redirect_ttl: Duration,
+
+    /// retry_policy is the retry policy of the backend request.
+    retry_policy: RetryPolicy,
+
+    /// put_client is the reqwest client without the retry middleware for putting the content,
+    /// because the streaming body can not be cloned for retrying.
+    put_client: reqwest::Client,
+
+    /// put_http_method is the http method to put the content, PUT or POST.
+    put_http_method: reqwest::Method,
+
+    /// put_chunk_size is the chunk size of reading the local file when putting the content.
+    put_chunk_size: usize,
+
+    /// put_concurrent_chunk_count is the number of chunks read concurrently when putting the content.
+    put_concurrent_chunk_count: usize,
+
+    /// proxy is the upstream proxy of the reqwest clients.
+    proxy: Option<reqwest::Proxy>,
 }
 
 /// HTTP implements the http interface.
@@ -96,7 +117,13 @@ impl HTTP {
         request_header: Option<HashMap<String, String>>,
         enable_cache_temporary_redirect: bool,
         cache_temporary_redirect_ttl: Duration,
+        retry_policy: RetryPolicy,
+        upstream_proxy: Option<&UpstreamProxy>,
     ) -> Result<HTTP> {
+        let proxy = upstream_proxy
+            .map(|upstream_proxy| upstream_proxy.reqwest_proxy())
+            .transpose()?;
+
         // Disable automatic compression to prevent double-decompression issues.
         //
         // Problem scenario:
@@ -115,7 +142,12 @@ impl HTTP {
                 .with_custom_certificate_verifier(NoVerifier::new())
                 .with_no_client_auth();
 
-            let client = reqwest::Client::builder()
+            let mut client_builder = reqwest::Client::builder();
+            if let Some(proxy) = proxy.clone() {
+                client_builder = client_builder.proxy(proxy);
+            }
+
+            let client = client_builder
                 // Disable automatic compression to prevent double-decompression issues.
                 .no_gzip()
                 .no_brotli()
@@ -144,11 +176,9 @@ impl HTTP {
                 })) // Disable automatic redirects when status is 307.
                 .build()?;
 
-            let retry_policy =
-                ExponentialBackoff::builder().build_with_max_retries(super::MAX_RETRY_TIMES);
             let client = ClientBuilder::new(client)
                 .with(TracingMiddleware::default())
-                .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+                .with(RetryMiddleware::new(scheme, retry_policy.clone()))
                 .build();
 
             Ok(client)
@@ -160,6 +190,7 @@ impl HTTP {
             clients.insert(i, client);
         }
 
+        let backend_config = BackendConfig::default();
         Ok(Self {
             scheme: scheme.to_string(),
             clients: Arc::new(clients),
@@ -169,9 +200,69 @@ impl HTTP {
             ))),
             enable_cache_temporary_redirect,
             cache_temporary_redirect_ttl,
+            retry_policy,
+            put_client: Self::make_put_client(None, proxy.clone())?,
+            put_http_method: backend_config.put_http_method,
+            put_chunk_size: backend_config.put_chunk_size.as_u64() as usize,
+            put_concurrent_chunk_count: backend_config.put_concurrent_chunk_count as usize,
+            proxy,
         })
     }
 
+    /// with_put_config sets the http method, the chunk size and the concurrent chunk count
+    /// for putting the content by the backend config.
+    pub fn with_put_config(mut self, config: &BackendConfig) -> Self {
+        self.put_http_method = config.put_http_method.clone();
+        self.put_chunk_size = config.put_chunk_size.as_u64() as usize;
+        self.put_concurrent_chunk_count = config.put_concurrent_chunk_count as usize;
+        self
+    }
+
+    /// make_put_client makes the reqwest client for putting the content. The client certificates
+    /// are used as the root certificates if provided, otherwise the certificate validation is disabled.
+    fn make_put_client(
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+        proxy: Option<reqwest::Proxy>,
+    ) -> Result<reqwest::Client> {
+        let client_config_builder = match client_cert {
+            Some(client_cert) => {
+                let mut root_cert_store = rustls::RootCertStore::empty();
+                root_cert_store.add_parsable_certificates(client_cert);
+                rustls::ClientConfig::builder()
+                    .with_root_certificates(root_cert_store)
+                    .with_no_client_auth()
+            }
+            None => rustls::ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(NoVerifier::new())
+                .with_no_client_auth(),
+        };
+
+        let mut client_builder = reqwest::Client::builder();
+        if let Some(proxy) = proxy {
+            client_builder = client_builder.proxy(proxy);
+        }
+
+        Ok(client_builder
+            .hickory_dns(true)
+            .use_preconfigured_tls(client_config_builder)
+            .pool_max_idle_per_host(super::POOL_MAX_IDLE_PER_HOST)
+            .tcp_keepalive(super::KEEP_ALIVE_INTERVAL)
+            .tcp_nodelay(true)
+            .build()?)
+    }
+
+    /// put_client returns the reqwest client for putting the content.
+    fn put_client(
+        &self,
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+    ) -> Result<reqwest::Client> {
+        match client_cert {
+            Some(client_cert) => Self::make_put_client(Some(client_cert), self.proxy.clone()),
+            None => Ok(self.put_client.clone()),
+        }
+    }
+
     /// client returns a new reqwest client.
     fn client(
         &self,
@@ -198,7 +289,12 @@ impl HTTP {
                 //
                 // Solution: Disable all compression formats (gzip, brotli, zstd, deflate) to ensure
                 // we receive and store uncompressed content, eliminating the double-decompression issue.
-                let client = reqwest::Client::builder()
+                let mut client_builder = reqwest::Client::builder();
+                if let Some(proxy) = self.proxy.clone() {
+                    client_builder = client_builder.proxy(proxy);
+                }
+
+                let client = client_builder
                     .no_gzip()
                     .no_brotli()
                     .no_zstd()
@@ -226,11 +322,12 @@ impl HTTP {
                     })) // Disable automatic redirects when status is 307.
                     .build()?;
 
-                let retry_policy =
-                    ExponentialBackoff::builder().build_with_max_retries(super::MAX_RETRY_TIMES);
                 let client = ClientBuilder::new(client)
                     .with(TracingMiddleware::default())
-                    .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+                    .with(RetryMiddleware::new(
+                        self.scheme.as_str(),
+                        self.retry_policy.clone(),
+                    ))
                     .build();
 
                 Ok(client)
@@ -595,10 +692,89 @@ impl super::Backend for HTTP {
         })
     }
 
-    /// put puts the content to the backend.
+    /// put puts the content of the local file to the backend by PUT or POST request. The
+    /// local file is read by chunks and uploaded with chunked transfer encoding.
     #[instrument(skip_all)]
-    async fn put(&self, _request: super::PutRequest) -> Result<super::PutResponse> {
-        unimplemented!()
+    async fn put(&self, request: super::PutRequest) -> Result<super::PutResponse> {
+        debug!(
+            "put request {:?} {}: {:?}",
+            request.path, request.url, request.http_header
+        );
+
+        // Make the custom request headers.
+        let mut request_header = request.http_header.unwrap_or_default();
+        self.make_request_headers(&mut request_header, None)?;
+
+        // The content is uploaded with chunked transfer encoding, so remove the content length.
+        request_header.remove(CONTENT_LENGTH);
+
+        // Initialize the fs operator to read the local file.
+        let fs_operator = Operator::new(opendal::services::Fs::default().root("/"))
+            .inspect_err(|err| {
+                error!("initialize fs operator failed: {}", err);
+            })?
+            .finish();
+
+        let source_path = request.path.to_string_lossy().to_string();
+        let content_length = fs_operator
+            .stat(&source_path)
+            .await
+            .inspect_err(|err| {
+                error!(
+                    "stat local file failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+            })?
+            .content_length();
+
+        let stream = fs_operator
+            .reader_with(&source_path)
+            .concurrent(self.put_concurrent_chunk_count)
+            .chunk(self.put_chunk_size)
+            .await?
+            .into_bytes_stream(..)
+            .await?;
+
+        let response = match self
+            .put_client(request.client_cert)?
+            .request(self.put_http_method.clone(), &request.url)
+            .headers(request_header)
+            .body(reqwest::Body::wrap_stream(stream))
+            .timeout(request.timeout)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                error!(
+                    "put request failed {:?} {}: {}",
+                    request.path, request.url, err
+                );
+
+                return Ok(super::PutResponse {
+                    success: false,
+                    content_length: None,
+                    http_header: None,
+                    http_status_code: None,
+                    error_message: Some(err.to_string()),
+                });
+            }
+        };
+
+        let response_header = response.headers().clone();
+        let response_status_code = response.status();
+        debug!(
+            "put response {:?} {}: {:?} {:?}",
+            request.path, request.url, response_status_code, response_header
+        );
+
+        Ok(super::PutResponse {
+            success: response_status_code.is_success(),
+            content_length: Some(content_length),
+            http_header: Some(response_header),
+            http_status_code: Some(response_status_code),
+            error_message: Some(response_status_code.to_string()),
+        })
     }
 
     /// exists checks whether the file exists in the backend.
@@ -691,7 +867,7 @@ mod tests {
     use super::*;
     use crate::{
         http::{HTTP, HTTPS_SCHEME, HTTP_SCHEME},
-        Backend, ExistsRequest, GetRequest, StatRequest,
+        Backend, ExistsRequest, GetRequest, PutRequest, StatRequest,
     };
     use dragonfly_client_util::tls::{load_certs_from_pem, load_key_from_pem};
     use http::header::{HeaderValue, USER_AGENT};
@@ -703,7 +879,7 @@ mod tests {
     use tokio_rustls::rustls::ServerConfig;
     use tokio_rustls::TlsAcceptor;
     use wiremock::{
-        matchers::{method, path},
+        matchers::{body_string, method, path},
         Mock, ResponseTemplate,
     };
 
@@ -863,19 +1039,26 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .mount(&server)
             .await;
 
-        let resp = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .stat(StatRequest {
-                task_id: "test".to_string(),
-                url: format!("{}/stat", server.uri()),
-                http_header: Some(HeaderMap::new()),
-                timeout: std::time::Duration::from_secs(5),
-                client_cert: None,
-                object_storage: None,
-                hdfs: None,
-            })
-            .await
-            .unwrap();
+        let resp = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .stat(StatRequest {
+            task_id: "test".to_string(),
+            url: format!("{}/stat", server.uri()),
+            http_header: Some(HeaderMap::new()),
+            timeout: std::time::Duration::from_secs(5),
+            client_cert: None,
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+        .unwrap();
 
         assert_eq!(resp.http_status_code, Some(StatusCode::OK))
     }
@@ -892,18 +1075,25 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .mount(&server)
             .await;
 
-        let resp = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .stat(StatRequest {
-                task_id: "test".to_string(),
-                url: format!("{}/stat", server.uri()),
-                http_header: None,
-                timeout: std::time::Duration::from_secs(5),
-                client_cert: None,
-                object_storage: None,
-                hdfs: None,
-            })
-            .await;
+        let resp = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .stat(StatRequest {
+            task_id: "test".to_string(),
+            url: format!("{}/stat", server.uri()),
+            http_header: None,
+            timeout: std::time::Duration::from_secs(5),
+            client_cert: None,
+            object_storage: None,
+            hdfs: None,
+        })
+        .await;
 
         assert!(resp.is_err());
     }
@@ -921,21 +1111,28 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .mount(&server)
             .await;
 
-        let mut resp = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .get(GetRequest {
-                task_id: "test".to_string(),
-                piece_id: "test".to_string(),
-                url: format!("{}/get", server.uri()),
-                range: None,
-                http_header: Some(HeaderMap::new()),
-                timeout: std::time::Duration::from_secs(5),
-                client_cert: None,
-                object_storage: None,
-                hdfs: None,
-            })
-            .await
-            .unwrap();
+        let mut resp = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .get(GetRequest {
+            task_id: "test".to_string(),
+            piece_id: "test".to_string(),
+            url: format!("{}/get", server.uri()),
+            range: None,
+            http_header: Some(HeaderMap::new()),
+            timeout: std::time::Duration::from_secs(5),
+            client_cert: None,
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+        .unwrap();
 
         assert_eq!(resp.http_status_code, Some(StatusCode::OK));
         assert_eq!(resp.text().await.unwrap(), "OK");
@@ -944,19 +1141,26 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
     #[tokio::test]
     async fn should_stat_response_with_self_signed_cert() {
         let server_addr = start_https_server(SERVER_CERT, SERVER_KEY).await;
-        let resp = HTTP::new(HTTPS_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .stat(StatRequest {
-                task_id: "test".to_string(),
-                url: server_addr,
-                http_header: Some(HeaderMap::new()),
-                timeout: Duration::from_secs(5),
-                client_cert: Some(load_certs_from_pem(CA_CERT).unwrap()),
-                object_storage: None,
-                hdfs: None,
-            })
-            .await
-            .unwrap();
+        let resp = HTTP::new(
+            HTTPS_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .stat(StatRequest {
+            task_id: "test".to_string(),
+            url: server_addr,
+            http_header: Some(HeaderMap::new()),
+            timeout: Duration::from_secs(5),
+            client_cert: Some(load_certs_from_pem(CA_CERT).unwrap()),
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+        .unwrap();
 
         assert_eq!(resp.http_status_code, Some(StatusCode::OK));
     }
@@ -964,18 +1168,25 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
     #[tokio::test]
     async fn should_return_error_response_when_stat_with_wrong_cert() {
         let server_addr = start_https_server(SERVER_CERT, SERVER_KEY).await;
-        let resp = HTTP::new(HTTPS_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .stat(StatRequest {
-                task_id: "test".to_string(),
-                url: server_addr,
-                http_header: Some(HeaderMap::new()),
-                timeout: Duration::from_secs(5),
-                client_cert: Some(load_certs_from_pem(WRONG_CA_CERT).unwrap()),
-                object_storage: None,
-                hdfs: None,
-            })
-            .await;
+        let resp = HTTP::new(
+            HTTPS_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .stat(StatRequest {
+            task_id: "test".to_string(),
+            url: server_addr,
+            http_header: Some(HeaderMap::new()),
+            timeout: Duration::from_secs(5),
+            client_cert: Some(load_certs_from_pem(WRONG_CA_CERT).unwrap()),
+            object_storage: None,
+            hdfs: None,
+        })
+        .await;
 
         assert!(!resp.unwrap().success);
     }
@@ -983,21 +1194,28 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
     #[tokio::test]
     async fn should_get_response_with_self_signed_cert() {
         let server_addr = start_https_server(SERVER_CERT, SERVER_KEY).await;
-        let mut resp = HTTP::new(HTTPS_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .get(GetRequest {
-                task_id: "test".to_string(),
-                piece_id: "test".to_string(),
-                url: server_addr,
-                range: None,
-                http_header: Some(HeaderMap::new()),
-                timeout: std::time::Duration::from_secs(5),
-                client_cert: Some(load_certs_from_pem(CA_CERT).unwrap()),
-                object_storage: None,
-                hdfs: None,
-            })
-            .await
-            .unwrap();
+        let mut resp = HTTP::new(
+            HTTPS_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .get(GetRequest {
+            task_id: "test".to_string(),
+            piece_id: "test".to_string(),
+            url: server_addr,
+            range: None,
+            http_header: Some(HeaderMap::new()),
+            timeout: std::time::Duration::from_secs(5),
+            client_cert: Some(load_certs_from_pem(CA_CERT).unwrap()),
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+        .unwrap();
 
         assert_eq!(resp.http_status_code, Some(StatusCode::OK));
         assert_eq!(resp.text().await.unwrap(), "OK");
@@ -1006,20 +1224,27 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
     #[tokio::test]
     async fn should_return_error_response_when_get_with_wrong_cert() {
         let server_addr = start_https_server(SERVER_CERT, SERVER_KEY).await;
-        let resp = HTTP::new(HTTPS_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .get(GetRequest {
-                task_id: "test".to_string(),
-                piece_id: "test".to_string(),
-                url: server_addr,
-                range: None,
-                http_header: Some(HeaderMap::new()),
-                timeout: std::time::Duration::from_secs(5),
-                client_cert: Some(load_certs_from_pem(WRONG_CA_CERT).unwrap()),
-                object_storage: None,
-                hdfs: None,
-            })
-            .await;
+        let resp = HTTP::new(
+            HTTPS_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .get(GetRequest {
+            task_id: "test".to_string(),
+            piece_id: "test".to_string(),
+            url: server_addr,
+            range: None,
+            http_header: Some(HeaderMap::new()),
+            timeout: std::time::Duration::from_secs(5),
+            client_cert: Some(load_certs_from_pem(WRONG_CA_CERT).unwrap()),
+            object_storage: None,
+            hdfs: None,
+        })
+        .await;
 
         assert!(!resp.unwrap().success);
     }
@@ -1027,19 +1252,26 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
     #[tokio::test]
     async fn should_stat_response_with_no_verifier() {
         let server_addr = start_https_server(SERVER_CERT, SERVER_KEY).await;
-        let resp = HTTP::new(HTTPS_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .stat(StatRequest {
-                task_id: "test".to_string(),
-                url: server_addr,
-                http_header: Some(HeaderMap::new()),
-                timeout: Duration::from_secs(5),
-                client_cert: None,
-                object_storage: None,
-                hdfs: None,
-            })
-            .await
-            .unwrap();
+        let resp = HTTP::new(
+            HTTPS_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .stat(StatRequest {
+            task_id: "test".to_string(),
+            url: server_addr,
+            http_header: Some(HeaderMap::new()),
+            timeout: Duration::from_secs(5),
+            client_cert: None,
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+        .unwrap();
 
         assert_eq!(resp.http_status_code, Some(StatusCode::OK));
     }
@@ -1047,7 +1279,14 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
     #[tokio::test]
     async fn should_get_response_with_no_verifier() {
         let server_addr = start_https_server(SERVER_CERT, SERVER_KEY).await;
-        let http_backend = HTTP::new(HTTPS_SCHEME, None, true, Duration::from_secs(600));
+        let http_backend = HTTP::new(
+            HTTPS_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        );
         let mut resp = http_backend
             .unwrap()
             .get(GetRequest {
@@ -1080,19 +1319,26 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .mount(&server)
             .await;
 
-        let resp = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .exists(ExistsRequest {
-                task_id: "test".to_string(),
-                url: format!("{}/exists", server.uri()),
-                http_header: Some(HeaderMap::new()),
-                timeout: Duration::from_secs(5),
-                client_cert: None,
-                object_storage: None,
-                hdfs: None,
-            })
-            .await
-            .unwrap();
+        let resp = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .exists(ExistsRequest {
+            task_id: "test".to_string(),
+            url: format!("{}/exists", server.uri()),
+            http_header: Some(HeaderMap::new()),
+            timeout: Duration::from_secs(5),
+            client_cert: None,
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+        .unwrap();
 
         assert!(resp);
     }
@@ -1109,19 +1355,26 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .mount(&server)
             .await;
 
-        let resp = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .exists(ExistsRequest {
-                task_id: "test".to_string(),
-                url: format!("{}/exists", server.uri()),
-                http_header: Some(HeaderMap::new()),
-                timeout: Duration::from_secs(5),
-                client_cert: None,
-                object_storage: None,
-                hdfs: None,
-            })
-            .await
-            .unwrap();
+        let resp = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .exists(ExistsRequest {
+            task_id: "test".to_string(),
+            url: format!("{}/exists", server.uri()),
+            http_header: Some(HeaderMap::new()),
+            timeout: Duration::from_secs(5),
+            client_cert: None,
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+        .unwrap();
 
         assert!(!resp);
     }
@@ -1138,18 +1391,25 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .mount(&server)
             .await;
 
-        let resp = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600))
-            .unwrap()
-            .exists(ExistsRequest {
-                task_id: "test".to_string(),
-                url: format!("{}/exists", server.uri()),
-                http_header: None,
-                timeout: Duration::from_secs(5),
-                client_cert: None,
-                object_storage: None,
-                hdfs: None,
-            })
-            .await;
+        let resp = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .exists(ExistsRequest {
+            task_id: "test".to_string(),
+            url: format!("{}/exists", server.uri()),
+            http_header: None,
+            timeout: Duration::from_secs(5),
+            client_cert: None,
+            object_storage: None,
+            hdfs: None,
+        })
+        .await;
 
         assert!(resp.is_err());
     }
@@ -1157,7 +1417,15 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
     #[test]
     fn should_make_request_headers() {
         // Apply default user-agent when not specified.
-        let http = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600)).unwrap();
+        let http = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap();
         let mut headers = HeaderMap::new();
         http.make_request_headers(&mut headers, None).unwrap();
         assert_eq!(
@@ -1199,6 +1467,8 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             Some(custom_headers),
             true,
             Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
         )
         .unwrap();
         let mut headers = HeaderMap::new();
@@ -1241,6 +1511,8 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             Some(custom_headers),
             true,
             Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
         )
         .unwrap();
         let mut headers = HeaderMap::new();
@@ -1257,6 +1529,8 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             Some(custom_headers),
             true,
             Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
         )
         .unwrap();
         let mut headers = HeaderMap::new();
@@ -1287,7 +1561,15 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .await;
 
         // First request - should store redirect url.
-        let backend = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(600)).unwrap();
+        let backend = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap();
         let mut response = backend
             .get(GetRequest {
                 task_id: "025a7b4c4615f86617acb34c7ec3404a0a475c2cfaf847ecead944c0bae6277d"
@@ -1350,7 +1632,15 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
             .await;
 
         // Use a very short TTL for this test (1 second).
-        let backend = HTTP::new(HTTP_SCHEME, None, true, Duration::from_secs(1)).unwrap();
+        let backend = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(1),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap();
 
         // First request - should store redirect url.
         let mut response = backend
@@ -1391,4 +1681,78 @@ TrIVG3cErZoBC6zqBs/Ibe9q3gdHGqS3QLAKy/k=
         assert_eq!(response.http_status_code, Some(StatusCode::OK));
         assert_eq!(response.text().await.unwrap(), "target content");
     }
+
+    #[tokio::test]
+    async fn should_put_content_by_chunks() {
+        let server = wiremock::MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/put"))
+            .and(body_string("hello dragonfly"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/post"))
+            .and(body_string("hello dragonfly"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let source_path = dir.path().join("source");
+        std::fs::write(&source_path, b"hello dragonfly").unwrap();
+
+        // Use a small chunk size to upload the content by multiple chunks.
+        let mut backend_config = BackendConfig {
+            put_chunk_size: bytesize::ByteSize::b(4),
+            ..Default::default()
+        };
+        let backend = HTTP::new(
+            HTTP_SCHEME,
+            None,
+            true,
+            Duration::from_secs(600),
+            RetryPolicy::default(),
+            None,
+        )
+        .unwrap()
+        .with_put_config(&backend_config);
+        let response = backend
+            .put(PutRequest {
+                task_id: "test".to_string(),
+                url: format!("{}/put", server.uri()),
+                path: source_path.clone(),
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.http_status_code, Some(StatusCode::CREATED));
+        assert_eq!(response.content_length, Some(15));
+
+        backend_config.put_http_method = reqwest::Method::POST;
+        let backend = backend.with_put_config(&backend_config);
+        let response = backend
+            .put(PutRequest {
+                task_id: "test".to_string(),
+                url: format!("{}/post", server.uri()),
+                path: source_path,
+                http_header: None,
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.http_status_code, Some(StatusCode::OK));
+    }
 }
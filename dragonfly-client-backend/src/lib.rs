@@ -21,7 +21,7 @@ use dragonfly_client_core::{
     Error, Result,
 };
 use libloading::Library;
-use reqwest::header::HeaderMap;
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, LAST_MODIFIED};
 use rustls_pki_types::CertificateDer;
 use std::path::Path;
 use std::path::PathBuf;
@@ -32,9 +32,12 @@ use tokio::io::{AsyncRead, AsyncReadExt};
 use tracing::{error, info, warn};
 use url::Url;
 
+pub mod file;
 pub mod hdfs;
 pub mod http;
 pub mod object_storage;
+pub mod oci;
+pub mod retry;
 
 /// POOL_MAX_IDLE_PER_HOST is the max idle connections per host.
 const POOL_MAX_IDLE_PER_HOST: usize = 1024;
@@ -57,9 +60,6 @@ const HTTP2_CONNECTION_WINDOW_SIZE: u32 = 16 * 1024 * 1024;
 /// HTTP2_MAX_FRAME_SIZE is the max frame size for HTTP2 connection.
 const HTTP2_MAX_FRAME_SIZE: u32 = 16 * 1024 * 1024;
 
-/// MAX_RETRY_TIMES is the max retry times for the request.
-const MAX_RETRY_TIMES: u32 = 1;
-
 /// NAME is the name of the package.
 pub const NAME: &str = "backend";
 
@@ -298,8 +298,8 @@ pub struct BackendFactory {
 /// BackendFactory implements the factory of the backend. It supports loading builtin
 /// backends and plugin backends.
 ///
-/// The builtin backends are http, https, etc., which are implemented
-/// by the HTTP struct.
+/// The builtin backends are http, https, object storages, hdfs, file and oci, which are
+/// implemented by the HTTP, ObjectStorage, Hdfs, File and OCI structs.
 ///
 /// The plugin backends are shared libraries, which are loaded
 /// by the `register_plugin` function. The file name of the shared
@@ -366,23 +366,33 @@ impl BackendFactory {
     ) -> Result<()> {
         self.backends.insert(
             "http".to_string(),
-            Box::new(http::HTTP::new(
-                http::HTTP_SCHEME,
-                self.config.backend.clone().request_header,
-                enable_cache_temporary_redirect,
-                cache_temporary_redirect_ttl,
-            )?),
+            Box::new(
+                http::HTTP::new(
+                    http::HTTP_SCHEME,
+                    self.config.backend.clone().request_header,
+                    enable_cache_temporary_redirect,
+                    cache_temporary_redirect_ttl,
+                    retry::RetryPolicy::new(self.config.backend.retry.clone()),
+                    self.config.network.upstream_proxy.as_ref(),
+                )?
+                .with_put_config(&self.config.backend),
+            ),
         );
         info!("load [http] builtin backend");
 
         self.backends.insert(
             "https".to_string(),
-            Box::new(http::HTTP::new(
-                http::HTTPS_SCHEME,
-                self.config.backend.clone().request_header,
-                enable_cache_temporary_redirect,
-                cache_temporary_redirect_ttl,
-            )?),
+            Box::new(
+                http::HTTP::new(
+                    http::HTTPS_SCHEME,
+                    self.config.backend.clone().request_header,
+                    enable_cache_temporary_redirect,
+                    cache_temporary_redirect_ttl,
+                    retry::RetryPolicy::new(self.config.backend.retry.clone()),
+                    self.config.network.upstream_proxy.as_ref(),
+                )?
+                .with_put_config(&self.config.backend),
+            ),
         );
         info!("load [https] builtin backend");
 
@@ -440,10 +450,30 @@ impl BackendFactory {
         );
         info!("load [cos] builtin backend");
 
-        self.backends
-            .insert("hdfs".to_string(), Box::new(hdfs::Hdfs::new()));
+        self.backends.insert(
+            "hdfs".to_string(),
+            Box::new(hdfs::Hdfs::new(self.config.clone())?),
+        );
         info!("load [hdfs] builtin backend");
 
+        // The file backend can access the local filesystem of the dfdaemon, so it is
+        // disabled unless the allowed roots are configured.
+        if self.config.backend.file.allowed_roots.is_empty() {
+            info!("skip loading [file] builtin backend, because the allowed roots are empty");
+        } else {
+            self.backends.insert(
+                "file".to_string(),
+                Box::new(file::File::new(self.config.clone())),
+            );
+            info!("load [file] builtin backend");
+        }
+
+        self.backends.insert(
+            "oci".to_string(),
+            Box::new(oci::OCI::new(self.config.network.upstream_proxy.as_ref())?),
+        );
+        info!("load [oci] builtin backend");
+
         Ok(())
     }
 
@@ -488,11 +518,48 @@ impl BackendFactory {
     }
 }
 
+/// make_metadata_header makes the header of the stat response by the metadata of the opendal
+/// operator, the ETag and the Last-Modified of the metadata are the version of the file.
+pub(crate) fn make_metadata_header(metadata: &opendal::Metadata) -> HeaderMap {
+    let mut header = HeaderMap::new();
+    if let Some(etag) = metadata
+        .etag()
+        .and_then(|etag| HeaderValue::from_str(etag).ok())
+    {
+        header.insert(ETAG, etag);
+    }
+
+    if let Some(last_modified) = metadata
+        .last_modified()
+        .and_then(|last_modified| HeaderValue::from_str(&last_modified.format_http_date()).ok())
+    {
+        header.insert(LAST_MODIFIED, last_modified);
+    }
+
+    header
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn should_make_metadata_header() {
+        let metadata = opendal::Metadata::new(opendal::EntryMode::FILE)
+            .with_etag("\"abc\"".to_string())
+            .with_last_modified("1994-11-06T08:49:37Z".parse().unwrap());
+        let header = make_metadata_header(&metadata);
+        assert_eq!(header.get(ETAG).unwrap(), "\"abc\"");
+        assert_eq!(
+            header.get(LAST_MODIFIED).unwrap(),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+
+        let header = make_metadata_header(&opendal::Metadata::new(opendal::EntryMode::FILE));
+        assert!(header.is_empty());
+    }
+
     #[test]
     fn should_create_backend_factory_without_plugin_dir() {
         let result = BackendFactory::new(Arc::new(Config::default()), None);
@@ -503,11 +570,17 @@ mod tests {
     fn should_load_builtin_backends() {
         let factory = BackendFactory::new(Arc::new(Config::default()), None).unwrap();
         let expected_backends = vec![
-            "http", "https", "s3", "gs", "abs", "oss", "obs", "cos", "hdfs",
+            "http", "https", "s3", "gs", "abs", "oss", "obs", "cos", "hdfs", "oci",
         ];
         for backend in expected_backends {
             assert!(factory.backends.contains_key(backend));
         }
+        assert!(!factory.backends.contains_key("file"));
+
+        let mut config = Config::default();
+        config.backend.file.allowed_roots = vec![PathBuf::from("/tmp")];
+        let factory = BackendFactory::new(Arc::new(config), None).unwrap();
+        assert!(factory.backends.contains_key("file"));
     }
 
     #[test]
@@ -535,7 +608,7 @@ mod tests {
         let plugin_dir = dir.path().join("non_existent_plugin_dir");
 
         let factory = BackendFactory::new(Arc::new(Config::default()), Some(&plugin_dir)).unwrap();
-        assert_eq!(factory.backends.len(), 9);
+        assert_eq!(factory.backends.len(), 11);
     }
 
     #[test]
@@ -579,7 +652,7 @@ mod tests {
 
         let factory = BackendFactory::new(Arc::new(Config::default()), Some(&plugin_dir)).unwrap();
         let schemes = vec![
-            "http", "https", "s3", "gs", "abs", "oss", "obs", "cos", "hdfs",
+            "http", "https", "s3", "gs", "abs", "oss", "obs", "cos", "hdfs", "file", "oci",
         ];
 
         for scheme in schemes {
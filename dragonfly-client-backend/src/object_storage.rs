@@ -14,11 +14,12 @@
  * limitations under the License.
  */
 
+use crate::retry::RetryPolicy;
 use dragonfly_api::common;
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::error::BackendError;
 use dragonfly_client_core::{Error as ClientError, Result as ClientResult};
-use opendal::{layers::HttpClientLayer, layers::TimeoutLayer, raw::HttpClient, Operator};
+use opendal::{layers::HttpClientLayer, layers::TimeoutLayer, Operator};
 use percent_encoding::percent_decode_str;
 use std::fmt;
 use std::result::Result;
@@ -177,14 +178,23 @@ pub struct ObjectStorage {
 
     /// client is the reqwest client.
     client: reqwest::Client,
+
+    /// retry_policy is the retry policy of the object storage request.
+    retry_policy: RetryPolicy,
 }
 
 /// ObjectStorage implements the ObjectStorage trait.
 impl ObjectStorage {
     /// Returns ObjectStorage that implements the Backend trait.
     pub fn new(scheme: Scheme, config: Arc<Config>) -> ClientResult<ObjectStorage> {
-        // Initialize the reqwest client.
-        let client = reqwest::Client::builder()
+        // Initialize the reqwest client, the object storage is requested via the upstream
+        // proxy if configured.
+        let mut client_builder = reqwest::Client::builder();
+        if let Some(upstream_proxy) = config.network.upstream_proxy.as_ref() {
+            client_builder = client_builder.proxy(upstream_proxy.reqwest_proxy()?);
+        }
+
+        let client = client_builder
             .no_gzip()
             .no_brotli()
             .no_zstd()
@@ -201,10 +211,12 @@ impl ObjectStorage {
             .http2_keep_alive_while_idle(true)
             .build()?;
 
+        let retry_policy = RetryPolicy::new(config.backend.retry.clone());
         Ok(Self {
             scheme,
             config,
             client,
+            retry_policy,
         })
     }
 
@@ -283,7 +295,10 @@ impl ObjectStorage {
         Ok(Operator::new(builder)?
             .finish()
             .layer(TimeoutLayer::new().with_timeout(timeout))
-            .layer(HttpClientLayer::new(HttpClient::with(self.client.clone()))))
+            .layer(HttpClientLayer::new(self.retry_policy.opendal_http_client(
+                &self.scheme.to_string(),
+                self.client.clone(),
+            ))))
     }
 
     /// gcs_operator initializes the GCS operator with the parsed URL and object storage.
@@ -316,7 +331,10 @@ impl ObjectStorage {
         Ok(Operator::new(builder)?
             .finish()
             .layer(TimeoutLayer::new().with_timeout(timeout))
-            .layer(HttpClientLayer::new(HttpClient::with(self.client.clone()))))
+            .layer(HttpClientLayer::new(self.retry_policy.opendal_http_client(
+                &self.scheme.to_string(),
+                self.client.clone(),
+            ))))
     }
 
     /// abs_operator initializes the ABS operator with the parsed URL and object storage.
@@ -358,7 +376,10 @@ impl ObjectStorage {
         Ok(Operator::new(builder)?
             .finish()
             .layer(TimeoutLayer::new().with_timeout(timeout))
-            .layer(HttpClientLayer::new(HttpClient::with(self.client.clone()))))
+            .layer(HttpClientLayer::new(self.retry_policy.opendal_http_client(
+                &self.scheme.to_string(),
+                self.client.clone(),
+            ))))
     }
 
     /// oss_operator initializes the OSS operator with the parsed URL and object storage.
@@ -411,7 +432,10 @@ impl ObjectStorage {
         Ok(Operator::new(builder)?
             .finish()
             .layer(TimeoutLayer::new().with_timeout(timeout))
-            .layer(HttpClientLayer::new(HttpClient::with(self.client.clone()))))
+            .layer(HttpClientLayer::new(self.retry_policy.opendal_http_client(
+                &self.scheme.to_string(),
+                self.client.clone(),
+            ))))
     }
 
     /// obs_operator initializes the OBS operator with the parsed URL and object storage.
@@ -453,7 +477,10 @@ impl ObjectStorage {
         Ok(Operator::new(builder)?
             .finish()
             .layer(TimeoutLayer::new().with_timeout(timeout))
-            .layer(HttpClientLayer::new(HttpClient::with(self.client.clone()))))
+            .layer(HttpClientLayer::new(self.retry_policy.opendal_http_client(
+                &self.scheme.to_string(),
+                self.client.clone(),
+            ))))
     }
 
     /// cos_operator initializes the COS operator with the parsed URL and object storage.
@@ -495,7 +522,10 @@ impl ObjectStorage {
         Ok(Operator::new(builder)?
             .finish()
             .layer(TimeoutLayer::new().with_timeout(timeout))
-            .layer(HttpClientLayer::new(HttpClient::with(self.client.clone()))))
+            .layer(HttpClientLayer::new(self.retry_policy.opendal_http_client(
+                &self.scheme.to_string(),
+                self.client.clone(),
+            ))))
     }
 }
 
@@ -587,7 +617,7 @@ impl crate::Backend for ObjectStorage {
         Ok(super::StatResponse {
             success: true,
             content_length: Some(response.content_length()),
-            http_header: None,
+            http_header: Some(super::make_metadata_header(&response)),
             http_status_code: None,
             error_message: None,
             entries,
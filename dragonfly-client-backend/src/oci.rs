@@ -0,0 +1,1209 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_api::common::v2::Range;
+use dragonfly_client_config::dfdaemon::UpstreamProxy;
+use dragonfly_client_core::error::{BackendError, ErrorType, OrErr};
+use dragonfly_client_core::{Error as ClientError, Result as ClientResult};
+use dragonfly_client_util::digest::{Algorithm, Hasher};
+use dragonfly_client_util::tls::NoVerifier;
+use futures::TryStreamExt;
+use lru::LruCache;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, RANGE, USER_AGENT, WWW_AUTHENTICATE,
+};
+use reqwest::{Method, StatusCode};
+use rustls_pki_types::CertificateDer;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Error as IOError, ErrorKind};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio_util::io::StreamReader;
+use tracing::{debug, error, instrument};
+use url::Url;
+
+/// OCI_SCHEME is the scheme of the OCI registry, e.g. oci://<registry>/<repository>:<tag>.
+pub const OCI_SCHEME: &str = "oci";
+
+/// OCI_PLATFORM_HEADER is the request header to select the platform of the image when the
+/// reference points to an image index, e.g. linux/amd64 or linux/arm64/v8. If it is not
+/// specified, the platform of the host is used.
+pub const OCI_PLATFORM_HEADER: &str = "X-Dragonfly-OCI-Platform";
+
+/// DEFAULT_TAG is the default tag of the image reference.
+const DEFAULT_TAG: &str = "latest";
+
+/// DOCKER_HUB_REGISTRY is the registry name of the docker hub used in the image reference.
+const DOCKER_HUB_REGISTRY: &str = "docker.io";
+
+/// DOCKER_HUB_ENDPOINT is the actual registry endpoint of the docker hub.
+const DOCKER_HUB_ENDPOINT: &str = "registry-1.docker.io";
+
+/// DOCKER_HUB_OFFICIAL_NAMESPACE is the namespace of the official images in the docker hub.
+const DOCKER_HUB_OFFICIAL_NAMESPACE: &str = "library";
+
+/// DEFAULT_TOKEN_TTL is the default ttl of the registry token if the token server
+/// does not return the expires_in field.
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(60);
+
+/// DEFAULT_TOKEN_CACHE_CAPACITY is the max number of the cached bearer tokens, the least
+/// recently used token is evicted.
+const DEFAULT_TOKEN_CACHE_CAPACITY: usize = 1024;
+
+/// MANIFEST_ACCEPT is the accept header of the manifest request, including the image index
+/// and the image manifest of the OCI and docker.
+const MANIFEST_ACCEPT: &str = "application/vnd.oci.image.index.v1+json, application/vnd.docker.distribution.manifest.list.v2+json, application/vnd.oci.image.manifest.v1+json, application/vnd.docker.distribution.manifest.v2+json";
+
+/// DEFAULT_USER_AGENT is the default user agent.
+const DEFAULT_USER_AGENT: &str = concat!("dragonfly", "/", env!("CARGO_PKG_VERSION"));
+
+/// Reference is the parsed image reference, e.g. <registry>/<repository>:<tag> or
+/// <registry>/<repository>@<digest>.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// registry is the registry of the image including the port, e.g. docker.io or localhost:5000.
+    pub registry: String,
+
+    /// repository is the repository of the image, e.g. library/nginx.
+    pub repository: String,
+
+    /// reference is the tag or the digest of the image, e.g. latest or sha256:<hex>.
+    pub reference: String,
+}
+
+/// Reference implements the image reference.
+impl Reference {
+    /// parse parses the image reference by the registry and the path of the url.
+    pub fn parse(registry: &str, path: &str) -> ClientResult<Reference> {
+        let path = path.trim_matches('/');
+        let (repository, reference) = match path.split_once('@') {
+            Some((repository, digest)) => (repository, digest),
+            None => match path.rsplit_once(':') {
+                Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+                _ => (path, DEFAULT_TAG),
+            },
+        };
+
+        if registry.is_empty() || repository.is_empty() || reference.is_empty() {
+            return Err(ClientError::InvalidURI(format!("{}/{}", registry, path)));
+        }
+
+        // The official images of the docker hub are in the library namespace.
+        let repository = if registry == DOCKER_HUB_REGISTRY && !repository.contains('/') {
+            format!("{}/{}", DOCKER_HUB_OFFICIAL_NAMESPACE, repository)
+        } else {
+            repository.to_string()
+        };
+
+        Ok(Reference {
+            registry: registry.to_string(),
+            repository,
+            reference: reference.to_string(),
+        })
+    }
+
+    /// endpoint returns the endpoint of the registry. The registries on the loopback
+    /// address are accessed by http, the same as docker does.
+    pub fn endpoint(&self) -> String {
+        let registry = if self.registry == DOCKER_HUB_REGISTRY {
+            DOCKER_HUB_ENDPOINT
+        } else {
+            self.registry.as_str()
+        };
+
+        let host = registry
+            .rsplit_once(':')
+            .filter(|(_, port)| port.parse::<u16>().is_ok())
+            .map_or(registry, |(host, _)| host);
+        match host {
+            "localhost" | "127.0.0.1" | "[::1]" => format!("http://{}", registry),
+            _ => format!("https://{}", registry),
+        }
+    }
+
+    /// manifest_url returns the manifest url of the reference in the registry.
+    pub fn manifest_url(&self, reference: &str) -> String {
+        format!(
+            "{}/v2/{}/manifests/{}",
+            self.endpoint(),
+            self.repository,
+            reference
+        )
+    }
+
+    /// blob_url returns the blob url of the digest in the registry.
+    pub fn blob_url(&self, digest: &str) -> String {
+        format!(
+            "{}/v2/{}/blobs/{}",
+            self.endpoint(),
+            self.repository,
+            digest
+        )
+    }
+}
+
+/// Blob is the parsed blob url, e.g. oci://<registry>/<repository>:<tag>/v2/<repository>/blobs/<digest>
+/// or oci://<registry>/v2/<repository>/blobs/<digest>.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blob {
+    /// reference is the image reference of the blob, the reference field is the digest of the blob.
+    pub reference: Reference,
+}
+
+/// Blob implements the blob url.
+impl Blob {
+    /// parse parses the blob by the registry and the path of the url, returns None if the path
+    /// is not a blob path.
+    pub fn parse(registry: &str, path: &str) -> Option<Blob> {
+        let (prefix, digest) = path.rsplit_once("/blobs/")?;
+        if digest.is_empty() || digest.contains('/') {
+            return None;
+        }
+
+        // The prefix is /v2/<repository> or /<repository>:<tag>/v2/<repository>, and the
+        // repository may contain the v2 component, so check every possible split.
+        for (index, _) in prefix.match_indices("/v2/") {
+            let (head, repository) = (&prefix[..index], &prefix[index + 4..]);
+            if repository.is_empty() {
+                continue;
+            }
+
+            let reference = Reference {
+                registry: registry.to_string(),
+                repository: repository.to_string(),
+                reference: digest.to_string(),
+            };
+
+            if head.is_empty() {
+                return Some(Blob { reference });
+            }
+
+            if let Ok(image) = Reference::parse(registry, head) {
+                if image.repository == repository {
+                    return Some(Blob { reference });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// url returns the blob url in the registry.
+    pub fn url(&self) -> String {
+        self.reference.blob_url(&self.reference.reference)
+    }
+}
+
+/// Platform is the platform of the image manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Platform {
+    /// architecture is the cpu architecture, e.g. amd64 or arm64.
+    pub architecture: String,
+
+    /// os is the operating system, e.g. linux.
+    pub os: String,
+
+    /// variant is the variant of the cpu architecture, e.g. v8.
+    #[serde(default)]
+    pub variant: Option<String>,
+}
+
+/// Platform implements the platform of the image.
+impl Platform {
+    /// host returns the platform of the host.
+    pub fn host() -> Platform {
+        let architecture = match std::env::consts::ARCH {
+            "x86_64" => "amd64",
+            "aarch64" => "arm64",
+            "x86" => "386",
+            "powerpc64" => "ppc64le",
+            arch => arch,
+        };
+
+        let os = match std::env::consts::OS {
+            "macos" => "darwin",
+            os => os,
+        };
+
+        Platform {
+            architecture: architecture.to_string(),
+            os: os.to_string(),
+            variant: None,
+        }
+    }
+
+    /// parse parses the platform, e.g. linux/amd64 or linux/arm64/v8.
+    pub fn parse(platform: &str) -> ClientResult<Platform> {
+        let mut parts = platform.trim().split('/');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(os), Some(architecture), variant, None)
+                if !os.is_empty() && !architecture.is_empty() =>
+            {
+                Ok(Platform {
+                    architecture: architecture.to_string(),
+                    os: os.to_string(),
+                    variant: variant.map(|variant| variant.to_string()),
+                })
+            }
+            _ => Err(ClientError::InvalidParameter),
+        }
+    }
+
+    /// matches returns whether the platform matches the required platform, the variant
+    /// is only compared when the required platform specifies it.
+    pub fn matches(&self, required: &Platform) -> bool {
+        self.os == required.os
+            && self.architecture == required.architecture
+            && (required.variant.is_none() || self.variant == required.variant)
+    }
+}
+
+/// Descriptor is the content descriptor of the OCI image.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    /// media_type is the media type of the content.
+    #[serde(default)]
+    pub media_type: Option<String>,
+
+    /// digest is the digest of the content.
+    pub digest: String,
+
+    /// size is the size of the content.
+    pub size: u64,
+
+    /// platform is the platform of the manifest in the image index.
+    #[serde(default)]
+    pub platform: Option<Platform>,
+}
+
+/// Manifest is the image manifest or the image index, the image index has the manifests
+/// field and the image manifest has the layers field.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// media_type is the media type of the manifest.
+    #[serde(default)]
+    pub media_type: Option<String>,
+
+    /// manifests is the manifests of the image index.
+    #[serde(default)]
+    pub manifests: Option<Vec<Descriptor>>,
+
+    /// layers is the layers of the image manifest.
+    #[serde(default)]
+    pub layers: Option<Vec<Descriptor>>,
+}
+
+/// Manifest implements the image manifest.
+impl Manifest {
+    /// select_manifest selects the manifest of the platform from the image index.
+    pub fn select_manifest(&self, platform: &Platform) -> Option<&Descriptor> {
+        self.manifests.as_ref()?.iter().find(|descriptor| {
+            descriptor
+                .platform
+                .as_ref()
+                .is_some_and(|descriptor_platform| descriptor_platform.matches(platform))
+        })
+    }
+}
+
+/// TokenResponse is the response of the token server.
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    /// token is the bearer token.
+    #[serde(default)]
+    token: Option<String>,
+
+    /// access_token is the bearer token in the OAuth2 compatible response.
+    #[serde(default)]
+    access_token: Option<String>,
+
+    /// expires_in is the lifetime of the token in seconds.
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// Token is the cached bearer token of the registry.
+#[derive(Debug, Clone)]
+struct Token {
+    /// value is the bearer token.
+    value: String,
+
+    /// expired_at is the time when the token is expired.
+    expired_at: Instant,
+}
+
+/// parse_challenge parses the WWW-Authenticate header, returns the auth scheme and the
+/// parameters, e.g. Bearer realm="https://auth.docker.io/token",service="registry.docker.io".
+pub fn parse_challenge(challenge: &str) -> Option<(String, HashMap<String, String>)> {
+    let challenge = challenge.trim();
+    let (scheme, params) = match challenge.split_once(' ') {
+        Some((scheme, params)) => (scheme, params),
+        None => (challenge, ""),
+    };
+
+    if scheme.is_empty() {
+        return None;
+    }
+
+    let mut parameters = HashMap::new();
+    let mut chars = params.chars().peekable();
+    loop {
+        // Skip the separators before the key.
+        while chars.peek().is_some_and(|c| *c == ',' || c.is_whitespace()) {
+            chars.next();
+        }
+
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.trim().is_empty() {
+            break;
+        }
+
+        // The value may be quoted and contain the comma, e.g. scope="repository:foo:pull,push".
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some(c) = chars.next() {
+                            value.push(c);
+                        }
+                    }
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.peek() {
+                if *c == ',' {
+                    break;
+                }
+
+                value.push(*c);
+                chars.next();
+            }
+        }
+
+        parameters.insert(key.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    Some((scheme.to_string(), parameters))
+}
+
+/// OCI is a struct that implements the Backend trait for the OCI registry. It resolves the
+/// image reference to the layer blobs, and each blob is exposed as an entry of the directory.
+pub struct OCI {
+    /// scheme is the scheme of the OCI registry.
+    scheme: String,
+
+    /// client is the default reqwest client without the certificate validation.
+    client: reqwest::Client,
+
+    /// tokens is the cached bearer tokens by the registry, the repository and the hash of the
+    /// credential (LRU eviction).
+    tokens: Mutex<LruCache<String, Token>>,
+
+    /// proxy is the upstream proxy of the reqwest clients.
+    proxy: Option<reqwest::Proxy>,
+}
+
+/// OCI implements the Backend trait.
+impl OCI {
+    /// new returns a new OCI backend.
+    pub fn new(upstream_proxy: Option<&UpstreamProxy>) -> ClientResult<OCI> {
+        let proxy = upstream_proxy
+            .map(|upstream_proxy| upstream_proxy.reqwest_proxy())
+            .transpose()?;
+
+        Ok(Self {
+            scheme: OCI_SCHEME.to_string(),
+            client: Self::make_client(None, proxy.clone())?,
+            tokens: Mutex::new(LruCache::new(
+                NonZeroUsize::new(DEFAULT_TOKEN_CACHE_CAPACITY).unwrap(),
+            )),
+            proxy,
+        })
+    }
+
+    /// make_client makes the reqwest client, the client certificates are used as the root
+    /// certificates if provided, otherwise the certificate validation is disabled.
+    fn make_client(
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+        proxy: Option<reqwest::Proxy>,
+    ) -> ClientResult<reqwest::Client> {
+        let client_config_builder = match client_cert {
+            Some(client_cert) => {
+                let mut root_cert_store = rustls::RootCertStore::empty();
+                root_cert_store.add_parsable_certificates(client_cert);
+                rustls::ClientConfig::builder()
+                    .with_root_certificates(root_cert_store)
+                    .with_no_client_auth()
+            }
+            None => rustls::ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(NoVerifier::new())
+                .with_no_client_auth(),
+        };
+
+        let mut client_builder = reqwest::Client::builder();
+        if let Some(proxy) = proxy {
+            client_builder = client_builder.proxy(proxy);
+        }
+
+        Ok(client_builder
+            .no_gzip()
+            .no_brotli()
+            .no_zstd()
+            .no_deflate()
+            .hickory_dns(true)
+            .use_preconfigured_tls(client_config_builder)
+            .pool_max_idle_per_host(super::POOL_MAX_IDLE_PER_HOST)
+            .tcp_keepalive(super::KEEP_ALIVE_INTERVAL)
+            .tcp_nodelay(true)
+            .build()?)
+    }
+
+    /// client returns the reqwest client by the client certificates.
+    fn client(
+        &self,
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+    ) -> ClientResult<reqwest::Client> {
+        match client_cert {
+            Some(client_cert) => Self::make_client(Some(client_cert), self.proxy.clone()),
+            None => Ok(self.client.clone()),
+        }
+    }
+
+    /// parse_url parses the oci url, returns the url and the registry including the port.
+    fn parse_url(url: &str) -> ClientResult<(Url, String)> {
+        let url = Url::parse(url).map_err(|_| ClientError::InvalidURI(url.to_string()))?;
+        let registry = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) if !host.is_empty() => format!("{}:{}", host, port),
+            (Some(host), None) if !host.is_empty() => host.to_string(),
+            _ => return Err(ClientError::InvalidURI(url.to_string())),
+        };
+
+        Ok((url, registry))
+    }
+
+    /// token_key returns the key of the cached token, the credential is hashed so it is not
+    /// kept in the memory in plaintext.
+    fn token_key(reference: &Reference, authorization: Option<&HeaderValue>) -> String {
+        let mut hasher = Hasher::new(Algorithm::Sha256);
+        if let Some(authorization) = authorization {
+            hasher.update(authorization.as_bytes());
+        }
+
+        format!(
+            "{}/{}#{}",
+            reference.registry,
+            reference.repository,
+            hasher.finalize()
+        )
+    }
+
+    /// get_token returns the cached token if it is not expired, the expired token is evicted.
+    async fn get_token(&self, token_key: &str) -> Option<String> {
+        let mut tokens = self.tokens.lock().await;
+        let token = tokens.get(token_key)?;
+        if token.expired_at > Instant::now() {
+            return Some(token.value.clone());
+        }
+
+        debug!("cached token {} expired", token_key);
+        tokens.pop(token_key);
+        None
+    }
+
+    /// fetch_token fetches the bearer token from the token server by the challenge, the
+    /// authorization of the original request is used as the credential of the token server.
+    async fn fetch_token(
+        &self,
+        client: &reqwest::Client,
+        reference: &Reference,
+        parameters: &HashMap<String, String>,
+        authorization: Option<&HeaderValue>,
+        timeout: Duration,
+    ) -> ClientResult<Token> {
+        let realm = parameters
+            .get("realm")
+            .ok_or_else(|| ClientError::Unknown("realm is missing in challenge".to_string()))?;
+
+        let mut token_url = Url::parse(realm).or_err(ErrorType::ParseError)?;
+        {
+            let mut query_pairs = token_url.query_pairs_mut();
+            if let Some(service) = parameters.get("service") {
+                query_pairs.append_pair("service", service);
+            }
+
+            match parameters.get("scope") {
+                Some(scope) => query_pairs.append_pair("scope", scope),
+                None => query_pairs.append_pair(
+                    "scope",
+                    format!("repository:{}:pull", reference.repository).as_str(),
+                ),
+            };
+        }
+
+        let mut request = client
+            .get(token_url.as_str())
+            .header(USER_AGENT, DEFAULT_USER_AGENT)
+            .timeout(timeout);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization.clone());
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            error!("fetch token failed {}: {}", token_url, status);
+            return Err(ClientError::BackendError(Box::new(BackendError {
+                message: format!("fetch token failed: {}", status),
+                status_code: Some(status),
+                header: Some(response.headers().clone()),
+            })));
+        }
+
+        let body = response.bytes().await?;
+        let token_response: TokenResponse =
+            serde_json::from_slice(&body).or_err(ErrorType::ParseError)?;
+        let value = token_response
+            .token
+            .or(token_response.access_token)
+            .ok_or_else(|| ClientError::Unknown("token is missing in response".to_string()))?;
+
+        Ok(Token {
+            value,
+            expired_at: Instant::now()
+                + token_response
+                    .expires_in
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_TOKEN_TTL),
+        })
+    }
+
+    /// send sends the request to the registry, and does the token auth handshake if the
+    /// registry responds with the bearer challenge.
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        reference: &Reference,
+        mut header: HeaderMap,
+        timeout: Duration,
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+    ) -> ClientResult<reqwest::Response> {
+        let client = self.client(client_cert)?;
+        let authorization = header.remove(AUTHORIZATION);
+        let token_key = Self::token_key(reference, authorization.as_ref());
+        header
+            .entry(USER_AGENT)
+            .or_insert(HeaderValue::from_static(DEFAULT_USER_AGENT));
+
+        // Use the cached token if it is not expired, otherwise use the original authorization.
+        let cached_token = self.get_token(&token_key).await;
+        let mut request_header = header.clone();
+        match (&cached_token, &authorization) {
+            (Some(token), _) => {
+                request_header.insert(AUTHORIZATION, format!("Bearer {}", token).parse()?);
+            }
+            (None, Some(authorization)) => {
+                request_header.insert(AUTHORIZATION, authorization.clone());
+            }
+            (None, None) => {}
+        }
+
+        let response = client
+            .request(method.clone(), url)
+            .headers(request_header)
+            .timeout(timeout)
+            .send()
+            .await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        // Parse the challenge of the registry, only the bearer challenge needs the handshake.
+        let Some((scheme, parameters)) = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|challenge| challenge.to_str().ok())
+            .and_then(parse_challenge)
+        else {
+            return Ok(response);
+        };
+
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            return Ok(response);
+        }
+
+        debug!(
+            "registry {} requires bearer token for {}",
+            reference.registry, url
+        );
+        let token = self
+            .fetch_token(
+                &client,
+                reference,
+                &parameters,
+                authorization.as_ref(),
+                timeout,
+            )
+            .await?;
+        self.tokens.lock().await.put(token_key, token.clone());
+
+        header.insert(AUTHORIZATION, format!("Bearer {}", token.value).parse()?);
+        Ok(client
+            .request(method, url)
+            .headers(header)
+            .timeout(timeout)
+            .send()
+            .await?)
+    }
+
+    /// get_manifest gets the manifest by the reference from the registry.
+    async fn get_manifest(
+        &self,
+        reference: &Reference,
+        manifest_reference: &str,
+        header: HeaderMap,
+        timeout: Duration,
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+    ) -> ClientResult<Manifest> {
+        let mut header = header;
+        header.insert(ACCEPT, HeaderValue::from_static(MANIFEST_ACCEPT));
+
+        let url = reference.manifest_url(manifest_reference);
+        let response = self
+            .send(Method::GET, &url, reference, header, timeout, client_cert)
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            error!("get manifest failed {}: {}", url, status);
+            return Err(ClientError::BackendError(Box::new(BackendError {
+                message: format!("get manifest {} failed: {}", url, status),
+                status_code: Some(status),
+                header: Some(response.headers().clone()),
+            })));
+        }
+
+        let body = response.bytes().await?;
+        serde_json::from_slice(&body).or_err(ErrorType::ParseError)
+    }
+
+    /// resolve_layers resolves the image reference to the layers of the image manifest. If
+    /// the reference points to an image index, the manifest of the platform is selected.
+    async fn resolve_layers(
+        &self,
+        reference: &Reference,
+        header: HeaderMap,
+        timeout: Duration,
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+    ) -> ClientResult<Vec<Descriptor>> {
+        let platform = match header
+            .get(OCI_PLATFORM_HEADER)
+            .and_then(|platform| platform.to_str().ok())
+        {
+            Some(platform) => Platform::parse(platform)?,
+            None => Platform::host(),
+        };
+
+        let mut header = header;
+        header.remove(OCI_PLATFORM_HEADER);
+
+        let manifest = self
+            .get_manifest(
+                reference,
+                &reference.reference,
+                header.clone(),
+                timeout,
+                client_cert.clone(),
+            )
+            .await?;
+        let manifest = match manifest.manifests {
+            Some(_) => {
+                let descriptor = manifest.select_manifest(&platform).ok_or_else(|| {
+                    error!(
+                        "platform {:?} not found in image index {:?}",
+                        platform, reference
+                    );
+                    ClientError::Unsupported(format!(
+                        "platform {}/{} not found in image index",
+                        platform.os, platform.architecture
+                    ))
+                })?;
+
+                self.get_manifest(reference, &descriptor.digest, header, timeout, client_cert)
+                    .await?
+            }
+            None => manifest,
+        };
+
+        manifest.layers.ok_or_else(|| {
+            ClientError::Unsupported(format!(
+                "manifest {:?} of {:?} has no layers",
+                manifest.media_type, reference
+            ))
+        })
+    }
+
+    /// get_blob gets the blob from the registry by the range.
+    async fn get_blob(
+        &self,
+        blob: &Blob,
+        method: Method,
+        range: Option<Range>,
+        header: HeaderMap,
+        timeout: Duration,
+        client_cert: Option<Vec<CertificateDer<'static>>>,
+    ) -> ClientResult<reqwest::Response> {
+        let mut header = header;
+        header.remove(OCI_PLATFORM_HEADER);
+        if let Some(range) = range {
+            header.insert(
+                RANGE,
+                format!("bytes={}-{}", range.start, range.start + range.length - 1).parse()?,
+            );
+        }
+
+        self.send(
+            method,
+            &blob.url(),
+            &blob.reference,
+            header,
+            timeout,
+            client_cert,
+        )
+        .await
+    }
+}
+
+/// Implement the Backend trait for OCI.
+#[tonic::async_trait]
+impl super::Backend for OCI {
+    /// scheme returns the scheme of the OCI backend.
+    fn scheme(&self) -> String {
+        self.scheme.clone()
+    }
+
+    /// stat gets the content length of the blob, or lists the layer blobs of the image
+    /// reference as the entries.
+    #[instrument(skip_all)]
+    async fn stat(&self, request: super::StatRequest) -> ClientResult<super::StatResponse> {
+        debug!(
+            "stat request {} {}: {:?}",
+            request.task_id, request.url, request.http_header
+        );
+
+        let (url, registry) = Self::parse_url(&request.url)?;
+        let header = request.http_header.unwrap_or_default();
+
+        // Stat the blob if the url points to a blob.
+        if let Some(blob) = Blob::parse(&registry, url.path()) {
+            let response = self
+                .get_blob(
+                    &blob,
+                    Method::HEAD,
+                    None,
+                    header,
+                    request.timeout,
+                    request.client_cert,
+                )
+                .await
+                .inspect_err(|err| {
+                    error!(
+                        "stat request failed {} {}: {}",
+                        request.task_id, request.url, err
+                    );
+                })?;
+
+            let response_header = response.headers().clone();
+            let response_status_code = response.status();
+            debug!(
+                "stat response {} {}: {:?} {:?}",
+                request.task_id, request.url, response_status_code, response_header
+            );
+
+            return Ok(super::StatResponse {
+                success: response_status_code.is_success(),
+                content_length: response.content_length(),
+                http_header: Some(response_header),
+                http_status_code: Some(response_status_code),
+                error_message: Some(response_status_code.to_string()),
+                entries: Vec::new(),
+            });
+        }
+
+        // Resolve the image reference to the layer blobs, the url of each entry is under the
+        // url of the reference, so the entries can be downloaded as a directory.
+        let reference = Reference::parse(&registry, url.path())?;
+        let layers = self
+            .resolve_layers(&reference, header, request.timeout, request.client_cert)
+            .await
+            .inspect_err(|err| {
+                error!(
+                    "stat request failed {} {}: {}",
+                    request.task_id, request.url, err
+                );
+            })?;
+
+        let base_path = url.path().trim_end_matches('/').to_string();
+        let mut entries = Vec::with_capacity(layers.len());
+        for layer in layers {
+            let mut entry_url = url.clone();
+            entry_url.set_path(&format!(
+                "{}/v2/{}/blobs/{}",
+                base_path, reference.repository, layer.digest
+            ));
+
+            entries.push(super::DirEntry {
+                url: entry_url.to_string(),
+                content_length: layer.size as usize,
+                is_dir: false,
+            });
+        }
+
+        debug!(
+            "stat response {} {}: {:?}",
+            request.task_id, request.url, entries
+        );
+
+        Ok(super::StatResponse {
+            success: true,
+            content_length: None,
+            http_header: None,
+            http_status_code: Some(StatusCode::OK),
+            error_message: None,
+            entries,
+        })
+    }
+
+    /// get gets the content of the blob from the registry.
+    #[instrument(skip_all)]
+    async fn get(
+        &self,
+        request: super::GetRequest,
+    ) -> ClientResult<super::GetResponse<super::Body>> {
+        debug!(
+            "get request {} {} {}: {:?}",
+            request.task_id, request.piece_id, request.url, request.http_header
+        );
+
+        let (url, registry) = Self::parse_url(&request.url)?;
+        let blob = Blob::parse(&registry, url.path())
+            .ok_or_else(|| ClientError::InvalidURI(request.url.clone()))?;
+
+        let response = match self
+            .get_blob(
+                &blob,
+                Method::GET,
+                request.range,
+                request.http_header.unwrap_or_default(),
+                request.timeout,
+                request.client_cert,
+            )
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                error!(
+                    "get request failed {} {} {}: {}",
+                    request.task_id, request.piece_id, request.url, err
+                );
+
+                return Ok(super::GetResponse {
+                    success: false,
+                    http_header: None,
+                    http_status_code: None,
+                    reader: Box::new(tokio::io::empty()),
+                    error_message: Some(err.to_string()),
+                });
+            }
+        };
+
+        let response_header = response.headers().clone();
+        let response_status_code = response.status();
+        let response_reader = Box::new(StreamReader::new(
+            response
+                .bytes_stream()
+                .map_err(|err| IOError::new(ErrorKind::Other, err)),
+        ));
+
+        debug!(
+            "get response {} {}: {:?} {:?}",
+            request.task_id, request.piece_id, response_status_code, response_header,
+        );
+
+        Ok(super::GetResponse {
+            success: response_status_code.is_success(),
+            http_header: Some(response_header),
+            http_status_code: Some(response_status_code),
+            reader: response_reader,
+            error_message: Some(response_status_code.to_string()),
+        })
+    }
+
+    /// put is not supported by the OCI backend.
+    #[instrument(skip_all)]
+    async fn put(&self, request: super::PutRequest) -> ClientResult<super::PutResponse> {
+        Err(ClientError::Unsupported(format!(
+            "put is not supported by {} backend: {}",
+            self.scheme, request.url
+        )))
+    }
+
+    /// exists checks whether the blob exists in the registry.
+    #[instrument(skip_all)]
+    async fn exists(&self, request: super::ExistsRequest) -> ClientResult<bool> {
+        debug!(
+            "exists request {} {}: {:?}",
+            request.task_id, request.url, request.http_header
+        );
+
+        let (url, registry) = Self::parse_url(&request.url)?;
+        let blob = Blob::parse(&registry, url.path())
+            .ok_or_else(|| ClientError::InvalidURI(request.url.clone()))?;
+
+        let response = self
+            .get_blob(
+                &blob,
+                Method::HEAD,
+                None,
+                request.http_header.unwrap_or_default(),
+                request.timeout,
+                request.client_cert,
+            )
+            .await?;
+        Ok(response.status().is_success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Backend, GetRequest, StatRequest};
+    use dragonfly_client_util::digest::is_blob_url;
+    use wiremock::{
+        matchers::{header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[test]
+    fn should_parse_reference() {
+        let reference = Reference::parse("docker.io", "/nginx:1.25/").unwrap();
+        assert_eq!(reference.repository, "library/nginx");
+        assert_eq!(reference.reference, "1.25");
+        assert_eq!(reference.endpoint(), "https://registry-1.docker.io");
+
+        let reference = Reference::parse("localhost:5000", "/foo/bar").unwrap();
+        assert_eq!(reference.repository, "foo/bar");
+        assert_eq!(reference.reference, DEFAULT_TAG);
+        assert_eq!(reference.endpoint(), "http://localhost:5000");
+
+        let reference = Reference::parse("ghcr.io", "/foo/bar@sha256:abc").unwrap();
+        assert_eq!(reference.repository, "foo/bar");
+        assert_eq!(reference.reference, "sha256:abc");
+
+        assert!(Reference::parse("ghcr.io", "/").is_err());
+    }
+
+    #[test]
+    fn should_parse_blob() {
+        let blob =
+            Blob::parse("ghcr.io", "/foo/v2/bar:1.0/v2/foo/v2/bar/blobs/sha256:abc").unwrap();
+        assert_eq!(blob.reference.repository, "foo/v2/bar");
+        assert_eq!(blob.url(), "https://ghcr.io/v2/foo/v2/bar/blobs/sha256:abc");
+
+        let blob = Blob::parse("ghcr.io", "/v2/foo/bar/blobs/sha256:abc").unwrap();
+        assert_eq!(blob.reference.repository, "foo/bar");
+
+        assert!(Blob::parse("ghcr.io", "/foo/bar:1.0/").is_none());
+        assert!(Blob::parse("ghcr.io", "/foo/bar:1.0/v2/baz/blobs/sha256:abc").is_none());
+    }
+
+    #[test]
+    fn should_parse_challenge() {
+        let (scheme, parameters) = parse_challenge(
+            r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:foo:pull,push""#,
+        )
+        .unwrap();
+        assert_eq!(scheme, "Bearer");
+        assert_eq!(parameters["realm"], "https://auth.docker.io/token");
+        assert_eq!(parameters["service"], "registry.docker.io");
+        assert_eq!(parameters["scope"], "repository:foo:pull,push");
+
+        let (scheme, parameters) = parse_challenge(r#"Basic realm=registry"#).unwrap();
+        assert_eq!(scheme, "Basic");
+        assert_eq!(parameters["realm"], "registry");
+    }
+
+    #[tokio::test]
+    async fn should_cache_token_by_hashed_credential() {
+        let oci = OCI::new(None).unwrap();
+        let reference = Reference::parse("ghcr.io", "/foo/bar:1.0").unwrap();
+        let authorization = HeaderValue::from_static("Basic dXNlcjpwYXNzd29yZA==");
+
+        let token_key = OCI::token_key(&reference, Some(&authorization));
+        assert!(token_key.starts_with("ghcr.io/foo/bar#sha256:"));
+        assert!(!token_key.contains("dXNlcjpwYXNzd29yZA=="));
+        assert_ne!(token_key, OCI::token_key(&reference, None));
+
+        oci.tokens.lock().await.put(
+            token_key.clone(),
+            Token {
+                value: "token".to_string(),
+                expired_at: Instant::now() + Duration::from_secs(60),
+            },
+        );
+        assert_eq!(oci.get_token(&token_key).await, Some("token".to_string()));
+
+        // The expired token is evicted when it is read.
+        oci.tokens.lock().await.put(
+            token_key.clone(),
+            Token {
+                value: "token".to_string(),
+                expired_at: Instant::now(),
+            },
+        );
+        assert_eq!(oci.get_token(&token_key).await, None);
+        assert!(oci.tokens.lock().await.is_empty());
+    }
+
+    #[test]
+    fn should_select_manifest_by_platform() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{
+                "mediaType": "application/vnd.oci.image.index.v1+json",
+                "manifests": [
+                    {"digest": "sha256:amd64", "size": 1, "platform": {"architecture": "amd64", "os": "linux"}},
+                    {"digest": "sha256:armv7", "size": 1, "platform": {"architecture": "arm", "os": "linux", "variant": "v7"}},
+                    {"digest": "sha256:arm64", "size": 1, "platform": {"architecture": "arm64", "os": "linux", "variant": "v8"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let platform = Platform::parse("linux/arm64").unwrap();
+        assert_eq!(
+            manifest.select_manifest(&platform).unwrap().digest,
+            "sha256:arm64"
+        );
+
+        let platform = Platform::parse("linux/arm/v6").unwrap();
+        assert!(manifest.select_manifest(&platform).is_none());
+        assert!(Platform::parse("linux").is_err());
+    }
+
+    #[tokio::test]
+    async fn should_resolve_image_with_token_auth() {
+        let server = MockServer::start().await;
+        let registry = format!("localhost:{}", server.address().port());
+        let layer_digest =
+            "sha256:2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("scope", "repository:foo/bar:pull"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token": "test-token"}"#))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/foo/bar/manifests/1.0"))
+            .and(header("authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"manifests": [{"digest": "sha256:manifest", "size": 1, "platform": {"architecture": "arm64", "os": "linux"}}]}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v2/foo/bar/manifests/sha256:manifest"))
+            .and(header("authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                r#"{{"layers": [{{"digest": "{}", "size": 5}}]}}"#,
+                layer_digest
+            )))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/foo/bar/blobs/{}", layer_digest)))
+            .and(header("authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).insert_header(
+                "WWW-Authenticate",
+                format!(r#"Bearer realm="{}/token",service="test""#, server.uri()).as_str(),
+            ))
+            .mount(&server)
+            .await;
+
+        let mut http_header = HeaderMap::new();
+        http_header.insert(OCI_PLATFORM_HEADER, "linux/arm64".parse().unwrap());
+
+        let oci = OCI::new(None).unwrap();
+        let response = oci
+            .stat(StatRequest {
+                task_id: "test".to_string(),
+                url: format!("oci://{}/foo/bar:1.0/", registry),
+                http_header: Some(http_header),
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.entries.len(), 1);
+
+        let entry = &response.entries[0];
+        assert_eq!(
+            entry.url,
+            format!(
+                "oci://{}/foo/bar:1.0/v2/foo/bar/blobs/{}",
+                registry, layer_digest
+            )
+        );
+        assert_eq!(entry.content_length, 5);
+        assert!(is_blob_url(&entry.url));
+
+        let mut response = oci
+            .get(GetRequest {
+                task_id: "test".to_string(),
+                piece_id: "test".to_string(),
+                url: entry.url.clone(),
+                range: None,
+                http_header: Some(HeaderMap::new()),
+                timeout: Duration::from_secs(5),
+                client_cert: None,
+                object_storage: None,
+                hdfs: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.text().await.unwrap(), "hello");
+    }
+}
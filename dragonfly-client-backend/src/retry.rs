@@ -0,0 +1,407 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_config::dfdaemon::BackendRetry;
+use dragonfly_client_metric::collect_backend_request_retry_metrics;
+use http::Extensions;
+use opendal::raw::{HttpBody, HttpClient, HttpFetch};
+use opendal::Buffer;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+use reqwest_retry::{default_on_request_failure, Retryable};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// RetryPolicy is the retry policy of the backend request, which is shared by the http,
+/// object storage and hdfs backends.
+#[derive(Debug, Clone, Default)]
+pub struct RetryPolicy {
+    /// config is the retry configuration of the backend request.
+    config: BackendRetry,
+}
+
+/// RetryPolicy implements the retry policy.
+impl RetryPolicy {
+    /// new returns a new RetryPolicy.
+    pub fn new(config: BackendRetry) -> Self {
+        Self { config }
+    }
+
+    /// max_attempts returns the max number of attempts, including the first attempt.
+    pub fn max_attempts(&self) -> u32 {
+        self.config.max_attempts.max(1)
+    }
+
+    /// is_retryable_status returns whether the response with the status code should be retried.
+    pub fn is_retryable_status(&self, status: StatusCode) -> bool {
+        self.config
+            .retryable_status_codes
+            .contains(&status.as_u16())
+    }
+
+    /// backoff returns the backoff before the retry, the retry starts from 1. The backoff
+    /// is doubled after each retry and limited by the max backoff. If the jitter is enabled,
+    /// the backoff is randomized in [backoff / 2, backoff].
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(31);
+        let backoff = self
+            .config
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.config.max_backoff);
+
+        if !self.config.jitter || backoff.is_zero() {
+            return backoff;
+        }
+
+        let half = backoff / 2;
+        half + Duration::from_nanos(fastrand::u64(0..=(backoff - half).as_nanos() as u64))
+    }
+
+    /// retry_after returns the duration specified by the Retry-After header, which is
+    /// either the delay seconds or the http date. The duration is limited by the max backoff.
+    pub fn retry_after(&self, header: &HeaderMap) -> Option<Duration> {
+        if !self.config.respect_retry_after {
+            return None;
+        }
+
+        let value = header.get(RETRY_AFTER)?.to_str().ok()?.trim();
+        let retry_after = match value.parse::<u64>() {
+            Ok(seconds) => Duration::from_secs(seconds),
+            Err(_) => httpdate::parse_http_date(value)
+                .ok()?
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        };
+
+        Some(retry_after.min(self.config.max_backoff))
+    }
+
+    /// opendal_http_client returns the http client of the opendal operator, which retries the
+    /// temporary errors and the responses with the retryable status codes by the retry policy.
+    pub fn opendal_http_client(&self, scheme: &str, client: reqwest::Client) -> HttpClient {
+        HttpClient::with(RetryHttpFetch {
+            scheme: scheme.to_string(),
+            policy: self.clone(),
+            client,
+        })
+    }
+}
+
+/// RetryHttpFetch is the http fetcher of the opendal based backends. The opendal converts
+/// the response to the error after fetching, so the retryable status codes are retried here
+/// instead of by the retry layer of the opendal.
+pub struct RetryHttpFetch {
+    /// scheme is the scheme of the backend.
+    scheme: String,
+
+    /// policy is the retry policy.
+    policy: RetryPolicy,
+
+    /// client is the reqwest client to send the request.
+    client: reqwest::Client,
+}
+
+/// RetryHttpFetch implements the HttpFetch of the opendal.
+impl HttpFetch for RetryHttpFetch {
+    async fn fetch(&self, req: http::Request<Buffer>) -> opendal::Result<http::Response<HttpBody>> {
+        let mut retry = 0;
+        loop {
+            // The body of the opendal request is buffered, so the request is cheap to clone.
+            let result = self.client.fetch(req.clone()).await;
+            retry += 1;
+            if retry >= self.policy.max_attempts() {
+                return result;
+            }
+
+            let backoff = match &result {
+                Ok(response) if self.policy.is_retryable_status(response.status()) => {
+                    warn!(
+                        "retry {} request {} by status {}",
+                        req.method(),
+                        req.uri(),
+                        response.status()
+                    );
+
+                    self.policy
+                        .retry_after(response.headers())
+                        .unwrap_or_else(|| self.policy.backoff(retry))
+                }
+                Ok(_) => return result,
+                Err(err) if err.is_temporary() => {
+                    warn!(
+                        "retry {} request {} by error {}",
+                        req.method(),
+                        req.uri(),
+                        err
+                    );
+                    self.policy.backoff(retry)
+                }
+                Err(_) => return result,
+            };
+
+            // Drop the response before sleeping to release the connection.
+            drop(result);
+            collect_backend_request_retry_metrics(self.scheme.as_str(), req.method().as_str());
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+/// RetryMiddleware is the middleware of the reqwest client to retry the http request by
+/// the retry policy. The transient errors and the responses with the retryable status
+/// codes are retried, and the Retry-After header of the response is respected.
+pub struct RetryMiddleware {
+    /// scheme is the scheme of the backend.
+    scheme: String,
+
+    /// policy is the retry policy.
+    policy: RetryPolicy,
+}
+
+/// RetryMiddleware implements the retry middleware.
+impl RetryMiddleware {
+    /// new returns a new RetryMiddleware.
+    pub fn new(scheme: &str, policy: RetryPolicy) -> Self {
+        Self {
+            scheme: scheme.to_string(),
+            policy,
+        }
+    }
+}
+
+/// RetryMiddleware implements the Middleware of the reqwest middleware.
+#[tonic::async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let mut retry = 0;
+        loop {
+            // The request with the streaming body can not be cloned, so send it without retry.
+            let Some(duplicate_request) = req.try_clone() else {
+                return next.run(req, extensions).await;
+            };
+
+            let result = next.clone().run(duplicate_request, extensions).await;
+            retry += 1;
+            if retry >= self.policy.max_attempts() {
+                return result;
+            }
+
+            let backoff = match &result {
+                Ok(response) if self.policy.is_retryable_status(response.status()) => {
+                    warn!(
+                        "retry {} request {} by status {}",
+                        req.method(),
+                        req.url(),
+                        response.status()
+                    );
+
+                    self.policy
+                        .retry_after(response.headers())
+                        .unwrap_or_else(|| self.policy.backoff(retry))
+                }
+                Ok(_) => return result,
+                Err(err) if default_on_request_failure(err) == Some(Retryable::Transient) => {
+                    warn!(
+                        "retry {} request {} by error {}",
+                        req.method(),
+                        req.url(),
+                        err
+                    );
+                    self.policy.backoff(retry)
+                }
+                Err(_) => return result,
+            };
+
+            // Drop the response before sleeping to release the connection.
+            drop(result);
+            collect_backend_request_retry_metrics(self.scheme.as_str(), req.method().as_str());
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest_middleware::ClientBuilder;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn policy(max_attempts: u32, jitter: bool) -> RetryPolicy {
+        RetryPolicy::new(BackendRetry {
+            max_attempts,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            jitter,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn should_calculate_backoff() {
+        let policy = policy(5, false);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(100), Duration::from_secs(1));
+
+        let policy = self::policy(5, true);
+        for retry in 1..10 {
+            let backoff = policy.backoff(retry);
+            let max = Duration::from_millis(100 << (retry - 1)).min(Duration::from_secs(1));
+            assert!(backoff >= max / 2 && backoff <= max);
+        }
+    }
+
+    #[test]
+    fn should_parse_retry_after() {
+        let policy = policy(2, false);
+        let mut header = HeaderMap::new();
+        assert_eq!(policy.retry_after(&header), None);
+
+        header.insert(RETRY_AFTER, "0".parse().unwrap());
+        assert_eq!(policy.retry_after(&header), Some(Duration::ZERO));
+
+        // The duration is limited by the max backoff.
+        header.insert(RETRY_AFTER, "120".parse().unwrap());
+        assert_eq!(policy.retry_after(&header), Some(Duration::from_secs(1)));
+
+        header.insert(
+            RETRY_AFTER,
+            httpdate::fmt_http_date(SystemTime::now() - Duration::from_secs(10))
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(policy.retry_after(&header), Some(Duration::ZERO));
+
+        header.insert(RETRY_AFTER, "invalid".parse().unwrap());
+        assert_eq!(policy.retry_after(&header), None);
+    }
+
+    #[test]
+    fn should_check_retryable_status() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(policy.is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!policy.is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!policy.is_retryable_status(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn should_retry_throttled_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/throttled"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/throttled"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryMiddleware::new("http", policy(3, true)))
+            .build();
+        let response = client
+            .get(format!("{}/throttled", server.uri()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn should_retry_opendal_request_by_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/bucket/object"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/bucket/object"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let fetcher = RetryHttpFetch {
+            scheme: "s3".to_string(),
+            policy: policy(3, false),
+            client: reqwest::Client::new(),
+        };
+        let request = http::Request::get(format!("{}/bucket/object", server.uri()))
+            .body(Buffer::new())
+            .unwrap();
+        let response = fetcher.fetch(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_not_retry_when_attempts_exhausted() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/unavailable"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/notfound"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryMiddleware::new("http", policy(2, false)))
+            .build();
+        let response = client
+            .get(format!("{}/unavailable", server.uri()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = client
+            .get(format!("{}/notfound", server.uri()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -238,6 +238,10 @@ pub enum DFError {
     #[error("max number of files to download exceeded: {0}")]
     MaxDownloadFilesExceeded(usize),
 
+    /// DownloadFilesFailed is the error for the failed files of the batch download.
+    #[error("{0} of {1} files failed to download")]
+    DownloadFilesFailed(usize, usize),
+
     /// Unsupported is the error for unsupported.
     #[error("unsupported {0}")]
     Unsupported(String),
@@ -156,6 +156,13 @@ lazy_static! {
             &["scheme", "method"]
         ).expect("metric can be created");
 
+    /// BACKEND_REQUEST_RETRY_COUNT is used to count the retried number of backend request.
+    pub static ref BACKEND_REQUEST_RETRY_COUNT: IntCounterVec =
+        IntCounterVec::new(
+            Opts::new("backend_request_retry_total", "Counter of the number of retried of the backend request.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &["scheme", "method"]
+        ).expect("metric can be created");
+
     /// BACKEND_REQUEST_DURATION is used to record the backend request duration.
     pub static ref BACKEND_REQUEST_DURATION: HistogramVec =
         HistogramVec::new(
@@ -184,6 +191,27 @@ lazy_static! {
             &[]
         ).expect("metric can be created");
 
+    /// PROXY_REQUEST_REJECTED_COUNT is used to count the number of proxy request rejected by the access control.
+    pub static ref PROXY_REQUEST_REJECTED_COUNT: IntCounterVec =
+        IntCounterVec::new(
+            Opts::new("proxy_request_rejected_total", "Counter of the number of the proxy request rejected by the access control.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &["reason"]
+        ).expect("metric can be created");
+
+    /// PROXY_TLS_INTERCEPTION_COUNT is used to count the number of HTTPS request intercepted or tunneled by the proxy.
+    pub static ref PROXY_TLS_INTERCEPTION_COUNT: IntCounterVec =
+        IntCounterVec::new(
+            Opts::new("proxy_tls_interception_total", "Counter of the number of the HTTPS request intercepted or tunneled by the proxy.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &["decision"]
+        ).expect("metric can be created");
+
+    /// PROXY_TLS_CERT_COUNT is used to count the number of leaf certificate loaded by the proxy.
+    pub static ref PROXY_TLS_CERT_COUNT: IntCounterVec =
+        IntCounterVec::new(
+            Opts::new("proxy_tls_cert_total", "Counter of the number of the leaf certificate loaded by the proxy.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &["source"]
+        ).expect("metric can be created");
+
     /// UPDATE_TASK_COUNT is used to count the number of update tasks.
     pub static ref UPDATE_TASK_COUNT: IntCounterVec =
         IntCounterVec::new(
@@ -281,6 +309,27 @@ lazy_static! {
             Opts::new("disk_usage_space_total", "Gauge of the disk usage space in bytes").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
             &[]
         ).expect("metric can be created");
+
+    /// STORAGE_SCRUB_COUNT is used to count the number of storage scrub.
+    pub static ref STORAGE_SCRUB_COUNT: IntCounterVec =
+        IntCounterVec::new(
+            Opts::new("storage_scrub_total", "Counter of the number of the storage scrub.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &[]
+        ).expect("metric can be created");
+
+    /// STORAGE_SCRUB_FINDING_COUNT is used to count the findings of storage scrub.
+    pub static ref STORAGE_SCRUB_FINDING_COUNT: IntCounterVec =
+        IntCounterVec::new(
+            Opts::new("storage_scrub_finding_total", "Counter of the number of the findings of the storage scrub.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME),
+            &["type"]
+        ).expect("metric can be created");
+
+    /// STORAGE_SCRUB_DURATION is used to record the storage scrub duration.
+    pub static ref STORAGE_SCRUB_DURATION: HistogramVec =
+        HistogramVec::new(
+            HistogramOpts::new("storage_scrub_duration_milliseconds", "Histogram of the storage scrub duration.").namespace(dragonfly_client_config::SERVICE_NAME).subsystem(dragonfly_client_config::NAME).buckets(exponential_buckets(1.0, 2.0, 32).unwrap()),
+            &[]
+        ).expect("metric can be created");
 }
 
 /// register_custom_metrics registers all custom metrics.
@@ -333,6 +382,10 @@ fn register_custom_metrics() {
         .register(Box::new(BACKEND_REQUEST_FAILURE_COUNT.clone()))
         .expect("metric can be registered");
 
+    REGISTRY
+        .register(Box::new(BACKEND_REQUEST_RETRY_COUNT.clone()))
+        .expect("metric can be registered");
+
     REGISTRY
         .register(Box::new(BACKEND_REQUEST_DURATION.clone()))
         .expect("metric can be registered");
@@ -349,6 +402,18 @@ fn register_custom_metrics() {
         .register(Box::new(PROXY_REQUEST_VIA_DFDAEMON_COUNT.clone()))
         .expect("metric can be registered");
 
+    REGISTRY
+        .register(Box::new(PROXY_REQUEST_REJECTED_COUNT.clone()))
+        .expect("metric can be registered");
+
+    REGISTRY
+        .register(Box::new(PROXY_TLS_INTERCEPTION_COUNT.clone()))
+        .expect("metric can be registered");
+
+    REGISTRY
+        .register(Box::new(PROXY_TLS_CERT_COUNT.clone()))
+        .expect("metric can be registered");
+
     REGISTRY
         .register(Box::new(UPDATE_TASK_COUNT.clone()))
         .expect("metric can be registered");
@@ -396,6 +461,18 @@ fn register_custom_metrics() {
     REGISTRY
         .register(Box::new(DISK_USAGE_SPACE.clone()))
         .expect("metric can be registered");
+
+    REGISTRY
+        .register(Box::new(STORAGE_SCRUB_COUNT.clone()))
+        .expect("metric can be registered");
+
+    REGISTRY
+        .register(Box::new(STORAGE_SCRUB_FINDING_COUNT.clone()))
+        .expect("metric can be registered");
+
+    REGISTRY
+        .register(Box::new(STORAGE_SCRUB_DURATION.clone()))
+        .expect("metric can be registered");
 }
 
 /// reset_custom_metrics resets all custom metrics.
@@ -412,10 +489,14 @@ fn reset_custom_metrics() {
     DOWNLOAD_TASK_DURATION.reset();
     BACKEND_REQUEST_COUNT.reset();
     BACKEND_REQUEST_FAILURE_COUNT.reset();
+    BACKEND_REQUEST_RETRY_COUNT.reset();
     BACKEND_REQUEST_DURATION.reset();
     PROXY_REQUEST_COUNT.reset();
     PROXY_REQUEST_FAILURE_COUNT.reset();
     PROXY_REQUEST_VIA_DFDAEMON_COUNT.reset();
+    PROXY_REQUEST_REJECTED_COUNT.reset();
+    PROXY_TLS_INTERCEPTION_COUNT.reset();
+    PROXY_TLS_CERT_COUNT.reset();
     UPDATE_TASK_COUNT.reset();
     UPDATE_TASK_FAILURE_COUNT.reset();
     STAT_TASK_COUNT.reset();
@@ -428,6 +509,9 @@ fn reset_custom_metrics() {
     DELETE_HOST_FAILURE_COUNT.reset();
     DISK_SPACE.reset();
     DISK_USAGE_SPACE.reset();
+    STORAGE_SCRUB_COUNT.reset();
+    STORAGE_SCRUB_FINDING_COUNT.reset();
+    STORAGE_SCRUB_DURATION.reset();
 }
 
 /// TaskSize represents the size of the task.
@@ -729,6 +813,13 @@ pub fn collect_backend_request_failure_metrics(scheme: &str, method: &str) {
         .inc();
 }
 
+/// collect_backend_request_retry_metrics collects the backend request retry metrics.
+pub fn collect_backend_request_retry_metrics(scheme: &str, method: &str) {
+    BACKEND_REQUEST_RETRY_COUNT
+        .with_label_values(&[scheme, method])
+        .inc();
+}
+
 /// collect_backend_request_finished_metrics collects the backend request finished metrics.
 pub fn collect_backend_request_finished_metrics(scheme: &str, method: &str, cost: Duration) {
     BACKEND_REQUEST_DURATION
@@ -753,6 +844,28 @@ pub fn collect_proxy_request_via_dfdaemon_metrics() {
         .inc();
 }
 
+/// collect_proxy_request_rejected_metrics collects the proxy request rejected metrics, the
+/// reason is one of denied, unauthorized and rate_limited.
+pub fn collect_proxy_request_rejected_metrics(reason: &str) {
+    PROXY_REQUEST_REJECTED_COUNT
+        .with_label_values(&[reason])
+        .inc();
+}
+
+/// collect_proxy_tls_interception_metrics collects the proxy tls interception metrics, the
+/// decision is one of intercepted and tunneled.
+pub fn collect_proxy_tls_interception_metrics(decision: &str) {
+    PROXY_TLS_INTERCEPTION_COUNT
+        .with_label_values(&[decision])
+        .inc();
+}
+
+/// collect_proxy_tls_cert_metrics collects the proxy tls cert metrics, the source is one of
+/// memory, disk and generated.
+pub fn collect_proxy_tls_cert_metrics(source: &str) {
+    PROXY_TLS_CERT_COUNT.with_label_values(&[source]).inc();
+}
+
 /// collect_update_task_started_metrics collects the update task started metrics.
 pub fn collect_update_task_started_metrics(typ: i32) {
     UPDATE_TASK_COUNT
@@ -853,6 +966,22 @@ pub fn collect_disk_metrics(path: &Path) {
         .set(usage_space as i64);
 }
 
+/// collect_storage_scrub_finished_metrics collects the storage scrub finished metrics.
+pub fn collect_storage_scrub_finished_metrics(cost: Duration) {
+    STORAGE_SCRUB_COUNT.with_label_values(&[]).inc();
+    STORAGE_SCRUB_DURATION
+        .with_label_values(&[])
+        .observe(cost.as_millis() as f64);
+}
+
+/// collect_storage_scrub_finding_metrics collects the findings of the storage scrub, the typ
+/// is the type of the finding, e.g. missing_content, corrupted_piece and orphan_file.
+pub fn collect_storage_scrub_finding_metrics(typ: &str, count: u64) {
+    STORAGE_SCRUB_FINDING_COUNT
+        .with_label_values(&[typ])
+        .inc_by(count);
+}
+
 /// Metrics is the metrics server.
 #[derive(Debug)]
 pub struct Metrics {
@@ -222,6 +222,12 @@ impl Cache {
         }
 
         let mut tasks = self.tasks.write().await;
+
+        // If the task already exists in the cache, keep the downloaded pieces.
+        if tasks.contains(task_id) {
+            return;
+        }
+
         while self.size.load(Ordering::Relaxed) + content_length > self.capacity {
             match tasks.pop_lru() {
                 Some((_, task)) => {
@@ -392,6 +398,32 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_put_task_twice() {
+        let config = Config {
+            storage: Storage {
+                cache_capacity: ByteSize::mib(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut cache = Cache::new(Arc::new(config));
+
+        cache.put_task("task", ByteSize::mib(1).as_u64()).await;
+        cache
+            .write_piece("task", "piece", Bytes::from("hello"))
+            .await
+            .unwrap();
+
+        // Putting the same task again should keep the pieces and not increase the size.
+        cache.put_task("task", ByteSize::mib(1).as_u64()).await;
+        assert!(cache.contains_piece("task", "piece").await);
+        assert_eq!(
+            cache.size.load(Ordering::Relaxed),
+            ByteSize::mib(1).as_u64()
+        );
+    }
+
     #[tokio::test]
     async fn test_put_task_lru() {
         let config = Config {
@@ -18,7 +18,7 @@ use dragonfly_api::common::v2::Range;
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::Result;
 use std::cmp::{max, min};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 #[cfg(target_os = "linux")]
@@ -71,6 +71,13 @@ pub async fn new_content(config: Arc<Config>, dir: &Path) -> Result<Content> {
     Content::new(config, dir).await
 }
 
+/// relative_task_path returns the path of the task relative to the content directory.
+pub fn relative_task_path(task_dir: &str, task_id: &str) -> PathBuf {
+    // The task needs split by the first 3 characters of task id(sha256) to
+    // avoid too many files in one directory.
+    Path::new(task_dir).join(&task_id[..3]).join(task_id)
+}
+
 /// calculate_piece_range calculates the target offset and length based on the piece range and
 /// request range.
 pub fn calculate_piece_range(offset: u64, length: u64, range: Option<Range>) -> (u64, u64) {
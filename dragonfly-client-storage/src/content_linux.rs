@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use bytesize::ByteSize;
+use super::disk::{DiskUsage, Disks};
 use dragonfly_api::common::v2::Range;
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::{Error, Result};
@@ -28,89 +28,48 @@ use tokio::io::{
 };
 use tokio_util::io::InspectReader;
 use tracing::{debug, error, info, instrument, warn};
-use walkdir::WalkDir;
 
 /// Content is the content of a piece.
 pub struct Content {
     /// config is the configuration of the dfdaemon.
     pub config: Arc<Config>,
 
-    /// dir is the directory to store content.
-    pub dir: PathBuf,
+    /// disks are the directories to store content on the disks.
+    pub disks: Disks,
 }
 
 /// Content implements the content storage.
 impl Content {
     /// new returns a new content.
     pub async fn new(config: Arc<Config>, dir: &Path) -> Result<Content> {
-        let dir = dir.join(super::content::DEFAULT_CONTENT_DIR);
-
-        // If the storage is not kept, remove the directory.
-        if !config.storage.keep {
-            fs::remove_dir_all(&dir).await.unwrap_or_else(|err| {
-                warn!("remove {:?} failed: {}", dir, err);
-            });
-        }
-
-        fs::create_dir_all(&dir.join(super::content::DEFAULT_TASK_DIR)).await?;
-        fs::create_dir_all(&dir.join(super::content::DEFAULT_PERSISTENT_TASK_DIR)).await?;
-        fs::create_dir_all(&dir.join(super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR)).await?;
-        info!("content initialized directory: {:?}", dir);
-        Ok(Content { config, dir })
+        let disks = Disks::new(config.clone(), dir).await?;
+        Ok(Content { config, disks })
     }
 
-    /// available_space returns the available space of the disk.
-    pub fn available_space(&self) -> Result<u64> {
-        let dist_threshold = self.config.gc.policy.dist_threshold;
-        if dist_threshold != ByteSize::default() {
-            let usage_space = WalkDir::new(&self.dir)
-                .into_iter()
-                .filter_map(|entry| entry.ok())
-                .filter_map(|entry| entry.metadata().ok())
-                .filter(|metadata| metadata.is_file())
-                .fold(0, |acc, m| acc + m.len());
-
-            if usage_space >= dist_threshold.as_u64() {
-                warn!(
-                    "usage space {} is greater than dist threshold {}, no need to calculate available space",
-                    usage_space, dist_threshold
-                );
-
-                return Ok(0);
-            }
-
-            return Ok(dist_threshold.as_u64() - usage_space);
-        }
-
-        let stat = fs2::statvfs(&self.dir)?;
-        Ok(stat.available_space())
+    /// available_space returns the available space of all disks.
+    pub async fn available_space(&self) -> Result<u64> {
+        self.disks.available_space().await
     }
 
-    /// total_space returns the total space of the disk.
-    pub fn total_space(&self) -> Result<u64> {
-        // If the dist_threshold is set, return it directly.
-        let dist_threshold = self.config.gc.policy.dist_threshold;
-        if dist_threshold != ByteSize::default() {
-            return Ok(dist_threshold.as_u64());
-        }
-
-        let stat = fs2::statvfs(&self.dir)?;
-        Ok(stat.total_space())
+    /// total_space returns the total space of all disks.
+    pub async fn total_space(&self) -> Result<u64> {
+        self.disks.total_space().await
     }
 
     /// has_enough_space checks if the storage has enough space to store the content.
-    pub fn has_enough_space(&self, content_length: u64) -> Result<bool> {
-        let available_space = self.available_space()?;
-        if available_space < content_length {
-            warn!(
-                "not enough space to store the task: available_space={}, content_length={}",
-                available_space, content_length
-            );
-
-            return Ok(false);
-        }
+    pub async fn has_enough_space(&self, content_length: u64) -> Result<bool> {
+        self.disks.has_enough_space(content_length).await
+    }
 
-        Ok(true)
+    /// disk_usages returns the space usages of the healthy disks.
+    pub async fn disk_usages(&self) -> Vec<DiskUsage> {
+        self.disks.usages().await
+    }
+
+    /// check_disks checks the disks, takes the failing disks out of the rotation and brings
+    /// the recovered disks back.
+    pub async fn check_disks(&self) {
+        self.disks.check().await
     }
 
     /// is_same_dev_inode checks if the source and target are the same device and inode.
@@ -128,7 +87,7 @@ impl Content {
 
     /// is_same_dev_inode_as_task checks if the task and target are the same device and inode.
     pub async fn is_same_dev_inode_as_task(&self, task_id: &str, to: &Path) -> Result<bool> {
-        let task_path = self.get_task_path(task_id);
+        let task_path = self.get_task_path(task_id).await;
         self.is_same_dev_inode(&task_path, to).await
     }
 
@@ -139,23 +98,30 @@ impl Content {
     /// 2. If the task does not exist, create the task directory and file.
     #[instrument(skip_all)]
     pub async fn create_task(&self, task_id: &str, length: u64) -> Result<PathBuf> {
-        let task_path = self.get_task_path(task_id);
+        let task_path = self.get_task_path(task_id).await;
         if task_path.exists() {
             return Ok(task_path);
         }
 
         let task_dir = self
-            .dir
+            .disks
+            .place(
+                &super::content::relative_task_path(super::content::DEFAULT_TASK_DIR, task_id),
+                length,
+            )
+            .await?
             .join(super::content::DEFAULT_TASK_DIR)
             .join(&task_id[..3]);
         fs::create_dir_all(&task_dir).await.inspect_err(|err| {
             error!("create {:?} failed: {}", task_dir, err);
+            self.disks.report_error(&task_dir, err);
         })?;
 
         let f = fs::File::create(task_dir.join(task_id))
             .await
             .inspect_err(|err| {
                 error!("create {:?} failed: {}", task_dir, err);
+                self.disks.report_error(&task_dir, err);
             })?;
 
         fallocate(&f, length).await.inspect_err(|err| {
@@ -176,7 +142,7 @@ impl Content {
     ///    2.2. If the hard link fails, copy the task content to the destination once the task is finished, then return immediately.
     #[instrument(skip_all)]
     pub async fn hard_link_task(&self, task_id: &str, to: &Path) -> Result<()> {
-        let task_path = self.get_task_path(task_id);
+        let task_path = self.get_task_path(task_id).await;
         if let Err(err) = fs::hard_link(task_path.clone(), to).await {
             if err.kind() == std::io::ErrorKind::AlreadyExists {
                 if let Ok(true) = self.is_same_dev_inode(&task_path, to).await {
@@ -196,7 +162,7 @@ impl Content {
     /// copy_task copies the task content to the destination.
     #[instrument(skip_all)]
     pub async fn copy_task(&self, task_id: &str, to: &Path) -> Result<()> {
-        fs::copy(self.get_task_path(task_id), to).await?;
+        fs::copy(self.get_task_path(task_id).await, to).await?;
         info!("copy to {:?} success", to);
         Ok(())
     }
@@ -213,7 +179,7 @@ impl Content {
             }
         }
 
-        let mut from_f = File::open(self.get_task_path(task_id)).await?;
+        let mut from_f = File::open(self.get_task_path(task_id).await).await?;
         from_f.seek(SeekFrom::Start(range.start)).await?;
         let range_reader = from_f.take(range.length);
 
@@ -235,7 +201,11 @@ impl Content {
     /// delete_task deletes the task content.
     pub async fn delete_task(&self, task_id: &str) -> Result<()> {
         info!("delete task content: {}", task_id);
-        let task_path = self.get_task_path(task_id);
+        let task_path = self.get_task_path(task_id).await;
+        self.disks.forget(&super::content::relative_task_path(
+            super::content::DEFAULT_TASK_DIR,
+            task_id,
+        ));
         fs::remove_file(task_path.as_path())
             .await
             .inspect_err(|err| {
@@ -253,7 +223,7 @@ impl Content {
         length: u64,
         range: Option<Range>,
     ) -> Result<impl AsyncRead> {
-        let task_path = self.get_task_path(task_id);
+        let task_path = self.get_task_path(task_id).await;
 
         // Calculate the target offset and length based on the range.
         let (target_offset, target_length) =
@@ -284,7 +254,7 @@ impl Content {
         length: u64,
         range: Option<Range>,
     ) -> Result<(impl AsyncRead, impl AsyncRead)> {
-        let task_path = self.get_task_path(task_id);
+        let task_path = self.get_task_path(task_id).await;
 
         // Calculate the target offset and length based on the range.
         let (target_offset, target_length) =
@@ -330,7 +300,7 @@ impl Content {
         reader: &mut R,
     ) -> Result<super::content::WritePieceResponse> {
         // Open the file and seek to the offset.
-        let task_path = self.get_task_path(task_id);
+        let task_path = self.get_task_path(task_id).await;
         let mut f = OpenOptions::new()
             .truncate(false)
             .write(true)
@@ -357,10 +327,12 @@ impl Content {
         debug!("start to write piece to {:?}", task_path);
         let length = io::copy(&mut tee, &mut writer).await.inspect_err(|err| {
             error!("copy {:?} failed: {}", task_path, err);
+            self.disks.report_error(&task_path, err);
         })?;
 
         writer.flush().await.inspect_err(|err| {
             error!("flush {:?} failed: {}", task_path, err);
+            self.disks.report_error(&task_path, err);
         })?;
         debug!("finish to write piece to {:?}", task_path);
 
@@ -379,14 +351,24 @@ impl Content {
     }
 
     /// get_task_path returns the task path by task id.
-    fn get_task_path(&self, task_id: &str) -> PathBuf {
-        // The task needs split by the first 3 characters of task id(sha256) to
-        // avoid too many files in one directory.
-        let sub_dir = &task_id[..3];
-        self.dir
-            .join(super::content::DEFAULT_TASK_DIR)
-            .join(sub_dir)
-            .join(task_id)
+    async fn get_task_path(&self, task_id: &str) -> PathBuf {
+        self.disks
+            .path(&super::content::relative_task_path(
+                super::content::DEFAULT_TASK_DIR,
+                task_id,
+            ))
+            .await
+    }
+
+    /// get_task_disk_dir returns the content directory of the disk storing the task.
+    pub async fn get_task_disk_dir(&self, task_id: &str) -> Option<PathBuf> {
+        self.disks
+            .dir(&super::content::relative_task_path(
+                super::content::DEFAULT_TASK_DIR,
+                task_id,
+            ))
+            .await
+            .map(Path::to_path_buf)
     }
 
     /// is_same_dev_inode_as_persistent_task checks if the persistent task and target
@@ -396,7 +378,7 @@ impl Content {
         task_id: &str,
         to: &Path,
     ) -> Result<bool> {
-        let task_path = self.get_persistent_task_path(task_id);
+        let task_path = self.get_persistent_task_path(task_id).await;
         self.is_same_dev_inode(&task_path, to).await
     }
 
@@ -407,23 +389,33 @@ impl Content {
     /// 2. If the persistent task does not exist, create the persistent task directory and file.
     #[instrument(skip_all)]
     pub async fn create_persistent_task(&self, task_id: &str, length: u64) -> Result<PathBuf> {
-        let task_path = self.get_persistent_task_path(task_id);
+        let task_path = self.get_persistent_task_path(task_id).await;
         if task_path.exists() {
             return Ok(task_path);
         }
 
         let task_dir = self
-            .dir
+            .disks
+            .place(
+                &super::content::relative_task_path(
+                    super::content::DEFAULT_PERSISTENT_TASK_DIR,
+                    task_id,
+                ),
+                length,
+            )
+            .await?
             .join(super::content::DEFAULT_PERSISTENT_TASK_DIR)
             .join(&task_id[..3]);
         fs::create_dir_all(&task_dir).await.inspect_err(|err| {
             error!("create {:?} failed: {}", task_dir, err);
+            self.disks.report_error(&task_dir, err);
         })?;
 
         let f = fs::File::create(task_dir.join(task_id))
             .await
             .inspect_err(|err| {
                 error!("create {:?} failed: {}", task_dir, err);
+                self.disks.report_error(&task_dir, err);
             })?;
 
         fallocate(&f, length).await.inspect_err(|err| {
@@ -436,17 +428,26 @@ impl Content {
     /// create_persistent_task_dir only creates the directory for the persistent task.
     #[instrument(skip_all)]
     pub async fn create_persistent_task_dir(&self, task_id: &str) -> Result<PathBuf> {
-        let task_path = self.get_persistent_task_path(task_id);
+        let task_path = self.get_persistent_task_path(task_id).await;
         if task_path.exists() {
             return Ok(task_path);
         }
 
         let task_dir = self
-            .dir
+            .disks
+            .place(
+                &super::content::relative_task_path(
+                    super::content::DEFAULT_PERSISTENT_TASK_DIR,
+                    task_id,
+                ),
+                0,
+            )
+            .await?
             .join(super::content::DEFAULT_PERSISTENT_TASK_DIR)
             .join(&task_id[..3]);
         fs::create_dir_all(&task_dir).await.inspect_err(|err| {
             error!("create {:?} failed: {}", task_dir, err);
+            self.disks.report_error(&task_dir, err);
         })?;
 
         Ok(task_dir)
@@ -463,7 +464,7 @@ impl Content {
     ///    2.2. If the hard link fails, copy the persistent task content to the destination once the task is finished, then return immediately.
     #[instrument(skip_all)]
     pub async fn hard_link_persistent_task(&self, task_id: &str, to: &Path) -> Result<()> {
-        let task_path = self.get_persistent_task_path(task_id);
+        let task_path = self.get_persistent_task_path(task_id).await;
         if let Err(err) = fs::hard_link(task_path.clone(), to).await {
             if err.kind() == std::io::ErrorKind::AlreadyExists {
                 if let Ok(true) = self.is_same_dev_inode(&task_path, to).await {
@@ -491,7 +492,7 @@ impl Content {
     ///    2.2. If hard link fails, return an error.
     #[instrument(skip_all)]
     pub async fn hard_link_to_persistent_task(&self, from: &Path, task_id: &str) -> Result<()> {
-        let task_path = self.get_persistent_task_path(task_id);
+        let task_path = self.get_persistent_task_path(task_id).await;
         if let Err(err) = fs::hard_link(from, &task_path).await {
             if err.kind() == std::io::ErrorKind::AlreadyExists {
                 if let Ok(true) = self.is_same_dev_inode(from, &task_path).await {
@@ -511,7 +512,7 @@ impl Content {
     /// copy_persistent_task copies the persistent task content to the destination.
     #[instrument(skip_all)]
     pub async fn copy_persistent_task(&self, task_id: &str, to: &Path) -> Result<()> {
-        fs::copy(self.get_persistent_task_path(task_id), to).await?;
+        fs::copy(self.get_persistent_task_path(task_id).await, to).await?;
         info!("copy to {:?} success", to);
         Ok(())
     }
@@ -525,7 +526,7 @@ impl Content {
         length: u64,
         range: Option<Range>,
     ) -> Result<impl AsyncRead> {
-        let task_path = self.get_persistent_task_path(task_id);
+        let task_path = self.get_persistent_task_path(task_id).await;
 
         // Calculate the target offset and length based on the range.
         let (target_offset, target_length) =
@@ -557,7 +558,7 @@ impl Content {
         reader: &mut R,
     ) -> Result<super::content::WritePieceResponse> {
         // Open the file and seek to the offset.
-        let task_path = self.get_persistent_task_path(task_id);
+        let task_path = self.get_persistent_task_path(task_id).await;
         let mut f = OpenOptions::new()
             .truncate(false)
             .write(true)
@@ -584,10 +585,12 @@ impl Content {
         debug!("start to write piece to {:?}", task_path);
         let length = io::copy(&mut tee, &mut writer).await.inspect_err(|err| {
             error!("copy {:?} failed: {}", task_path, err);
+            self.disks.report_error(&task_path, err);
         })?;
 
         writer.flush().await.inspect_err(|err| {
             error!("flush {:?} failed: {}", task_path, err);
+            self.disks.report_error(&task_path, err);
         })?;
         debug!("finish to write piece to {:?}", task_path);
 
@@ -608,7 +611,11 @@ impl Content {
     /// delete_task deletes the persistent task content.
     pub async fn delete_persistent_task(&self, task_id: &str) -> Result<()> {
         info!("delete persistent task content: {}", task_id);
-        let persistent_task_path = self.get_persistent_task_path(task_id);
+        let persistent_task_path = self.get_persistent_task_path(task_id).await;
+        self.disks.forget(&super::content::relative_task_path(
+            super::content::DEFAULT_PERSISTENT_TASK_DIR,
+            task_id,
+        ));
         fs::remove_file(persistent_task_path.as_path())
             .await
             .inspect_err(|err| {
@@ -618,13 +625,25 @@ impl Content {
     }
 
     /// get_persistent_task_path returns the persistent task path by task id.
-    fn get_persistent_task_path(&self, task_id: &str) -> PathBuf {
-        // The persistent task needs split by the first 3 characters of task id(sha256) to
-        // avoid too many files in one directory.
-        self.dir
-            .join(super::content::DEFAULT_PERSISTENT_TASK_DIR)
-            .join(&task_id[..3])
-            .join(task_id)
+    async fn get_persistent_task_path(&self, task_id: &str) -> PathBuf {
+        self.disks
+            .path(&super::content::relative_task_path(
+                super::content::DEFAULT_PERSISTENT_TASK_DIR,
+                task_id,
+            ))
+            .await
+    }
+
+    /// get_persistent_cache_task_disk_dir returns the content directory of the disk storing the
+    /// persistent cache task.
+    pub async fn get_persistent_cache_task_disk_dir(&self, task_id: &str) -> Option<PathBuf> {
+        self.disks
+            .dir(&super::content::relative_task_path(
+                super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR,
+                task_id,
+            ))
+            .await
+            .map(Path::to_path_buf)
     }
 
     /// is_same_dev_inode_as_persistent_cache_task checks if the persistent cache task and target
@@ -634,7 +653,7 @@ impl Content {
         task_id: &str,
         to: &Path,
     ) -> Result<bool> {
-        let task_path = self.get_persistent_cache_task_path(task_id);
+        let task_path = self.get_persistent_cache_task_path(task_id).await;
         self.is_same_dev_inode(&task_path, to).await
     }
 
@@ -649,23 +668,33 @@ impl Content {
         task_id: &str,
         length: u64,
     ) -> Result<PathBuf> {
-        let task_path = self.get_persistent_cache_task_path(task_id);
+        let task_path = self.get_persistent_cache_task_path(task_id).await;
         if task_path.exists() {
             return Ok(task_path);
         }
 
         let task_dir = self
-            .dir
+            .disks
+            .place(
+                &super::content::relative_task_path(
+                    super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR,
+                    task_id,
+                ),
+                length,
+            )
+            .await?
             .join(super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR)
             .join(&task_id[..3]);
         fs::create_dir_all(&task_dir).await.inspect_err(|err| {
             error!("create {:?} failed: {}", task_dir, err);
+            self.disks.report_error(&task_dir, err);
         })?;
 
         let f = fs::File::create(task_dir.join(task_id))
             .await
             .inspect_err(|err| {
                 error!("create {:?} failed: {}", task_dir, err);
+                self.disks.report_error(&task_dir, err);
             })?;
 
         fallocate(&f, length).await.inspect_err(|err| {
@@ -678,17 +707,26 @@ impl Content {
     /// create_persistent_cache_task_dir only creates the directory for the persistent cache task.
     #[instrument(skip_all)]
     pub async fn create_persistent_cache_task_dir(&self, task_id: &str) -> Result<PathBuf> {
-        let task_path = self.get_persistent_cache_task_path(task_id);
+        let task_path = self.get_persistent_cache_task_path(task_id).await;
         if task_path.exists() {
             return Ok(task_path);
         }
 
         let task_dir = self
-            .dir
+            .disks
+            .place(
+                &super::content::relative_task_path(
+                    super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR,
+                    task_id,
+                ),
+                0,
+            )
+            .await?
             .join(super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR)
             .join(&task_id[..3]);
         fs::create_dir_all(&task_dir).await.inspect_err(|err| {
             error!("create {:?} failed: {}", task_dir, err);
+            self.disks.report_error(&task_dir, err);
         })?;
 
         Ok(task_dir)
@@ -705,7 +743,7 @@ impl Content {
     ///    2.2. If the hard link fails, copy the persistent cache task content to the destination once the task is finished, then return immediately.
     #[instrument(skip_all)]
     pub async fn hard_link_persistent_cache_task(&self, task_id: &str, to: &Path) -> Result<()> {
-        let task_path = self.get_persistent_cache_task_path(task_id);
+        let task_path = self.get_persistent_cache_task_path(task_id).await;
         if let Err(err) = fs::hard_link(task_path.clone(), to).await {
             if err.kind() == std::io::ErrorKind::AlreadyExists {
                 if let Ok(true) = self.is_same_dev_inode(&task_path, to).await {
@@ -737,7 +775,7 @@ impl Content {
         from: &Path,
         task_id: &str,
     ) -> Result<()> {
-        let task_path = self.get_persistent_cache_task_path(task_id);
+        let task_path = self.get_persistent_cache_task_path(task_id).await;
         if let Err(err) = fs::hard_link(from, &task_path).await {
             if err.kind() == std::io::ErrorKind::AlreadyExists {
                 if let Ok(true) = self.is_same_dev_inode(from, &task_path).await {
@@ -757,7 +795,7 @@ impl Content {
     /// copy_persistent_cache_task copies the persistent cache task content to the destination.
     #[instrument(skip_all)]
     pub async fn copy_persistent_cache_task(&self, task_id: &str, to: &Path) -> Result<()> {
-        fs::copy(self.get_persistent_cache_task_path(task_id), to).await?;
+        fs::copy(self.get_persistent_cache_task_path(task_id).await, to).await?;
         info!("copy to {:?} success", to);
         Ok(())
     }
@@ -771,7 +809,7 @@ impl Content {
         length: u64,
         range: Option<Range>,
     ) -> Result<impl AsyncRead> {
-        let task_path = self.get_persistent_cache_task_path(task_id);
+        let task_path = self.get_persistent_cache_task_path(task_id).await;
 
         // Calculate the target offset and length based on the range.
         let (target_offset, target_length) =
@@ -803,7 +841,7 @@ impl Content {
         reader: &mut R,
     ) -> Result<super::content::WritePieceResponse> {
         // Open the file and seek to the offset.
-        let task_path = self.get_persistent_cache_task_path(task_id);
+        let task_path = self.get_persistent_cache_task_path(task_id).await;
         let mut f = OpenOptions::new()
             .truncate(false)
             .write(true)
@@ -830,10 +868,12 @@ impl Content {
         debug!("start to write piece to {:?}", task_path);
         let length = io::copy(&mut tee, &mut writer).await.inspect_err(|err| {
             error!("copy {:?} failed: {}", task_path, err);
+            self.disks.report_error(&task_path, err);
         })?;
 
         writer.flush().await.inspect_err(|err| {
             error!("flush {:?} failed: {}", task_path, err);
+            self.disks.report_error(&task_path, err);
         })?;
         debug!("finish to write piece to {:?}", task_path);
 
@@ -854,7 +894,11 @@ impl Content {
     /// delete_task deletes the persistent cache task content.
     pub async fn delete_persistent_cache_task(&self, task_id: &str) -> Result<()> {
         info!("delete persistent cache task content: {}", task_id);
-        let persistent_cache_task_path = self.get_persistent_cache_task_path(task_id);
+        let persistent_cache_task_path = self.get_persistent_cache_task_path(task_id).await;
+        self.disks.forget(&super::content::relative_task_path(
+            super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR,
+            task_id,
+        ));
         fs::remove_file(persistent_cache_task_path.as_path())
             .await
             .inspect_err(|err| {
@@ -864,13 +908,13 @@ impl Content {
     }
 
     /// get_persistent_cache_task_path returns the persistent cache task path by task id.
-    fn get_persistent_cache_task_path(&self, task_id: &str) -> PathBuf {
-        // The persistent cache task needs split by the first 3 characters of task id(sha256) to
-        // avoid too many files in one directory.
-        self.dir
-            .join(super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR)
-            .join(&task_id[..3])
-            .join(task_id)
+    async fn get_persistent_cache_task_path(&self, task_id: &str) -> PathBuf {
+        self.disks
+            .path(&super::content::relative_task_path(
+                super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR,
+                task_id,
+            ))
+            .await
     }
 }
 
@@ -878,6 +922,7 @@ impl Content {
 mod tests {
     use super::*;
     use crate::content;
+    use bytesize::ByteSize;
     use std::io::Cursor;
     use tempfile::tempdir;
 
@@ -1289,10 +1334,10 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let content = Content::new(config, temp_dir.path()).await.unwrap();
 
-        let has_space = content.has_enough_space(1).unwrap();
+        let has_space = content.has_enough_space(1).await.unwrap();
         assert!(has_space);
 
-        let has_space = content.has_enough_space(u64::MAX).unwrap();
+        let has_space = content.has_enough_space(u64::MAX).await.unwrap();
         assert!(!has_space);
 
         let mut config = Config::default();
@@ -1311,10 +1356,14 @@ mod tests {
 
         let has_space = content
             .has_enough_space(ByteSize::mib(9).as_u64() + 1)
+            .await
             .unwrap();
         assert!(!has_space);
 
-        let has_space = content.has_enough_space(ByteSize::mib(9).as_u64()).unwrap();
+        let has_space = content
+            .has_enough_space(ByteSize::mib(9).as_u64())
+            .await
+            .unwrap();
         assert!(has_space);
     }
 }
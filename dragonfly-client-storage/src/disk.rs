@@ -0,0 +1,674 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use bytesize::ByteSize;
+use dashmap::DashMap;
+use dragonfly_client_config::dfdaemon::{Config, StoragePlacementPolicy};
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Error, Result,
+};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tracing::{error, info, warn};
+use walkdir::WalkDir;
+
+/// PROBE_FILE_NAME is the name of the file to probe whether the disk is writable.
+const PROBE_FILE_NAME: &str = ".probe";
+
+/// USAGE_SPACE_TTL is the ttl of the cached usage space of the disk. The usage space is
+/// calculated by walking the content directory, so it is cached to avoid walking the whole
+/// disk for every placement.
+const USAGE_SPACE_TTL: Duration = Duration::from_secs(10);
+
+/// DiskUsage is the space usage of the disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// dir is the content directory on the disk.
+    pub dir: PathBuf,
+
+    /// total_space is the total space of the disk.
+    pub total_space: u64,
+
+    /// available_space is the available space of the disk.
+    pub available_space: u64,
+}
+
+/// Disk is the content directory on a disk.
+struct Disk {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
+    /// dir is the content directory on the disk.
+    dir: PathBuf,
+
+    /// healthy indicates whether the disk is healthy, the unhealthy disk is taken out of
+    /// the rotation.
+    healthy: AtomicBool,
+
+    /// usage_space is the cached usage space of the disk and the time it is calculated.
+    usage_space: Mutex<Option<(Instant, u64)>>,
+}
+
+/// Disk implements the disk.
+impl Disk {
+    /// new returns a new disk, and initializes the content directories on the disk.
+    async fn new(config: Arc<Config>, dir: PathBuf) -> Result<Disk> {
+        // If the storage is not kept, remove the directory.
+        if !config.storage.keep {
+            fs::remove_dir_all(&dir).await.unwrap_or_else(|err| {
+                warn!("remove {:?} failed: {}", dir, err);
+            });
+        }
+
+        fs::create_dir_all(&dir.join(super::content::DEFAULT_TASK_DIR)).await?;
+        fs::create_dir_all(&dir.join(super::content::DEFAULT_PERSISTENT_TASK_DIR)).await?;
+        fs::create_dir_all(&dir.join(super::content::DEFAULT_PERSISTENT_CACHE_TASK_DIR)).await?;
+        info!("content initialized directory: {:?}", dir);
+
+        Ok(Disk {
+            config,
+            dir,
+            healthy: AtomicBool::new(true),
+            usage_space: Mutex::new(None),
+        })
+    }
+
+    /// is_healthy returns whether the disk is healthy.
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// available_space returns the available space of the disk.
+    fn available_space(&self) -> Result<u64> {
+        let dist_threshold = self.config.gc.policy.dist_threshold;
+        if dist_threshold != ByteSize::default() {
+            let usage_space = self.usage_space();
+            if usage_space >= dist_threshold.as_u64() {
+                warn!(
+                    "usage space {} of {:?} is greater than dist threshold {}, no need to calculate available space",
+                    usage_space, self.dir, dist_threshold
+                );
+
+                return Ok(0);
+            }
+
+            return Ok(dist_threshold.as_u64() - usage_space);
+        }
+
+        let stat = fs2::statvfs(&self.dir)?;
+        Ok(stat.available_space())
+    }
+
+    /// usage_space returns the usage space of the content directory. The usage space is
+    /// cached for USAGE_SPACE_TTL, and recalculated by walking the directory after expired.
+    /// The lock is not held while walking the directory, so the placements are not blocked.
+    fn usage_space(&self) -> u64 {
+        if let Some((calculated_at, usage_space)) = *self.usage_space.lock().unwrap() {
+            if calculated_at.elapsed() < USAGE_SPACE_TTL {
+                return usage_space;
+            }
+        }
+
+        let usage_space = WalkDir::new(&self.dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .fold(0, |acc, m| acc + m.len());
+
+        *self.usage_space.lock().unwrap() = Some((Instant::now(), usage_space));
+        usage_space
+    }
+
+    /// reserve_space adds the content length of the placed content to the cached usage
+    /// space, so the placements before the next calculation see the reserved space.
+    fn reserve_space(&self, content_length: u64) {
+        if let Some((_, usage_space)) = self.usage_space.lock().unwrap().as_mut() {
+            *usage_space += content_length;
+        }
+    }
+
+    /// total_space returns the total space of the disk.
+    fn total_space(&self) -> Result<u64> {
+        // If the dist_threshold is set, return it directly.
+        let dist_threshold = self.config.gc.policy.dist_threshold;
+        if dist_threshold != ByteSize::default() {
+            return Ok(dist_threshold.as_u64());
+        }
+
+        let stat = fs2::statvfs(&self.dir)?;
+        Ok(stat.total_space())
+    }
+
+    /// probe checks whether the disk is writable by writing and removing a probe file.
+    fn probe(&self) -> std::io::Result<()> {
+        let probe_path = self.dir.join(PROBE_FILE_NAME);
+        std::fs::write(&probe_path, b"")?;
+        std::fs::remove_file(&probe_path)
+    }
+
+    /// spawn_blocking runs the function of the disk in the blocking thread pool, because
+    /// walking the directory, statvfs and probing the disk are blocking filesystem calls.
+    async fn spawn_blocking<T, F>(self: &Arc<Self>, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Disk) -> T + Send + 'static,
+    {
+        let disk = self.clone();
+        tokio::task::spawn_blocking(move || f(&disk))
+            .await
+            .or_err(ErrorType::AsyncRuntimeError)
+    }
+}
+
+/// Disks manages the content directories on multiple disks, selects the directory for the new
+/// task by the placement policy, and takes the failing disk out of the rotation.
+pub struct Disks {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
+    /// disks are the content directories on the disks.
+    disks: Vec<Arc<Disk>>,
+
+    /// next is the next index of the disk for the round robin policy.
+    next: AtomicUsize,
+
+    /// locations are the indexes of the disks storing the content, the key is the relative
+    /// path of the content.
+    locations: DashMap<PathBuf, usize>,
+}
+
+/// Disks implements the disks.
+impl Disks {
+    /// new returns new disks. If the storage dirs are not set, the content is stored in the
+    /// given dir. The disk failed to initialize is taken out of the rotation, and returns an
+    /// error only if all disks are failed.
+    pub async fn new(config: Arc<Config>, dir: &Path) -> Result<Disks> {
+        let dirs = if config.storage.dirs.is_empty() {
+            vec![dir.to_path_buf()]
+        } else {
+            config.storage.dirs.clone()
+        };
+
+        let mut disks = Vec::with_capacity(dirs.len());
+        for dir in dirs {
+            let dir = dir.join(super::content::DEFAULT_CONTENT_DIR);
+            match Disk::new(config.clone(), dir.clone()).await {
+                Ok(disk) => disks.push(Arc::new(disk)),
+                Err(err) => {
+                    error!("initialize {:?} failed: {}", dir, err);
+                    disks.push(Arc::new(Disk {
+                        config: config.clone(),
+                        dir,
+                        healthy: AtomicBool::new(false),
+                        usage_space: Mutex::new(None),
+                    }));
+                }
+            }
+        }
+
+        if !disks.iter().any(|disk| disk.is_healthy()) {
+            return Err(Error::NoSpace("no healthy storage directory".to_string()));
+        }
+
+        Ok(Disks {
+            config,
+            disks,
+            next: AtomicUsize::new(0),
+            locations: DashMap::new(),
+        })
+    }
+
+    /// path returns the path of the content by the relative path. If the content is not found
+    /// in the healthy disks, returns the path in the first healthy disk.
+    pub async fn path(&self, relative_path: &Path) -> PathBuf {
+        if let Some(index) = self.locate(relative_path).await {
+            return self.disks[index].dir.join(relative_path);
+        }
+
+        self.disks
+            .iter()
+            .find(|disk| disk.is_healthy())
+            .unwrap_or(&self.disks[0])
+            .dir
+            .join(relative_path)
+    }
+
+    /// dir returns the content directory of the disk storing the content by the relative path.
+    pub async fn dir(&self, relative_path: &Path) -> Option<&Path> {
+        self.locate(relative_path)
+            .await
+            .map(|index| self.disks[index].dir.as_path())
+    }
+
+    /// dirs returns the content directories of the healthy disks.
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        self.disks
+            .iter()
+            .filter(|disk| disk.is_healthy())
+            .map(|disk| disk.dir.clone())
+            .collect()
+    }
+
+    /// place returns the content directory to store the content by the relative path. If the
+    /// content is already stored, returns the directory of the disk storing it, otherwise selects
+    /// the disk by the placement policy.
+    pub async fn place(&self, relative_path: &Path, content_length: u64) -> Result<PathBuf> {
+        if let Some(index) = self.locate(relative_path).await {
+            return Ok(self.disks[index].dir.clone());
+        }
+
+        let index = self.select(relative_path, content_length).await?;
+        self.disks[index].reserve_space(content_length);
+        self.locations.insert(relative_path.to_path_buf(), index);
+        Ok(self.disks[index].dir.clone())
+    }
+
+    /// forget forgets the location of the content by the relative path, when the content is
+    /// deleted.
+    pub fn forget(&self, relative_path: &Path) {
+        self.locations.remove(relative_path);
+    }
+
+    /// report_error reports the io error of the content path. If the disk of the content is not
+    /// writable, takes the disk out of the rotation.
+    pub fn report_error(&self, path: &Path, err: &std::io::Error) {
+        if matches!(
+            err.kind(),
+            std::io::ErrorKind::NotFound | std::io::ErrorKind::AlreadyExists
+        ) {
+            return;
+        }
+
+        let Some(index) = self
+            .disks
+            .iter()
+            .position(|disk| path.starts_with(&disk.dir))
+        else {
+            return;
+        };
+
+        if let Err(err) = self.disks[index].probe() {
+            self.mark_unhealthy(index, &err);
+        }
+    }
+
+    /// check probes all disks, takes the failing disks out of the rotation and brings the
+    /// recovered disks back.
+    pub async fn check(&self) {
+        for (index, disk) in self.disks.iter().enumerate() {
+            let result = match disk.spawn_blocking(|disk| disk.probe()).await {
+                Ok(result) => result,
+                Err(err) => {
+                    error!("probe {:?} failed: {}", disk.dir, err);
+                    continue;
+                }
+            };
+
+            match result {
+                Ok(_) => {
+                    if !disk.healthy.swap(true, Ordering::Relaxed) {
+                        info!("disk {:?} is recovered", disk.dir);
+                    }
+                }
+                Err(err) => self.mark_unhealthy(index, &err),
+            }
+        }
+    }
+
+    /// usages returns the space usages of the healthy disks.
+    pub async fn usages(&self) -> Vec<DiskUsage> {
+        let mut usages = Vec::with_capacity(self.disks.len());
+        for disk in self.disks.iter().filter(|disk| disk.is_healthy()) {
+            let result = disk
+                .spawn_blocking(|disk| {
+                    Ok::<_, Error>((disk.total_space()?, disk.available_space()?))
+                })
+                .await
+                .and_then(|result| result);
+
+            match result {
+                Ok((total_space, available_space)) => usages.push(DiskUsage {
+                    dir: disk.dir.clone(),
+                    total_space,
+                    available_space,
+                }),
+                Err(err) => error!("get space usage of {:?} failed: {}", disk.dir, err),
+            }
+        }
+
+        usages
+    }
+
+    /// available_space returns the available space of all healthy disks.
+    pub async fn available_space(&self) -> Result<u64> {
+        let mut available_space = 0;
+        for disk in self.disks.iter().filter(|disk| disk.is_healthy()) {
+            available_space += disk.spawn_blocking(|disk| disk.available_space()).await??;
+        }
+
+        Ok(available_space)
+    }
+
+    /// total_space returns the total space of all healthy disks.
+    pub async fn total_space(&self) -> Result<u64> {
+        let mut total_space = 0;
+        for disk in self.disks.iter().filter(|disk| disk.is_healthy()) {
+            total_space += disk.spawn_blocking(|disk| disk.total_space()).await??;
+        }
+
+        Ok(total_space)
+    }
+
+    /// has_enough_space checks if any healthy disk has enough space to store the content.
+    pub async fn has_enough_space(&self, content_length: u64) -> Result<bool> {
+        let mut max_available_space = 0;
+        for disk in self.disks.iter().filter(|disk| disk.is_healthy()) {
+            let available_space = disk.spawn_blocking(|disk| disk.available_space()).await??;
+            if available_space >= content_length {
+                return Ok(true);
+            }
+
+            max_available_space = max_available_space.max(available_space);
+        }
+
+        warn!(
+            "not enough space to store the task: available_space={}, content_length={}",
+            max_available_space, content_length
+        );
+
+        Ok(false)
+    }
+
+    /// locate returns the index of the healthy disk storing the content by the relative path.
+    async fn locate(&self, relative_path: &Path) -> Option<usize> {
+        if let Some(index) = self.locations.get(relative_path) {
+            if self.disks[*index].is_healthy() {
+                return Some(*index);
+            }
+        }
+
+        for (index, disk) in self.disks.iter().enumerate() {
+            if !disk.is_healthy() {
+                continue;
+            }
+
+            if fs::try_exists(disk.dir.join(relative_path))
+                .await
+                .unwrap_or_default()
+            {
+                self.locations.insert(relative_path.to_path_buf(), index);
+                return Some(index);
+            }
+        }
+
+        None
+    }
+
+    /// select selects the healthy disk to store the new content by the placement policy. The disk
+    /// without enough space is skipped, if no disk has enough space, the disk selected by the
+    /// policy is returned.
+    async fn select(&self, relative_path: &Path, content_length: u64) -> Result<usize> {
+        let candidates = self
+            .disks
+            .iter()
+            .enumerate()
+            .filter(|(_, disk)| disk.is_healthy())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Err(Error::NoSpace("no healthy storage directory".to_string()));
+        }
+
+        // Only one disk is available, no need to select.
+        if candidates.len() == 1 {
+            return Ok(candidates[0]);
+        }
+
+        let start = match self.config.storage.placement_policy {
+            StoragePlacementPolicy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed),
+            StoragePlacementPolicy::Hash => {
+                let key = relative_path
+                    .file_name()
+                    .unwrap_or(relative_path.as_os_str());
+                crc32fast::hash(key.as_encoded_bytes()) as usize
+            }
+            StoragePlacementPolicy::MostFreeSpace => {
+                let mut most_free = None;
+                for index in candidates.iter() {
+                    let Ok(Ok(available_space)) = self.disks[*index]
+                        .spawn_blocking(|disk| disk.available_space())
+                        .await
+                    else {
+                        continue;
+                    };
+
+                    if most_free
+                        .is_none_or(|(_, most_free_space)| available_space > most_free_space)
+                    {
+                        most_free = Some((*index, available_space));
+                    }
+                }
+
+                return Ok(most_free.map(|(index, _)| index).unwrap_or(candidates[0]));
+            }
+        } % candidates.len();
+
+        // Select the first disk with enough space from the start disk in turn.
+        for offset in 0..candidates.len() {
+            let index = candidates[(start + offset) % candidates.len()];
+            let available_space = self.disks[index]
+                .spawn_blocking(|disk| disk.available_space())
+                .await
+                .and_then(|result| result);
+            match available_space {
+                Ok(available_space) if available_space >= content_length => return Ok(index),
+                Ok(_) => {}
+                Err(err) => error!(
+                    "get available space of {:?} failed: {}",
+                    self.disks[index].dir, err
+                ),
+            }
+        }
+
+        warn!(
+            "no storage directory has enough space for {}, use {:?}",
+            content_length, self.disks[candidates[start]].dir
+        );
+        Ok(candidates[start])
+    }
+
+    /// mark_unhealthy takes the disk out of the rotation.
+    fn mark_unhealthy(&self, index: usize, err: &std::io::Error) {
+        let disk = &self.disks[index];
+        if disk.healthy.swap(false, Ordering::Relaxed) {
+            error!(
+                "disk {:?} is unhealthy and taken out of the rotation: {}",
+                disk.dir, err
+            );
+
+            self.locations.retain(|_, location| *location != index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dragonfly_client_config::dfdaemon::Storage;
+    use tempfile::tempdir;
+
+    async fn new_disks(placement_policy: StoragePlacementPolicy, dirs: Vec<PathBuf>) -> Disks {
+        let config = Arc::new(Config {
+            storage: Storage {
+                dirs,
+                placement_policy,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        Disks::new(config, Path::new("/tmp/unused")).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_use_dir_without_dirs() {
+        let temp_dir = tempdir().unwrap();
+        let disks = Disks::new(Arc::new(Config::default()), temp_dir.path())
+            .await
+            .unwrap();
+
+        let relative_path = Path::new("tasks/604/604");
+        assert_eq!(
+            disks.place(relative_path, 0).await.unwrap(),
+            temp_dir.path().join("content")
+        );
+        assert_eq!(
+            disks.path(relative_path).await,
+            temp_dir.path().join("content/tasks/604/604")
+        );
+        assert_eq!(disks.usages().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_place_by_round_robin() {
+        let dirs = vec![tempdir().unwrap(), tempdir().unwrap(), tempdir().unwrap()];
+        let disks = new_disks(
+            StoragePlacementPolicy::RoundRobin,
+            dirs.iter().map(|dir| dir.path().to_path_buf()).collect(),
+        )
+        .await;
+
+        for (i, task_id) in ["a00", "b00", "c00", "d00"].iter().enumerate() {
+            let relative_path = Path::new("tasks").join(task_id);
+            assert_eq!(
+                disks.place(&relative_path, 0).await.unwrap(),
+                dirs[i % dirs.len()].path().join("content")
+            );
+        }
+
+        // The placed content is stored in the same disk.
+        assert_eq!(
+            disks.place(Path::new("tasks/a00"), 0).await.unwrap(),
+            dirs[0].path().join("content")
+        );
+    }
+
+    #[tokio::test]
+    async fn should_place_by_hash() {
+        let dirs = vec![tempdir().unwrap(), tempdir().unwrap()];
+        let dirs = dirs
+            .iter()
+            .map(|dir| dir.path().to_path_buf())
+            .collect::<Vec<_>>();
+
+        let relative_path = Path::new("tasks/604/60409bd0ec44160f44c53c39b3fe1c5f");
+        let disks = new_disks(StoragePlacementPolicy::Hash, dirs.clone()).await;
+        let dir = disks.place(relative_path, 0).await.unwrap();
+
+        // The same task is placed in the same disk by the new disks.
+        let disks = new_disks(StoragePlacementPolicy::Hash, dirs).await;
+        assert_eq!(disks.place(relative_path, 0).await.unwrap(), dir);
+    }
+
+    #[tokio::test]
+    async fn should_locate_existing_content() {
+        let dirs = vec![tempdir().unwrap(), tempdir().unwrap()];
+        let dirs = dirs
+            .iter()
+            .map(|dir| dir.path().to_path_buf())
+            .collect::<Vec<_>>();
+
+        let relative_path = Path::new("tasks/604/604");
+        let content_path = dirs[1].join("content").join(relative_path);
+        let disks = new_disks(StoragePlacementPolicy::RoundRobin, dirs.clone()).await;
+        std::fs::create_dir_all(content_path.parent().unwrap()).unwrap();
+        std::fs::write(&content_path, b"content").unwrap();
+
+        assert_eq!(disks.path(relative_path).await, content_path);
+        assert_eq!(
+            disks.dir(relative_path).await,
+            Some(dirs[1].join("content").as_path())
+        );
+        assert_eq!(
+            disks.place(relative_path, 0).await.unwrap(),
+            dirs[1].join("content")
+        );
+
+        std::fs::remove_file(&content_path).unwrap();
+        disks.forget(relative_path);
+        assert_eq!(disks.dir(relative_path).await, None);
+    }
+
+    #[tokio::test]
+    async fn should_take_failing_disk_out_of_rotation() {
+        let dirs = vec![tempdir().unwrap(), tempdir().unwrap()];
+        let disks = new_disks(
+            StoragePlacementPolicy::RoundRobin,
+            dirs.iter().map(|dir| dir.path().to_path_buf()).collect(),
+        )
+        .await;
+
+        // Remove the content directory of the first disk to simulate the disk failure.
+        std::fs::remove_dir_all(dirs[0].path().join("content")).unwrap();
+        disks.check().await;
+        assert_eq!(disks.usages().await.len(), 1);
+
+        for task_id in ["a00", "b00", "c00"] {
+            assert_eq!(
+                disks
+                    .place(&Path::new("tasks").join(task_id), 0)
+                    .await
+                    .unwrap(),
+                dirs[1].path().join("content")
+            );
+        }
+
+        // Bring the disk back after it is recovered.
+        std::fs::create_dir_all(dirs[0].path().join("content")).unwrap();
+        disks.check().await;
+        assert_eq!(disks.usages().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_cache_usage_space() {
+        let temp_dir = tempdir().unwrap();
+        let config = Arc::new(Config {
+            gc: dragonfly_client_config::dfdaemon::GC {
+                policy: dragonfly_client_config::dfdaemon::Policy {
+                    dist_threshold: ByteSize::kib(10),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let disks = Disks::new(config, temp_dir.path()).await.unwrap();
+        assert_eq!(disks.available_space().await.unwrap(), 10 * 1024);
+
+        // The placed content is reserved in the cached usage space.
+        disks.place(Path::new("tasks/a00"), 1024).await.unwrap();
+        assert_eq!(disks.available_space().await.unwrap(), 9 * 1024);
+
+        // The written content is not walked until the cached usage space is expired.
+        std::fs::write(temp_dir.path().join("content/tasks/b00"), vec![0; 4096]).unwrap();
+        assert_eq!(disks.available_space().await.unwrap(), 9 * 1024);
+    }
+}
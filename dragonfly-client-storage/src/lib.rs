@@ -27,6 +27,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
 use tokio::time::sleep;
 use tokio_util::either::Either;
 use tokio_util::io::InspectReader;
@@ -41,7 +42,9 @@ mod content_macos;
 pub mod cache;
 pub mod client;
 pub mod content;
+pub mod disk;
 pub mod metadata;
+pub mod scrub;
 pub mod server;
 pub mod storage_engine;
 
@@ -68,6 +71,16 @@ impl Storage {
     /// new returns a new storage.
     pub async fn new(config: Arc<Config>, dir: &Path, log_dir: PathBuf) -> Result<Self> {
         let metadata = metadata::Metadata::new(config.clone(), dir, &log_dir)?;
+
+        // The downloads of the last run are interrupted by the restart, reset the started
+        // pieces, so the tasks are resumed from the finished pieces. The metadata of the last
+        // run is kept only if the storage.keep is true, otherwise there is nothing to reset.
+        let count = metadata.reset_started_pieces()?;
+        info!(
+            "reset {} started pieces of the interrupted downloads",
+            count
+        );
+
         let content = content::new_content(config.clone(), dir).await?;
         let cache = cache::Cache::new(config.clone());
 
@@ -79,19 +92,41 @@ impl Storage {
         })
     }
 
-    /// total_space returns the total space of the disk.
-    pub fn total_space(&self) -> Result<u64> {
-        self.content.total_space()
+    /// total_space returns the total space of all disks.
+    pub async fn total_space(&self) -> Result<u64> {
+        self.content.total_space().await
+    }
+
+    /// available_space returns the available space of all disks.
+    pub async fn available_space(&self) -> Result<u64> {
+        self.content.available_space().await
+    }
+
+    /// disk_usages returns the space usages of the healthy disks.
+    pub async fn disk_usages(&self) -> Vec<disk::DiskUsage> {
+        self.content.disk_usages().await
+    }
+
+    /// check_disks checks the disks, takes the failing disks out of the rotation and brings
+    /// the recovered disks back.
+    pub async fn check_disks(&self) {
+        self.content.check_disks().await
+    }
+
+    /// get_task_disk_dir returns the content directory of the disk storing the task.
+    pub async fn get_task_disk_dir(&self, id: &str) -> Option<PathBuf> {
+        self.content.get_task_disk_dir(id).await
     }
 
-    /// available_space returns the available space of the disk.
-    pub fn available_space(&self) -> Result<u64> {
-        self.content.available_space()
+    /// get_persistent_cache_task_disk_dir returns the content directory of the disk storing the
+    /// persistent cache task.
+    pub async fn get_persistent_cache_task_disk_dir(&self, id: &str) -> Option<PathBuf> {
+        self.content.get_persistent_cache_task_disk_dir(id).await
     }
 
     /// has_enough_space checks if the storage has enough space to store the content.
-    pub fn has_enough_space(&self, content_length: u64) -> Result<bool> {
-        self.content.has_enough_space(content_length)
+    pub async fn has_enough_space(&self, content_length: u64) -> Result<bool> {
+        self.content.has_enough_space(content_length).await
     }
 
     /// hard_link_task hard links the task content to the destination.
@@ -189,10 +224,6 @@ impl Storage {
             .delete_task(id)
             .unwrap_or_else(|err| error!("delete task metadata failed: {}", err));
 
-        self.metadata.delete_pieces(id).unwrap_or_else(|err| {
-            error!("delete piece metadatas failed: {}", err);
-        });
-
         self.content.delete_task(id).await.unwrap_or_else(|err| {
             error!("delete task content failed: {}", err);
         });
@@ -356,10 +387,6 @@ impl Storage {
                 error!("delete persistent task metadata failed: {}", err);
             });
 
-        self.metadata.delete_pieces(id).unwrap_or_else(|err| {
-            error!("delete persistent piece metadatas failed: {}", err);
-        });
-
         self.content
             .delete_persistent_task(id)
             .await
@@ -543,10 +570,6 @@ impl Storage {
                 error!("delete persistent cache task metadata failed: {}", err);
             });
 
-        self.metadata.delete_pieces(id).unwrap_or_else(|err| {
-            error!("delete persistent cache piece metadatas failed: {}", err);
-        });
-
         self.content
             .delete_persistent_cache_task(id)
             .await
@@ -555,6 +578,91 @@ impl Storage {
             });
     }
 
+    /// prepare_download_cache_task_started returns the cache task if it has been downloaded
+    /// finished and the content is still in the cache, otherwise returns None.
+    #[instrument(skip_all)]
+    pub async fn prepare_download_cache_task_started(
+        &self,
+        id: &str,
+    ) -> Result<Option<metadata::CacheTask>> {
+        match self.metadata.get_cache_task(id)? {
+            Some(task) if task.is_finished() => {
+                if task.is_empty() || self.cache.contains_task(id).await {
+                    return Ok(Some(task));
+                }
+
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// copy_cache_task copies the cache task content from the cache to the destination. The pieces
+    /// must be finished and contiguous from offset 0 to the content length, otherwise a partially
+    /// downloaded cache task would produce a corrupt copy.
+    #[instrument(skip_all)]
+    pub async fn copy_cache_task(&self, id: &str, to: &Path) -> Result<()> {
+        let task = self
+            .metadata
+            .get_cache_task(id)?
+            .ok_or_else(|| Error::TaskNotFound(id.to_string()))?;
+
+        let mut pieces = self.metadata.get_pieces(id)?;
+        pieces.sort_by_key(|piece| piece.number);
+        Self::check_cache_task_pieces(id, task.content_length(), &pieces)?;
+
+        if let Some(parent) = to.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut f = tokio::fs::File::create(to).await?;
+        for piece in pieces {
+            let piece_id = self.metadata.piece_id(id, piece.number);
+            let mut reader = self
+                .cache
+                .read_piece(id, piece_id.as_str(), piece, None)
+                .await?;
+            tokio::io::copy(&mut reader, &mut f).await?;
+        }
+
+        f.flush().await?;
+        Ok(())
+    }
+
+    /// check_cache_task_pieces checks the sorted pieces are finished and cover the content of the
+    /// cache task without gaps.
+    fn check_cache_task_pieces(
+        id: &str,
+        content_length: Option<u64>,
+        pieces: &[metadata::Piece],
+    ) -> Result<()> {
+        let mut offset = 0;
+        for (index, piece) in pieces.iter().enumerate() {
+            if !piece.is_finished() {
+                return Err(Error::InvalidState(format!(
+                    "cache task {} piece {} is not finished",
+                    id, piece.number
+                )));
+            }
+
+            if piece.number as usize != index || piece.offset != offset {
+                return Err(Error::InvalidState(format!(
+                    "cache task {} piece {} is not contiguous, expected offset {}",
+                    id, piece.number, offset
+                )));
+            }
+
+            offset += piece.length;
+        }
+
+        match content_length {
+            Some(content_length) if content_length != offset => {
+                Err(Error::ContentLengthMismatch(content_length, offset))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// download_cache_task_started updates the metadata of the cache task and create cache task content
     /// when the cache task downloads started.
     #[instrument(skip_all)]
@@ -565,8 +673,22 @@ impl Storage {
         content_length: u64,
         response_header: Option<HeaderMap>,
     ) -> Result<metadata::CacheTask> {
+        // If the cache task is not in the cache, the pieces in the metadata are stale,
+        // because the content of the cache task has been evicted or the dfdaemon has been restarted.
         let mut cache = self.cache.clone();
-        cache.put_task(id, content_length).await;
+        if !cache.contains_task(id).await {
+            self.metadata.delete_cache_task(id).unwrap_or_else(|err| {
+                error!("delete stale cache task metadata failed: {}", err);
+            });
+
+            cache.put_task(id, content_length).await;
+            if content_length > 0 && !cache.contains_task(id).await {
+                return Err(Error::NoSpace(format!(
+                    "cache task {} content length {} exceeds cache capacity",
+                    id, content_length
+                )));
+            }
+        }
 
         self.metadata
             .download_cache_task_started(id, piece_length, content_length, response_header)
@@ -615,10 +737,6 @@ impl Storage {
             .delete_cache_task(id)
             .unwrap_or_else(|err| error!("delete cache task metadata failed: {}", err));
 
-        self.metadata.delete_pieces(id).unwrap_or_else(|err| {
-            error!("delete cache piece metadatas failed: {}", err);
-        });
-
         let mut cache = self.cache.clone();
         cache.delete_task(id).await.unwrap_or_else(|err| {
             info!("delete cache task from cache failed: {}", err);
@@ -921,6 +1039,30 @@ impl Storage {
         }
     }
 
+    /// read_piece returns the data of the finished piece, it is used to verify the content of
+    /// the task, so the upload of the task is not recorded.
+    #[instrument(skip_all)]
+    pub async fn read_piece(
+        &self,
+        piece_id: &str,
+        task_id: &str,
+        piece: metadata::Piece,
+    ) -> Result<impl AsyncRead> {
+        if self.cache.contains_piece(task_id, piece_id).await {
+            let reader = self
+                .cache
+                .read_piece(task_id, piece_id, piece, None)
+                .await?;
+            return Ok(Either::Left(reader));
+        }
+
+        let reader = self
+            .content
+            .read_piece(task_id, piece.offset, piece.length, None)
+            .await?;
+        Ok(Either::Right(reader))
+    }
+
     /// get_piece returns the piece metadata.
     pub fn get_piece(&self, piece_id: &str) -> Result<Option<metadata::Piece>> {
         self.metadata.get_piece(piece_id)
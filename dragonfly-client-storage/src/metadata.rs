@@ -15,7 +15,7 @@
  */
 
 use chrono::{NaiveDateTime, Utc};
-use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_config::dfdaemon::{Config, MetadataEngine};
 use dragonfly_client_core::{Error, Result};
 use dragonfly_client_util::{digest, http::headermap_to_hashmap};
 use reqwest::header::HeaderMap;
@@ -27,7 +27,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing::{error, info, instrument};
 
-use crate::storage_engine::{rocksdb::RocksdbStorageEngine, DatabaseObject, StorageEngineOwned};
+use crate::storage_engine::{DatabaseObject, Engine, StorageEngineOwned, WriteBatch};
 
 /// Task is the metadata of the task.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -521,8 +521,21 @@ impl Piece {
     }
 }
 
+/// NAMESPACES are the namespaces of the metadata, each namespace is a column family in the
+/// storage engine.
+const NAMESPACES: &[&str] = &[
+    Task::NAMESPACE,
+    Piece::NAMESPACE,
+    PersistentTask::NAMESPACE,
+    PersistentCacheTask::NAMESPACE,
+    CacheTask::NAMESPACE,
+];
+
+/// MIGRATE_BATCH_SIZE is the number of the objects written in a transaction of the migration.
+const MIGRATE_BATCH_SIZE: usize = 1000;
+
 /// Metadata manages the metadata of Task, Piece, PersistentCacheTask, etc.
-pub struct Metadata<E = RocksdbStorageEngine>
+pub struct Metadata<E = Engine>
 where
     E: StorageEngineOwned,
 {
@@ -582,8 +595,17 @@ impl<E: StorageEngineOwned> Metadata<E> {
             .map(headermap_to_hashmap)
             .unwrap_or_default();
 
+        let mut batch = WriteBatch::new();
         let task = match self.db.get::<Task>(id.as_bytes())? {
             Some(mut task) => {
+                // If the piece length or content length is changed, the downloaded pieces
+                // are stale, so delete them with the task metadata update atomically.
+                if task.piece_length != Some(piece_length)
+                    || task.content_length != Some(content_length)
+                {
+                    self.delete_pieces_in_batch(id, &mut batch)?;
+                }
+
                 // If the task exists, update the task metadata.
                 task.updated_at = Utc::now().naive_utc();
                 task.failed_at = None;
@@ -603,7 +625,8 @@ impl<E: StorageEngineOwned> Metadata<E> {
             },
         };
 
-        self.db.put(id.as_bytes(), &task)?;
+        batch.put::<Task>(id.as_bytes(), &task)?;
+        self.db.write_batch(batch)?;
         Ok(task)
     }
 
@@ -640,6 +663,31 @@ impl<E: StorageEngineOwned> Metadata<E> {
         Ok(task)
     }
 
+    /// download_task_corrupted updates the metadata of the task when the finished pieces of the
+    /// task are corrupted. The corrupted pieces are deleted and the task is marked as unfinished
+    /// atomically, so the pieces will be downloaded again.
+    #[instrument(skip_all)]
+    pub fn download_task_corrupted(&self, id: &str, piece_ids: &[String]) -> Result<Task> {
+        let task = match self.db.get::<Task>(id.as_bytes())? {
+            Some(mut task) => {
+                task.updated_at = Utc::now().naive_utc();
+                task.finished_at = None;
+                task
+            }
+            None => return Err(Error::TaskNotFound(id.to_string())),
+        };
+
+        let mut batch = WriteBatch::new();
+        for piece_id in piece_ids {
+            info!("delete corrupted piece metadata {}", piece_id);
+            batch.delete::<Piece>(piece_id.as_bytes());
+        }
+
+        batch.put::<Task>(id.as_bytes(), &task)?;
+        self.db.write_batch(batch)?;
+        Ok(task)
+    }
+
     /// prefetch_task_started updates the metadata of the task when the task prefetch started.
     #[instrument(skip_all)]
     pub fn prefetch_task_started(&self, id: &str) -> Result<Task> {
@@ -758,11 +806,14 @@ impl<E: StorageEngineOwned> Metadata<E> {
             .collect()
     }
 
-    /// delete_task deletes the task metadata.
+    /// delete_task deletes the task metadata and its piece metadatas atomically.
     #[instrument(skip_all)]
     pub fn delete_task(&self, id: &str) -> Result<()> {
         info!("delete task metadata {}", id);
-        self.db.delete::<Task>(id.as_bytes())
+        let mut batch = WriteBatch::new();
+        batch.delete::<Task>(id.as_bytes());
+        self.delete_pieces_in_batch(id, &mut batch)?;
+        self.db.write_batch(batch)
     }
 
     /// create_persistent_task creates a new persistent task.
@@ -889,6 +940,35 @@ impl<E: StorageEngineOwned> Metadata<E> {
         Ok(task)
     }
 
+    /// download_persistent_task_corrupted updates the metadata of the persistent task when the finished pieces
+    /// of the persistent task are corrupted. The corrupted pieces are deleted and the persistent task is marked
+    /// as unfinished atomically, so the pieces will be downloaded again.
+    #[instrument(skip_all)]
+    pub fn download_persistent_task_corrupted(
+        &self,
+        id: &str,
+        piece_ids: &[String],
+    ) -> Result<PersistentTask> {
+        let task = match self.db.get::<PersistentTask>(id.as_bytes())? {
+            Some(mut task) => {
+                task.updated_at = Utc::now().naive_utc();
+                task.finished_at = None;
+                task
+            }
+            None => return Err(Error::TaskNotFound(id.to_string())),
+        };
+
+        let mut batch = WriteBatch::new();
+        for piece_id in piece_ids {
+            info!("delete corrupted piece metadata {}", piece_id);
+            batch.delete::<Piece>(piece_id.as_bytes());
+        }
+
+        batch.put::<PersistentTask>(id.as_bytes(), &task)?;
+        self.db.write_batch(batch)?;
+        Ok(task)
+    }
+
     /// upload_persistent_task_started updates the metadata of the persistent task when persistent task uploads started.
     #[instrument(skip_all)]
     pub fn upload_persistent_task_started(&self, id: &str) -> Result<PersistentTask> {
@@ -973,11 +1053,14 @@ impl<E: StorageEngineOwned> Metadata<E> {
         iter.map(|ele| ele.map(|(_, task)| task)).collect()
     }
 
-    /// delete_persistent_task deletes the persistent task metadata.
+    /// delete_persistent_task deletes the persistent task metadata and its piece metadatas atomically.
     #[instrument(skip_all)]
     pub fn delete_persistent_task(&self, id: &str) -> Result<()> {
         info!("delete persistent task metadata {}", id);
-        self.db.delete::<PersistentTask>(id.as_bytes())
+        let mut batch = WriteBatch::new();
+        batch.delete::<PersistentTask>(id.as_bytes());
+        self.delete_pieces_in_batch(id, &mut batch)?;
+        self.db.write_batch(batch)
     }
 
     /// create_persistent_cache_task creates a new persistent cache task.
@@ -1104,6 +1187,35 @@ impl<E: StorageEngineOwned> Metadata<E> {
         Ok(task)
     }
 
+    /// download_persistent_cache_task_corrupted updates the metadata of the persistent cache task when the finished pieces
+    /// of the persistent cache task are corrupted. The corrupted pieces are deleted and the persistent cache task is marked
+    /// as unfinished atomically, so the pieces will be downloaded again.
+    #[instrument(skip_all)]
+    pub fn download_persistent_cache_task_corrupted(
+        &self,
+        id: &str,
+        piece_ids: &[String],
+    ) -> Result<PersistentCacheTask> {
+        let task = match self.db.get::<PersistentCacheTask>(id.as_bytes())? {
+            Some(mut task) => {
+                task.updated_at = Utc::now().naive_utc();
+                task.finished_at = None;
+                task
+            }
+            None => return Err(Error::TaskNotFound(id.to_string())),
+        };
+
+        let mut batch = WriteBatch::new();
+        for piece_id in piece_ids {
+            info!("delete corrupted piece metadata {}", piece_id);
+            batch.delete::<Piece>(piece_id.as_bytes());
+        }
+
+        batch.put::<PersistentCacheTask>(id.as_bytes(), &task)?;
+        self.db.write_batch(batch)?;
+        Ok(task)
+    }
+
     /// upload_persistent_cache_task_started updates the metadata of the persistent cache task when persistent cache task uploads started.
     #[instrument(skip_all)]
     pub fn upload_persistent_cache_task_started(&self, id: &str) -> Result<PersistentCacheTask> {
@@ -1188,11 +1300,14 @@ impl<E: StorageEngineOwned> Metadata<E> {
         iter.map(|ele| ele.map(|(_, task)| task)).collect()
     }
 
-    /// delete_persistent_cache_task deletes the persistent cache task metadata.
+    /// delete_persistent_cache_task deletes the persistent cache task metadata and its piece metadatas atomically.
     #[instrument(skip_all)]
     pub fn delete_persistent_cache_task(&self, id: &str) -> Result<()> {
         info!("delete persistent cache task metadata {}", id);
-        self.db.delete::<PersistentCacheTask>(id.as_bytes())
+        let mut batch = WriteBatch::new();
+        batch.delete::<PersistentCacheTask>(id.as_bytes());
+        self.delete_pieces_in_batch(id, &mut batch)?;
+        self.db.write_batch(batch)
     }
 
     /// download_cache_task_started updates the metadata of the cache task when the cache task downloads started.
@@ -1210,8 +1325,17 @@ impl<E: StorageEngineOwned> Metadata<E> {
             .map(headermap_to_hashmap)
             .unwrap_or_default();
 
+        let mut batch = WriteBatch::new();
         let task = match self.db.get::<CacheTask>(id.as_bytes())? {
             Some(mut task) => {
+                // If the piece length or content length is changed, the downloaded pieces
+                // are stale, so delete them with the cache task metadata update atomically.
+                if task.piece_length != Some(piece_length)
+                    || task.content_length != Some(content_length)
+                {
+                    self.delete_pieces_in_batch(id, &mut batch)?;
+                }
+
                 // If the task exists, update the task metadata.
                 task.updated_at = Utc::now().naive_utc();
                 task.failed_at = None;
@@ -1231,7 +1355,8 @@ impl<E: StorageEngineOwned> Metadata<E> {
             },
         };
 
-        self.db.put(id.as_bytes(), &task)?;
+        batch.put::<CacheTask>(id.as_bytes(), &task)?;
+        self.db.write_batch(batch)?;
         Ok(task)
     }
 
@@ -1347,11 +1472,14 @@ impl<E: StorageEngineOwned> Metadata<E> {
             .collect()
     }
 
-    /// delete_cache_task deletes the cache task metadata.
+    /// delete_cache_task deletes the cache task metadata and its piece metadatas atomically.
     #[instrument(skip_all)]
     pub fn delete_cache_task(&self, id: &str) -> Result<()> {
         info!("delete cache task metadata {}", id);
-        self.db.delete::<CacheTask>(id.as_bytes())
+        let mut batch = WriteBatch::new();
+        batch.delete::<CacheTask>(id.as_bytes());
+        self.delete_pieces_in_batch(id, &mut batch)?;
+        self.db.write_batch(batch)
     }
 
     /// create_persistent_piece creates a new persistent piece, which is imported by
@@ -1510,6 +1638,43 @@ impl<E: StorageEngineOwned> Metadata<E> {
     /// delete_pieces deletes the piece metadatas.
     #[instrument(skip_all)]
     pub fn delete_pieces(&self, task_id: &str) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        self.delete_pieces_in_batch(task_id, &mut batch)?;
+        self.db.write_batch(batch)
+    }
+
+    /// reset_started_pieces deletes the metadatas of the pieces which downloads started but not
+    /// finished, and returns the number of the deleted pieces. The pieces are left in the started
+    /// state by the downloads interrupted by the restart, so nobody will finish them, and they
+    /// must be downloaded again instead of being waited for.
+    #[instrument(skip_all)]
+    pub fn reset_started_pieces(&self) -> Result<u64> {
+        let mut batch = WriteBatch::new();
+        for ele in self.db.iter::<Piece>()? {
+            let (piece_id, piece) = ele?;
+            if piece.is_finished() {
+                continue;
+            }
+
+            info!(
+                "reset started piece metadata {}",
+                std::str::from_utf8(&piece_id).unwrap_or_default(),
+            );
+
+            batch.delete::<Piece>(&piece_id);
+        }
+
+        let count = batch.len() as u64;
+        if !batch.is_empty() {
+            self.db.write_batch(batch)?;
+        }
+
+        Ok(count)
+    }
+
+    /// delete_pieces_in_batch adds the deletions of the piece metadatas to the batch, which
+    /// are written atomically with the other operations in the batch.
+    fn delete_pieces_in_batch(&self, task_id: &str, batch: &mut WriteBatch) -> Result<()> {
         let piece_ids = self
             .db
             .prefix_iter_raw::<Piece>(task_id.as_bytes())?
@@ -1519,20 +1684,15 @@ impl<E: StorageEngineOwned> Metadata<E> {
             })
             .collect::<Result<Vec<Box<[u8]>>>>()?;
 
-        let piece_ids_refs = piece_ids
-            .iter()
-            .map(|id| {
-                let id_ref = id.as_ref();
-                info!(
-                    "delete piece metadata {} in batch",
-                    std::str::from_utf8(id_ref).unwrap_or_default(),
-                );
-
-                id_ref
-            })
-            .collect::<Vec<&[u8]>>();
+        for piece_id in piece_ids {
+            info!(
+                "delete piece metadata {} in batch",
+                std::str::from_utf8(&piece_id).unwrap_or_default(),
+            );
+
+            batch.delete::<Piece>(&piece_id);
+        }
 
-        self.db.batch_delete::<Piece>(piece_ids_refs)?;
         Ok(())
     }
 
@@ -1544,36 +1704,399 @@ impl<E: StorageEngineOwned> Metadata<E> {
 }
 
 /// Metadata implements the metadata of the storage engine.
-impl Metadata<RocksdbStorageEngine> {
-    /// new creates a new metadata instance.
+impl Metadata<Engine> {
+    /// new creates a new metadata instance with the metadata engine in the config.
     #[instrument(skip_all)]
-    pub fn new(
-        config: Arc<Config>,
-        dir: &Path,
-        log_dir: &PathBuf,
-    ) -> Result<Metadata<RocksdbStorageEngine>> {
-        let db = RocksdbStorageEngine::open(
+    pub fn new(config: Arc<Config>, dir: &Path, log_dir: &PathBuf) -> Result<Metadata<Engine>> {
+        Self::open(
+            config.storage.metadata_engine,
             dir,
             log_dir,
-            &[
-                Task::NAMESPACE,
-                Piece::NAMESPACE,
-                PersistentTask::NAMESPACE,
-                PersistentCacheTask::NAMESPACE,
-                CacheTask::NAMESPACE,
-            ],
             config.storage.keep,
-        )?;
+        )
+    }
 
+    /// open opens the metadata instance with the given metadata engine.
+    #[instrument(skip_all)]
+    pub fn open(
+        engine: MetadataEngine,
+        dir: &Path,
+        log_dir: &PathBuf,
+        keep: bool,
+    ) -> Result<Metadata<Engine>> {
+        let db = Engine::open(engine, dir, log_dir, NAMESPACES, keep)?;
         Ok(Metadata { db })
     }
 }
 
+/// Metadata implements the migration between the storage engines.
+impl<E: StorageEngineOwned> Metadata<E> {
+    /// migrate_to copies the metadata of all namespaces to the destination, and returns the
+    /// number of the copied objects. The objects are written in batches, and the destination
+    /// is flushed durably before returning. The dfdaemon must be stopped during the migration.
+    #[instrument(skip_all)]
+    pub fn migrate_to<D: StorageEngineOwned>(&self, destination: &Metadata<D>) -> Result<u64> {
+        let mut count = 0;
+        count += self.migrate_namespace_to::<Task, D>(destination)?;
+        count += self.migrate_namespace_to::<Piece, D>(destination)?;
+        count += self.migrate_namespace_to::<PersistentTask, D>(destination)?;
+        count += self.migrate_namespace_to::<PersistentCacheTask, D>(destination)?;
+        count += self.migrate_namespace_to::<CacheTask, D>(destination)?;
+
+        destination.db.flush()?;
+        Ok(count)
+    }
+
+    /// migrate_namespace_to copies the objects of the namespace to the destination in batches
+    /// of MIGRATE_BATCH_SIZE objects.
+    fn migrate_namespace_to<O, D>(&self, destination: &Metadata<D>) -> Result<u64>
+    where
+        O: DatabaseObject,
+        D: StorageEngineOwned,
+    {
+        let mut count = 0;
+        let mut batch = WriteBatch::new();
+        for ele in self.db.iter::<O>()? {
+            let (key, object) = ele?;
+            batch.put::<O>(&key, &object)?;
+            count += 1;
+
+            if batch.len() >= MIGRATE_BATCH_SIZE {
+                destination.db.write_batch(std::mem::take(&mut batch))?;
+            }
+        }
+
+        if !batch.is_empty() {
+            destination.db.write_batch(batch)?;
+        }
+
+        info!("migrate {} objects of {}", count, O::NAMESPACE);
+        Ok(count)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage_engine::{Operations, StorageEngine};
+    use dragonfly_client_config::dfdaemon::Storage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tempfile::tempdir;
 
+    /// ENGINES are the metadata engines to run the metadata tests against.
+    const ENGINES: [MetadataEngine; 2] = [MetadataEngine::Rocksdb, MetadataEngine::Redb];
+
+    fn new_config(engine: MetadataEngine) -> Arc<Config> {
+        Arc::new(Config {
+            storage: Storage {
+                metadata_engine: engine,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    /// CrashEngine injects a crash into the storage engine, the writes after the crash point
+    /// fail and are lost, like the dfdaemon crashes in the middle of the state transitions.
+    struct CrashEngine {
+        /// inner is the storage engine to inject the crash.
+        inner: Engine,
+
+        /// remaining_writes is the number of the writes before the crash.
+        remaining_writes: AtomicUsize,
+    }
+
+    /// CrashEngine implements the crash injection.
+    impl CrashEngine {
+        fn new(inner: Engine, crash_point: usize) -> Self {
+            Self {
+                inner,
+                remaining_writes: AtomicUsize::new(crash_point),
+            }
+        }
+
+        /// write consumes a write, and returns an error if the engine has crashed.
+        fn write(&self) -> Result<()> {
+            self.remaining_writes
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .map(|_| ())
+                .map_err(|_| Error::Unknown("crash injected".to_string()))
+        }
+    }
+
+    impl Operations for CrashEngine {
+        fn get<O: DatabaseObject>(&self, key: &[u8]) -> Result<Option<O>> {
+            self.inner.get(key)
+        }
+
+        fn exists<O: DatabaseObject>(&self, key: &[u8]) -> Result<bool> {
+            self.inner.exists::<O>(key)
+        }
+
+        fn put<O: DatabaseObject>(&self, key: &[u8], value: &O) -> Result<()> {
+            self.write()?;
+            self.inner.put(key, value)
+        }
+
+        fn delete<O: DatabaseObject>(&self, key: &[u8]) -> Result<()> {
+            self.write()?;
+            self.inner.delete::<O>(key)
+        }
+
+        fn iter<O: DatabaseObject>(&self) -> Result<impl Iterator<Item = Result<(Box<[u8]>, O)>>> {
+            self.inner.iter::<O>()
+        }
+
+        fn iter_raw<O: DatabaseObject>(
+            &self,
+        ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>>> {
+            self.inner.iter_raw::<O>()
+        }
+
+        fn prefix_iter<O: DatabaseObject>(
+            &self,
+            prefix: &[u8],
+        ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, O)>>> {
+            self.inner.prefix_iter::<O>(prefix)
+        }
+
+        fn prefix_iter_raw<O: DatabaseObject>(
+            &self,
+            prefix: &[u8],
+        ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>>> {
+            self.inner.prefix_iter_raw::<O>(prefix)
+        }
+
+        fn batch_delete<O: DatabaseObject>(&self, keys: Vec<&[u8]>) -> Result<()> {
+            self.write()?;
+            self.inner.batch_delete::<O>(keys)
+        }
+
+        fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+            self.write()?;
+            self.inner.write_batch(batch)
+        }
+
+        fn flush(&self) -> Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl StorageEngine<'_> for CrashEngine {}
+
+    #[test]
+    fn should_keep_task_and_pieces_consistent_after_crash() {
+        let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+        for engine in ENGINES {
+            for crash_point in 0..=2 {
+                let dir = tempdir().unwrap();
+                let log_dir = dir.path().join("log");
+
+                // Download the task with the finished pieces.
+                {
+                    let metadata = Metadata::open(engine, dir.path(), &log_dir, false).unwrap();
+                    metadata
+                        .download_task_started(task_id, 1024, 2048, None)
+                        .unwrap();
+                    for number in 0..2 {
+                        let piece_id = metadata.piece_id(task_id, number);
+                        metadata.download_piece_started(&piece_id, number).unwrap();
+                        metadata
+                            .download_piece_finished(
+                                &piece_id,
+                                number as u64 * 1024,
+                                1024,
+                                "digest",
+                                None,
+                            )
+                            .unwrap();
+                    }
+                }
+
+                // Restart the task with another piece length and delete it, the crash is
+                // injected during the state transitions.
+                {
+                    let inner =
+                        Engine::open(engine, dir.path(), &log_dir, NAMESPACES, true).unwrap();
+                    let metadata = Metadata {
+                        db: CrashEngine::new(inner, crash_point),
+                    };
+
+                    let result = metadata.download_task_started(task_id, 512, 2048, None);
+                    assert_eq!(result.is_ok(), crash_point > 0);
+                    let result = metadata.delete_task(task_id);
+                    assert_eq!(result.is_ok(), crash_point > 1);
+                }
+
+                // Reopen the metadata like the dfdaemon restarts, the task and its pieces
+                // must be consistent.
+                let metadata = Metadata::open(engine, dir.path(), &log_dir, true).unwrap();
+                let task = metadata.get_task(task_id).unwrap();
+                let pieces = metadata.get_pieces(task_id).unwrap();
+                match crash_point {
+                    0 => {
+                        assert_eq!(task.unwrap().piece_length, Some(1024));
+                        assert_eq!(pieces.len(), 2);
+                    }
+                    1 => {
+                        assert_eq!(task.unwrap().piece_length, Some(512));
+                        assert!(pieces.is_empty());
+                    }
+                    _ => {
+                        assert!(task.is_none());
+                        assert!(pieces.is_empty());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_recover_consistent_metadata_after_interrupted_write() {
+        let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+        for torn_bytes in [1, 8, 32] {
+            let dir = tempdir().unwrap();
+            let log_dir = dir.path().join("log");
+
+            {
+                let metadata =
+                    Metadata::open(MetadataEngine::Rocksdb, dir.path(), &log_dir, false).unwrap();
+                metadata
+                    .download_task_started(task_id, 1024, 2048, None)
+                    .unwrap();
+                for number in 0..2 {
+                    let piece_id = metadata.piece_id(task_id, number);
+                    metadata.download_piece_started(&piece_id, number).unwrap();
+                    metadata
+                        .download_piece_finished(
+                            &piece_id,
+                            number as u64 * 1024,
+                            1024,
+                            "digest",
+                            None,
+                        )
+                        .unwrap();
+                }
+
+                // The last write restarts the task with another piece length, it deletes
+                // the pieces and updates the task in one batch.
+                metadata
+                    .download_task_started(task_id, 512, 2048, None)
+                    .unwrap();
+            }
+
+            // Tear the tail of the write ahead log, like the dfdaemon is killed in the
+            // middle of the last write.
+            let wal = std::fs::read_dir(dir.path().join("metadata"))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+                .max()
+                .unwrap();
+            let file = std::fs::OpenOptions::new().write(true).open(&wal).unwrap();
+            let len = file.metadata().unwrap().len();
+            file.set_len(len.saturating_sub(torn_bytes)).unwrap();
+            drop(file);
+
+            // Reopen the metadata, the last write is either applied or lost entirely.
+            let metadata =
+                Metadata::open(MetadataEngine::Rocksdb, dir.path(), &log_dir, true).unwrap();
+            let task = metadata.get_task(task_id).unwrap().unwrap();
+            let pieces = metadata.get_pieces(task_id).unwrap();
+            match task.piece_length {
+                Some(1024) => {
+                    assert_eq!(pieces.len(), 2);
+                    assert!(pieces.iter().all(|piece| piece.is_finished()));
+                }
+                Some(512) => assert!(pieces.is_empty()),
+                piece_length => panic!("unexpected piece length {:?}", piece_length),
+            }
+        }
+    }
+
+    #[test]
+    fn should_delete_cache_task_with_pieces() {
+        for engine in ENGINES {
+            let dir = tempdir().unwrap();
+            let log_dir = dir.path().join("log");
+            let metadata = Metadata::new(new_config(engine), dir.path(), &log_dir).unwrap();
+            let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+
+            metadata
+                .download_cache_task_started(task_id, 1024, 2048, None)
+                .unwrap();
+            for number in 0..2 {
+                let piece_id = metadata.piece_id(task_id, number);
+                metadata.download_piece_started(&piece_id, number).unwrap();
+            }
+
+            // The pieces are kept if the task is restarted with the same piece length.
+            metadata
+                .download_cache_task_started(task_id, 1024, 2048, None)
+                .unwrap();
+            assert_eq!(metadata.get_pieces(task_id).unwrap().len(), 2);
+
+            metadata.delete_cache_task(task_id).unwrap();
+            assert!(metadata.get_cache_task(task_id).unwrap().is_none());
+            assert!(metadata.get_pieces(task_id).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn should_reset_started_pieces_after_restart() {
+        for engine in ENGINES {
+            let dir = tempdir().unwrap();
+            let log_dir = dir.path().join("log");
+            let config = Arc::new(Config {
+                storage: Storage {
+                    metadata_engine: engine,
+                    keep: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+
+            {
+                let metadata = Metadata::new(config.clone(), dir.path(), &log_dir).unwrap();
+                metadata
+                    .download_task_started(task_id, 1024, 4096, None)
+                    .unwrap();
+                for number in 0..4 {
+                    let piece_id = metadata.piece_id(task_id, number);
+                    metadata.download_piece_started(&piece_id, number).unwrap();
+                }
+
+                // The first two pieces are finished before the restart.
+                for number in 0..2 {
+                    let piece_id = metadata.piece_id(task_id, number);
+                    metadata
+                        .download_piece_finished(
+                            &piece_id,
+                            number as u64 * 1024,
+                            1024,
+                            "crc32:3299754941",
+                            None,
+                        )
+                        .unwrap();
+                }
+            }
+
+            let metadata = Metadata::new(config, dir.path(), &log_dir).unwrap();
+            assert_eq!(metadata.reset_started_pieces().unwrap(), 2);
+
+            // The task is resumed from the finished pieces.
+            let (task, reused) = metadata.prepare_download_task(task_id).unwrap();
+            assert!(reused);
+            assert!(!task.is_finished());
+
+            let pieces = metadata.get_pieces(task_id).unwrap();
+            assert_eq!(pieces.len(), 2);
+            assert!(pieces.iter().all(|piece| piece.is_finished()));
+            assert_eq!(metadata.reset_started_pieces().unwrap(), 0);
+        }
+    }
+
     #[test]
     fn test_calculate_digest() {
         let piece = Piece {
@@ -1590,184 +2113,248 @@ mod tests {
 
     #[test]
     fn should_create_metadata() {
-        let dir = tempdir().unwrap();
-        let log_dir = dir.path().join("log");
-        let metadata = Metadata::new(Arc::new(Config::default()), dir.path(), &log_dir).unwrap();
-        assert!(metadata.get_tasks().unwrap().is_empty());
-        assert!(metadata
-            .get_pieces("d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c")
-            .unwrap()
-            .is_empty());
+        for engine in ENGINES {
+            let dir = tempdir().unwrap();
+            let log_dir = dir.path().join("log");
+            let metadata = Metadata::new(new_config(engine), dir.path(), &log_dir).unwrap();
+            assert!(metadata.get_tasks().unwrap().is_empty());
+            assert!(metadata
+                .get_pieces("d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c")
+                .unwrap()
+                .is_empty());
+        }
     }
 
     #[test]
     fn test_task_lifecycle() {
-        let dir = tempdir().unwrap();
-        let log_dir = dir.path().join("log");
-        let metadata = Metadata::new(Arc::new(Config::default()), dir.path(), &log_dir).unwrap();
-        let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
-
-        // Test download_task_started.
-        metadata
-            .download_task_started(task_id, 1024, 1024, None)
-            .unwrap();
-        let task = metadata
-            .get_task(task_id)
-            .unwrap()
-            .expect("task should exist after download_task_started");
-        assert_eq!(task.id, task_id);
-        assert_eq!(task.piece_length, Some(1024));
-        assert_eq!(task.content_length, Some(1024));
-        assert!(task.response_header.is_empty());
-        assert_eq!(task.uploading_count, 0);
-        assert_eq!(task.uploaded_count, 0);
-        assert!(!task.is_finished());
-
-        // Test download_task_finished.
-        metadata.download_task_finished(task_id).unwrap();
-        let task = metadata.get_task(task_id).unwrap().unwrap();
-        assert!(task.is_finished());
-
-        // Test upload_task_started.
-        metadata.upload_task_started(task_id).unwrap();
-        let task = metadata.get_task(task_id).unwrap().unwrap();
-        assert_eq!(task.uploading_count, 1);
-
-        // Test upload_task_finished.
-        metadata.upload_task_finished(task_id).unwrap();
-        let task = metadata.get_task(task_id).unwrap().unwrap();
-        assert_eq!(task.uploading_count, 0);
-        assert_eq!(task.uploaded_count, 1);
-
-        // Test upload_task_failed.
-        let task = metadata.upload_task_started(task_id).unwrap();
-        assert_eq!(task.uploading_count, 1);
-        let task = metadata.upload_task_failed(task_id).unwrap();
-        assert_eq!(task.uploading_count, 0);
-        assert_eq!(task.uploaded_count, 1);
-
-        // Test get_tasks.
-        let task_id = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
-        metadata
-            .download_task_started(task_id, 1024, 0, None)
-            .unwrap();
-        let tasks = metadata.get_tasks().unwrap();
-        assert_eq!(tasks.len(), 2);
-
-        // Test delete_task.
-        metadata.delete_task(task_id).unwrap();
-        let task = metadata.get_task(task_id).unwrap();
-        assert!(task.is_none());
+        for engine in ENGINES {
+            let dir = tempdir().unwrap();
+            let log_dir = dir.path().join("log");
+            let metadata = Metadata::new(new_config(engine), dir.path(), &log_dir).unwrap();
+            let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+
+            // Test download_task_started.
+            metadata
+                .download_task_started(task_id, 1024, 1024, None)
+                .unwrap();
+            let task = metadata
+                .get_task(task_id)
+                .unwrap()
+                .expect("task should exist after download_task_started");
+            assert_eq!(task.id, task_id);
+            assert_eq!(task.piece_length, Some(1024));
+            assert_eq!(task.content_length, Some(1024));
+            assert!(task.response_header.is_empty());
+            assert_eq!(task.uploading_count, 0);
+            assert_eq!(task.uploaded_count, 0);
+            assert!(!task.is_finished());
+
+            // Test download_task_finished.
+            metadata.download_task_finished(task_id).unwrap();
+            let task = metadata.get_task(task_id).unwrap().unwrap();
+            assert!(task.is_finished());
+
+            // Test download_task_corrupted.
+            let piece_id = metadata.piece_id(task_id, 0);
+            metadata.download_piece_started(&piece_id, 0).unwrap();
+            metadata
+                .download_piece_finished(&piece_id, 0, 1024, "digest", None)
+                .unwrap();
+            let task = metadata
+                .download_task_corrupted(task_id, &[piece_id.clone()])
+                .unwrap();
+            assert!(!task.is_finished());
+            assert!(metadata.get_piece(&piece_id).unwrap().is_none());
+            metadata.download_task_finished(task_id).unwrap();
+
+            // Test upload_task_started.
+            metadata.upload_task_started(task_id).unwrap();
+            let task = metadata.get_task(task_id).unwrap().unwrap();
+            assert_eq!(task.uploading_count, 1);
+
+            // Test upload_task_finished.
+            metadata.upload_task_finished(task_id).unwrap();
+            let task = metadata.get_task(task_id).unwrap().unwrap();
+            assert_eq!(task.uploading_count, 0);
+            assert_eq!(task.uploaded_count, 1);
+
+            // Test upload_task_failed.
+            let task = metadata.upload_task_started(task_id).unwrap();
+            assert_eq!(task.uploading_count, 1);
+            let task = metadata.upload_task_failed(task_id).unwrap();
+            assert_eq!(task.uploading_count, 0);
+            assert_eq!(task.uploaded_count, 1);
+
+            // Test get_tasks.
+            let task_id = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
+            metadata
+                .download_task_started(task_id, 1024, 0, None)
+                .unwrap();
+            let tasks = metadata.get_tasks().unwrap();
+            assert_eq!(tasks.len(), 2);
+
+            // Test delete_task.
+            metadata.delete_task(task_id).unwrap();
+            let task = metadata.get_task(task_id).unwrap();
+            assert!(task.is_none());
+        }
     }
 
     #[test]
     fn test_cache_task_lifecycle() {
-        let dir = tempdir().unwrap();
-        let log_dir = dir.path().join("log");
-        let metadata = Metadata::new(Arc::new(Config::default()), dir.path(), &log_dir).unwrap();
-        let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
-
-        // Test download_task_started.
-        metadata
-            .download_cache_task_started(task_id, 1024, 1024, None)
-            .unwrap();
-        let task = metadata
-            .get_cache_task(task_id)
-            .unwrap()
-            .expect("task should exist after download_cache_task_started");
-        assert_eq!(task.id, task_id);
-        assert_eq!(task.piece_length, Some(1024));
-        assert_eq!(task.content_length, Some(1024));
-        assert!(task.response_header.is_empty());
-        assert_eq!(task.uploading_count, 0);
-        assert_eq!(task.uploaded_count, 0);
-        assert!(!task.is_finished());
-
-        // Test download_cache_task_finished.
-        metadata.download_cache_task_finished(task_id).unwrap();
-        let task = metadata.get_cache_task(task_id).unwrap().unwrap();
-        assert!(task.is_finished());
-
-        // Test upload_cache_task_started.
-        metadata.upload_cache_task_started(task_id).unwrap();
-        let task = metadata.get_cache_task(task_id).unwrap().unwrap();
-        assert_eq!(task.uploading_count, 1);
-
-        // Test upload_cache_task_finished.
-        metadata.upload_cache_task_finished(task_id).unwrap();
-        let task = metadata.get_cache_task(task_id).unwrap().unwrap();
-        assert_eq!(task.uploading_count, 0);
-        assert_eq!(task.uploaded_count, 1);
-
-        // Test upload_cache_task_failed.
-        let task = metadata.upload_cache_task_started(task_id).unwrap();
-        assert_eq!(task.uploading_count, 1);
-        let task = metadata.upload_cache_task_failed(task_id).unwrap();
-        assert_eq!(task.uploading_count, 0);
-        assert_eq!(task.uploaded_count, 1);
-
-        // Test get_cache_tasks.
-        let task_id = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
-        metadata
-            .download_cache_task_started(task_id, 1024, 0, None)
-            .unwrap();
-        let tasks = metadata.get_cache_tasks().unwrap();
-        assert_eq!(tasks.len(), 2);
-
-        // Test delete_cache_task.
-        metadata.delete_cache_task(task_id).unwrap();
-        let task = metadata.get_cache_task(task_id).unwrap();
-        assert!(task.is_none());
+        for engine in ENGINES {
+            let dir = tempdir().unwrap();
+            let log_dir = dir.path().join("log");
+            let metadata = Metadata::new(new_config(engine), dir.path(), &log_dir).unwrap();
+            let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+
+            // Test download_task_started.
+            metadata
+                .download_cache_task_started(task_id, 1024, 1024, None)
+                .unwrap();
+            let task = metadata
+                .get_cache_task(task_id)
+                .unwrap()
+                .expect("task should exist after download_cache_task_started");
+            assert_eq!(task.id, task_id);
+            assert_eq!(task.piece_length, Some(1024));
+            assert_eq!(task.content_length, Some(1024));
+            assert!(task.response_header.is_empty());
+            assert_eq!(task.uploading_count, 0);
+            assert_eq!(task.uploaded_count, 0);
+            assert!(!task.is_finished());
+
+            // Test download_cache_task_finished.
+            metadata.download_cache_task_finished(task_id).unwrap();
+            let task = metadata.get_cache_task(task_id).unwrap().unwrap();
+            assert!(task.is_finished());
+
+            // Test upload_cache_task_started.
+            metadata.upload_cache_task_started(task_id).unwrap();
+            let task = metadata.get_cache_task(task_id).unwrap().unwrap();
+            assert_eq!(task.uploading_count, 1);
+
+            // Test upload_cache_task_finished.
+            metadata.upload_cache_task_finished(task_id).unwrap();
+            let task = metadata.get_cache_task(task_id).unwrap().unwrap();
+            assert_eq!(task.uploading_count, 0);
+            assert_eq!(task.uploaded_count, 1);
+
+            // Test upload_cache_task_failed.
+            let task = metadata.upload_cache_task_started(task_id).unwrap();
+            assert_eq!(task.uploading_count, 1);
+            let task = metadata.upload_cache_task_failed(task_id).unwrap();
+            assert_eq!(task.uploading_count, 0);
+            assert_eq!(task.uploaded_count, 1);
+
+            // Test get_cache_tasks.
+            let task_id = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
+            metadata
+                .download_cache_task_started(task_id, 1024, 0, None)
+                .unwrap();
+            let tasks = metadata.get_cache_tasks().unwrap();
+            assert_eq!(tasks.len(), 2);
+
+            // Test delete_cache_task.
+            metadata.delete_cache_task(task_id).unwrap();
+            let task = metadata.get_cache_task(task_id).unwrap();
+            assert!(task.is_none());
+        }
     }
 
     #[test]
     fn test_piece_lifecycle() {
+        for engine in ENGINES {
+            let dir = tempdir().unwrap();
+            let log_dir = dir.path().join("log");
+            let metadata = Metadata::new(new_config(engine), dir.path(), &log_dir).unwrap();
+            let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+            let piece_id = metadata.piece_id(task_id, 1);
+
+            // Test download_piece_started.
+            metadata
+                .download_piece_started(piece_id.as_str(), 1)
+                .unwrap();
+            let piece = metadata.get_piece(piece_id.as_str()).unwrap().unwrap();
+            assert_eq!(piece.number, 1);
+
+            // Test download_piece_finished.
+            metadata
+                .download_piece_finished(piece_id.as_str(), 0, 1024, "digest1", None)
+                .unwrap();
+            let piece = metadata.get_piece(piece_id.as_str()).unwrap().unwrap();
+            assert_eq!(piece.length, 1024);
+            assert_eq!(piece.digest, "digest1");
+
+            // Test get_pieces.
+            metadata
+                .download_piece_started(metadata.piece_id(task_id, 2).as_str(), 2)
+                .unwrap();
+            metadata
+                .download_piece_started(metadata.piece_id(task_id, 3).as_str(), 3)
+                .unwrap();
+            let pieces = metadata.get_pieces(task_id).unwrap();
+            assert_eq!(pieces.len(), 3);
+
+            // Test download_piece_failed.
+            let piece_id = metadata.piece_id(task_id, 2);
+            metadata
+                .download_piece_started(piece_id.as_str(), 2)
+                .unwrap();
+            metadata
+                .download_piece_started(metadata.piece_id(task_id, 3).as_str(), 3)
+                .unwrap();
+            metadata.download_piece_failed(piece_id.as_str()).unwrap();
+            let piece = metadata.get_piece(piece_id.as_str()).unwrap();
+            assert!(piece.is_none());
+
+            // Test delete_pieces.
+            metadata.delete_pieces(task_id).unwrap();
+            let pieces = metadata.get_pieces(task_id).unwrap();
+            assert!(pieces.is_empty());
+        }
+    }
+
+    #[test]
+    fn should_migrate_metadata_between_engines() {
         let dir = tempdir().unwrap();
         let log_dir = dir.path().join("log");
-        let metadata = Metadata::new(Arc::new(Config::default()), dir.path(), &log_dir).unwrap();
         let task_id = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
-        let piece_id = metadata.piece_id(task_id, 1);
 
-        // Test download_piece_started.
-        metadata
-            .download_piece_started(piece_id.as_str(), 1)
+        let source = Metadata::open(MetadataEngine::Rocksdb, dir.path(), &log_dir, false).unwrap();
+        source
+            .download_task_started(task_id, 1024, 2048, None)
             .unwrap();
-        let piece = metadata.get_piece(piece_id.as_str()).unwrap().unwrap();
-        assert_eq!(piece.number, 1);
+        source.download_task_finished(task_id).unwrap();
+        for number in 0..2 {
+            let piece_id = source.piece_id(task_id, number);
+            source.download_piece_started(&piece_id, number).unwrap();
+            source
+                .download_piece_finished(&piece_id, number as u64 * 1024, 1024, "digest", None)
+                .unwrap();
+        }
 
-        // Test download_piece_finished.
-        metadata
-            .download_piece_finished(piece_id.as_str(), 0, 1024, "digest1", None)
-            .unwrap();
-        let piece = metadata.get_piece(piece_id.as_str()).unwrap().unwrap();
-        assert_eq!(piece.length, 1024);
-        assert_eq!(piece.digest, "digest1");
+        let destination =
+            Metadata::open(MetadataEngine::Redb, dir.path(), &log_dir, false).unwrap();
+        assert_eq!(source.migrate_to(&destination).unwrap(), 3);
 
-        // Test get_pieces.
-        metadata
-            .download_piece_started(metadata.piece_id(task_id, 2).as_str(), 2)
-            .unwrap();
-        metadata
-            .download_piece_started(metadata.piece_id(task_id, 3).as_str(), 3)
-            .unwrap();
-        let pieces = metadata.get_pieces(task_id).unwrap();
-        assert_eq!(pieces.len(), 3);
+        // The migrated metadata is persisted after the destination is reopened.
+        drop(destination);
+        let destination = Metadata::open(MetadataEngine::Redb, dir.path(), &log_dir, true).unwrap();
 
-        // Test download_piece_failed.
-        let piece_id = metadata.piece_id(task_id, 2);
-        metadata
-            .download_piece_started(piece_id.as_str(), 2)
-            .unwrap();
-        metadata
-            .download_piece_started(metadata.piece_id(task_id, 3).as_str(), 3)
-            .unwrap();
-        metadata.download_piece_failed(piece_id.as_str()).unwrap();
-        let piece = metadata.get_piece(piece_id.as_str()).unwrap();
-        assert!(piece.is_none());
-
-        // Test delete_pieces.
-        metadata.delete_pieces(task_id).unwrap();
-        let pieces = metadata.get_pieces(task_id).unwrap();
-        assert!(pieces.is_empty());
+        let task = destination.get_task(task_id).unwrap().unwrap();
+        assert_eq!(task, source.get_task(task_id).unwrap().unwrap());
+        assert!(task.is_finished());
+        assert_eq!(
+            destination.get_pieces(task_id).unwrap(),
+            source.get_pieces(task_id).unwrap()
+        );
+
+        // Migrate the metadata back to the rocksdb.
+        let target_dir = tempdir().unwrap();
+        let target =
+            Metadata::open(MetadataEngine::Rocksdb, target_dir.path(), &log_dir, false).unwrap();
+        assert_eq!(destination.migrate_to(&target).unwrap(), 3);
+        assert_eq!(target.get_pieces(task_id).unwrap().len(), 2);
     }
 }
@@ -0,0 +1,811 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::content::{
+    relative_task_path, DEFAULT_PERSISTENT_CACHE_TASK_DIR, DEFAULT_PERSISTENT_TASK_DIR,
+    DEFAULT_TASK_DIR,
+};
+use crate::{metadata, Storage};
+use chrono::{NaiveDateTime, Utc};
+use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_core::Result;
+use dragonfly_client_metric::{
+    collect_storage_scrub_finding_metrics, collect_storage_scrub_finished_metrics,
+};
+use dragonfly_client_util::digest::{Algorithm, Digest};
+use dragonfly_client_util::shutdown;
+use leaky_bucket::RateLimiter;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, instrument, warn};
+
+/// ORPHAN_FILE_GRACE_PERIOD is the grace period of the orphan content file. The content is
+/// created before the metadata when the task downloads started, so the content file modified
+/// in the grace period is not treated as orphan.
+const ORPHAN_FILE_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// ACTIVE_TASK_GRACE_PERIOD is the grace period of the active task. The task updated in the
+/// grace period may be downloaded or uploaded, so it is not scrubbed.
+const ACTIVE_TASK_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// MISSING_CONTENT_FINDING is the finding type of the task whose content file is missing.
+const MISSING_CONTENT_FINDING: &str = "missing_content";
+
+/// CORRUPTED_PIECE_FINDING is the finding type of the piece whose length or digest mismatches.
+const CORRUPTED_PIECE_FINDING: &str = "corrupted_piece";
+
+/// ORPHAN_FILE_FINDING is the finding type of the content file without metadata.
+const ORPHAN_FILE_FINDING: &str = "orphan_file";
+
+/// ScrubReport is the report of the storage scrub.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrubReport {
+    /// started_at is the time when the scrub started.
+    pub started_at: Option<NaiveDateTime>,
+
+    /// finished_at is the time when the scrub finished.
+    pub finished_at: Option<NaiveDateTime>,
+
+    /// scanned_tasks is the number of the scanned tasks.
+    pub scanned_tasks: u64,
+
+    /// scanned_pieces is the number of the scanned finished pieces.
+    pub scanned_pieces: u64,
+
+    /// scanned_bytes is the number of the bytes read to verify the pieces.
+    pub scanned_bytes: u64,
+
+    /// missing_contents is the number of the tasks whose content file is missing, the
+    /// metadata of the tasks is deleted.
+    pub missing_contents: u64,
+
+    /// corrupted_pieces is the number of the pieces whose length or digest mismatches, the
+    /// pieces are deleted to be downloaded again.
+    pub corrupted_pieces: u64,
+
+    /// orphan_files is the number of the content files without metadata, the files are
+    /// deleted.
+    pub orphan_files: u64,
+
+    /// errors is the number of the tasks failed to scrub.
+    pub errors: u64,
+}
+
+/// TaskKind is the kind of the task stored in the content directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskKind {
+    /// Task is the task downloaded by the dfdaemon.
+    Task,
+
+    /// PersistentTask is the persistent task.
+    PersistentTask,
+
+    /// PersistentCacheTask is the persistent cache task.
+    PersistentCacheTask,
+}
+
+/// TaskKind implements the task kind.
+impl TaskKind {
+    /// ALL is all kinds of the task.
+    const ALL: [TaskKind; 3] = [
+        TaskKind::Task,
+        TaskKind::PersistentTask,
+        TaskKind::PersistentCacheTask,
+    ];
+
+    /// dir returns the directory of the task kind in the content directory.
+    fn dir(&self) -> &'static str {
+        match self {
+            TaskKind::Task => DEFAULT_TASK_DIR,
+            TaskKind::PersistentTask => DEFAULT_PERSISTENT_TASK_DIR,
+            TaskKind::PersistentCacheTask => DEFAULT_PERSISTENT_CACHE_TASK_DIR,
+        }
+    }
+}
+
+/// Scrubber reconciles the metadata of the tasks and pieces with the content files on the
+/// disks. It runs when the dfdaemon starts and periodically.
+pub struct Scrubber {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
+    /// storage is the local storage.
+    storage: Arc<Storage>,
+
+    /// deleted_task_tx sends the id of the task deleted by the scrubber, so the task is
+    /// deleted from the scheduler.
+    deleted_task_tx: Option<mpsc::UnboundedSender<String>>,
+
+    /// rate_limiter limits the rate of reading the content to verify the pieces.
+    rate_limiter: RateLimiter,
+
+    /// orphan_file_grace_period is the grace period of the orphan content file.
+    orphan_file_grace_period: Duration,
+
+    /// active_task_grace_period is the grace period of the active task.
+    active_task_grace_period: Duration,
+
+    /// last_report is the report of the last finished scrub.
+    last_report: RwLock<Option<ScrubReport>>,
+
+    /// shutdown is used to shutdown the scrubber.
+    shutdown: shutdown::Shutdown,
+
+    /// _shutdown_complete is used to notify the scrubber is shutdown.
+    _shutdown_complete: mpsc::UnboundedSender<()>,
+}
+
+/// Scrubber implements the scrub of the storage.
+impl Scrubber {
+    /// new creates a new Scrubber.
+    pub fn new(
+        config: Arc<Config>,
+        storage: Arc<Storage>,
+        deleted_task_tx: Option<mpsc::UnboundedSender<String>>,
+        shutdown: shutdown::Shutdown,
+        shutdown_complete_tx: mpsc::UnboundedSender<()>,
+    ) -> Self {
+        let rate_limit = config.storage.scrub.rate_limit.as_u64() as usize;
+        let rate_limiter = RateLimiter::builder()
+            .initial(rate_limit)
+            .refill(rate_limit)
+            .max(rate_limit)
+            .interval(Duration::from_secs(1))
+            .fair(false)
+            .build();
+
+        Self {
+            config,
+            storage,
+            deleted_task_tx,
+            rate_limiter,
+            orphan_file_grace_period: ORPHAN_FILE_GRACE_PERIOD,
+            active_task_grace_period: ACTIVE_TASK_GRACE_PERIOD,
+            last_report: RwLock::new(None),
+            shutdown,
+            _shutdown_complete: shutdown_complete_tx,
+        }
+    }
+
+    /// run runs the scrubber, the first scrub starts immediately.
+    pub async fn run(&self) {
+        // Clone the shutdown channel.
+        let mut shutdown = self.shutdown.clone();
+
+        // If the scrub is disabled, wait for the shutdown.
+        if !self.config.storage.scrub.enable {
+            info!("scrub is disabled");
+            shutdown.recv().await;
+            info!("scrubber shutting down");
+            return;
+        }
+
+        // Start the scrub loop.
+        let mut interval = tokio::time::interval(self.config.storage.scrub.interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(err) = self.scrub().await {
+                        error!("failed to scrub storage: {}", err);
+                    }
+                }
+                _ = shutdown.recv() => {
+                    // Shutdown the scrubber.
+                    info!("scrubber shutting down");
+                    return
+                }
+            }
+        }
+    }
+
+    /// last_report returns the report of the last finished scrub.
+    pub fn last_report(&self) -> Option<ScrubReport> {
+        self.last_report.read().unwrap().clone()
+    }
+
+    /// scrub scrubs the storage once. The task whose content file is missing is deleted, the
+    /// corrupted pieces are deleted, and the orphan content files are deleted. The active tasks
+    /// are skipped.
+    #[instrument(skip_all)]
+    pub async fn scrub(&self) -> Result<ScrubReport> {
+        info!("start to scrub storage");
+        let start_time = Instant::now();
+        let mut report = ScrubReport {
+            started_at: Some(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        self.scrub_tasks(&mut report).await?;
+        self.scrub_persistent_tasks(&mut report).await?;
+        self.scrub_persistent_cache_tasks(&mut report).await?;
+        self.scrub_orphan_files(&mut report).await;
+        report.finished_at = Some(Utc::now().naive_utc());
+
+        // Collect the scrub metrics.
+        collect_storage_scrub_finished_metrics(start_time.elapsed());
+        collect_storage_scrub_finding_metrics(MISSING_CONTENT_FINDING, report.missing_contents);
+        collect_storage_scrub_finding_metrics(CORRUPTED_PIECE_FINDING, report.corrupted_pieces);
+        collect_storage_scrub_finding_metrics(ORPHAN_FILE_FINDING, report.orphan_files);
+
+        info!("scrub storage done: {:?}", report);
+        *self.last_report.write().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// scrub_tasks scrubs the tasks, the corrupted pieces are deleted and the task is marked
+    /// as unfinished, so the pieces will be downloaded again. The task whose content file is
+    /// missing is deleted from the storage and the scheduler.
+    async fn scrub_tasks(&self, report: &mut ScrubReport) -> Result<()> {
+        for task in self.storage.get_tasks()? {
+            // The task without content length has not created the content file.
+            if task.content_length().is_none() {
+                continue;
+            }
+
+            let Some(pieces) = self.get_inactive_pieces(&task.id, task.updated_at, report) else {
+                continue;
+            };
+
+            report.scanned_tasks += 1;
+            if !self.content_exists(TaskKind::Task, &task.id).await {
+                warn!("content of task {} is missing", task.id);
+                report.missing_contents += 1;
+                self.storage.delete_task(&task.id).await;
+                self.delete_task_from_scheduler(&task.id);
+                continue;
+            }
+
+            match self
+                .verify_pieces(TaskKind::Task, &task.id, pieces, report)
+                .await
+            {
+                Ok(piece_ids) if piece_ids.is_empty() => {}
+                Ok(piece_ids) => {
+                    if let Err(err) = self
+                        .storage
+                        .metadata
+                        .download_task_corrupted(&task.id, &piece_ids)
+                    {
+                        error!(
+                            "delete corrupted pieces of task {} failed: {}",
+                            task.id, err
+                        );
+                        report.errors += 1;
+                    }
+                }
+                Err(err) => {
+                    error!("verify pieces of task {} failed: {}", task.id, err);
+                    report.errors += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// scrub_persistent_tasks scrubs the persistent tasks, the corrupted pieces are deleted and
+    /// the persistent task is marked as unfinished, so the pieces will be downloaded again.
+    async fn scrub_persistent_tasks(&self, report: &mut ScrubReport) -> Result<()> {
+        for task in self.storage.get_persistent_tasks()? {
+            let Some(pieces) = self.get_inactive_pieces(&task.id, task.updated_at, report) else {
+                continue;
+            };
+
+            report.scanned_tasks += 1;
+            if !self
+                .content_exists(TaskKind::PersistentTask, &task.id)
+                .await
+            {
+                warn!("content of persistent task {} is missing", task.id);
+                report.missing_contents += 1;
+                self.storage.delete_persistent_task(&task.id).await;
+                continue;
+            }
+
+            match self
+                .verify_pieces(TaskKind::PersistentTask, &task.id, pieces, report)
+                .await
+            {
+                Ok(piece_ids) if piece_ids.is_empty() => {}
+                Ok(piece_ids) => {
+                    if let Err(err) = self
+                        .storage
+                        .metadata
+                        .download_persistent_task_corrupted(&task.id, &piece_ids)
+                    {
+                        error!(
+                            "delete corrupted pieces of persistent task {} failed: {}",
+                            task.id, err
+                        );
+                        report.errors += 1;
+                    }
+                }
+                Err(err) => {
+                    error!(
+                        "verify pieces of persistent task {} failed: {}",
+                        task.id, err
+                    );
+                    report.errors += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// scrub_persistent_cache_tasks scrubs the persistent cache tasks, the corrupted pieces are
+    /// deleted and the persistent cache task is marked as unfinished, so the pieces will be
+    /// downloaded again.
+    async fn scrub_persistent_cache_tasks(&self, report: &mut ScrubReport) -> Result<()> {
+        for task in self.storage.get_persistent_cache_tasks()? {
+            let Some(pieces) = self.get_inactive_pieces(&task.id, task.updated_at, report) else {
+                continue;
+            };
+
+            report.scanned_tasks += 1;
+            if !self
+                .content_exists(TaskKind::PersistentCacheTask, &task.id)
+                .await
+            {
+                warn!("content of persistent cache task {} is missing", task.id);
+                report.missing_contents += 1;
+                self.storage.delete_persistent_cache_task(&task.id).await;
+                continue;
+            }
+
+            match self
+                .verify_pieces(TaskKind::PersistentCacheTask, &task.id, pieces, report)
+                .await
+            {
+                Ok(piece_ids) if piece_ids.is_empty() => {}
+                Ok(piece_ids) => {
+                    if let Err(err) = self
+                        .storage
+                        .metadata
+                        .download_persistent_cache_task_corrupted(&task.id, &piece_ids)
+                    {
+                        error!(
+                            "delete corrupted pieces of persistent cache task {} failed: {}",
+                            task.id, err
+                        );
+                        report.errors += 1;
+                    }
+                }
+                Err(err) => {
+                    error!(
+                        "verify pieces of persistent cache task {} failed: {}",
+                        task.id, err
+                    );
+                    report.errors += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// get_inactive_pieces returns the pieces of the task if the task is not active. The task
+    /// updated in the grace period or with the started and unfinished pieces may be downloading,
+    /// so None is returned to skip the task.
+    fn get_inactive_pieces(
+        &self,
+        task_id: &str,
+        updated_at: NaiveDateTime,
+        report: &mut ScrubReport,
+    ) -> Option<Vec<metadata::Piece>> {
+        let elapsed = Utc::now()
+            .naive_utc()
+            .signed_duration_since(updated_at)
+            .to_std()
+            .unwrap_or_default();
+        if elapsed < self.active_task_grace_period {
+            debug!("skip task {} updated recently", task_id);
+            return None;
+        }
+
+        let pieces = match self.storage.get_pieces(task_id) {
+            Ok(pieces) => pieces,
+            Err(err) => {
+                error!("get pieces of task {} failed: {}", task_id, err);
+                report.errors += 1;
+                return None;
+            }
+        };
+
+        if pieces.iter().any(|piece| !piece.is_finished()) {
+            debug!("skip task {} with unfinished pieces", task_id);
+            return None;
+        }
+
+        Some(pieces)
+    }
+
+    /// delete_task_from_scheduler sends the id of the deleted task, so the task is deleted
+    /// from the scheduler like the task evicted by the garbage collector.
+    fn delete_task_from_scheduler(&self, task_id: &str) {
+        if let Some(deleted_task_tx) = &self.deleted_task_tx {
+            deleted_task_tx
+                .send(task_id.to_string())
+                .unwrap_or_else(|err| {
+                    error!("send deleted task {} failed: {}", task_id, err);
+                });
+        }
+    }
+
+    /// scrub_orphan_files deletes the content files without metadata, and the duplicated
+    /// content files which are not located by the disks.
+    async fn scrub_orphan_files(&self, report: &mut ScrubReport) {
+        let disks = &self.storage.content.disks;
+        for dir in disks.dirs() {
+            for kind in TaskKind::ALL {
+                let entries = walkdir::WalkDir::new(dir.join(kind.dir()))
+                    .min_depth(2)
+                    .max_depth(2)
+                    .into_iter()
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().is_file());
+
+                for entry in entries {
+                    let Some(task_id) = entry.file_name().to_str() else {
+                        continue;
+                    };
+
+                    if !self.is_orphan_file(kind, &dir, task_id, entry.path()).await {
+                        continue;
+                    }
+
+                    info!("delete orphan content file {:?}", entry.path());
+                    match tokio::fs::remove_file(entry.path()).await {
+                        Ok(_) => report.orphan_files += 1,
+                        Err(err) => {
+                            error!("remove {:?} failed: {}", entry.path(), err);
+                            report.errors += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// is_orphan_file checks whether the content file is orphan. The content file is orphan if
+    /// the metadata of the task does not exist, or the content is located in another disk.
+    async fn is_orphan_file(&self, kind: TaskKind, dir: &Path, task_id: &str, path: &Path) -> bool {
+        // The task id is the sha256 hex, skip the files created by others.
+        if task_id.len() < 3 || !task_id.is_ascii() {
+            return false;
+        }
+
+        // Skip the content file which may be created by the downloading task.
+        let modified_at = std::fs::metadata(path).and_then(|metadata| metadata.modified());
+        match modified_at {
+            Ok(modified_at) => {
+                let elapsed = SystemTime::now()
+                    .duration_since(modified_at)
+                    .unwrap_or_default();
+                if elapsed < self.orphan_file_grace_period {
+                    return false;
+                }
+            }
+            Err(err) => {
+                warn!("get modified time of {:?} failed: {}", path, err);
+                return false;
+            }
+        }
+
+        let exists = match kind {
+            TaskKind::Task => self.storage.is_task_exists(task_id),
+            TaskKind::PersistentTask => self.storage.is_persistent_task_exists(task_id),
+            TaskKind::PersistentCacheTask => self.storage.is_persistent_cache_task_exists(task_id),
+        };
+
+        match exists {
+            Ok(true) => {
+                let relative_path = relative_task_path(kind.dir(), task_id);
+                self.storage.content.disks.dir(&relative_path).await != Some(dir)
+            }
+            Ok(false) => true,
+            Err(err) => {
+                error!("check metadata of {} failed: {}", task_id, err);
+                false
+            }
+        }
+    }
+
+    /// content_exists checks whether the content file of the task exists in the disks.
+    async fn content_exists(&self, kind: TaskKind, task_id: &str) -> bool {
+        let path = self
+            .storage
+            .content
+            .disks
+            .path(&relative_task_path(kind.dir(), task_id))
+            .await;
+
+        tokio::fs::try_exists(&path).await.unwrap_or_else(|err| {
+            warn!("check content {:?} failed: {}", path, err);
+            true
+        })
+    }
+
+    /// verify_pieces verifies the length and digest of the finished pieces of the task, and
+    /// returns the ids of the corrupted pieces.
+    async fn verify_pieces(
+        &self,
+        kind: TaskKind,
+        task_id: &str,
+        pieces: Vec<metadata::Piece>,
+        report: &mut ScrubReport,
+    ) -> Result<Vec<String>> {
+        let mut corrupted_piece_ids = Vec::new();
+        for piece in pieces {
+            report.scanned_pieces += 1;
+            report.scanned_bytes += piece.length;
+            if !self.verify_piece(kind, task_id, &piece).await? {
+                warn!(
+                    "piece {} of task {} is corrupted, expected digest {}",
+                    piece.number, task_id, piece.digest
+                );
+                report.corrupted_pieces += 1;
+                corrupted_piece_ids.push(self.storage.piece_id(task_id, piece.number));
+            }
+        }
+
+        Ok(corrupted_piece_ids)
+    }
+
+    /// verify_piece reads the piece from the content, and checks whether the length and crc32
+    /// digest of the piece match the metadata.
+    async fn verify_piece(
+        &self,
+        kind: TaskKind,
+        task_id: &str,
+        piece: &metadata::Piece,
+    ) -> Result<bool> {
+        self.rate_limiter.acquire(piece.length as usize).await;
+        let content = &self.storage.content;
+        match kind {
+            TaskKind::Task => {
+                let reader = content
+                    .read_piece(task_id, piece.offset, piece.length, None)
+                    .await?;
+                verify_piece_content(reader, piece).await
+            }
+            TaskKind::PersistentTask => {
+                let reader = content
+                    .read_persistent_piece(task_id, piece.offset, piece.length, None)
+                    .await?;
+                verify_piece_content(reader, piece).await
+            }
+            TaskKind::PersistentCacheTask => {
+                let reader = content
+                    .read_persistent_cache_piece(task_id, piece.offset, piece.length, None)
+                    .await?;
+                verify_piece_content(reader, piece).await
+            }
+        }
+    }
+}
+
+/// verify_piece_content checks whether the length and crc32 digest of the content read from
+/// the reader match the piece metadata. The piece without digest only checks the length.
+async fn verify_piece_content<R: AsyncRead>(reader: R, piece: &metadata::Piece) -> Result<bool> {
+    tokio::pin!(reader);
+
+    let mut hasher = crc32fast::Hasher::new();
+    let mut length = 0;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..n]);
+        length += n as u64;
+    }
+
+    if length != piece.length {
+        return Ok(false);
+    }
+
+    if piece.digest.is_empty() {
+        return Ok(true);
+    }
+
+    let digest = Digest::new(Algorithm::Crc32, hasher.finalize().to_string());
+    Ok(digest.to_string() == piece.digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    const TASK_ID: &str = "d3c4e940ad06c47fc36ac67801e6f8e36cb400e2391708620bc7e865b102062c";
+
+    async fn new_scrubber(dir: &Path) -> Scrubber {
+        let config = Arc::new(Config::default());
+        let storage = Storage::new(config.clone(), dir, dir.join("log"))
+            .await
+            .unwrap();
+        let (shutdown_complete_tx, _) = mpsc::unbounded_channel();
+        let mut scrubber = Scrubber::new(
+            config,
+            Arc::new(storage),
+            None,
+            shutdown::Shutdown::default(),
+            shutdown_complete_tx,
+        );
+        scrubber.orphan_file_grace_period = Duration::ZERO;
+        scrubber.active_task_grace_period = Duration::ZERO;
+        scrubber
+    }
+
+    async fn download_task(storage: &Storage, content: &[u8], piece_length: u64) {
+        storage
+            .download_task_started(TASK_ID, piece_length, content.len() as u64, None)
+            .await
+            .unwrap();
+
+        for (number, chunk) in content.chunks(piece_length as usize).enumerate() {
+            let piece_id = storage.piece_id(TASK_ID, number as u32);
+            storage
+                .download_piece_started(&piece_id, number as u32)
+                .await
+                .unwrap();
+            storage
+                .download_piece_from_source_finished(
+                    &piece_id,
+                    TASK_ID,
+                    number as u64 * piece_length,
+                    chunk.len() as u64,
+                    &mut Cursor::new(chunk),
+                    Duration::from_secs(5),
+                )
+                .await
+                .unwrap();
+        }
+
+        storage.download_task_finished(TASK_ID).unwrap();
+    }
+
+    fn task_path(dir: &Path) -> std::path::PathBuf {
+        dir.join(crate::content::DEFAULT_CONTENT_DIR)
+            .join(relative_task_path(DEFAULT_TASK_DIR, TASK_ID))
+    }
+
+    #[tokio::test]
+    async fn should_verify_piece_content() {
+        let content = b"hello dragonfly";
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(content);
+        let piece = metadata::Piece {
+            length: content.len() as u64,
+            digest: Digest::new(Algorithm::Crc32, hasher.finalize().to_string()).to_string(),
+            ..Default::default()
+        };
+
+        assert!(verify_piece_content(&content[..], &piece).await.unwrap());
+        assert!(!verify_piece_content(&b"hello dragonflx"[..], &piece)
+            .await
+            .unwrap());
+        assert!(!verify_piece_content(&content[..5], &piece).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_mark_corrupted_pieces_failed() {
+        let dir = tempdir().unwrap();
+        let scrubber = new_scrubber(dir.path()).await;
+        download_task(&scrubber.storage, b"0123456789abcdef", 8).await;
+
+        let report = scrubber.scrub().await.unwrap();
+        assert_eq!(report.scanned_tasks, 1);
+        assert_eq!(report.scanned_pieces, 2);
+        assert_eq!(report.corrupted_pieces, 0);
+
+        // Corrupt the second piece.
+        let path = task_path(dir.path());
+        tokio::fs::write(&path, b"0123456789abcdeX").await.unwrap();
+
+        let report = scrubber.scrub().await.unwrap();
+        assert_eq!(report.corrupted_pieces, 1);
+        assert_eq!(scrubber.last_report(), Some(report));
+
+        let task = scrubber.storage.get_task(TASK_ID).unwrap().unwrap();
+        assert!(!task.is_finished());
+        assert!(scrubber
+            .storage
+            .get_piece(&scrubber.storage.piece_id(TASK_ID, 0))
+            .unwrap()
+            .is_some());
+        assert!(scrubber
+            .storage
+            .get_piece(&scrubber.storage.piece_id(TASK_ID, 1))
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn should_delete_task_with_missing_content() {
+        let dir = tempdir().unwrap();
+        let mut scrubber = new_scrubber(dir.path()).await;
+        let (deleted_task_tx, mut deleted_task_rx) = mpsc::unbounded_channel();
+        scrubber.deleted_task_tx = Some(deleted_task_tx);
+        download_task(&scrubber.storage, b"0123456789abcdef", 8).await;
+
+        tokio::fs::remove_file(task_path(dir.path())).await.unwrap();
+
+        let report = scrubber.scrub().await.unwrap();
+        assert_eq!(report.missing_contents, 1);
+        assert!(scrubber.storage.get_task(TASK_ID).unwrap().is_none());
+        assert!(scrubber.storage.get_pieces(TASK_ID).unwrap().is_empty());
+        assert_eq!(deleted_task_rx.try_recv().unwrap(), TASK_ID);
+    }
+
+    #[tokio::test]
+    async fn should_skip_active_tasks() {
+        let dir = tempdir().unwrap();
+        let mut scrubber = new_scrubber(dir.path()).await;
+        download_task(&scrubber.storage, b"0123456789abcdef", 8).await;
+        tokio::fs::remove_file(task_path(dir.path())).await.unwrap();
+
+        // The task updated in the grace period is skipped.
+        scrubber.active_task_grace_period = Duration::from_secs(60);
+        let report = scrubber.scrub().await.unwrap();
+        assert_eq!(report.scanned_tasks, 0);
+        assert!(scrubber.storage.get_task(TASK_ID).unwrap().is_some());
+
+        // The task with the started and unfinished pieces is skipped.
+        scrubber.active_task_grace_period = Duration::ZERO;
+        let piece_id = scrubber.storage.piece_id(TASK_ID, 2);
+        scrubber
+            .storage
+            .metadata
+            .download_piece_started(&piece_id, 2)
+            .unwrap();
+        let report = scrubber.scrub().await.unwrap();
+        assert_eq!(report.scanned_tasks, 0);
+        assert_eq!(report.missing_contents, 0);
+        assert!(scrubber.storage.get_task(TASK_ID).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn should_delete_orphan_files() {
+        let dir = tempdir().unwrap();
+        let scrubber = new_scrubber(dir.path()).await;
+        download_task(&scrubber.storage, b"0123456789abcdef", 8).await;
+
+        let orphan_task_id = "a535b115f18d96870f0422ac891f91dd162f2f391e4778fb84279701fcd02dd1";
+        let orphan_path = dir
+            .path()
+            .join(crate::content::DEFAULT_CONTENT_DIR)
+            .join(relative_task_path(DEFAULT_TASK_DIR, orphan_task_id));
+        tokio::fs::create_dir_all(orphan_path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&orphan_path, b"orphan").await.unwrap();
+
+        let report = scrubber.scrub().await.unwrap();
+        assert_eq!(report.orphan_files, 1);
+        assert!(!orphan_path.exists());
+        assert!(task_path(dir.path()).exists());
+    }
+}
@@ -14,12 +14,16 @@
  * limitations under the License.
  */
 
+use dragonfly_client_config::dfdaemon::MetadataEngine;
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
     Result,
 };
+use either::Either;
 use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
 
+pub mod redb;
 pub mod rocksdb;
 
 /// DatabaseObject marks a type can be stored in database, which has a namespace.
@@ -85,4 +89,219 @@ pub trait Operations {
 
     // batch_delete deletes objects by keys.
     fn batch_delete<O: DatabaseObject>(&self, keys: Vec<&[u8]>) -> Result<()>;
+
+    /// write_batch writes the operations in the batch atomically, the operations are either
+    /// all applied or none of them is applied, even if the dfdaemon crashes during the write.
+    fn write_batch(&self, batch: WriteBatch) -> Result<()>;
+
+    /// flush persists all the written objects durably, the objects written before the flush
+    /// are not lost even if the dfdaemon crashes after the flush returns.
+    fn flush(&self) -> Result<()>;
+}
+
+/// WriteOperation is the operation of the object in the write batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteOperation {
+    /// Put puts the serialized object by key in the namespace.
+    Put {
+        namespace: &'static str,
+        key: Box<[u8]>,
+        value: Vec<u8>,
+    },
+
+    /// Delete deletes the object by key in the namespace.
+    Delete {
+        namespace: &'static str,
+        key: Box<[u8]>,
+    },
 }
+
+/// WriteBatch collects the operations of the objects in different namespaces, which are
+/// written atomically by the storage engine, for example the state transitions of the task
+/// and its pieces.
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    /// operations are the operations in the batch, which are applied in order.
+    operations: Vec<WriteOperation>,
+}
+
+/// WriteBatch implements the write batch.
+impl WriteBatch {
+    /// new returns a new empty WriteBatch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// put puts the object by key in the batch.
+    pub fn put<O: DatabaseObject>(&mut self, key: &[u8], value: &O) -> Result<()> {
+        self.operations.push(WriteOperation::Put {
+            namespace: O::NAMESPACE,
+            key: key.into(),
+            value: value.serialized()?,
+        });
+
+        Ok(())
+    }
+
+    /// delete deletes the object by key in the batch.
+    pub fn delete<O: DatabaseObject>(&mut self, key: &[u8]) {
+        self.operations.push(WriteOperation::Delete {
+            namespace: O::NAMESPACE,
+            key: key.into(),
+        });
+    }
+
+    /// len returns the number of the operations in the batch.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// is_empty returns whether the batch has no operation.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+/// WriteBatch implements the IntoIterator to consume the operations in order.
+impl IntoIterator for WriteBatch {
+    type Item = WriteOperation;
+    type IntoIter = std::vec::IntoIter<WriteOperation>;
+
+    /// into_iter returns the iterator of the operations.
+    fn into_iter(self) -> Self::IntoIter {
+        self.operations.into_iter()
+    }
+}
+
+/// Engine is the storage engine selected by the metadata engine configuration.
+pub enum Engine {
+    /// Rocksdb is the storage engine based on rocksdb.
+    Rocksdb(self::rocksdb::RocksdbStorageEngine),
+
+    /// Redb is the storage engine based on redb.
+    Redb(self::redb::RedbStorageEngine),
+}
+
+/// Engine implements the storage engine.
+impl Engine {
+    /// open opens the storage engine by the metadata engine with the given directory and
+    /// namespaces.
+    pub fn open(
+        engine: MetadataEngine,
+        dir: &Path,
+        log_dir: &PathBuf,
+        namespaces: &[&str],
+        keep: bool,
+    ) -> Result<Self> {
+        match engine {
+            MetadataEngine::Rocksdb => Ok(Engine::Rocksdb(
+                self::rocksdb::RocksdbStorageEngine::open(dir, log_dir, namespaces, keep)?,
+            )),
+            MetadataEngine::Redb => Ok(Engine::Redb(self::redb::RedbStorageEngine::open(
+                dir, namespaces, keep,
+            )?)),
+        }
+    }
+}
+
+/// Engine implements the storage engine operations by dispatching to the inner engine.
+impl Operations for Engine {
+    /// get gets the object by key.
+    fn get<O: DatabaseObject>(&self, key: &[u8]) -> Result<Option<O>> {
+        match self {
+            Engine::Rocksdb(db) => db.get(key),
+            Engine::Redb(db) => db.get(key),
+        }
+    }
+
+    /// exists checks if the object exists by key.
+    fn exists<O: DatabaseObject>(&self, key: &[u8]) -> Result<bool> {
+        match self {
+            Engine::Rocksdb(db) => db.exists::<O>(key),
+            Engine::Redb(db) => db.exists::<O>(key),
+        }
+    }
+
+    /// put puts the object by key.
+    fn put<O: DatabaseObject>(&self, key: &[u8], value: &O) -> Result<()> {
+        match self {
+            Engine::Rocksdb(db) => db.put(key, value),
+            Engine::Redb(db) => db.put(key, value),
+        }
+    }
+
+    /// delete deletes the object by key.
+    fn delete<O: DatabaseObject>(&self, key: &[u8]) -> Result<()> {
+        match self {
+            Engine::Rocksdb(db) => db.delete::<O>(key),
+            Engine::Redb(db) => db.delete::<O>(key),
+        }
+    }
+
+    /// iter iterates all objects.
+    fn iter<O: DatabaseObject>(&self) -> Result<impl Iterator<Item = Result<(Box<[u8]>, O)>>> {
+        match self {
+            Engine::Rocksdb(db) => Ok(Either::Left(db.iter::<O>()?)),
+            Engine::Redb(db) => Ok(Either::Right(db.iter::<O>()?)),
+        }
+    }
+
+    /// iter_raw iterates all objects without serialization.
+    fn iter_raw<O: DatabaseObject>(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>>> {
+        match self {
+            Engine::Rocksdb(db) => Ok(Either::Left(db.iter_raw::<O>()?)),
+            Engine::Redb(db) => Ok(Either::Right(db.iter_raw::<O>()?)),
+        }
+    }
+
+    /// prefix_iter iterates all objects with prefix.
+    fn prefix_iter<O: DatabaseObject>(
+        &self,
+        prefix: &[u8],
+    ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, O)>>> {
+        match self {
+            Engine::Rocksdb(db) => Ok(Either::Left(db.prefix_iter::<O>(prefix)?)),
+            Engine::Redb(db) => Ok(Either::Right(db.prefix_iter::<O>(prefix)?)),
+        }
+    }
+
+    /// prefix_iter_raw iterates all objects with prefix without serialization.
+    fn prefix_iter_raw<O: DatabaseObject>(
+        &self,
+        prefix: &[u8],
+    ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>>> {
+        match self {
+            Engine::Rocksdb(db) => Ok(Either::Left(db.prefix_iter_raw::<O>(prefix)?)),
+            Engine::Redb(db) => Ok(Either::Right(db.prefix_iter_raw::<O>(prefix)?)),
+        }
+    }
+
+    /// batch_delete deletes objects by keys.
+    fn batch_delete<O: DatabaseObject>(&self, keys: Vec<&[u8]>) -> Result<()> {
+        match self {
+            Engine::Rocksdb(db) => db.batch_delete::<O>(keys),
+            Engine::Redb(db) => db.batch_delete::<O>(keys),
+        }
+    }
+
+    /// write_batch writes the operations in the batch atomically.
+    fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        match self {
+            Engine::Rocksdb(db) => db.write_batch(batch),
+            Engine::Redb(db) => db.write_batch(batch),
+        }
+    }
+
+    /// flush persists all the written objects durably.
+    fn flush(&self) -> Result<()> {
+        match self {
+            Engine::Rocksdb(db) => db.flush(),
+            Engine::Redb(db) => db.flush(),
+        }
+    }
+}
+
+/// Engine implements the storage engine.
+impl StorageEngine<'_> for Engine {}
@@ -0,0 +1,499 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::storage_engine::{
+    DatabaseObject, Operations, StorageEngine, WriteBatch, WriteOperation,
+};
+use dragonfly_client_core::{
+    error::{ErrorType, ExternalError, OrErr},
+    Error, Result,
+};
+use redb::{Durability, ReadOnlyTable, ReadableTable, TableDefinition, TableError};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// RedbStorageEngine is a storage engine based on redb, which is an embedded key-value
+/// database in pure rust. It has a smaller memory footprint than rocksdb, and is suitable
+/// for the small edge nodes.
+pub struct RedbStorageEngine {
+    /// inner is the inner redb database.
+    inner: redb::Database,
+}
+
+/// RedbStorageEngine implements the storage engine of the redb.
+impl RedbStorageEngine {
+    /// DEFAULT_FILE_NAME is the default file name to store metadata.
+    const DEFAULT_FILE_NAME: &'static str = "metadata.redb";
+
+    /// DEFAULT_CACHE_SIZE is the default cache size for redb, default is 64MB.
+    const DEFAULT_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+    /// open opens a redb storage engine with the given directory and tables.
+    pub fn open(dir: &Path, table_names: &[&str], keep: bool) -> Result<Self> {
+        info!("initializing metadata file: {:?} {:?}", dir, table_names);
+        let path = dir.join(Self::DEFAULT_FILE_NAME);
+
+        // If the storage is not kept, remove the database file.
+        if !keep {
+            std::fs::remove_file(&path).unwrap_or_else(|err| {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    warn!("remove {:?} failed: {}", path, err);
+                }
+            });
+        }
+
+        // Open redb.
+        let db = redb::Database::builder()
+            .set_cache_size(Self::DEFAULT_CACHE_SIZE)
+            .create(&path)
+            .or_err(ErrorType::StorageError)?;
+
+        // Initialize tables, the table is the column family of the rocksdb.
+        let txn = db.begin_write().or_err(ErrorType::StorageError)?;
+        for name in table_names {
+            txn.open_table(table_definition(name))
+                .or_err(ErrorType::StorageError)?;
+        }
+        txn.commit().or_err(ErrorType::StorageError)?;
+
+        Ok(Self { inner: db })
+    }
+
+    /// open_read_table opens the table of the object in a read transaction.
+    fn open_read_table<O: DatabaseObject>(
+        &self,
+    ) -> Result<ReadOnlyTable<&'static [u8], &'static [u8]>> {
+        let txn = self.inner.begin_read().or_err(ErrorType::StorageError)?;
+        txn.open_table(table_definition(O::NAMESPACE))
+            .map_err(|err| table_error(O::NAMESPACE, err))
+    }
+
+    /// write writes the objects in a write transaction with the given durability.
+    fn write<O, F>(&self, durability: Durability, f: F) -> Result<()>
+    where
+        O: DatabaseObject,
+        F: FnOnce(&mut redb::Table<&'static [u8], &'static [u8]>) -> Result<()>,
+    {
+        let mut txn = self.inner.begin_write().or_err(ErrorType::StorageError)?;
+        txn.set_durability(durability);
+
+        {
+            let mut table = txn
+                .open_table(table_definition(O::NAMESPACE))
+                .map_err(|err| table_error(O::NAMESPACE, err))?;
+            f(&mut table)?;
+        }
+
+        txn.commit().or_err(ErrorType::StorageError)?;
+        Ok(())
+    }
+}
+
+/// RedbStorageEngine implements the storage engine operations.
+impl Operations for RedbStorageEngine {
+    /// get gets the object by key.
+    fn get<O: DatabaseObject>(&self, key: &[u8]) -> Result<Option<O>> {
+        let table = self.open_read_table::<O>()?;
+        match table.get(key).or_err(ErrorType::StorageError)? {
+            Some(value) => Ok(Some(O::deserialize_from(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// exists checks if the object exists by key.
+    fn exists<O: DatabaseObject>(&self, key: &[u8]) -> Result<bool> {
+        let table = self.open_read_table::<O>()?;
+        Ok(table.get(key).or_err(ErrorType::StorageError)?.is_some())
+    }
+
+    /// put puts the object by key. Like the put of rocksdb without sync, the transaction is
+    /// committed with the eventual durability, and persisted by the next transaction with the
+    /// immediate durability.
+    fn put<O: DatabaseObject>(&self, key: &[u8], value: &O) -> Result<()> {
+        let value = value.serialized()?;
+        self.write::<O, _>(Durability::Eventual, |table| {
+            table
+                .insert(key, value.as_slice())
+                .or_err(ErrorType::StorageError)?;
+            Ok(())
+        })
+    }
+
+    /// delete deletes the object by key. Like the delete of rocksdb with sync, the transaction
+    /// is committed with the immediate durability.
+    fn delete<O: DatabaseObject>(&self, key: &[u8]) -> Result<()> {
+        self.write::<O, _>(Durability::Immediate, |table| {
+            table.remove(key).or_err(ErrorType::StorageError)?;
+            Ok(())
+        })
+    }
+
+    /// iter iterates all objects.
+    fn iter<O: DatabaseObject>(&self) -> Result<impl Iterator<Item = Result<(Box<[u8]>, O)>>> {
+        Ok(self.iter_raw::<O>()?.map(|ele| {
+            let (key, value) = ele?;
+            Ok((key, O::deserialize_from(&value)?))
+        }))
+    }
+
+    /// iter_raw iterates all objects without serialization. The objects are read lazily in a
+    /// snapshot of the read transaction, which is kept alive until the iterator is dropped.
+    fn iter_raw<O: DatabaseObject>(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>>> {
+        let table = self.open_read_table::<O>()?;
+        let range = table.range::<&[u8]>(..).or_err(ErrorType::StorageError)?;
+        Ok(range.map(|ele| {
+            let (key, value) = ele.or_err(ErrorType::StorageError)?;
+            Ok((Box::from(key.value()), Box::from(value.value())))
+        }))
+    }
+
+    /// prefix_iter iterates all objects with prefix.
+    fn prefix_iter<O: DatabaseObject>(
+        &self,
+        prefix: &[u8],
+    ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, O)>>> {
+        Ok(self.prefix_iter_raw::<O>(prefix)?.map(|ele| {
+            let (key, value) = ele?;
+            Ok((key, O::deserialize_from(&value)?))
+        }))
+    }
+
+    /// prefix_iter_raw iterates all objects with prefix without serialization. The objects are
+    /// read lazily in a snapshot of the read transaction, which is kept alive until the iterator
+    /// is dropped.
+    fn prefix_iter_raw<O: DatabaseObject>(
+        &self,
+        prefix: &[u8],
+    ) -> Result<impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>>> {
+        let table = self.open_read_table::<O>()?;
+        let range = table.range(prefix..).or_err(ErrorType::StorageError)?;
+
+        // The keys are sorted, so the iteration stops at the first key without the prefix.
+        let prefix = prefix.to_vec();
+        Ok(range
+            .take_while(move |ele| match ele {
+                Ok((key, _)) => key.value().starts_with(&prefix),
+                Err(_) => true,
+            })
+            .map(|ele| {
+                let (key, value) = ele.or_err(ErrorType::StorageError)?;
+                Ok((Box::from(key.value()), Box::from(value.value())))
+            }))
+    }
+
+    /// batch_delete deletes objects by keys in a transaction with the immediate durability.
+    fn batch_delete<O: DatabaseObject>(&self, keys: Vec<&[u8]>) -> Result<()> {
+        self.write::<O, _>(Durability::Immediate, |table| {
+            for key in keys {
+                table.remove(key).or_err(ErrorType::StorageError)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// write_batch writes the operations in the batch atomically in a write transaction with
+    /// the immediate durability, the transaction is aborted if any operation fails.
+    fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        let mut txn = self.inner.begin_write().or_err(ErrorType::StorageError)?;
+        txn.set_durability(Durability::Immediate);
+
+        for operation in batch {
+            match operation {
+                WriteOperation::Put {
+                    namespace,
+                    key,
+                    value,
+                } => {
+                    let mut table = txn
+                        .open_table(table_definition(namespace))
+                        .map_err(|err| table_error(namespace, err))?;
+                    table
+                        .insert(key.as_ref(), value.as_slice())
+                        .or_err(ErrorType::StorageError)?;
+                }
+                WriteOperation::Delete { namespace, key } => {
+                    let mut table = txn
+                        .open_table(table_definition(namespace))
+                        .map_err(|err| table_error(namespace, err))?;
+                    table.remove(key.as_ref()).or_err(ErrorType::StorageError)?;
+                }
+            }
+        }
+
+        txn.commit().or_err(ErrorType::StorageError)?;
+        Ok(())
+    }
+
+    /// flush commits an empty write transaction with the immediate durability, which also
+    /// persists the previous transactions committed with the eventual durability.
+    fn flush(&self) -> Result<()> {
+        let mut txn = self.inner.begin_write().or_err(ErrorType::StorageError)?;
+        txn.set_durability(Durability::Immediate);
+        txn.commit().or_err(ErrorType::StorageError)?;
+        Ok(())
+    }
+}
+
+/// RedbStorageEngine implements the redb of the storage engine.
+impl StorageEngine<'_> for RedbStorageEngine {}
+
+/// table_definition returns the table definition for the given name.
+fn table_definition(name: &str) -> TableDefinition<'_, &'static [u8], &'static [u8]> {
+    TableDefinition::new(name)
+}
+
+/// table_error converts the table error to the client error, the table which does not exist
+/// is treated as the column family not found.
+fn table_error(name: &str, err: TableError) -> Error {
+    match err {
+        TableError::TableDoesNotExist(_) => Error::ColumnFamilyNotFound(name.to_string()),
+        err => Error::ExternalError(
+            ExternalError::new(ErrorType::StorageError).with_cause(Box::new(err)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use tempfile::tempdir;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+    struct Object {
+        id: String,
+        value: i32,
+    }
+
+    impl DatabaseObject for Object {
+        const NAMESPACE: &'static str = "object";
+    }
+
+    fn create_test_engine() -> RedbStorageEngine {
+        let temp_dir = tempdir().unwrap();
+        RedbStorageEngine::open(temp_dir.path(), &[Object::NAMESPACE], false).unwrap()
+    }
+
+    #[test]
+    fn test_put_get_and_delete() {
+        let engine = create_test_engine();
+
+        let object = Object {
+            id: "1".to_string(),
+            value: 42,
+        };
+
+        assert!(!engine.exists::<Object>(object.id.as_bytes()).unwrap());
+        engine.put::<Object>(object.id.as_bytes(), &object).unwrap();
+        assert!(engine.exists::<Object>(object.id.as_bytes()).unwrap());
+
+        let retrieved_object = engine.get::<Object>(object.id.as_bytes()).unwrap().unwrap();
+        assert_eq!(object, retrieved_object);
+
+        engine.delete::<Object>(object.id.as_bytes()).unwrap();
+        assert!(!engine.exists::<Object>(object.id.as_bytes()).unwrap());
+        assert!(engine
+            .get::<Object>(object.id.as_bytes())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_batch_delete_and_iter() {
+        let engine = create_test_engine();
+
+        let objects = (1..=3)
+            .map(|i| Object {
+                id: i.to_string(),
+                value: i * 10,
+            })
+            .collect::<Vec<_>>();
+
+        for object in &objects {
+            engine.put::<Object>(object.id.as_bytes(), object).unwrap();
+        }
+
+        let retrieved_objects = engine
+            .iter::<Object>()
+            .unwrap()
+            .map(|ele| ele.map(|(_, object)| object))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(retrieved_objects, objects);
+
+        let ids: Vec<&[u8]> = objects.iter().map(|object| object.id.as_bytes()).collect();
+        engine.batch_delete::<Object>(ids).unwrap();
+        assert_eq!(engine.iter_raw::<Object>().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_prefix_iter() {
+        let engine = create_test_engine();
+
+        let prefix_a = [b'a'; 64];
+        let prefix_b = [b'b'; 64];
+        let keys = [
+            [&prefix_a[..], b"_suffix1"].concat(),
+            [&prefix_a[..], b"_suffix2"].concat(),
+            [&prefix_b[..], b"_suffix1"].concat(),
+        ];
+
+        for (i, key) in keys.iter().enumerate() {
+            engine
+                .put::<Object>(
+                    key,
+                    &Object {
+                        id: i.to_string(),
+                        value: i as i32,
+                    },
+                )
+                .unwrap();
+        }
+
+        let retrieved_objects = engine
+            .prefix_iter::<Object>(&prefix_a)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(retrieved_objects.len(), 2);
+        assert!(retrieved_objects
+            .iter()
+            .all(|(key, _)| key.starts_with(&prefix_a)));
+
+        let retrieved_objects = engine
+            .prefix_iter_raw::<Object>(&prefix_b)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(retrieved_objects.len(), 1);
+        assert_eq!(retrieved_objects[0].0.as_ref(), keys[2].as_slice());
+    }
+
+    #[test]
+    fn test_iter_while_writing() {
+        let engine = create_test_engine();
+        for i in 0..10 {
+            engine
+                .put::<Object>(
+                    format!("key{}", i).as_bytes(),
+                    &Object {
+                        id: i.to_string(),
+                        value: i,
+                    },
+                )
+                .unwrap();
+        }
+
+        // The iterator reads the snapshot lazily, so the objects can be deleted while iterating.
+        let mut count = 0;
+        for ele in engine.iter::<Object>().unwrap() {
+            let (key, _) = ele.unwrap();
+            engine.delete::<Object>(&key).unwrap();
+            count += 1;
+        }
+
+        assert_eq!(count, 10);
+        assert_eq!(engine.iter_raw::<Object>().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_keep_and_reopen() {
+        let temp_dir = tempdir().unwrap();
+        let object = Object {
+            id: "1".to_string(),
+            value: 42,
+        };
+
+        {
+            let engine =
+                RedbStorageEngine::open(temp_dir.path(), &[Object::NAMESPACE], false).unwrap();
+            engine.put::<Object>(object.id.as_bytes(), &object).unwrap();
+        }
+
+        {
+            let engine =
+                RedbStorageEngine::open(temp_dir.path(), &[Object::NAMESPACE], true).unwrap();
+            assert!(engine.exists::<Object>(object.id.as_bytes()).unwrap());
+        }
+
+        let engine = RedbStorageEngine::open(temp_dir.path(), &[Object::NAMESPACE], false).unwrap();
+        assert!(!engine.exists::<Object>(object.id.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_write_batch() {
+        let temp_dir = tempdir().unwrap();
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct OtherObject {
+            data: String,
+        }
+
+        impl DatabaseObject for OtherObject {
+            const NAMESPACE: &'static str = "other";
+        }
+
+        let namespaces = [Object::NAMESPACE, OtherObject::NAMESPACE];
+        let object = Object {
+            id: "1".to_string(),
+            value: 42,
+        };
+        let other = OtherObject {
+            data: "other".to_string(),
+        };
+
+        {
+            let engine = RedbStorageEngine::open(temp_dir.path(), &namespaces, false).unwrap();
+            engine.put::<Object>(b"2", &object).unwrap();
+
+            let mut batch = WriteBatch::new();
+            batch.put::<Object>(object.id.as_bytes(), &object).unwrap();
+            batch.put::<OtherObject>(b"1", &other).unwrap();
+            batch.delete::<Object>(b"2");
+            assert_eq!(batch.len(), 3);
+            engine.write_batch(batch).unwrap();
+        }
+
+        // The batch is durable after reopening the engine.
+        let engine = RedbStorageEngine::open(temp_dir.path(), &namespaces, true).unwrap();
+        assert_eq!(
+            engine.get::<Object>(object.id.as_bytes()).unwrap(),
+            Some(object)
+        );
+        assert_eq!(engine.get::<OtherObject>(b"1").unwrap(), Some(other));
+        assert!(!engine.exists::<Object>(b"2").unwrap());
+    }
+
+    #[test]
+    fn test_table_not_found() {
+        let engine = create_test_engine();
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct UnregisteredObject {
+            data: String,
+        }
+
+        impl DatabaseObject for UnregisteredObject {
+            const NAMESPACE: &'static str = "unregistered";
+        }
+
+        let result = engine.get::<UnregisteredObject>(b"unregistered");
+        assert!(matches!(result, Err(Error::ColumnFamilyNotFound(_))));
+    }
+}
@@ -14,7 +14,9 @@
  * limitations under the License.
  */
 
-use crate::storage_engine::{DatabaseObject, Operations, StorageEngine};
+use crate::storage_engine::{
+    DatabaseObject, Operations, StorageEngine, WriteBatch, WriteOperation,
+};
 use dragonfly_client_core::{
     error::{ErrorType, OrErr},
     Error, Result,
@@ -242,6 +244,36 @@ impl Operations for RocksdbStorageEngine {
             .write_opt(batch, &options)
             .or_err(ErrorType::StorageError)?)
     }
+
+    /// write_batch writes the operations in the batch atomically by the rocksdb write batch.
+    fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        // Resolve all column families before writing, so the batch is not applied
+        // partially if any column family is not found.
+        let mut inner = rocksdb::WriteBatch::default();
+        for operation in batch {
+            match operation {
+                WriteOperation::Put {
+                    namespace,
+                    key,
+                    value,
+                } => inner.put_cf(namespace_cf_handle(self, namespace)?, key, value),
+                WriteOperation::Delete { namespace, key } => {
+                    inner.delete_cf(namespace_cf_handle(self, namespace)?, key)
+                }
+            }
+        }
+
+        let mut options = WriteOptions::default();
+        options.set_sync(true);
+        Ok(self
+            .write_opt(inner, &options)
+            .or_err(ErrorType::StorageError)?)
+    }
+
+    /// flush syncs the write ahead log, so the writes before the flush are persisted.
+    fn flush(&self) -> Result<()> {
+        Ok(self.flush_wal(true).or_err(ErrorType::StorageError)?)
+    }
 }
 
 /// RocksdbStorageEngine implements the rocksdb of the storage engine.
@@ -252,9 +284,16 @@ fn cf_handle<T>(db: &rocksdb::DB) -> Result<&rocksdb::ColumnFamily>
 where
     T: DatabaseObject,
 {
-    let cf_name = T::NAMESPACE;
-    db.cf_handle(cf_name)
-        .ok_or_else(|| Error::ColumnFamilyNotFound(cf_name.to_string()))
+    namespace_cf_handle(db, T::NAMESPACE)
+}
+
+/// namespace_cf_handle returns the column family handle for the given namespace.
+fn namespace_cf_handle<'db>(
+    db: &'db rocksdb::DB,
+    namespace: &str,
+) -> Result<&'db rocksdb::ColumnFamily> {
+    db.cf_handle(namespace)
+        .ok_or_else(|| Error::ColumnFamilyNotFound(namespace.to_string()))
 }
 
 #[cfg(test)]
@@ -629,6 +668,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_write_batch() {
+        let engine = create_test_engine();
+
+        let objects = (1..=3)
+            .map(|i| Object {
+                id: i.to_string(),
+                value: i,
+            })
+            .collect::<Vec<_>>();
+        engine
+            .put::<Object>(objects[2].id.as_bytes(), &objects[2])
+            .unwrap();
+
+        let mut batch = WriteBatch::new();
+        batch
+            .put::<Object>(objects[0].id.as_bytes(), &objects[0])
+            .unwrap();
+        batch
+            .put::<Object>(objects[1].id.as_bytes(), &objects[1])
+            .unwrap();
+        batch.delete::<Object>(objects[2].id.as_bytes());
+        engine.write_batch(batch).unwrap();
+
+        assert!(engine.exists::<Object>(objects[0].id.as_bytes()).unwrap());
+        assert!(engine.exists::<Object>(objects[1].id.as_bytes()).unwrap());
+        assert!(!engine.exists::<Object>(objects[2].id.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_write_batch_is_atomic() {
+        let engine = create_test_engine();
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct UnregisteredObject {
+            data: String,
+        }
+
+        impl DatabaseObject for UnregisteredObject {
+            const NAMESPACE: &'static str = "unregistered";
+        }
+
+        let object = Object {
+            id: "1".to_string(),
+            value: 1,
+        };
+
+        // The last operation fails, so the first operation must not be applied.
+        let mut batch = WriteBatch::new();
+        batch.put::<Object>(object.id.as_bytes(), &object).unwrap();
+        batch
+            .put::<UnregisteredObject>(
+                b"1",
+                &UnregisteredObject {
+                    data: "data".to_string(),
+                },
+            )
+            .unwrap();
+
+        let result = engine.write_batch(batch);
+        assert!(matches!(result, Err(Error::ColumnFamilyNotFound(_))));
+        assert!(!engine.exists::<Object>(object.id.as_bytes()).unwrap());
+    }
+
     #[test]
     fn test_column_family_not_found() {
         let engine = create_test_engine();
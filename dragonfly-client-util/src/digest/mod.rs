@@ -171,6 +171,66 @@ impl FromStr for Digest {
     }
 }
 
+/// Hasher calculates the digest of the content incrementally, the content can be updated in
+/// chunks while it is streamed.
+pub enum Hasher {
+    /// Crc32 is the hasher of the crc32 algorithm.
+    Crc32(crc32fast::Hasher),
+
+    /// Sha256 is the hasher of the sha256 algorithm.
+    Sha256(sha2::Sha256),
+
+    /// Sha512 is the hasher of the sha512 algorithm.
+    Sha512(sha2::Sha512),
+}
+
+/// Hasher implements the incremental hasher.
+impl Hasher {
+    /// new returns a new Hasher by the algorithm.
+    pub fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            Algorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            Algorithm::Sha512 => Hasher::Sha512(sha2::Sha512::new()),
+        }
+    }
+
+    /// update updates the hasher with the chunk of the content.
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Crc32(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    /// finalize returns the digest of the content.
+    pub fn finalize(self) -> Digest {
+        match self {
+            Hasher::Crc32(hasher) => Digest::new(Algorithm::Crc32, hasher.finalize().to_string()),
+            Hasher::Sha256(hasher) => {
+                Digest::new(Algorithm::Sha256, hex::encode(hasher.finalize()))
+            }
+            Hasher::Sha512(hasher) => {
+                Digest::new(Algorithm::Sha512, hex::encode(hasher.finalize()))
+            }
+        }
+    }
+
+    /// verify finalizes the hasher and verifies the digest against an expected digest.
+    pub fn verify(self, expected_digest: &Digest) -> ClientResult<()> {
+        let digest = self.finalize();
+        if digest.to_string() != expected_digest.to_string() {
+            return Err(ClientError::DigestMismatch(
+                expected_digest.to_string(),
+                digest.to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 /// calculate_file_digest calculates the digest of a file.
 #[instrument(skip_all)]
 pub fn calculate_file_digest(algorithm: Algorithm, path: &Path) -> ClientResult<Digest> {
@@ -322,6 +382,31 @@ mod tests {
         assert_eq!(digest.encoded(), expected_crc32);
     }
 
+    #[test]
+    fn test_hasher() {
+        let expected_sha256 = "6ae8a75555209fd6c44157c0aed8016e763ff435a19cf186f76863140143ff72";
+        let mut hasher = Hasher::new(Algorithm::Sha256);
+        hasher.update(b"test ");
+        hasher.update(b"content");
+        assert_eq!(hasher.finalize().encoded(), expected_sha256);
+
+        let mut hasher = Hasher::new(Algorithm::Crc32);
+        hasher.update(b"test content");
+        assert_eq!(hasher.finalize().encoded(), "1475635037");
+
+        let expected_digest = Digest::new(Algorithm::Sha256, expected_sha256.to_string());
+        let mut hasher = Hasher::new(Algorithm::Sha256);
+        hasher.update(b"test content");
+        assert!(hasher.verify(&expected_digest).is_ok());
+
+        let mut hasher = Hasher::new(Algorithm::Sha256);
+        hasher.update(b"corrupted content");
+        assert!(matches!(
+            hasher.verify(&expected_digest),
+            Err(ClientError::DigestMismatch(_, _))
+        ));
+    }
+
     #[test]
     fn test_verify_file_digest() {
         let content = b"test content";
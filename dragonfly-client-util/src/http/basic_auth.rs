@@ -40,6 +40,38 @@ impl Credentials {
         }
     }
 
+    /// encode encodes the credentials to the value of the basic auth header.
+    pub fn encode(&self) -> String {
+        format!(
+            "Basic {}",
+            BASE64_STANDARD.encode(format!("{}:{}", self.username, self.password))
+        )
+    }
+
+    /// decode decodes the credentials from the value of the basic auth header.
+    pub fn decode(value: &str) -> Result<Credentials> {
+        let Some((typ, payload)) = value.trim().split_once(' ') else {
+            return Err(Error::Unauthorized);
+        };
+
+        if !typ.eq_ignore_ascii_case("basic") {
+            return Err(Error::Unauthorized);
+        }
+
+        let decoded = String::from_utf8(
+            BASE64_STANDARD
+                .decode(payload.trim())
+                .or_err(ErrorType::ParseError)?,
+        )
+        .or_err(ErrorType::ParseError)?;
+
+        let Some((username, password)) = decoded.split_once(':') else {
+            return Err(Error::Unauthorized);
+        };
+
+        Ok(Credentials::new(username, password))
+    }
+
     /// verify verifies the basic auth with the header.
     pub fn verify(&self, header: &HeaderMap) -> Result<()> {
         let Some(auth_header) = header.get(header::AUTHORIZATION) else {
@@ -83,6 +115,36 @@ mod tests {
     use super::*;
     use http::header::HeaderValue;
 
+    #[test]
+    fn test_encode() {
+        let credentials = Credentials::new("user", "pass");
+        assert_eq!(credentials.encode(), "Basic dXNlcjpwYXNz");
+
+        let mut header = HeaderMap::new();
+        header.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&credentials.encode()).unwrap(),
+        );
+        assert!(credentials.verify(&header).is_ok());
+    }
+
+    #[test]
+    fn test_decode() {
+        let credentials = Credentials::decode("Basic dXNlcjpwYXNz").unwrap();
+        assert_eq!(credentials.username, "user");
+        assert_eq!(credentials.password, "pass");
+
+        assert!(matches!(
+            Credentials::decode("Bearer some_token"),
+            Err(Error::Unauthorized)
+        ));
+        assert!(matches!(
+            Credentials::decode("Basic dXNlcg=="),
+            Err(Error::Unauthorized)
+        ));
+        assert!(Credentials::decode("Basic invalid_base64").is_err());
+    }
+
     #[test]
     fn test_verify_no_auth_header() {
         let credentials = Credentials::new("user", "pass");
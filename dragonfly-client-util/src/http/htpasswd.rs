@@ -0,0 +1,253 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use base64::prelude::*;
+use dragonfly_client_core::Result;
+use md5::{Digest as _, Md5};
+use sha1::Sha1;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::warn;
+
+/// APR1_MAGIC is the magic prefix of the apache md5 password hash.
+const APR1_MAGIC: &str = "$apr1$";
+
+/// SHA_PREFIX is the prefix of the sha1 password hash.
+const SHA_PREFIX: &str = "{SHA}";
+
+/// CRYPT_ALPHABET is the alphabet of the base64 encoding used by the crypt.
+const CRYPT_ALPHABET: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Htpasswd is the credentials of the htpasswd file, refer to
+/// https://httpd.apache.org/docs/current/misc/password_encryptions.html. The apache md5
+/// ($apr1$), the sha1 ({SHA}) and the plaintext passwords are supported.
+#[derive(Debug, Clone, Default)]
+pub struct Htpasswd {
+    /// entries are the password hashes by the username.
+    entries: HashMap<String, String>,
+}
+
+/// Htpasswd implements the htpasswd.
+impl Htpasswd {
+    /// from_file loads the htpasswd from the file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&content))
+    }
+
+    /// parse parses the htpasswd from the content, the blank lines and the comments are
+    /// ignored, the entries of the unsupported hash are skipped.
+    pub fn parse(content: &str) -> Self {
+        let mut entries = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((username, hash)) = line.split_once(':') else {
+                warn!("invalid htpasswd entry without password");
+                continue;
+            };
+
+            if hash.starts_with("$2") {
+                warn!("unsupported bcrypt password of user {}", username);
+                continue;
+            }
+
+            entries.insert(username.to_string(), hash.to_string());
+        }
+
+        Self { entries }
+    }
+
+    /// len returns the number of the entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// is_empty returns whether the htpasswd has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// verify verifies the password of the user.
+    pub fn verify(&self, username: &str, password: &str) -> bool {
+        let Some(hash) = self.entries.get(username) else {
+            return false;
+        };
+
+        if let Some(salt) = hash
+            .strip_prefix(APR1_MAGIC)
+            .and_then(|hash| hash.split_once('$'))
+            .map(|(salt, _)| salt)
+        {
+            return constant_time_eq(apr1(password, salt).as_bytes(), hash.as_bytes());
+        }
+
+        if let Some(hash) = hash.strip_prefix(SHA_PREFIX) {
+            let digest = BASE64_STANDARD.encode(Sha1::digest(password.as_bytes()));
+            return constant_time_eq(digest.as_bytes(), hash.as_bytes());
+        }
+
+        constant_time_eq(password.as_bytes(), hash.as_bytes())
+    }
+}
+
+/// apr1 hashes the password with the salt by the apache md5 algorithm, which is the md5 crypt
+/// with the $apr1$ magic.
+fn apr1(password: &str, salt: &str) -> String {
+    let password = password.as_bytes();
+    let salt = &salt.as_bytes()[..salt.len().min(8)];
+
+    let alternate = Md5::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut hasher = Md5::new()
+        .chain_update(password)
+        .chain_update(APR1_MAGIC.as_bytes())
+        .chain_update(salt);
+
+    for chunk in (0..password.len()).step_by(16) {
+        hasher.update(&alternate[..(password.len() - chunk).min(16)]);
+    }
+
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 == 1 {
+            hasher.update([0u8]);
+        } else {
+            hasher.update(&password[..1]);
+        }
+
+        length >>= 1;
+    }
+
+    let mut digest = hasher.finalize();
+    for round in 0..1000 {
+        let mut hasher = Md5::new();
+        if round & 1 == 1 {
+            hasher.update(password);
+        } else {
+            hasher.update(digest);
+        }
+
+        if round % 3 != 0 {
+            hasher.update(salt);
+        }
+
+        if round % 7 != 0 {
+            hasher.update(password);
+        }
+
+        if round & 1 == 1 {
+            hasher.update(digest);
+        } else {
+            hasher.update(password);
+        }
+
+        digest = hasher.finalize();
+    }
+
+    let mut encoded = String::with_capacity(22);
+    for (a, b, c) in [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)] {
+        encode_crypt(
+            &mut encoded,
+            ((digest[a] as u32) << 16) | ((digest[b] as u32) << 8) | digest[c] as u32,
+            4,
+        );
+    }
+    encode_crypt(&mut encoded, digest[11] as u32, 2);
+
+    format!(
+        "{}{}${}",
+        APR1_MAGIC,
+        String::from_utf8_lossy(salt),
+        encoded
+    )
+}
+
+/// encode_crypt encodes the value to the characters of the crypt base64 alphabet.
+fn encode_crypt(encoded: &mut String, mut value: u32, count: usize) {
+    for _ in 0..count {
+        encoded.push(CRYPT_ALPHABET[(value & 0x3f) as usize] as char);
+        value >>= 6;
+    }
+}
+
+/// constant_time_eq compares the bytes in constant time to avoid the timing attack.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_apr1() {
+        assert_eq!(
+            apr1("secret", "saltsalt"),
+            "$apr1$saltsalt$LrttParrLPdxvgutaSXWJ0"
+        );
+        assert_eq!(
+            apr1("password", "r31....."),
+            "$apr1$r31.....$ARC3pREO82RIm0aQ2zszC0"
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let htpasswd = Htpasswd::parse(
+            "# comment\n\
+             alice:$apr1$saltsalt$LrttParrLPdxvgutaSXWJ0\n\
+             bob:{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ=\n\
+             \n\
+             carol:secret\n\
+             dave:$2y$05$c4WoMPo3SXsafkva.HHa6uXQZWr7oboPiC2bT/r7q1BB8I2s0BRqC\n\
+             invalid\n",
+        );
+        assert_eq!(htpasswd.len(), 3);
+
+        assert!(htpasswd.verify("alice", "secret"));
+        assert!(htpasswd.verify("bob", "secret"));
+        assert!(htpasswd.verify("carol", "secret"));
+        assert!(!htpasswd.verify("alice", "wrong"));
+        assert!(!htpasswd.verify("bob", "wrong"));
+        assert!(!htpasswd.verify("carol", "wrong"));
+        assert!(!htpasswd.verify("dave", "secret"));
+        assert!(!htpasswd.verify("unknown", "secret"));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "alice:$apr1$r31.....$ARC3pREO82RIm0aQ2zszC0").unwrap();
+
+        let htpasswd = Htpasswd::from_file(file.path()).unwrap();
+        assert!(htpasswd.verify("alice", "password"));
+        assert!(Htpasswd::from_file(Path::new("/nonexistent/htpasswd")).is_err());
+    }
+}
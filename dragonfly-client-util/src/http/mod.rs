@@ -19,10 +19,12 @@ use dragonfly_client_core::{
     error::{ErrorType, OrErr},
     Error, Result,
 };
+use http_range_header::{EndPosition, StartPosition};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use std::collections::HashMap;
 
 pub mod basic_auth;
+pub mod htpasswd;
 pub mod query_params;
 
 /// headermap_to_hashmap converts a headermap to a hashmap.
@@ -77,6 +79,75 @@ pub fn get_range(header: &HeaderMap, content_length: u64) -> Result<Option<Range
     }
 }
 
+/// get_ranges gets the multiple ranges from http header.
+pub fn get_ranges(header: &HeaderMap, content_length: u64) -> Result<Option<Vec<Range>>> {
+    match header.get(reqwest::header::RANGE) {
+        Some(range) => {
+            let range = range.to_str().or_err(ErrorType::ParseError)?;
+            Ok(Some(parse_ranges_header(range, content_length)?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// parse_ranges_header parses a Range header string with multiple ranges as per RFC 9110,
+/// refer to https://www.rfc-editor.org/rfc/rfc9110#section-14.1.2. The unsatisfiable ranges
+/// are ignored, and the overlapping or adjacent ranges are coalesced in ascending order. If
+/// none of the ranges is satisfiable, return the EmptyHTTPRangeError.
+pub fn parse_ranges_header(range_header_value: &str, content_length: u64) -> Result<Vec<Range>> {
+    let parsed_ranges =
+        http_range_header::parse_range_header(range_header_value).or_err(ErrorType::ParseError)?;
+
+    let mut ranges = parsed_ranges
+        .ranges
+        .iter()
+        .filter_map(|range| {
+            if content_length == 0 {
+                return None;
+            }
+
+            let start = match range.start {
+                StartPosition::Index(start) => start,
+                StartPosition::FromLast(length) => content_length.saturating_sub(length),
+            };
+
+            let end = match range.end {
+                EndPosition::Index(end) => end.min(content_length - 1),
+                EndPosition::LastByte => content_length - 1,
+            };
+
+            // The range is unsatisfiable if the first position is greater than the
+            // length of the content.
+            if start > end {
+                return None;
+            }
+
+            Some(Range {
+                start,
+                length: end - start + 1,
+            })
+        })
+        .collect::<Vec<Range>>();
+    ranges.sort_by_key(|range| range.start);
+
+    let mut coalesced_ranges: Vec<Range> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match coalesced_ranges.last_mut() {
+            Some(last) if range.start <= last.start + last.length => {
+                let end = (last.start + last.length).max(range.start + range.length);
+                last.length = end - last.start;
+            }
+            _ => coalesced_ranges.push(range),
+        }
+    }
+
+    if coalesced_ranges.is_empty() {
+        return Err(Error::EmptyHTTPRangeError);
+    }
+
+    Ok(coalesced_ranges)
+}
+
 /// parse_range_header parses a Range header string as per RFC 7233,
 /// supported Range Header: "Range": "bytes=100-200", "Range": "bytes=-50",
 /// "Range": "bytes=150-", "Range": "bytes=0-0,-1".
@@ -162,6 +233,53 @@ mod tests {
         assert_eq!(range.length, 101);
     }
 
+    #[test]
+    fn test_get_ranges() {
+        let mut header = HeaderMap::new();
+        header.insert(
+            reqwest::header::RANGE,
+            HeaderValue::from_static("bytes=0-9,20-29"),
+        );
+
+        let ranges = get_ranges(&header, 200).unwrap().unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!((ranges[1].start, ranges[1].length), (20, 10));
+        assert!(get_ranges(&HeaderMap::new(), 200).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_ranges_header() {
+        let test_cases = vec![
+            ("bytes=0-9,20-29", vec![(0, 10), (20, 10)]),
+            ("bytes=20-29,0-9", vec![(0, 10), (20, 10)]),
+            ("bytes=0-9,5-14,15-19", vec![(0, 20)]),
+            ("bytes=-10,0-0", vec![(0, 1), (190, 10)]),
+            ("bytes=190-,300-400", vec![(190, 10)]),
+            ("bytes=0-1000", vec![(0, 200)]),
+        ];
+
+        for (range_header_value, expected) in test_cases {
+            let ranges = parse_ranges_header(range_header_value, 200).unwrap();
+            assert_eq!(
+                ranges
+                    .iter()
+                    .map(|range| (range.start, range.length))
+                    .collect::<Vec<(u64, u64)>>(),
+                expected
+            );
+        }
+
+        assert!(matches!(
+            parse_ranges_header("bytes=200-300", 200),
+            Err(Error::EmptyHTTPRangeError)
+        ));
+        assert!(matches!(
+            parse_ranges_header("bytes=0-10", 0),
+            Err(Error::EmptyHTTPRangeError)
+        ));
+        assert!(parse_ranges_header("invalid", 200).is_err());
+    }
+
     #[test]
     fn test_parse_range_header() {
         let range = parse_range_header("bytes=0-100", 200).unwrap();
@@ -111,6 +111,19 @@ impl IDGenerator {
     /// task_id generates the task id.
     #[inline]
     pub fn task_id(&self, parameter: TaskIDParameter) -> Result<String> {
+        self.generate_task_id(parameter, TaskType::Standard)
+    }
+
+    /// cache_task_id generates the cache task id. The cache task id is different from the
+    /// task id with the same parameter, because the content of the cache task is stored in memory.
+    #[inline]
+    pub fn cache_task_id(&self, parameter: TaskIDParameter) -> Result<String> {
+        self.generate_task_id(parameter, TaskType::Cache)
+    }
+
+    /// generate_task_id generates the task id by the parameter and the task type.
+    #[inline]
+    fn generate_task_id(&self, parameter: TaskIDParameter, task_type: TaskType) -> Result<String> {
         match parameter {
             TaskIDParameter::Content(content) => {
                 Ok(hex::encode(Sha256::digest(content.as_bytes())))
@@ -163,7 +176,7 @@ impl IDGenerator {
                     hasher.update(piece_length.to_string());
                 }
 
-                hasher.update(TaskType::Standard.as_str_name().as_bytes());
+                hasher.update(task_type.as_str_name().as_bytes());
 
                 // Generate the task id.
                 Ok(hex::encode(hasher.finalize()))
@@ -394,6 +407,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_generate_cache_task_id() {
+        let generator = IDGenerator::new("127.0.0.1".to_string(), "localhost".to_string(), false);
+        let parameter = || TaskIDParameter::URLBased {
+            url: "https://example.com?foo=foo&bar=bar".to_string(),
+            piece_length: Some(1024_u64),
+            tag: Some("foo".to_string()),
+            application: Some("bar".to_string()),
+            filtered_query_params: vec!["foo".to_string()],
+        };
+
+        let cache_task_id = generator.cache_task_id(parameter()).unwrap();
+        assert_eq!(cache_task_id, generator.cache_task_id(parameter()).unwrap());
+        assert_ne!(cache_task_id, generator.task_id(parameter()).unwrap());
+    }
+
     #[test]
     fn should_generate_persistent_task_id() {
         let test_cases = vec![(
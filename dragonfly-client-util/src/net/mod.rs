@@ -187,6 +187,41 @@ impl Interface {
     }
 }
 
+/// is_ip_in_cidr returns whether the ip is in the CIDR, the CIDR without the prefix length
+/// matches the ip exactly, e.g. 10.0.0.0/8, fd00::/8 or 192.168.1.1.
+pub fn is_ip_in_cidr(ip: IpAddr, cidr: &str) -> bool {
+    let (network, prefix) = match cidr.trim().split_once('/') {
+        Some((network, prefix)) => (network, prefix.parse::<u32>().ok()),
+        None => (cidr.trim(), None),
+    };
+
+    let Ok(network) = network.parse::<IpAddr>() else {
+        return false;
+    };
+
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let prefix = prefix.unwrap_or(32);
+            if prefix > 32 {
+                return false;
+            }
+
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let prefix = prefix.unwrap_or(128);
+            if prefix > 128 {
+                return false;
+            }
+
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
 /// set_tcp_fastopen_connect enables TCP Fast Open for client connections on the given socket file
 /// descriptor.
 #[cfg(target_os = "linux")]
@@ -278,4 +313,24 @@ mod tests {
             assert_eq!(result, expected);
         }
     }
+
+    #[test]
+    fn test_is_ip_in_cidr() {
+        let test_cases = vec![
+            ("10.1.2.3", "10.0.0.0/8", true),
+            ("11.1.2.3", "10.0.0.0/8", false),
+            ("192.168.1.1", "192.168.1.1", true),
+            ("192.168.1.2", "192.168.1.1", false),
+            ("192.168.1.2", "0.0.0.0/0", true),
+            ("fd00::1", "fd00::/8", true),
+            ("fe80::1", "fd00::/8", false),
+            ("10.1.2.3", "fd00::/8", false),
+            ("10.1.2.3", "10.0.0.0/33", false),
+            ("10.1.2.3", "invalid", false),
+        ];
+
+        for (ip, cidr, expected) in test_cases {
+            assert_eq!(is_ip_in_cidr(ip.parse().unwrap(), cidr), expected);
+        }
+    }
 }
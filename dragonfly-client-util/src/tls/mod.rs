@@ -140,6 +140,13 @@ pub fn generate_cert_from_pem(cert_path: &PathBuf) -> ClientResult<Vec<Certifica
     Ok(certs)
 }
 
+/// Generate private key from PEM format file.
+#[instrument(skip_all)]
+pub fn generate_key_from_pem(key_path: &PathBuf) -> ClientResult<PrivateKeyDer<'static>> {
+    let key_pem = fs::read(key_path)?;
+    load_key_from_pem(std::str::from_utf8(&key_pem)?)
+}
+
 /// generate_self_signed_certs_by_ca_cert generates a self-signed certificates
 /// by given subject alternative names with CA certificate.
 #[instrument(skip_all)]
@@ -201,6 +208,29 @@ pub fn generate_simple_self_signed_certs(
     Ok((certs, key))
 }
 
+/// generate_self_signed_cert_pem generates a self-signed certificate and private key with PEM
+/// format by given subject alternative names. The certificate is signed by the CA certificate
+/// if it is given, otherwise it is a simple self-signed certificate.
+#[instrument(skip_all)]
+pub fn generate_self_signed_cert_pem(
+    ca_cert: Option<&Certificate>,
+    subject_alt_names: Vec<String>,
+) -> ClientResult<(String, String)> {
+    let Some(ca_cert) = ca_cert else {
+        let cert = rcgen::generate_simple_self_signed(subject_alt_names)
+            .or_err(ErrorType::CertificateError)?;
+        let cert_pem = cert.serialize_pem().or_err(ErrorType::CertificateError)?;
+        return Ok((cert_pem, cert.serialize_private_key_pem()));
+    };
+
+    let params = CertificateParams::new(subject_alt_names);
+    let cert = Certificate::from_params(params).or_err(ErrorType::CertificateError)?;
+    let cert_pem = cert
+        .serialize_pem_with_signer(ca_cert)
+        .or_err(ErrorType::CertificateError)?;
+    Ok((cert_pem, cert.serialize_private_key_pem()))
+}
+
 /// certs_to_raw_certs converts DER format of the certificates to raw certificates.
 #[instrument(skip_all)]
 pub fn certs_to_raw_certs(certs: Vec<CertificateDer<'static>>) -> Vec<Vec<u8>> {
@@ -351,6 +381,15 @@ Z+yQ5jhu/fmSBNhqO/8Lp+Y=
         assert!(!result.unwrap().is_empty());
     }
 
+    #[test]
+    fn test_generate_key_from_pem() {
+        let key_file = NamedTempFile::new().unwrap();
+        key_file.as_file().write_all(SERVER_KEY.as_bytes()).unwrap();
+
+        let result = generate_key_from_pem(&key_file.path().to_path_buf());
+        assert!(matches!(result.unwrap(), PrivateKeyDer::Pkcs8(_)));
+    }
+
     #[test]
     fn test_generate_self_signed_certs_by_ca_cert() {
         let ca_cert_file = NamedTempFile::new().unwrap();
@@ -380,6 +419,33 @@ Z+yQ5jhu/fmSBNhqO/8Lp+Y=
         assert!(matches!(key, PrivateKeyDer::Pkcs8(_)));
     }
 
+    #[test]
+    fn test_generate_self_signed_cert_pem() {
+        let ca_cert_file = NamedTempFile::new().unwrap();
+        let ca_key_file = NamedTempFile::new().unwrap();
+        ca_cert_file
+            .as_file()
+            .write_all(SERVER_CERT.as_bytes())
+            .unwrap();
+        ca_key_file
+            .as_file()
+            .write_all(SERVER_KEY.as_bytes())
+            .unwrap();
+
+        let ca_cert = generate_ca_cert_from_pem(
+            &ca_cert_file.path().to_path_buf(),
+            &ca_key_file.path().to_path_buf(),
+        )
+        .unwrap();
+
+        for ca_cert in [Some(&ca_cert), None] {
+            let (cert_pem, key_pem) =
+                generate_self_signed_cert_pem(ca_cert, vec!["example.com".to_string()]).unwrap();
+            assert!(!load_certs_from_pem(&cert_pem).unwrap().is_empty());
+            assert!(load_key_from_pem(&key_pem).is_ok());
+        }
+    }
+
     #[test]
     fn test_certs_to_raw_certs() {
         let cert_file = NamedTempFile::new().unwrap();
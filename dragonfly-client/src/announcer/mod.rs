@@ -24,7 +24,9 @@ use dragonfly_client_config::{
 use dragonfly_client_core::error::{ErrorType, OrErr};
 use dragonfly_client_core::Result;
 use dragonfly_client_util::{net::Interface, shutdown};
+use std::collections::HashSet;
 use std::env;
+use std::os::unix::fs::MetadataExt;
 use std::sync::Arc;
 use std::time::Duration;
 use sysinfo::System;
@@ -176,10 +178,23 @@ impl SchedulerAnnouncer {
             ..Default::default()
         };
 
-        // Get the disk information.
-        let stats = fs2::statvfs(self.config.storage.dir.as_path())?;
-        let total_space = stats.total_space();
-        let available_space = stats.available_space();
+        // Get the disk information, the space of the storage dirs on the same device is
+        // counted only once, and the failing dirs are skipped.
+        let mut devices = HashSet::new();
+        let mut total_space = 0;
+        let mut available_space = 0;
+        for dir in self.config.storage.content_dirs() {
+            let (Ok(metadata), Ok(stats)) = (std::fs::metadata(&dir), fs2::statvfs(&dir)) else {
+                error!("get disk information of {:?} failed", dir);
+                continue;
+            };
+
+            if devices.insert(metadata.dev()) {
+                total_space += stats.total_space();
+                available_space += stats.available_space();
+            }
+        }
+
         let used_space = total_space - available_space;
         let used_percent = (used_space as f64 / (total_space) as f64) * 100.0;
 
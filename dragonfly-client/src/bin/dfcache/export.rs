@@ -125,6 +125,14 @@ pub struct ExportCommand {
 
     #[arg(long, default_value_t = false, help = "Specify whether to print log")]
     console: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format, json writes the progress and the result as newline delimited JSON events to stdout"
+    )]
+    output_format: OutputFormat,
 }
 
 /// Implement the execute for ExportCommand.
@@ -143,42 +151,47 @@ impl ExportCommand {
         // Initialize tracing.
         let _guards = init_command_tracing(self.log_level, self.console);
 
+        // Initialize the reporter of the output format.
+        let mut reporter = Reporter::new(self.output_format);
+
         // Validate the command line arguments.
         if let Err(err) = self.validate_args() {
-            println!(
-                "{}{}{}Validating Failed!{}",
-                color::Fg(color::Red),
-                style::Italic,
-                style::Bold,
-                style::Reset
-            );
-
-            println!(
-                "{}{}{}****************************************{}",
-                color::Fg(color::Black),
-                style::Italic,
-                style::Bold,
-                style::Reset
-            );
-
-            println!(
-                "{}{}{}Message:{} {}",
-                color::Fg(color::Cyan),
-                style::Italic,
-                style::Bold,
-                style::Reset,
-                err,
-            );
-
-            println!(
-                "{}{}{}****************************************{}",
-                color::Fg(color::Black),
-                style::Italic,
-                style::Bold,
-                style::Reset
-            );
-
-            std::process::exit(1);
+            if !reporter.is_json() {
+                println!(
+                    "{}{}{}Validating Failed!{}",
+                    color::Fg(color::Red),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                println!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                println!(
+                    "{}{}{}Message:{} {}",
+                    color::Fg(color::Cyan),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset,
+                    err,
+                );
+
+                println!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+            }
+
+            reporter.fail(ErrorClass::InvalidArgument, &err);
         }
 
         // Get dfdaemon download client.
@@ -186,52 +199,9 @@ impl ExportCommand {
             match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
                 Ok(client) => client,
                 Err(err) => {
-                    println!(
-                        "{}{}{}Connect Dfdaemon Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        err,
-                        self.endpoint.to_string_lossy(),
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    std::process::exit(1);
-                }
-            };
-
-        // Run export command.
-        if let Err(err) = self.run(dfdaemon_download_client).await {
-            match err {
-                Error::TonicStatus(status) => {
-                    let details = status.details();
-                    if let Ok(backend_err) = serde_json::from_slice::<Backend>(details) {
+                    if !reporter.is_json() {
                         println!(
-                            "{}{}{}Exporting Failed!{}",
+                            "{}{}{}Connect Dfdaemon Failed!{}",
                             color::Fg(color::Red),
                             style::Italic,
                             style::Bold,
@@ -246,27 +216,174 @@ impl ExportCommand {
                             style::Reset
                         );
 
-                        if let Some(status_code) = backend_err.status_code {
+                        println!(
+                            "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            err,
+                            self.endpoint.to_string_lossy(),
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
+
+                    reporter.fail(ErrorClass::DfdaemonUnavailable, &err);
+                }
+            };
+
+        // Run export command.
+        if let Err(err) = self.run(dfdaemon_download_client, &mut reporter).await {
+            if !reporter.is_json() {
+                match &err {
+                    Error::TonicStatus(status) => {
+                        let details = status.details();
+                        if let Ok(backend_err) = serde_json::from_slice::<Backend>(details) {
                             println!(
-                                "{}{}{}Bad Status Code:{} {}",
+                                "{}{}{}Exporting Failed!{}",
                                 color::Fg(color::Red),
                                 style::Italic,
                                 style::Bold,
+                                style::Reset
+                            );
+
+                            println!(
+                                "{}{}{}****************************************{}",
+                                color::Fg(color::Black),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset
+                            );
+
+                            if let Some(status_code) = backend_err.status_code {
+                                println!(
+                                    "{}{}{}Bad Status Code:{} {}",
+                                    color::Fg(color::Red),
+                                    style::Italic,
+                                    style::Bold,
+                                    style::Reset,
+                                    status_code
+                                );
+                            }
+
+                            println!(
+                                "{}{}{}Message:{} {}",
+                                color::Fg(color::Cyan),
+                                style::Italic,
+                                style::Bold,
                                 style::Reset,
-                                status_code
+                                backend_err.message
+                            );
+
+                            if !backend_err.header.is_empty() {
+                                println!(
+                                    "{}{}{}Header:{}",
+                                    color::Fg(color::Cyan),
+                                    style::Italic,
+                                    style::Bold,
+                                    style::Reset
+                                );
+                                for (key, value) in backend_err.header.iter() {
+                                    println!("  [{}]: {}", key.as_str(), value.as_str());
+                                }
+                            }
+
+                            println!(
+                                "{}{}{}****************************************{}",
+                                color::Fg(color::Black),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset
+                            );
+                        } else {
+                            println!(
+                                "{}{}{}Exporting Failed!{}",
+                                color::Fg(color::Red),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset
+                            );
+
+                            println!(
+                                "{}{}{}*********************************{}",
+                                color::Fg(color::Black),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset
+                            );
+
+                            println!(
+                                "{}{}{}Bad Code:{} {}",
+                                color::Fg(color::Red),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset,
+                                status.code()
+                            );
+
+                            println!(
+                                "{}{}{}Message:{} {}",
+                                color::Fg(color::Cyan),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset,
+                                status.message()
+                            );
+
+                            if !status.details().is_empty() {
+                                println!(
+                                    "{}{}{}Details:{} {}",
+                                    color::Fg(color::Cyan),
+                                    style::Italic,
+                                    style::Bold,
+                                    style::Reset,
+                                    std::str::from_utf8(status.details()).unwrap()
+                                );
+                            }
+
+                            println!(
+                                "{}{}{}*********************************{}",
+                                color::Fg(color::Black),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset
                             );
                         }
+                    }
+                    Error::BackendError(err) => {
+                        println!(
+                            "{}{}{}Exporting Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
 
                         println!(
                             "{}{}{}Message:{} {}",
-                            color::Fg(color::Cyan),
+                            color::Fg(color::Red),
                             style::Italic,
                             style::Bold,
                             style::Reset,
-                            backend_err.message
+                            err.message
                         );
 
-                        if !backend_err.header.is_empty() {
+                        if err.header.is_some() {
                             println!(
                                 "{}{}{}Header:{}",
                                 color::Fg(color::Cyan),
@@ -274,8 +391,8 @@ impl ExportCommand {
                                 style::Bold,
                                 style::Reset
                             );
-                            for (key, value) in backend_err.header.iter() {
-                                println!("  [{}]: {}", key.as_str(), value.as_str());
+                            for (key, value) in err.header.clone().unwrap_or_default().iter() {
+                                println!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
                             }
                         }
 
@@ -286,7 +403,8 @@ impl ExportCommand {
                             style::Bold,
                             style::Reset
                         );
-                    } else {
+                    }
+                    err => {
                         println!(
                             "{}{}{}Exporting Failed!{}",
                             color::Fg(color::Red),
@@ -296,7 +414,7 @@ impl ExportCommand {
                         );
 
                         println!(
-                            "{}{}{}*********************************{}",
+                            "{}{}{}****************************************{}",
                             color::Fg(color::Black),
                             style::Italic,
                             style::Bold,
@@ -304,36 +422,16 @@ impl ExportCommand {
                         );
 
                         println!(
-                            "{}{}{}Bad Code:{} {}",
+                            "{}{}{}Message:{} {}",
                             color::Fg(color::Red),
                             style::Italic,
                             style::Bold,
                             style::Reset,
-                            status.code()
+                            err
                         );
 
                         println!(
-                            "{}{}{}Message:{} {}",
-                            color::Fg(color::Cyan),
-                            style::Italic,
-                            style::Bold,
-                            style::Reset,
-                            status.message()
-                        );
-
-                        if !status.details().is_empty() {
-                            println!(
-                                "{}{}{}Details:{} {}",
-                                color::Fg(color::Cyan),
-                                style::Italic,
-                                style::Bold,
-                                style::Reset,
-                                std::str::from_utf8(status.details()).unwrap()
-                            );
-                        }
-
-                        println!(
-                            "{}{}{}*********************************{}",
+                            "{}{}{}****************************************{}",
                             color::Fg(color::Black),
                             style::Italic,
                             style::Bold,
@@ -341,92 +439,12 @@ impl ExportCommand {
                         );
                     }
                 }
-                Error::BackendError(err) => {
-                    println!(
-                        "{}{}{}Exporting Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Message:{} {}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        err.message
-                    );
-
-                    if err.header.is_some() {
-                        println!(
-                            "{}{}{}Header:{}",
-                            color::Fg(color::Cyan),
-                            style::Italic,
-                            style::Bold,
-                            style::Reset
-                        );
-                        for (key, value) in err.header.unwrap_or_default().iter() {
-                            println!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
-                        }
-                    }
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-                }
-                err => {
-                    println!(
-                        "{}{}{}Exporting Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Message:{} {}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        err
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-                }
             }
 
-            std::process::exit(1);
+            reporter.fail(ErrorClass::from(&err), &err);
         }
 
+        reporter.succeed();
         Ok(())
     }
 
@@ -437,7 +455,11 @@ impl ExportCommand {
     /// by dfdaemon (hardlink/copy) or streaming piece content through the client for manual
     /// file assembly. The operation provides real-time progress feedback and handles file
     /// creation, directory setup, and efficient piece-by-piece writing with sparse file allocation.
-    async fn run(&self, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
+    async fn run(
+        &self,
+        dfdaemon_download_client: DfdaemonDownloadClient,
+        reporter: &mut Reporter,
+    ) -> Result<()> {
         // Dfcache needs to notify dfdaemon to transfer the piece content of downloading file via unix domain socket
         // when the `transfer_from_dfdaemon` is true. Otherwise, dfdaemon will download the file and hardlink or
         // copy the file to the output path.
@@ -501,8 +523,12 @@ impl ExportCommand {
             None
         };
 
+        reporter.set_source(self.id.as_str());
+        reporter.set_output(&self.output);
+        reporter.set_digest(self.digest.clone());
+
         // Initialize progress bar.
-        let progress_bar = if self.no_progress {
+        let progress_bar = if self.no_progress || reporter.is_json() {
             ProgressBar::hidden()
         } else {
             ProgressBar::new(0)
@@ -541,6 +567,12 @@ impl ExportCommand {
                             }
 
                             progress_bar.set_length(response.content_length);
+                            reporter.started(
+                                &message.host_id,
+                                &message.task_id,
+                                &message.peer_id,
+                                response.content_length,
+                            );
                         }
                         Some(download_persistent_cache_task_response::Response::DownloadPieceFinishedResponse(
                             response,
@@ -556,6 +588,7 @@ impl ExportCommand {
                                     return Err(Error::InvalidParameter);
                                 }
                             };
+                            reporter.piece_finished(&piece);
 
                             // Dfcache needs to write the piece content to the output file.
                             if let Some(f) = &mut f {
@@ -642,6 +675,14 @@ impl ExportCommand {
     /// directory existence, prevents accidental file overwrites, and validates path accessibility
     /// before allowing the export operation to proceed.
     fn validate_args(&self) -> Result<()> {
+        // The events in the json output format are written to stdout, which can not be mixed
+        // with the console log.
+        if self.output_format == OutputFormat::Json && self.console {
+            return Err(Error::ValidationError(
+                "console log can not be used with json output format".to_string(),
+            ));
+        }
+
         let absolute_path = Path::new(&self.output).absolutize()?;
         match absolute_path.parent() {
             Some(parent_path) => {
@@ -17,6 +17,7 @@
 use clap::{Parser, Subcommand};
 use dragonfly_client::grpc::dfdaemon_download::DfdaemonDownloadClient;
 use dragonfly_client::grpc::health::HealthClient;
+use dragonfly_client::output::{ErrorClass, OutputFormat, Reporter, EXIT_CODES_HELP};
 use dragonfly_client::tracing::init_command_tracing;
 use dragonfly_client_config::VersionValueParser;
 use dragonfly_client_config::{dfcache, dfdaemon};
@@ -36,6 +37,7 @@ pub mod stat;
     about = "dfcache is a cache command line based on P2P technology in Dragonfly.",
     long_about = "A cache command line based on P2P technology in Dragonfly that can import file and export file in P2P network, \
     and it can create multiple replicas on different peers. P2P cache is effectively used for fast read and write cache.",
+    after_long_help = EXIT_CODES_HELP,
     disable_version_flag = true
 )]
 struct Args {
@@ -61,7 +63,8 @@ pub enum Command {
         author,
         version,
         about = "Import a file into Dragonfly P2P network",
-        long_about = "Import a local file into Dragonfly P2P network and create multiple replicas on different peers. If import successfully, it will return a task ID."
+        long_about = "Import a local file into Dragonfly P2P network and create multiple replicas on different peers. If import successfully, it will return a task ID.",
+        after_long_help = EXIT_CODES_HELP
     )]
     Import(import::ImportCommand),
 
@@ -70,7 +73,8 @@ pub enum Command {
         author,
         version,
         about = "Export a file from Dragonfly P2P network",
-        long_about = "Export a file from Dragonfly P2P network by task ID. If export successfully, it will return the local file path."
+        long_about = "Export a file from Dragonfly P2P network by task ID. If export successfully, it will return the local file path.",
+        after_long_help = EXIT_CODES_HELP
     )]
     Export(export::ExportCommand),
 
@@ -79,7 +83,8 @@ pub enum Command {
         author,
         version,
         about = "Stat a file in Dragonfly P2P network",
-        long_about = "Stat a file in Dragonfly P2P network by task ID. If stat successfully, it will return the file information."
+        long_about = "Stat a file in Dragonfly P2P network by task ID. If stat successfully, it will return the file information.",
+        after_long_help = EXIT_CODES_HELP
     )]
     Stat(stat::StatCommand),
 }
@@ -57,6 +57,14 @@ pub struct StatCommand {
 
     #[arg(long, default_value_t = false, help = "Specify whether to print log")]
     console: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format, json writes the task information as the result event to stdout"
+    )]
+    output_format: OutputFormat,
 }
 
 /// Implement the execute for StatCommand.
@@ -75,143 +83,191 @@ impl StatCommand {
         // Initialize tracing.
         let _guards = init_command_tracing(self.log_level, self.console);
 
+        // Initialize the reporter of the output format.
+        let mut reporter = Reporter::new(self.output_format);
+
+        // Validate the command line arguments.
+        if let Err(err) = self.validate_args() {
+            if !reporter.is_json() {
+                println!(
+                    "{}{}{}Validating Failed!{}",
+                    color::Fg(color::Red),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                println!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                println!(
+                    "{}{}{}Message:{} {}",
+                    color::Fg(color::Cyan),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset,
+                    err,
+                );
+
+                println!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+            }
+
+            reporter.fail(ErrorClass::InvalidArgument, &err);
+        }
+
         // Get dfdaemon download client.
         let dfdaemon_download_client =
             match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
                 Ok(client) => client,
                 Err(err) => {
-                    println!(
-                        "{}{}{}Connect Dfdaemon Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        err,
-                        self.endpoint.to_string_lossy(),
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    std::process::exit(1);
+                    if !reporter.is_json() {
+                        println!(
+                            "{}{}{}Connect Dfdaemon Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            err,
+                            self.endpoint.to_string_lossy(),
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
+
+                    reporter.fail(ErrorClass::DfdaemonUnavailable, &err);
                 }
             };
 
         // Run stat sub command.
-        if let Err(err) = self.run(dfdaemon_download_client).await {
-            match err {
-                Error::TonicStatus(status) => {
-                    println!(
-                        "{}{}{}Stating Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                    );
-
-                    println!(
-                        "{}{}{}*********************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Bad Code:{} {}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        status.code()
-                    );
-
-                    println!(
-                        "{}{}{}Message:{} {}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        status.message()
-                    );
-
-                    println!(
-                        "{}{}{}Details:{} {}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        std::str::from_utf8(status.details()).unwrap()
-                    );
-
-                    println!(
-                        "{}{}{}*********************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-                }
-                err => {
-                    println!(
-                        "{}{}{}Stating Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Message:{} {}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        err
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
+        if let Err(err) = self.run(dfdaemon_download_client, &mut reporter).await {
+            if !reporter.is_json() {
+                match &err {
+                    Error::TonicStatus(status) => {
+                        println!(
+                            "{}{}{}Stating Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                        );
+
+                        println!(
+                            "{}{}{}*********************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}Bad Code:{} {}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            status.code()
+                        );
+
+                        println!(
+                            "{}{}{}Message:{} {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            status.message()
+                        );
+
+                        println!(
+                            "{}{}{}Details:{} {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            std::str::from_utf8(status.details()).unwrap()
+                        );
+
+                        println!(
+                            "{}{}{}*********************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
+                    err => {
+                        println!(
+                            "{}{}{}Stating Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}Message:{} {}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            err
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
                 }
             }
 
-            std::process::exit(1);
+            reporter.fail(ErrorClass::from(&err), &err);
         }
 
+        reporter.succeed();
         Ok(())
     }
 
@@ -221,7 +277,11 @@ impl StatCommand {
     /// persistent cache task and presents it in a formatted table for user consumption.
     /// It handles data conversion from raw protocol buffer values to human-readable formats
     /// including byte sizes, durations, and timestamps with proper timezone conversion.
-    async fn run(&self, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
+    async fn run(
+        &self,
+        dfdaemon_download_client: DfdaemonDownloadClient,
+        reporter: &mut Reporter,
+    ) -> Result<()> {
         let task = dfdaemon_download_client
             .stat_persistent_cache_task(StatPersistentCacheTaskRequest {
                 task_id: self.id.clone(),
@@ -229,6 +289,14 @@ impl StatCommand {
             })
             .await?;
 
+        // The task information is written in the result event in the json output format.
+        if reporter.is_json() {
+            reporter.set_task_id(task.id.clone());
+            reporter.set_content_length(task.content_length);
+            reporter.set_details(&task);
+            return Ok(());
+        }
+
         // Define the table struct for printing.
         #[derive(Debug, Default, Tabled)]
         #[tabled(rename_all = "UPPERCASE")]
@@ -303,4 +371,18 @@ impl StatCommand {
 
         Ok(())
     }
+
+    /// Validates command line arguments for the stat operation.
+    ///
+    /// The task information is written as the result event to stdout in the json output
+    /// format, so the console log is not allowed with it.
+    fn validate_args(&self) -> Result<()> {
+        if self.output_format == OutputFormat::Json && self.console {
+            return Err(Error::ValidationError(
+                "console log can not be used with json output format".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
 }
@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use dragonfly_client::announcer::SchedulerAnnouncer;
 use dragonfly_client::dynconfig::Dynconfig;
 use dragonfly_client::gc::GC;
@@ -25,14 +25,17 @@ use dragonfly_client::grpc::{
 use dragonfly_client::health::Health;
 use dragonfly_client::proxy::Proxy;
 use dragonfly_client::resource::{
-    persistent_cache_task::PersistentCacheTask, persistent_task::PersistentTask, task::Task,
+    cache_task::CacheTask, persistent_cache_task::PersistentCacheTask,
+    persistent_task::PersistentTask, task::Task,
 };
 use dragonfly_client::stats::Stats;
 use dragonfly_client::tracing::init_tracing;
 use dragonfly_client_backend::BackendFactory;
 use dragonfly_client_config::{dfdaemon, VersionValueParser};
 use dragonfly_client_metric::Metrics;
-use dragonfly_client_storage::{server::quic::QUICServer, server::tcp::TCPServer, Storage};
+use dragonfly_client_storage::{
+    scrub::Scrubber, server::quic::QUICServer, server::tcp::TCPServer, Storage,
+};
 use dragonfly_client_util::{id_generator::IDGenerator, net::Interface, shutdown};
 use leaky_bucket::RateLimiter;
 use std::net::SocketAddr;
@@ -44,6 +47,9 @@ use tokio::sync::mpsc;
 use tokio::sync::Barrier;
 use tracing::{error, info, Level};
 
+mod migrate;
+mod scrub;
+
 #[cfg(not(target_env = "msvc"))]
 #[global_allocator]
 static ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
@@ -106,6 +112,34 @@ struct Args {
         value_parser = VersionValueParser
     )]
     version: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    #[command(
+        name = "migrate-metadata",
+        author,
+        version,
+        about = "Migrate the metadata between storage engines",
+        long_about = "Migrate the metadata of all tasks and pieces in the storage dir between storage engines offline. \
+        The dfdaemon must be stopped during the migration, and storage.metadataEngine needs to be set to the destination engine \
+        before starting dfdaemon."
+    )]
+    MigrateMetadata(migrate::MigrateMetadataCommand),
+
+    #[command(
+        name = "scrub",
+        author,
+        version,
+        about = "Scrub the storage offline",
+        long_about = "Scrub the storage offline, reconcile the metadata of the tasks and pieces with the content files. \
+        The task whose content file is missing is deleted, the corrupted pieces are marked as failed and the orphan \
+        content files are deleted. The dfdaemon must be stopped during the scrub, and storage.keep needs to be true."
+    )]
+    Scrub(scrub::ScrubCommand),
 }
 
 #[tokio::main]
@@ -157,6 +191,16 @@ async fn main() -> Result<(), anyhow::Error> {
         args.console,
     );
 
+    // Run the subcommand without starting the dfdaemon.
+    if let Some(command) = args.command {
+        match command {
+            Command::MigrateMetadata(cmd) => cmd.execute(&config, &args.log_dir)?,
+            Command::Scrub(cmd) => cmd.execute(config.clone(), &args.log_dir).await?,
+        }
+
+        return Ok(());
+    }
+
     // Initialize storage.
     let storage = Storage::new(config.clone(), config.storage.dir.as_path(), args.log_dir)
         .await
@@ -292,6 +336,21 @@ async fn main() -> Result<(), anyhow::Error> {
     )?;
     let persistent_cache_task = Arc::new(persistent_cache_task);
 
+    // Initialize cache task manager.
+    let cache_task = CacheTask::new(
+        config.clone(),
+        id_generator.clone(),
+        storage.clone(),
+        scheduler_client.clone(),
+        backend_factory.clone(),
+        download_rate_limiter.clone(),
+        upload_rate_limiter.clone(),
+        prefetch_rate_limiter.clone(),
+        shutdown.clone(),
+        shutdown_complete_tx.clone(),
+    )?;
+    let cache_task = Arc::new(cache_task);
+
     let interface = Interface::new(config.host.ip.unwrap(), config.upload.rate_limit);
     let interface = Arc::new(interface);
 
@@ -309,9 +368,21 @@ async fn main() -> Result<(), anyhow::Error> {
         shutdown_complete_tx.clone(),
     );
 
+    // Initialize storage scrubber, the tasks deleted by the scrubber are deleted from the
+    // scheduler by the garbage collector.
+    let (scrubbed_task_tx, scrubbed_task_rx) = mpsc::unbounded_channel();
+    let scrubber = Arc::new(Scrubber::new(
+        config.clone(),
+        storage.clone(),
+        Some(scrubbed_task_tx),
+        shutdown.clone(),
+        shutdown_complete_tx.clone(),
+    ));
+
     // Initialize stats server.
     let stats = Stats::new(
         SocketAddr::new(config.stats.server.ip.unwrap(), config.stats.server.port),
+        scrubber.clone(),
         shutdown.clone(),
         shutdown_complete_tx.clone(),
     );
@@ -349,7 +420,10 @@ async fn main() -> Result<(), anyhow::Error> {
         task.clone(),
         shutdown.clone(),
         shutdown_complete_tx.clone(),
-    );
+    )
+    .inspect_err(|err| {
+        error!("initialize proxy failed: {}", err);
+    })?;
 
     // Initialize scheduler announcer.
     let scheduler_announcer = SchedulerAnnouncer::new(
@@ -372,6 +446,7 @@ async fn main() -> Result<(), anyhow::Error> {
         task.clone(),
         persistent_task.clone(),
         persistent_cache_task.clone(),
+        cache_task.clone(),
         interface.clone(),
         shutdown.clone(),
         shutdown_complete_tx.clone(),
@@ -384,6 +459,7 @@ async fn main() -> Result<(), anyhow::Error> {
         task.clone(),
         persistent_task.clone(),
         persistent_cache_task.clone(),
+        cache_task.clone(),
         shutdown.clone(),
         shutdown_complete_tx.clone(),
     );
@@ -394,6 +470,7 @@ async fn main() -> Result<(), anyhow::Error> {
         id_generator.host_id(),
         storage.clone(),
         scheduler_client.clone(),
+        scrubbed_task_rx,
         shutdown.clone(),
         shutdown_complete_tx.clone(),
     );
@@ -430,6 +507,10 @@ async fn main() -> Result<(), anyhow::Error> {
             info!("garbage collector exited");
         },
 
+        _ = tokio::spawn(async move { scrubber.run().await }) => {
+            info!("storage scrubber exited");
+        },
+
         _ = {
             tokio::spawn(async move {
                 storage_tcp_server.run().await.unwrap_or_else(|err| error!("storage tcp server failed: {}", err));
@@ -491,6 +572,10 @@ async fn main() -> Result<(), anyhow::Error> {
     // of scheduler_client, so scheduler_client can be released normally.
     drop(persistent_cache_task);
 
+    // Drop cache task to release scheduler_client. when drop the cache task, it will release the Arc reference
+    // of scheduler_client, so scheduler_client can be released normally.
+    drop(cache_task);
+
     // Drop scheduler_client to release dynconfig. when drop the scheduler_client, it will release the
     // Arc reference of dynconfig, so dynconfig can be released normally.
     drop(scheduler_client);
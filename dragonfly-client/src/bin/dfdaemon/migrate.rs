@@ -0,0 +1,106 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use clap::Parser;
+use dragonfly_client_config::dfdaemon::{Config, MetadataEngine};
+use dragonfly_client_core::{Error, Result};
+use dragonfly_client_storage::metadata::Metadata;
+use std::path::PathBuf;
+use termion::{color, style};
+use tracing::{error, info};
+
+/// MigrateMetadataCommand is the subcommand of migrate-metadata.
+#[derive(Debug, Clone, Parser)]
+pub struct MigrateMetadataCommand {
+    #[arg(
+        long,
+        default_value_t = MetadataEngine::Rocksdb,
+        help = "Specify the metadata engine to migrate from [rocksdb, redb]"
+    )]
+    from: MetadataEngine,
+
+    #[arg(
+        long,
+        default_value_t = MetadataEngine::Redb,
+        help = "Specify the metadata engine to migrate to [rocksdb, redb]"
+    )]
+    to: MetadataEngine,
+}
+
+/// Implement the execute for MigrateMetadataCommand.
+impl MigrateMetadataCommand {
+    /// execute migrates the metadata of all namespaces in the storage dir from the source engine
+    /// to the destination engine. The existing metadata of the destination engine is replaced,
+    /// and the dfdaemon must be stopped during the migration.
+    pub fn execute(&self, config: &Config, log_dir: &PathBuf) -> Result<()> {
+        if let Err(err) = self.run(config, log_dir) {
+            println!(
+                "{}{}{}Migrating Failed!{}",
+                color::Fg(color::Red),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
+
+            println!(
+                "{}{}{}Message:{} {}",
+                color::Fg(color::Cyan),
+                style::Italic,
+                style::Bold,
+                style::Reset,
+                err
+            );
+
+            error!("migrate metadata failed: {}", err);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// run runs the migrate-metadata command.
+    fn run(&self, config: &Config, log_dir: &PathBuf) -> Result<()> {
+        if self.from == self.to {
+            return Err(Error::ValidationError(format!(
+                "the source and destination metadata engines are both {}",
+                self.from
+            )));
+        }
+
+        let dir = config.storage.dir.as_path();
+        let source = Metadata::open(self.from, dir, log_dir, true)?;
+        let destination = Metadata::open(self.to, dir, log_dir, false)?;
+        let count = source.migrate_to(&destination)?;
+        info!(
+            "migrate {} objects from {} to {} in {:?}",
+            count, self.from, self.to, dir
+        );
+
+        println!(
+            "{}{}{}Migrated {} objects from {} to {}, set storage.metadataEngine to {} before starting dfdaemon.{}",
+            color::Fg(color::Green),
+            style::Italic,
+            style::Bold,
+            count,
+            self.from,
+            self.to,
+            self.to,
+            style::Reset
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use clap::Parser;
+use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Error, Result,
+};
+use dragonfly_client_storage::{scrub::Scrubber, Storage};
+use dragonfly_client_util::shutdown;
+use std::path::PathBuf;
+use std::sync::Arc;
+use termion::{color, style};
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// ScrubCommand is the subcommand of scrub.
+#[derive(Debug, Clone, Parser)]
+pub struct ScrubCommand {}
+
+/// Implement the execute for ScrubCommand.
+impl ScrubCommand {
+    /// execute scrubs the storage once and prints the report. The dfdaemon must be stopped
+    /// during the scrub.
+    pub async fn execute(&self, config: Arc<Config>, log_dir: &PathBuf) -> Result<()> {
+        if let Err(err) = self.run(config, log_dir).await {
+            println!(
+                "{}{}{}Scrubbing Failed!{}",
+                color::Fg(color::Red),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
+
+            println!(
+                "{}{}{}Message:{} {}",
+                color::Fg(color::Cyan),
+                style::Italic,
+                style::Bold,
+                style::Reset,
+                err
+            );
+
+            error!("scrub storage failed: {}", err);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// run runs the scrub command.
+    async fn run(&self, config: Arc<Config>, log_dir: &PathBuf) -> Result<()> {
+        // The storage is destroyed when it is not kept, so the scrub is meaningless.
+        if !config.storage.keep {
+            return Err(Error::ValidationError(
+                "storage.keep must be true to scrub the storage".to_string(),
+            ));
+        }
+
+        let storage = Storage::new(
+            config.clone(),
+            config.storage.dir.as_path(),
+            log_dir.clone(),
+        )
+        .await?;
+        let (shutdown_complete_tx, _) = mpsc::unbounded_channel();
+        // The scheduler is not connected when scrubbing offline, so the deleted tasks are
+        // not reported to the scheduler.
+        let scrubber = Scrubber::new(
+            config,
+            Arc::new(storage),
+            None,
+            shutdown::Shutdown::default(),
+            shutdown_complete_tx,
+        );
+
+        let report = scrubber.scrub().await?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).or_err(ErrorType::SerializeError)?
+        );
+
+        Ok(())
+    }
+}
@@ -16,35 +16,43 @@
 
 use bytesize::ByteSize;
 use clap::Parser;
-use dragonfly_api::common::v2::{Download, Hdfs, ObjectStorage, TaskType};
+use dragonfly_api::common::v2::{Download, Hdfs, ObjectStorage, Range, TaskType};
 use dragonfly_api::dfdaemon::v2::{
     download_task_response, DownloadTaskRequest, ListTaskEntriesRequest,
 };
 use dragonfly_api::errordetails::v2::Backend;
 use dragonfly_client::grpc::dfdaemon_download::DfdaemonDownloadClient;
 use dragonfly_client::grpc::health::HealthClient;
+use dragonfly_client::output::{ErrorClass, OutputFormat, Reporter, EXIT_CODES_HELP};
 use dragonfly_client::resource::piece::MIN_PIECE_LENGTH;
 use dragonfly_client::tracing::init_command_tracing;
-use dragonfly_client_backend::{hdfs, object_storage, BackendFactory, DirEntry};
+use dragonfly_client_backend::{hdfs, object_storage, oci, BackendFactory, DirEntry, StatRequest};
 use dragonfly_client_config::VersionValueParser;
 use dragonfly_client_config::{self, dfdaemon, dfget};
-use dragonfly_client_core::error::{ErrorType, OrErr};
+use dragonfly_client_core::error::{BackendError, ErrorType, OrErr};
 use dragonfly_client_core::{Error, Result};
 use dragonfly_client_util::{
-    fs::fallocate, http::header_vec_to_hashmap,
+    digest::{is_blob_url, Digest},
+    fs::fallocate,
     http::query_params::default_proxy_rule_filtered_query_params,
+    http::{header_vec_to_hashmap, header_vec_to_headermap},
 };
+use futures::{stream, StreamExt, TryStreamExt};
 use glob::Pattern;
+use http::header::RANGE;
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
 use local_ip_address::local_ip;
+use ordered::OrderedWriter;
 use path_absolutize::*;
 use percent_encoding::percent_decode_str;
-use std::collections::HashSet;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{cmp::min, fmt::Write};
+use sync::{remove_extraneous_files, RemoteFile, SyncState, DEFAULT_SYNC_STATE_FILE_NAME};
 use termion::{color, style};
 use tokio::fs::{self, OpenOptions};
 use tokio::io::{AsyncSeekExt, AsyncWriteExt, SeekFrom};
@@ -54,6 +62,16 @@ use tracing::{debug, error, info, warn, Instrument, Level};
 use url::Url;
 use uuid::Uuid;
 
+mod ordered;
+mod sync;
+
+/// STDOUT_OUTPUT is the output path to write the content of the downloading file to stdout.
+const STDOUT_OUTPUT: &str = "-";
+
+/// SYNC_STATE_SAVE_INTERVAL is the interval to save the sync state while downloading the
+/// changed files of the directory.
+const SYNC_STATE_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
 const LONG_ABOUT: &str = r#"
 A download command line based on P2P technology in Dragonfly that can download resources of different protocols.
 
@@ -63,6 +81,30 @@ Examples:
   # Download a file from HTTP server.
   $ dfget https://<host>:<port>/<path> -O /tmp/file.txt
 
+  # Continue the interrupted download into the same output file.
+  $ dfget https://<host>:<port>/<path> -O /tmp/file.txt --continue
+
+  # Download a file to stdout and extract it by the pipe without staging it on disk.
+  $ dfget https://<host>:<port>/<path>/rootfs.tar.zst -O - | zstd -d | tar x
+
+  # Download the footer of a parquet file, only the pieces of the ranges are downloaded.
+  $ dfget https://<host>:<port>/<path>/data.parquet -O /tmp/footer --range=-65536 --range-output=compact
+
+  # Download a file and write the progress and the result as newline delimited JSON events.
+  $ dfget https://<host>:<port>/<path> -O /tmp/file.txt --output-format json
+
+  # Download the files in the manifest file in batch, each line of the manifest is `<url> <output> [<digest>]`.
+  $ dfget --input-file /tmp/manifest.txt --max-concurrent-requests 10
+
+  # Download a file from the local filesystem, e.g. NFS mounts and shared volumes.
+  $ dfget file:///<path> -O /tmp/file.txt
+
+  # Sync a directory from Amazon Simple Storage Service(S3), only the changed files are downloaded and the deleted files are removed.
+  $ dfget s3://<bucket>/<path>/ -O /tmp/dir/ -r --sync --delete --max-files=1000 --storage-access-key-id=<access_key_id> --storage-access-key-secret=<access_key_secret>
+
+  # Download all layers of an image from OCI registry, the blobs are shared with the image pulled by the proxy.
+  $ dfget oci://<registry>/<repository>:<tag> -O /tmp/image/ -r --oci-platform=linux/amd64
+
   # Download a file from HDFS.
   $ dfget hdfs://<host>:<port>/<path> -O /tmp/file.txt --hdfs-delegation-token=<delegation_token>
 
@@ -85,6 +127,16 @@ Examples:
   $ dfget cos://<bucket>/<path> -O /tmp/file.txt --storage-access-key-id=<access_key_id> --storage-access-key-secret=<access_key_secret> --storage-endpoint=<endpoint>
 "#;
 
+/// RangeOutput is the layout of the downloaded ranges in the output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum RangeOutput {
+    /// Sparse writes the ranges at their offsets of the whole file.
+    Sparse,
+
+    /// Compact concatenates the ranges in order.
+    Compact,
+}
+
 #[derive(Debug, Parser, Clone)]
 #[command(
     name = dfget::NAME,
@@ -92,11 +144,16 @@ Examples:
     version,
     about = "dfget is a download command line based on P2P technology",
     long_about = LONG_ABOUT,
+    after_long_help = EXIT_CODES_HELP,
     disable_version_flag = true,
 )]
 struct Args {
-    #[arg(help = "Specify the URL to download")]
-    url: Url,
+    #[arg(
+        required_unless_present = "input_file",
+        conflicts_with = "input_file",
+        help = "Specify the URL to download"
+    )]
+    url: Option<Url>,
 
     #[arg(
         long = "transfer-from-dfdaemon",
@@ -112,6 +169,14 @@ struct Args {
     )]
     overwrite: bool,
 
+    #[arg(
+        short = 'c',
+        long = "continue",
+        default_value_t = false,
+        help = "Specify whether to continue the interrupted download into the existing output file. If it is true, dfget will resume the download from the pieces already finished by dfdaemon, and keep the partial output file if the download fails, so the download can be continued by running the same command again. The download interrupted by the restart of dfdaemon is resumed only if the storage.keep of dfdaemon is true."
+    )]
+    continue_download: bool,
+
     #[arg(
         long = "force-hard-link",
         default_value_t = false,
@@ -128,9 +193,25 @@ struct Args {
     #[arg(
         short = 'O',
         long = "output",
-        help = "Specify the output path of downloading file"
+        required_unless_present = "input_file",
+        conflicts_with = "input_file",
+        help = "Specify the output path of downloading file. If it is `-`, dfget will write the content of downloading file to stdout in order by transferring the content from dfdaemon's unix domain socket"
     )]
-    output: PathBuf,
+    output: Option<PathBuf>,
+
+    #[arg(
+        long = "stdout-buffer-size",
+        default_value = "128mib",
+        help = "Specify the max size of the pieces buffered in memory when writing to stdout. The pieces finished before the pieces in front of them are buffered until they can be written in order, and the pieces exceeding the buffer are spilled to a temporary file. The value needs to be set with human readable format, for example: 64mib, 1gib"
+    )]
+    stdout_buffer_size: ByteSize,
+
+    #[arg(
+        long = "input-file",
+        conflicts_with = "recursive",
+        help = "Specify the manifest file to download files in batch. The text manifest has an entry per line in the format of `<url> <output> [<digest>]`, and the manifest with the .jsonl extension has a JSON object per line with the url, output, digest, header, tag and application fields. The headers of the entry are appended to the headers of the command line, and the tag and application of the command line are used if they are not set in the entry. The relative output path is resolved against the current directory, and the files are downloaded concurrently bounded by --max-concurrent-requests"
+    )]
+    input_file: Option<PathBuf>,
 
     #[arg(
         short = 'e',
@@ -148,6 +229,46 @@ struct Args {
     )]
     recursive: bool,
 
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "recursive",
+        help = "Specify whether to sync the directory. If it is true, dfget only downloads the files which are new or changed by comparing the size, the ETag and the Last-Modified of the remote files with the last sync, and the local files modified after the last sync. The --max-files limits the count of the files to download"
+    )]
+    sync: bool,
+
+    #[arg(
+        long = "delete",
+        default_value_t = false,
+        requires = "sync",
+        conflicts_with = "include_files",
+        help = "Specify whether to delete the local files which no longer exist in the remote directory when syncing"
+    )]
+    delete: bool,
+
+    #[arg(
+        long = "force-delete",
+        default_value_t = false,
+        requires = "delete",
+        help = "Specify whether to delete all the local files when the remote directory has no files. If it is false, the sync with --delete is refused if the remote directory has no files, which may be caused by the wrong URL or credentials"
+    )]
+    force_delete: bool,
+
+    #[arg(
+        long,
+        requires = "sync",
+        help = "Specify the path of the sync state file, which records the synced files to resume the interrupted sync. If it is not specified, the .dfget-sync.json in the output directory is used"
+    )]
+    sync_state_file: Option<PathBuf>,
+
+    #[arg(
+        long = "dfdaemon-config",
+        default_value_os_t = dfdaemon::default_dfdaemon_config_path(),
+        requires = "sync",
+        help = "Specify the config file of dfdaemon when syncing. The remote files are stated by the backend with the upstream proxy and the retry of the config, and the default config is used if the config file does not exist"
+    )]
+    dfdaemon_config: PathBuf,
+
     #[arg(
         long = "timeout",
         value_parser= humantime::parse_duration,
@@ -163,6 +284,21 @@ struct Args {
     )]
     digest: Option<String>,
 
+    #[arg(
+        long = "range",
+        conflicts_with_all = ["digest", "continue_download", "recursive"],
+        help = "Specify the byte range to download in the format of `<start>-<end>`, `<start>-` or `-<suffix-length>`, the end is inclusive as the HTTP Range header. It can be repeated to download multiple ranges, and only the pieces of the ranges are downloaded. Examples: --range=0-1023 --range=-8"
+    )]
+    range: Vec<String>,
+
+    #[arg(
+        long = "range-output",
+        value_enum,
+        default_value_t = RangeOutput::Sparse,
+        help = "Specify the layout of the ranges in the output file. If it is sparse, the ranges are written at their offsets in the sparse output file with the size of the whole file. If it is compact, the ranges are concatenated in the order of --range. The ranges are always concatenated when writing to stdout"
+    )]
+    range_output: RangeOutput,
+
     #[arg(
         short = 'p',
         long = "priority",
@@ -271,6 +407,12 @@ struct Args {
     )]
     hdfs_delegation_token: Option<String>,
 
+    #[arg(
+        long,
+        help = "Specify the platform of the image when downloading from OCI registry and the reference is an image index, e.g. linux/amd64 or linux/arm64/v8. If it is not specified, the platform of the host is used"
+    )]
+    oci_platform: Option<String>,
+
     #[arg(
         long,
         default_value_t = 10,
@@ -281,7 +423,7 @@ struct Args {
     #[arg(
         long,
         default_value_t = 1,
-        help = "Specify the max count of concurrent download files when downloading a directory"
+        help = "Specify the max count of concurrent download files when downloading a directory or the files in the manifest file"
     )]
     max_concurrent_requests: usize,
 
@@ -292,6 +434,14 @@ struct Args {
     )]
     no_progress: bool,
 
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format, json writes the progress and the result as newline delimited JSON events to stdout"
+    )]
+    output_format: OutputFormat,
+
     #[arg(
         short = 'l',
         long,
@@ -322,42 +472,47 @@ async fn main() -> anyhow::Result<()> {
     // Initialize tracing.
     let _guards = init_command_tracing(args.log_level, args.console);
 
+    // Initialize the reporter of the output format.
+    let mut reporter = Reporter::new(args.output_format);
+
     // Validate command line arguments.
     if let Err(err) = validate_args(&args) {
-        println!(
-            "{}{}{}Validating Failed!{}",
-            color::Fg(color::Red),
-            style::Italic,
-            style::Bold,
-            style::Reset
-        );
+        if !reporter.is_json() {
+            println!(
+                "{}{}{}Validating Failed!{}",
+                color::Fg(color::Red),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
 
-        println!(
-            "{}{}{}****************************************{}",
-            color::Fg(color::Black),
-            style::Italic,
-            style::Bold,
-            style::Reset
-        );
+            println!(
+                "{}{}{}****************************************{}",
+                color::Fg(color::Black),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
 
-        println!(
-            "{}{}{}Message:{} {}",
-            color::Fg(color::Cyan),
-            style::Italic,
-            style::Bold,
-            style::Reset,
-            err,
-        );
+            println!(
+                "{}{}{}Message:{} {}",
+                color::Fg(color::Cyan),
+                style::Italic,
+                style::Bold,
+                style::Reset,
+                err,
+            );
 
-        println!(
-            "{}{}{}****************************************{}",
-            color::Fg(color::Black),
-            style::Italic,
-            style::Bold,
-            style::Reset
-        );
+            println!(
+                "{}{}{}****************************************{}",
+                color::Fg(color::Black),
+                style::Italic,
+                style::Bold,
+                style::Reset
+            );
+        }
 
-        std::process::exit(1);
+        reporter.fail(ErrorClass::InvalidArgument, &err);
     }
 
     // Get dfdaemon download client.
@@ -365,50 +520,165 @@ async fn main() -> anyhow::Result<()> {
         match get_dfdaemon_download_client(args.endpoint.to_path_buf()).await {
             Ok(client) => client,
             Err(err) => {
-                println!(
-                    "{}{}{}Connect Dfdaemon Failed!{}",
-                    color::Fg(color::Red),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+                if !reporter.is_json() {
+                    println!(
+                        "{}{}{}Connect Dfdaemon Failed!{}",
+                        color::Fg(color::Red),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
 
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+                    println!(
+                        "{}{}{}****************************************{}",
+                        color::Fg(color::Black),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
 
-                println!(
-                    "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
-                    color::Fg(color::Cyan),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset,
-                    err,
-                    args.endpoint.to_string_lossy(),
-                );
+                    println!(
+                        "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
+                        color::Fg(color::Cyan),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset,
+                        err,
+                        args.endpoint.to_string_lossy(),
+                    );
 
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+                    println!(
+                        "{}{}{}****************************************{}",
+                        color::Fg(color::Black),
+                        style::Italic,
+                        style::Bold,
+                        style::Reset
+                    );
+                }
 
-                std::process::exit(1);
+                reporter.fail(ErrorClass::DfdaemonUnavailable, &err);
             }
         };
 
     // Run dfget command.
-    if let Err(err) = run(args, dfdaemon_download_client).await {
-        match err {
-            Error::TonicStatus(status) => {
-                let details = status.details();
-                if let Ok(backend_err) = serde_json::from_slice::<Backend>(details) {
+    if let Err(err) = run(args, dfdaemon_download_client, &mut reporter).await {
+        if !reporter.is_json() {
+            match &err {
+                Error::TonicStatus(status) => {
+                    let details = status.details();
+                    if let Ok(backend_err) = serde_json::from_slice::<Backend>(details) {
+                        println!(
+                            "{}{}{}Downloading Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        if let Some(status_code) = backend_err.status_code {
+                            println!(
+                                "{}{}{}Bad Status Code:{} {}",
+                                color::Fg(color::Red),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset,
+                                status_code
+                            );
+                        }
+
+                        println!(
+                            "{}{}{}Message:{} {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            backend_err.message
+                        );
+
+                        if !backend_err.header.is_empty() {
+                            println!(
+                                "{}{}{}Header:{}",
+                                color::Fg(color::Cyan),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset
+                            );
+                            for (key, value) in backend_err.header.iter() {
+                                println!("  [{}]: {}", key.as_str(), value.as_str());
+                            }
+                        }
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    } else {
+                        println!(
+                            "{}{}{}Downloading Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}*********************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}Bad Code:{} {}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            status.code()
+                        );
+
+                        println!(
+                            "{}{}{}Message:{} {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            status.message()
+                        );
+
+                        if !status.details().is_empty() {
+                            println!(
+                                "{}{}{}Details:{} {}",
+                                color::Fg(color::Cyan),
+                                style::Italic,
+                                style::Bold,
+                                style::Reset,
+                                std::str::from_utf8(status.details()).unwrap()
+                            );
+                        }
+
+                        println!(
+                            "{}{}{}*********************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
+                }
+                Error::BackendError(err) => {
                     println!(
                         "{}{}{}Downloading Failed!{}",
                         color::Fg(color::Red),
@@ -425,27 +695,16 @@ async fn main() -> anyhow::Result<()> {
                         style::Reset
                     );
 
-                    if let Some(status_code) = backend_err.status_code {
-                        println!(
-                            "{}{}{}Bad Status Code:{} {}",
-                            color::Fg(color::Red),
-                            style::Italic,
-                            style::Bold,
-                            style::Reset,
-                            status_code
-                        );
-                    }
-
                     println!(
                         "{}{}{}Message:{} {}",
-                        color::Fg(color::Cyan),
+                        color::Fg(color::Red),
                         style::Italic,
                         style::Bold,
                         style::Reset,
-                        backend_err.message
+                        err.message
                     );
 
-                    if !backend_err.header.is_empty() {
+                    if err.header.is_some() {
                         println!(
                             "{}{}{}Header:{}",
                             color::Fg(color::Cyan),
@@ -453,8 +712,8 @@ async fn main() -> anyhow::Result<()> {
                             style::Bold,
                             style::Reset
                         );
-                        for (key, value) in backend_err.header.iter() {
-                            println!("  [{}]: {}", key.as_str(), value.as_str());
+                        for (key, value) in err.header.clone().unwrap_or_default().iter() {
+                            println!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
                         }
                     }
 
@@ -465,7 +724,8 @@ async fn main() -> anyhow::Result<()> {
                         style::Bold,
                         style::Reset
                     );
-                } else {
+                }
+                err => {
                     println!(
                         "{}{}{}Downloading Failed!{}",
                         color::Fg(color::Red),
@@ -475,44 +735,24 @@ async fn main() -> anyhow::Result<()> {
                     );
 
                     println!(
-                        "{}{}{}*********************************{}",
+                        "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
                         style::Bold,
                         style::Reset
                     );
 
-                    println!(
-                        "{}{}{}Bad Code:{} {}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        status.code()
-                    );
-
                     println!(
                         "{}{}{}Message:{} {}",
-                        color::Fg(color::Cyan),
+                        color::Fg(color::Red),
                         style::Italic,
                         style::Bold,
                         style::Reset,
-                        status.message()
+                        err
                     );
 
-                    if !status.details().is_empty() {
-                        println!(
-                            "{}{}{}Details:{} {}",
-                            color::Fg(color::Cyan),
-                            style::Italic,
-                            style::Bold,
-                            style::Reset,
-                            std::str::from_utf8(status.details()).unwrap()
-                        );
-                    }
-
                     println!(
-                        "{}{}{}*********************************{}",
+                        "{}{}{}****************************************{}",
                         color::Fg(color::Black),
                         style::Italic,
                         style::Bold,
@@ -520,110 +760,47 @@ async fn main() -> anyhow::Result<()> {
                     );
                 }
             }
-            Error::BackendError(err) => {
-                println!(
-                    "{}{}{}Downloading Failed!{}",
-                    color::Fg(color::Red),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+        }
 
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+        reporter.fail(ErrorClass::from(&err), &err);
+    }
 
-                println!(
-                    "{}{}{}Message:{} {}",
-                    color::Fg(color::Red),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset,
-                    err.message
-                );
+    reporter.succeed();
+    Ok(())
+}
 
-                if err.header.is_some() {
-                    println!(
-                        "{}{}{}Header:{}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-                    for (key, value) in err.header.unwrap_or_default().iter() {
-                        println!("  [{}]: {}", key.as_str(), value.to_str().unwrap());
-                    }
-                }
+/// Runs the dfget command to download files or directories from a given URL.
+///
+/// This function serves as the main entry point for the dfget download operation.
+/// It handles both single file downloads and directory downloads based on the URL format,
+/// and the batch downloads of the files in the manifest file.
+/// The function performs path normalization, validates the URL scheme's capabilities,
+/// and delegates to the appropriate download handler.
+async fn run(
+    mut args: Args,
+    dfdaemon_download_client: DfdaemonDownloadClient,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    // If the manifest file is set, download the files in the manifest file in batch.
+    if let Some(input_file) = args.input_file.clone() {
+        return download_manifest(args, &input_file, dfdaemon_download_client).await;
+    }
 
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
-            }
-            err => {
-                println!(
-                    "{}{}{}Downloading Failed!{}",
-                    color::Fg(color::Red),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
+    let (Some(url), Some(output)) = (args.url.clone(), args.output.as_ref()) else {
+        return Err(Error::InvalidParameter);
+    };
 
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
-
-                println!(
-                    "{}{}{}Message:{} {}",
-                    color::Fg(color::Red),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset,
-                    err
-                );
-
-                println!(
-                    "{}{}{}****************************************{}",
-                    color::Fg(color::Black),
-                    style::Italic,
-                    style::Bold,
-                    style::Reset
-                );
-            }
-        }
-
-        std::process::exit(1);
-    }
-
-    Ok(())
-}
-
-/// Runs the dfget command to download files or directories from a given URL.
-///
-/// This function serves as the main entry point for the dfget download operation.
-/// It handles both single file downloads and directory downloads based on the URL format.
-/// The function performs path normalization, validates the URL scheme's capabilities,
-/// and delegates to the appropriate download handler.
-async fn run(mut args: Args, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
-    // Get the absolute path of the output file.
-    args.output = Path::new(&args.output).absolutize()?.into();
-    info!("download file to: {}", args.output.to_string_lossy());
+    // Get the absolute path of the output file, stdout is not a path.
+    if !is_stdout(output) {
+        let output: PathBuf = Path::new(output).absolutize()?.into();
+        info!("download file to: {}", output.to_string_lossy());
+        args.output = Some(output);
+    }
 
     // If the path has end with '/' and the scheme supports directory download,
     // then download all files in the directory. Otherwise, download the single file.
-    let scheme = args.url.scheme();
-    if args.url.path().ends_with('/') {
+    let scheme = url.scheme();
+    if url.path().ends_with('/') {
         if BackendFactory::unsupported_download_directory(scheme) {
             return Err(Error::Unsupported(format!("{} download directory", scheme)));
         };
@@ -637,7 +814,7 @@ async fn run(mut args: Args, dfdaemon_download_client: DfdaemonDownloadClient) -
         ProgressBar::new(0)
     };
 
-    download(args, progress_bar, dfdaemon_download_client).await
+    download(args, progress_bar, dfdaemon_download_client, reporter).await
 }
 
 /// Downloads all files in a directory from various storage backends (object storage, HDFS, etc.).
@@ -647,7 +824,15 @@ async fn run(mut args: Args, dfdaemon_download_client: DfdaemonDownloadClient) -
 /// enforces download limits, and performs concurrent downloads with configurable
 /// concurrency control. The function creates the necessary directory structure
 /// locally and downloads files while preserving the remote directory hierarchy.
+/// In sync mode, only the new and changed files are downloaded, the entries listed by
+/// dfdaemon only carry the size, so the file entries are stated by the backend, and the
+/// files are compared by the size, the ETag and the Last-Modified of the remote files and
+/// the local modification time recorded in the sync state file.
 async fn download_dir(args: Args, download_client: DfdaemonDownloadClient) -> Result<()> {
+    let (Some(url), Some(output)) = (args.url.clone(), args.output.clone()) else {
+        return Err(Error::InvalidParameter);
+    };
+
     // Initialize the object storage config and the hdfs config.
     let object_storage = Some(ObjectStorage {
         access_key_id: args.storage_access_key_id.clone(),
@@ -666,22 +851,87 @@ async fn download_dir(args: Args, download_client: DfdaemonDownloadClient) -> Re
 
     // Get all entries in the directory with include files filter.
     let entries: Vec<DirEntry> = get_all_entries(
-        &args.url,
+        &url,
         args.header.clone(),
         args.include_files.clone(),
-        object_storage,
-        hdfs,
+        object_storage.clone(),
+        hdfs.clone(),
         download_client.clone(),
     )
     .await?;
 
-    // If the entries is empty, then return directly.
-    if entries.is_empty() {
-        warn!("no entries found in directory {}", args.url);
+    // Refuse to delete all the local files if the remote directory has no files, because the
+    // empty listing may be caused by the wrong URL or credentials.
+    if args.delete && !args.force_delete && entries.iter().all(|entry| entry.is_dir) {
+        return Err(Error::ValidationError(format!(
+            "no files found in directory {}, use --force-delete to delete all the local files",
+            url
+        )));
+    }
+
+    // If the entries is empty, then return directly. In sync mode, the local files still
+    // need to be deleted if the remote directory is empty.
+    if entries.is_empty() && !args.sync {
+        warn!("no entries found in directory {}", url);
         return Ok(());
     }
 
+    // In sync mode, skip the files which are synced with the local files.
+    let state_path = args
+        .sync_state_file
+        .clone()
+        .unwrap_or_else(|| output.join(DEFAULT_SYNC_STATE_FILE_NAME));
+    let mut sync_state = None;
+    let mut remote_files = HashMap::new();
+
+    // The keys of all the remote files in the listing, which are kept when deleting the
+    // extraneous local files.
+    let mut remote_keys = HashSet::new();
+    let entries = if args.sync {
+        for entry in entries.iter().filter(|entry| !entry.is_dir) {
+            let entry_output = make_output_by_entry(url.clone(), &output, entry.clone())?;
+            remote_keys.insert(make_sync_key(&output, &entry_output)?);
+        }
+
+        let mut state = SyncState::load(&state_path, url.as_str()).await?;
+        let stated_entries = stat_entries(&args, &entries, object_storage, hdfs).await?;
+        let mut changed_entries = Vec::with_capacity(entries.len());
+        for (entry, remote_file) in entries.into_iter().zip(stated_entries) {
+            let Some(remote_file) = remote_file else {
+                changed_entries.push(entry);
+                continue;
+            };
+
+            let entry_output = make_output_by_entry(url.clone(), &output, entry.clone())?;
+            let key = make_sync_key(&output, &entry_output)?;
+            if state.is_synced(&key, &remote_file, &entry_output).await {
+                debug!("skip synced file {:?}", entry_output);
+                state.record(&key, &remote_file, &entry_output).await?;
+            } else {
+                changed_entries.push(entry);
+            }
+
+            remote_files.insert(key, remote_file);
+        }
+
+        info!(
+            "sync {} files in directory {}, {} files are changed",
+            remote_files.len(),
+            url,
+            changed_entries.iter().filter(|entry| !entry.is_dir).count()
+        );
+
+        // Save the sync state before downloading, so the files which are interrupted in
+        // downloading are not compared by the size when the sync is resumed.
+        state.save(&state_path).await?;
+        sync_state = Some(state);
+        changed_entries
+    } else {
+        entries
+    };
+
     // If the actual file count is greater than the max_files, then reject the downloading.
+    // In sync mode, only the changed files are counted.
     let count = entries.iter().filter(|entry| !entry.is_dir).count();
     if count > args.max_files {
         return Err(Error::MaxDownloadFilesExceeded(count));
@@ -707,14 +957,15 @@ async fn download_dir(args: Args, download_client: DfdaemonDownloadClient) -> Re
         // If entry is a directory, then create the output directory. If entry is a file,
         // then download the file to the output directory.
         if entry.is_dir {
-            let output_dir = make_output_by_entry(args.url.clone(), &args.output, entry)?;
+            let output_dir = make_output_by_entry(url.clone(), &output, entry)?;
             fs::create_dir_all(&output_dir).await.inspect_err(|err| {
                 error!("create {} failed: {}", output_dir.to_string_lossy(), err);
             })?;
         } else {
+            let entry_output = make_output_by_entry(url.clone(), &output, entry)?;
             let mut entry_args = args.clone();
-            entry_args.output = make_output_by_entry(args.url.clone(), &args.output, entry)?;
-            entry_args.url = entry_url;
+            entry_args.output = Some(entry_output.clone());
+            entry_args.url = Some(entry_url);
 
             let progress_bar = multi_progress_bar.add(ProgressBar::new(0));
             let download_client = download_client.clone();
@@ -722,33 +973,329 @@ async fn download_dir(args: Args, download_client: DfdaemonDownloadClient) -> Re
             join_set.spawn(
                 async move {
                     let _permit = permit;
-                    download(entry_args, progress_bar, download_client).await
+                    let mut reporter = Reporter::new(entry_args.output_format);
+                    let result =
+                        download(entry_args, progress_bar, download_client, &mut reporter).await;
+                    reporter.finished(&result);
+                    (entry_output, result)
                 }
                 .in_current_span(),
             );
         }
     }
 
-    // Wait for all download tasks finished.
-    while let Some(message) = join_set
+    // Wait for all download tasks finished. In sync mode, the downloaded files are recorded
+    // in the sync state, which is saved periodically to resume the interrupted sync.
+    let mut saved_at = Instant::now();
+    while let Some((entry_output, result)) = join_set
         .join_next()
         .await
         .transpose()
         .or_err(ErrorType::AsyncRuntimeError)?
     {
-        match message {
-            Ok(_) => continue,
+        match result {
+            Ok(_) => {
+                let Some(state) = sync_state.as_mut() else {
+                    continue;
+                };
+
+                let key = make_sync_key(&output, &entry_output)?;
+                if let Some(remote_file) = remote_files.get(&key) {
+                    state.record(&key, remote_file, &entry_output).await?;
+                }
+
+                if saved_at.elapsed() >= SYNC_STATE_SAVE_INTERVAL {
+                    state.save(&state_path).await?;
+                    saved_at = Instant::now();
+                }
+            }
             Err(err) => {
                 error!("download entry failed: {}", err);
                 join_set.shutdown().await;
+                if let Some(state) = sync_state.as_ref() {
+                    state.save(&state_path).await?;
+                }
+
                 return Err(err);
             }
         }
     }
 
+    // In sync mode, delete the local files which no longer exist in the remote directory,
+    // and save the sync state of the remote files. The deletion is reached only if all the
+    // remote files are stated and downloaded successfully, otherwise the error is returned
+    // above and the local files are kept.
+    if let Some(mut state) = sync_state {
+        if args.delete && output.is_dir() {
+            let removed = remove_extraneous_files(&output, &remote_keys, &state_path).await?;
+            info!("remove {} extraneous files in {:?}", removed.len(), output);
+        }
+
+        state.retain(&remote_keys);
+        state.save(&state_path).await?;
+    }
+
+    Ok(())
+}
+
+/// Makes the key of the file in the sync state, which is the path relative to the output
+/// directory.
+fn make_sync_key(output: &Path, entry_output: &Path) -> Result<String> {
+    let relative_path = entry_output
+        .strip_prefix(output)
+        .or_err(ErrorType::ParseError)?;
+    Ok(relative_path.to_string_lossy().to_string())
+}
+
+/// ManifestEntry is the entry of the manifest file to download in batch.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct ManifestEntry {
+    /// url is the URL to download.
+    url: String,
+
+    /// output is the output path of the downloading file.
+    output: PathBuf,
+
+    /// digest is used to verify the integrity of the downloaded file.
+    #[serde(default)]
+    digest: Option<String>,
+
+    /// header is appended to the headers of the command line.
+    #[serde(default)]
+    header: Vec<String>,
+
+    /// tag overrides the tag of the command line.
+    #[serde(default)]
+    tag: Option<String>,
+
+    /// application overrides the application of the command line.
+    #[serde(default)]
+    application: Option<String>,
+}
+
+/// Downloads the files in the manifest file in batch.
+///
+/// This function parses the entries of the manifest file and downloads them concurrently
+/// with the dfdaemon download client shared by all entries, bounded by the max concurrent
+/// requests. The entries are downloaded independently, so a failed entry does not cancel
+/// the others. After all entries are finished, the result of each entry is printed, and
+/// an error is returned if any entry is failed.
+async fn download_manifest(
+    args: Args,
+    input_file: &Path,
+    download_client: DfdaemonDownloadClient,
+) -> Result<()> {
+    let content = fs::read_to_string(input_file).await.inspect_err(|err| {
+        error!("read input file {:?} failed: {}", input_file, err);
+    })?;
+
+    let entries = parse_manifest(input_file, &content)?;
+    info!(
+        "download {} entries in input file {:?}",
+        entries.len(),
+        input_file
+    );
+
+    // Initialize the multi progress bar.
+    let multi_progress_bar = if args.no_progress {
+        let multi_progress = MultiProgress::new();
+        multi_progress.set_draw_target(ProgressDrawTarget::hidden());
+        multi_progress
+    } else {
+        MultiProgress::new()
+    };
+
+    // Initialize the join set.
+    let mut join_set = JoinSet::new();
+    let semaphore = Arc::new(Semaphore::new(args.max_concurrent_requests));
+    let mut results: Vec<Option<Result<()>>> = Vec::with_capacity(entries.len());
+
+    for (index, entry) in entries.iter().enumerate() {
+        let entry_args = match make_args_by_manifest_entry(&args, entry) {
+            Ok(entry_args) => entry_args,
+            Err(err) => {
+                error!("invalid entry {}: {}", entry.url, err);
+                let mut reporter = Reporter::new(args.output_format);
+                reporter.set_source(entry.url.as_str());
+                reporter.set_output(&entry.output);
+
+                let result = Err(err);
+                reporter.finished(&result);
+                results.push(Some(result));
+                continue;
+            }
+        };
+        results.push(None);
+
+        let progress_bar = multi_progress_bar.add(ProgressBar::new(0));
+        let download_client = download_client.clone();
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        join_set.spawn(
+            async move {
+                let _permit = permit;
+                let mut reporter = Reporter::new(entry_args.output_format);
+                let result =
+                    download(entry_args, progress_bar, download_client, &mut reporter).await;
+                reporter.finished(&result);
+                (index, result)
+            }
+            .in_current_span(),
+        );
+    }
+
+    // Wait for all download tasks finished, the failed entry does not cancel the others.
+    while let Some((index, result)) = join_set
+        .join_next()
+        .await
+        .transpose()
+        .or_err(ErrorType::AsyncRuntimeError)?
+    {
+        if let Err(err) = &result {
+            error!("download entry {} failed: {}", entries[index].url, err);
+        }
+
+        results[index] = Some(result);
+    }
+
+    // Print the result of each entry, the results are written as the finished events in the
+    // json output format.
+    let mut failed = 0;
+    for (entry, result) in entries.iter().zip(results) {
+        match result.unwrap_or(Err(Error::UnexpectedResponse)) {
+            Ok(_) if args.output_format == OutputFormat::Json => {}
+            Err(_) if args.output_format == OutputFormat::Json => failed += 1,
+            Ok(_) => {
+                println!(
+                    "{}{}Success:{} {} -> {}",
+                    color::Fg(color::Green),
+                    style::Bold,
+                    style::Reset,
+                    entry.url,
+                    entry.output.to_string_lossy()
+                );
+            }
+            Err(err) => {
+                failed += 1;
+                println!(
+                    "{}{}Failed:{} {} -> {}: {}",
+                    color::Fg(color::Red),
+                    style::Bold,
+                    style::Reset,
+                    entry.url,
+                    entry.output.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::DownloadFilesFailed(failed, entries.len()));
+    }
+
     Ok(())
 }
 
+/// Parses the entries of the manifest file to download in batch.
+///
+/// The manifest file with the .jsonl extension has a JSON object per line, and the other
+/// manifest files have the URL, the output path and the optional digest separated by
+/// whitespaces per line. The blank lines and the lines starting with '#' are ignored. The
+/// relative output path is resolved against the current directory, and the duplicate
+/// output paths are rejected, because the downloads overwrite each other.
+fn parse_manifest(input_file: &Path, content: &str) -> Result<Vec<ManifestEntry>> {
+    let is_jsonl = input_file.extension().is_some_and(|ext| ext == "jsonl");
+    let mut outputs = HashSet::new();
+    let mut entries = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |message: String| {
+            Error::ValidationError(format!(
+                "invalid entry at line {} of input file {}: {}",
+                number + 1,
+                input_file.to_string_lossy(),
+                message
+            ))
+        };
+
+        let mut entry = if is_jsonl {
+            serde_json::from_str::<ManifestEntry>(line).map_err(|err| invalid(err.to_string()))?
+        } else {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if !(2..=3).contains(&fields.len()) {
+                return Err(invalid(
+                    "expected the format of <url> <output> [<digest>]".to_string(),
+                ));
+            }
+
+            ManifestEntry {
+                url: fields[0].to_string(),
+                output: PathBuf::from(fields[1]),
+                digest: fields.get(2).map(|digest| digest.to_string()),
+                header: Vec::new(),
+                tag: None,
+                application: None,
+            }
+        };
+
+        entry.output = Path::new(&entry.output).absolutize()?.into();
+        if !outputs.insert(entry.output.clone()) {
+            return Err(invalid(format!(
+                "duplicate output path {}",
+                entry.output.to_string_lossy()
+            )));
+        }
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Makes the arguments to download the entry of the manifest file.
+///
+/// The arguments of the command line are used as the defaults of the entry, and the
+/// arguments of the entry are validated the same as the single file download.
+fn make_args_by_manifest_entry(args: &Args, entry: &ManifestEntry) -> Result<Args> {
+    let url: Url = entry.url.parse().or_err(ErrorType::ParseError)?;
+    if url.path().ends_with('/') {
+        return Err(Error::Unsupported(format!(
+            "download directory {} in input file",
+            url
+        )));
+    }
+
+    let mut entry_args = args.clone();
+    entry_args.input_file = None;
+    entry_args.url = Some(url);
+    entry_args.output = Some(entry.output.clone());
+    if entry.digest.is_some() {
+        entry_args.digest = entry.digest.clone();
+    }
+
+    if !entry.header.is_empty() {
+        entry_args
+            .header
+            .get_or_insert_with(Vec::new)
+            .extend(entry.header.iter().cloned());
+    }
+
+    if let Some(tag) = entry.tag.as_ref() {
+        entry_args.tag = tag.clone();
+    }
+
+    if let Some(application) = entry.application.as_ref() {
+        entry_args.application = application.clone();
+    }
+
+    validate_args(&entry_args)?;
+    Ok(entry_args)
+}
+
 /// Get all entries in the directory with include files filter.
 async fn get_all_entries(
     base_url: &Url,
@@ -858,34 +1405,22 @@ async fn download(
     args: Args,
     progress_bar: ProgressBar,
     download_client: DfdaemonDownloadClient,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    // Only initialize object storage when the scheme is an object storage protocol.
-    let object_storage = match object_storage::Scheme::from_str(args.url.scheme()) {
-        Ok(_) => Some(ObjectStorage {
-            access_key_id: args.storage_access_key_id.clone(),
-            access_key_secret: args.storage_access_key_secret.clone(),
-            security_token: args.storage_security_token.clone(),
-            session_token: args.storage_session_token.clone(),
-            region: args.storage_region.clone(),
-            endpoint: args.storage_endpoint.clone(),
-            credential_path: args.storage_credential_path.clone(),
-            predefined_acl: args.storage_predefined_acl.clone(),
-        }),
-        Err(_) => None,
+    let (Some(url), Some(output)) = (args.url.clone(), args.output.clone()) else {
+        return Err(Error::InvalidParameter);
     };
 
-    // Only initialize HDFS when the scheme is HDFS protocol.
-    let hdfs = match args.url.scheme() {
-        hdfs::HDFS_SCHEME => Some(Hdfs {
-            delegation_token: args.hdfs_delegation_token.clone(),
-        }),
-        _ => None,
-    };
+    reporter.set_source(url.as_str());
+    reporter.set_digest(args.digest.clone());
+    if !is_stdout(&output) {
+        reporter.set_output(&output);
+    }
 
-    // If the `filtered_query_params` is not provided, then use the default value.
-    let filtered_query_params = args
-        .filtered_query_params
-        .unwrap_or_else(default_proxy_rule_filtered_query_params);
+    // If the ranges are specified, only the pieces of the ranges are downloaded.
+    if !args.range.is_empty() {
+        return download_ranges(args, url, output, progress_bar, download_client, reporter).await;
+    }
 
     // Dfget needs to notify dfdaemon to transfer the piece content of downloading file via unix domain socket
     // when the `transfer_from_dfdaemon` is true. Otherwise, dfdaemon will download the file and hardlink or
@@ -893,57 +1428,27 @@ async fn download(
     let (output_path, need_piece_content) = if args.transfer_from_dfdaemon {
         (None, true)
     } else {
-        (Some(args.output.to_string_lossy().to_string()), false)
+        (Some(output.to_string_lossy().to_string()), false)
     };
 
     // Create dfdaemon client.
     let response = download_client
         .download_task(DownloadTaskRequest {
-            download: Some(Download {
-                url: args.url.to_string(),
-                digest: args.digest,
-                // NOTE: Dfget does not support range download.
-                range: None,
-                r#type: TaskType::Standard as i32,
-                tag: Some(args.tag),
-                application: Some(args.application),
-                priority: args.priority,
-                filtered_query_params,
-                request_header: header_vec_to_hashmap(args.header.unwrap_or_default())?,
-                piece_length: args.piece_length.map(|piece_length| piece_length.as_u64()),
-                output_path,
-                timeout: Some(
-                    prost_wkt_types::Duration::try_from(args.timeout)
-                        .or_err(ErrorType::ParseError)?,
-                ),
-                need_back_to_source: false,
-                disable_back_to_source: args.disable_back_to_source,
-                certificate_chain: Vec::new(),
-                prefetch: false,
-                is_prefetch: false,
-                need_piece_content,
-                object_storage,
-                hdfs,
-                force_hard_link: args.force_hard_link,
-                content_for_calculating_task_id: args.content_for_calculating_task_id,
-                remote_ip: Some(local_ip().unwrap().to_string()),
-                concurrent_piece_count: None,
-                overwrite: args.overwrite,
-                actual_piece_length: None,
-                actual_content_length: None,
-                actual_piece_count: None,
-                enable_task_id_based_blob_digest: false,
-            }),
+            download: Some(make_download(&args, &url, output_path, need_piece_content)?),
         })
         .await
         .inspect_err(|err| {
             error!("download task failed: {}", err);
         })?;
 
+    // If the output is stdout, then dfget needs to write the piece content to stdout in order.
+    let mut stdout = (args.transfer_from_dfdaemon && is_stdout(&output))
+        .then(|| OrderedWriter::new(tokio::io::stdout(), args.stdout_buffer_size.as_u64()));
+
     // If transfer_from_dfdaemon is true, then dfget needs to create the output file and write the
     // piece content to the output file.
-    let mut f = if args.transfer_from_dfdaemon {
-        if let Some(parent) = args.output.parent() {
+    let mut f = if args.transfer_from_dfdaemon && stdout.is_none() {
+        if let Some(parent) = output.parent() {
             if !parent.exists() {
                 fs::create_dir_all(parent).await.inspect_err(|err| {
                     error!("failed to create directory {:?}: {}", parent, err);
@@ -953,13 +1458,13 @@ async fn download(
 
         let f = OpenOptions::new()
             .create(true)
-            .truncate(true)
+            .truncate(!args.continue_download)
             .write(true)
             .mode(dfget::DEFAULT_OUTPUT_FILE_MODE)
-            .open(&args.output)
+            .open(&output)
             .await
             .inspect_err(|err| {
-                error!("open file {:?} failed: {}", args.output, err);
+                error!("open file {:?} failed: {}", output, err);
             })?;
 
         Some(f)
@@ -968,7 +1473,7 @@ async fn download(
     };
 
     // Get actual path rather than percentage encoded path as download path.
-    let download_path = percent_decode_str(args.url.path()).decode_utf8_lossy();
+    let download_path = percent_decode_str(url.path()).decode_utf8_lossy();
     progress_bar.set_style(
         ProgressStyle::with_template(
             "{msg:.bold}\n[{elapsed_precise}] [{bar:60.green/red}] {percent:3}% ({bytes_per_sec:.red}, {eta:.cyan})",
@@ -993,16 +1498,20 @@ async fn download(
                     )) => {
                         if let Some(f) = &f {
                             if let Err(err) = fallocate(f, response.content_length).await {
-                                error!("fallocate {:?} failed: {}", args.output, err);
-                                fs::remove_file(&args.output).await.inspect_err(|err| {
-                                    error!("remove file {:?} failed: {}", args.output, err);
-                                })?;
+                                error!("fallocate {:?} failed: {}", output, err);
+                                remove_output(&output, args.continue_download).await?;
 
                                 return Err(err);
                             }
                         }
 
                         progress_bar.set_length(response.content_length);
+                        reporter.started(
+                            &message.host_id,
+                            &message.task_id,
+                            &message.peer_id,
+                            response.content_length,
+                        );
                     }
                     Some(download_task_response::Response::DownloadPieceFinishedResponse(
                         response,
@@ -1011,22 +1520,19 @@ async fn download(
                             Some(piece) => piece,
                             None => {
                                 error!("response piece is missing");
-                                fs::remove_file(&args.output).await.inspect_err(|err| {
-                                    error!("remove file {:?} failed: {}", args.output, err);
-                                })?;
+                                remove_output(&output, args.continue_download).await?;
 
                                 return Err(Error::InvalidParameter);
                             }
                         };
+                        reporter.piece_finished(&piece);
 
                         // Dfget needs to write the piece content to the output file.
                         if let Some(f) = &mut f {
-                            debug!("copy piece {} to {:?} started", piece.number, args.output);
+                            debug!("copy piece {} to {:?} started", piece.number, output);
                             if let Err(err) = f.seek(SeekFrom::Start(piece.offset)).await {
-                                error!("seek {:?} failed: {}", args.output, err);
-                                fs::remove_file(&args.output).await.inspect_err(|err| {
-                                    error!("remove file {:?} failed: {}", args.output, err);
-                                })?;
+                                error!("seek {:?} failed: {}", output, err);
+                                remove_output(&output, args.continue_download).await?;
 
                                 return Err(Error::IO(err));
                             }
@@ -1035,9 +1541,7 @@ async fn download(
                                 Some(content) => content,
                                 None => {
                                     error!("piece content is missing");
-                                    fs::remove_file(&args.output).await.inspect_err(|err| {
-                                        error!("remove file {:?} failed: {}", args.output, err);
-                                    })?;
+                                    remove_output(&output, args.continue_download).await?;
 
                                     return Err(Error::InvalidParameter);
                                 }
@@ -1046,16 +1550,24 @@ async fn download(
                             if let Err(err) = f.write_all(&content).await {
                                 error!(
                                     "write piece {} to {:?} failed: {}",
-                                    piece.number, args.output, err
+                                    piece.number, output, err
                                 );
-                                fs::remove_file(&args.output).await.inspect_err(|err| {
-                                    error!("remove file {:?} failed: {}", args.output, err);
-                                })?;
+                                remove_output(&output, args.continue_download).await?;
 
                                 return Err(Error::IO(err));
                             }
 
-                            debug!("copy piece {} to {:?} success", piece.number, args.output);
+                            debug!("copy piece {} to {:?} success", piece.number, output);
+                        } else if let Some(stdout) = &mut stdout {
+                            let Some(content) = piece.content else {
+                                error!("piece content is missing");
+                                return Err(Error::InvalidParameter);
+                            };
+
+                            if let Err(err) = stdout.write_piece(piece.offset, content).await {
+                                error!("write piece {} to stdout failed: {}", piece.number, err);
+                                return Err(err);
+                            }
                         }
 
                         downloaded += piece.length;
@@ -1067,9 +1579,7 @@ async fn download(
                     }
                     None => {
                         error!("response is missing");
-                        fs::remove_file(&args.output).await.inspect_err(|err| {
-                            error!("remove file {:?} failed: {}", args.output, err);
-                        })?;
+                        remove_output(&output, args.continue_download).await?;
 
                         return Err(Error::UnexpectedResponse);
                     }
@@ -1078,28 +1588,341 @@ async fn download(
             Ok(None) => break,
             Err(err) => {
                 error!("get message failed: {}", err);
-                fs::remove_file(&args.output).await.inspect_err(|err| {
-                    error!("remove file {:?} failed: {}", args.output, err);
-                })?;
+                remove_output(&output, args.continue_download).await?;
+
+                return Err(Error::TonicStatus(err));
+            }
+        }
+    }
+
+    if let Some(f) = &mut f {
+        if let Err(err) = f.flush().await {
+            error!("flush {:?} failed: {}", output, err);
+            remove_output(&output, args.continue_download).await?;
+
+            return Err(Error::IO(err));
+        }
+    };
+
+    if let Some(stdout) = stdout {
+        stdout.finish().await.inspect_err(|err| {
+            error!("flush stdout failed: {}", err);
+        })?;
+    }
+    info!("flush {:?} success", output);
+
+    progress_bar.finish();
+    Ok(())
+}
+
+/// Downloads the byte ranges of a file by the dfdaemon service.
+///
+/// This function downloads the ranges one by one, and dfdaemon only downloads the pieces
+/// of the range which is resolved by the Range header of the request. The piece content is
+/// transferred through the client and sliced by the resolved range, then written to the
+/// output file at the offsets of the whole file, or concatenated in order if the output is
+/// compact or stdout. The output file is removed if the download fails.
+async fn download_ranges(
+    args: Args,
+    url: Url,
+    output: PathBuf,
+    progress_bar: ProgressBar,
+    download_client: DfdaemonDownloadClient,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    // The ranges are always concatenated when writing to stdout.
+    let mut stdout = is_stdout(&output)
+        .then(|| OrderedWriter::new(tokio::io::stdout(), args.stdout_buffer_size.as_u64()));
+    let compact = stdout.is_some() || args.range_output == RangeOutput::Compact;
+
+    let mut f = if stdout.is_none() {
+        let f = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .mode(dfget::DEFAULT_OUTPUT_FILE_MODE)
+            .open(&output)
+            .await
+            .inspect_err(|err| {
+                error!("open file {:?} failed: {}", output, err);
+            })?;
+
+        Some(f)
+    } else {
+        None
+    };
+
+    progress_bar.set_style(
+        ProgressStyle::with_template(
+            "{msg:.bold}\n[{elapsed_precise}] [{bar:60.green/red}] {percent:3}% ({bytes_per_sec:.red}, {eta:.cyan})",
+        )
+        .or_err(ErrorType::ParseError)?
+        .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
+            write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
+        })
+        .progress_chars("=>-"),
+    );
+    progress_bar.set_message(
+        percent_decode_str(url.path())
+            .decode_utf8_lossy()
+            .to_string(),
+    );
+
+    // The offset of the current range in the compact output.
+    let mut compact_offset = 0;
+    let download = make_download(&args, &url, None, true)?;
+    for raw_range in args.range.iter() {
+        let mut download = download.clone();
+        download
+            .request_header
+            .insert(RANGE.to_string(), format!("bytes={}", raw_range));
+
+        let result = async {
+            let response = download_client
+                .download_task(DownloadTaskRequest {
+                    download: Some(download),
+                })
+                .await?;
+
+            let mut range = None;
+            let mut out_stream = response.into_inner();
+            while let Some(message) = out_stream.message().await? {
+                match message.response {
+                    Some(download_task_response::Response::DownloadTaskStartedResponse(
+                        response,
+                    )) => {
+                        let Some(resolved_range) = response.range else {
+                            error!("range {} is not resolved", raw_range);
+                            return Err(Error::UnexpectedResponse);
+                        };
+
+                        // The sparse output file has the size of the whole file.
+                        if let Some(f) = &f {
+                            if !compact {
+                                f.set_len(response.content_length).await?;
+                            }
+                        }
+
+                        progress_bar.inc_length(resolved_range.length);
+                        reporter.started(
+                            &message.host_id,
+                            &message.task_id,
+                            &message.peer_id,
+                            response.content_length,
+                        );
+                        range = Some(resolved_range);
+                    }
+                    Some(download_task_response::Response::DownloadPieceFinishedResponse(
+                        response,
+                    )) => {
+                        let (Some(range), Some(piece)) = (range.as_ref(), response.piece) else {
+                            error!("response piece is missing");
+                            return Err(Error::InvalidParameter);
+                        };
+                        reporter.piece_finished(&piece);
+
+                        let Some(content) = piece.content else {
+                            error!("piece content is missing");
+                            return Err(Error::InvalidParameter);
+                        };
+
+                        let Some((start, end)) =
+                            slice_piece_by_range(piece.offset, content.len() as u64, range)
+                        else {
+                            continue;
+                        };
+
+                        let content = &content
+                            [(start - piece.offset) as usize..(end - piece.offset) as usize];
+                        let offset = if compact {
+                            compact_offset + start - range.start
+                        } else {
+                            start
+                        };
+
+                        debug!(
+                            "copy piece {} in range {} to {:?}",
+                            piece.number, raw_range, output
+                        );
+                        if let Some(f) = &mut f {
+                            f.seek(SeekFrom::Start(offset)).await?;
+                            f.write_all(content).await?;
+                        } else if let Some(stdout) = &mut stdout {
+                            stdout.write_piece(offset, content.to_vec()).await?;
+                        }
+
+                        progress_bar.inc(end - start);
+                    }
+                    None => {
+                        error!("response is missing");
+                        return Err(Error::UnexpectedResponse);
+                    }
+                }
+            }
+
+            range.ok_or(Error::UnexpectedResponse)
+        }
+        .await;
+
+        match result {
+            Ok(range) => compact_offset += range.length,
+            Err(err) => {
+                error!("download range {} failed: {}", raw_range, err);
+                remove_output(&output, false).await?;
+                return Err(err);
+            }
+        }
+    }
+
+    if let Some(f) = &mut f {
+        f.flush().await?;
+    }
+
+    if let Some(stdout) = stdout {
+        stdout.finish().await?;
+    }
+    info!("download ranges {:?} to {:?} success", args.range, output);
+
+    progress_bar.finish();
+    Ok(())
+}
+
+/// Slices the piece by the range, and returns the start and end offsets of the interested
+/// content in the whole file. The end offset is exclusive, and None is returned if the piece
+/// is not in the range.
+fn slice_piece_by_range(offset: u64, length: u64, range: &Range) -> Option<(u64, u64)> {
+    let start = offset.max(range.start);
+    let end = (offset + length).min(range.start + range.length);
+    (start < end).then_some((start, end))
+}
+
+/// Returns whether the range is in the format of `<start>-<end>`, `<start>-` or
+/// `-<suffix-length>`, the end is inclusive and the suffix length is positive.
+fn is_valid_range(range: &str) -> bool {
+    let Some((start, end)) = range.split_once('-') else {
+        return false;
+    };
+
+    match (start.parse::<u64>(), end.parse::<u64>()) {
+        (Ok(start), Ok(end)) => start <= end,
+        (Ok(_), Err(_)) => end.is_empty(),
+        (Err(_), Ok(suffix_length)) => start.is_empty() && suffix_length > 0,
+        (Err(_), Err(_)) => false,
+    }
+}
+
+/// Makes the download request of the file by the command line arguments.
+///
+/// This function initializes the backend options by the scheme of the URL, e.g. the
+/// object storage and HDFS, and the digest of the OCI blob. The range of the download is
+/// not set, dfdaemon resolves it by the Range header of the request.
+fn make_download(
+    args: &Args,
+    url: &Url,
+    output_path: Option<String>,
+    need_piece_content: bool,
+) -> Result<Download> {
+    // Only initialize object storage when the scheme is an object storage protocol.
+    let object_storage = match object_storage::Scheme::from_str(url.scheme()) {
+        Ok(_) => Some(ObjectStorage {
+            access_key_id: args.storage_access_key_id.clone(),
+            access_key_secret: args.storage_access_key_secret.clone(),
+            security_token: args.storage_security_token.clone(),
+            session_token: args.storage_session_token.clone(),
+            region: args.storage_region.clone(),
+            endpoint: args.storage_endpoint.clone(),
+            credential_path: args.storage_credential_path.clone(),
+            predefined_acl: args.storage_predefined_acl.clone(),
+        }),
+        Err(_) => None,
+    };
+
+    // Only initialize HDFS when the scheme is HDFS protocol.
+    let hdfs = match url.scheme() {
+        hdfs::HDFS_SCHEME => Some(Hdfs {
+            delegation_token: args.hdfs_delegation_token.clone(),
+        }),
+        _ => None,
+    };
+
+    // The blob of the OCI registry uses the digest of the blob to generate the task id, so
+    // that the blob is shared with the image pulled by the proxy. The digest of the blob is
+    // also used to verify the downloaded file if the digest is not specified, except for the
+    // range download, because the digest of the blob can not verify the content of a range.
+    let is_oci_blob = url.scheme() == oci::OCI_SCHEME && is_blob_url(url.as_str());
+    let digest = match args.digest.clone() {
+        Some(digest) => Some(digest),
+        None if is_oci_blob && args.range.is_empty() => {
+            Digest::extract_from_blob_url(url.as_str()).map(|digest| digest.to_string())
+        }
+        None => None,
+    };
+
+    // If the `filtered_query_params` is not provided, then use the default value.
+    let filtered_query_params = args
+        .filtered_query_params
+        .clone()
+        .unwrap_or_else(default_proxy_rule_filtered_query_params);
+
+    Ok(Download {
+        url: url.to_string(),
+        digest,
+        range: None,
+        r#type: TaskType::Standard as i32,
+        tag: Some(args.tag.clone()),
+        application: Some(args.application.clone()),
+        priority: args.priority,
+        filtered_query_params,
+        request_header: header_vec_to_hashmap(args.header.clone().unwrap_or_default())?,
+        piece_length: args.piece_length.map(|piece_length| piece_length.as_u64()),
+        output_path,
+        timeout: Some(
+            prost_wkt_types::Duration::try_from(args.timeout).or_err(ErrorType::ParseError)?,
+        ),
+        need_back_to_source: false,
+        disable_back_to_source: args.disable_back_to_source,
+        certificate_chain: Vec::new(),
+        prefetch: false,
+        is_prefetch: false,
+        need_piece_content,
+        object_storage,
+        hdfs,
+        force_hard_link: args.force_hard_link,
+        content_for_calculating_task_id: args.content_for_calculating_task_id.clone(),
+        remote_ip: Some(local_ip().unwrap().to_string()),
+        concurrent_piece_count: None,
+        // If the download is continued, the existing output file is the partial output
+        // of the interrupted download, so it can be overwritten.
+        overwrite: args.overwrite || args.continue_download,
+        actual_piece_length: None,
+        actual_content_length: None,
+        actual_piece_count: None,
+        enable_task_id_based_blob_digest: is_oci_blob,
+    })
+}
 
-                return Err(Error::TonicStatus(err));
-            }
-        }
+/// Removes the output file of the failed download.
+///
+/// If the download is continued by `--continue`, the partial output file is kept, because
+/// the finished pieces are written into it, and the download can be continued by running
+/// the same command again. Nothing is removed if the output is stdout.
+async fn remove_output(output: &Path, continue_download: bool) -> Result<()> {
+    if is_stdout(output) {
+        return Ok(());
     }
 
-    if let Some(f) = &mut f {
-        if let Err(err) = f.flush().await {
-            error!("flush {:?} failed: {}", args.output, err);
-            fs::remove_file(&args.output).await.inspect_err(|err| {
-                error!("remove file {:?} failed: {}", args.output, err);
-            })?;
+    if continue_download {
+        info!(
+            "keep the partial output file {:?} to continue the download",
+            output
+        );
+        return Ok(());
+    }
 
-            return Err(Error::IO(err));
-        }
-    };
-    info!("flush {:?} success", args.output);
+    fs::remove_file(output).await.inspect_err(|err| {
+        error!("remove file {:?} failed: {}", output, err);
+    })?;
 
-    progress_bar.finish();
     Ok(())
 }
 
@@ -1146,6 +1969,95 @@ async fn get_entries(
         .collect())
 }
 
+/// Stats the file entries in the directory by the backend to get the versions of the remote
+/// files for syncing.
+///
+/// The entries listed by dfdaemon only carry the size, so each file entry is stated by the
+/// backend to get the ETag and the Last-Modified of the remote file. The backend is built by
+/// the config of dfdaemon given by --dfdaemon-config. The entries are stated
+/// concurrently bounded by --max-concurrent-requests, the result is in the order of the
+/// entries and is None for the directory entries.
+async fn stat_entries(
+    args: &Args,
+    entries: &[DirEntry],
+    object_storage: Option<ObjectStorage>,
+    hdfs: Option<Hdfs>,
+) -> Result<Vec<Option<RemoteFile>>> {
+    // Load the config of dfdaemon, so the remote files are stated in the same way as
+    // dfdaemon downloads them.
+    let config = if args.dfdaemon_config.exists() {
+        Arc::new(
+            dfdaemon::Config::load(&args.dfdaemon_config)
+                .await
+                .inspect_err(|err| {
+                    error!(
+                        "load dfdaemon config {:?} failed: {}",
+                        args.dfdaemon_config, err
+                    );
+                })?,
+        )
+    } else {
+        warn!(
+            "dfdaemon config {:?} not found, use the default config",
+            args.dfdaemon_config
+        );
+        Arc::new(dfdaemon::Config::default())
+    };
+
+    let backend_factory =
+        BackendFactory::new(config.clone(), Some(config.server.plugin_dir.as_path()))?;
+    let header = header_vec_to_headermap(args.header.clone().unwrap_or_default())?;
+
+    let backend_factory = &backend_factory;
+    let header = &header;
+    let object_storage = &object_storage;
+    let hdfs = &hdfs;
+    stream::iter(entries)
+        .map(|entry| async move {
+            if entry.is_dir {
+                return Ok(None);
+            }
+
+            let response = backend_factory
+                .build(&entry.url)?
+                .stat(StatRequest {
+                    task_id: Uuid::new_v4().to_string(),
+                    url: entry.url.clone(),
+                    http_header: Some(header.clone()),
+                    timeout: args.timeout,
+                    client_cert: None,
+                    object_storage: object_storage.clone(),
+                    hdfs: hdfs.clone(),
+                })
+                .await
+                .inspect_err(|err| {
+                    error!("stat entry {} failed: {}", entry.url, err);
+                })?;
+
+            if !response.success {
+                error!(
+                    "stat entry {} failed: {:?}",
+                    entry.url, response.error_message
+                );
+                return Err(Error::BackendError(Box::new(BackendError {
+                    message: response.error_message.unwrap_or_default(),
+                    status_code: response.http_status_code,
+                    header: response.http_header,
+                })));
+            }
+
+            Ok(Some(RemoteFile::new(
+                response
+                    .content_length
+                    .unwrap_or(entry.content_length as u64),
+                &response.http_header.unwrap_or_default(),
+            )))
+        })
+        .buffered(args.max_concurrent_requests)
+        .try_collect()
+        .await
+}
+
 /// Constructs the local output path for a directory entry based on its remote URL.
 ///
 /// This function maps a remote directory entry to its corresponding local file system
@@ -1192,11 +2104,34 @@ async fn get_dfdaemon_download_client(endpoint: PathBuf) -> Result<DfdaemonDownl
 fn convert_args(mut args: Args) -> Args {
     // If the URL is a directory and the recursive flag is set, ensure the URL ends with '/'.
     // This is necessary to ensure that the URL is treated as a directory, can be downloaded recursively.
-    if args.recursive && !args.url.path().ends_with('/') {
-        let mut path = args.url.path().to_string();
-        path.push('/');
-        args.url.set_path(&path);
+    if let Some(url) = args.url.as_mut() {
+        if args.recursive && !url.path().ends_with('/') {
+            let mut path = url.path().to_string();
+            path.push('/');
+            url.set_path(&path);
+        }
+    }
+
+    // The content is written to stdout by transferring from dfdaemon's unix domain socket,
+    // because dfdaemon can not hardlink or copy the file to stdout.
+    if args.output.as_deref().is_some_and(is_stdout) {
+        args.transfer_from_dfdaemon = true;
+    }
+
+    // The progress bar is replaced by the progress events in the json output format.
+    if args.output_format == OutputFormat::Json {
+        args.no_progress = true;
     }
+
+    // If the platform of the OCI image is set, pass it to the OCI backend by the request header.
+    if let Some(platform) = args.oci_platform.as_ref() {
+        args.header.get_or_insert_with(Vec::new).push(format!(
+            "{}: {}",
+            oci::OCI_PLATFORM_HEADER,
+            platform
+        ));
+    }
+
     args
 }
 
@@ -1208,39 +2143,72 @@ fn convert_args(mut args: Args) -> Args {
 /// The validation prevents common user errors and potential security issues before
 /// starting the download process.
 fn validate_args(args: &Args) -> Result<()> {
-    // If the URL is a directory, the output path should be a directory.
-    if args.url.path().ends_with('/') && !args.output.is_dir() {
-        return Err(Error::ValidationError(format!(
-            "output path {} is not a directory",
-            args.output.to_string_lossy()
-        )));
+    // The events in the json output format are written to stdout, which can not be mixed
+    // with the console log.
+    if args.output_format == OutputFormat::Json && args.console {
+        return Err(Error::ValidationError(
+            "console log can not be used with json output format".to_string(),
+        ));
+    }
+
+    // The URL and the output path of the files in the manifest file are validated by entry.
+    if let Some(input_file) = args.input_file.as_ref() {
+        if !input_file.is_file() {
+            return Err(Error::ValidationError(format!(
+                "input file {} is not a file",
+                input_file.to_string_lossy()
+            )));
+        }
     }
 
-    // If the URL is a file, the output path should be a file and the parent directory should
-    // exist.
-    if !args.url.path().ends_with('/') {
-        let absolute_path = Path::new(&args.output).absolutize()?;
-        match absolute_path.parent() {
-            Some(parent_path) => {
-                if !parent_path.is_dir() {
+    if let (Some(url), Some(output)) = (args.url.as_ref(), args.output.as_ref()) {
+        if is_stdout(output) {
+            validate_stdout_args(args, url)?;
+        } else {
+            // If the URL is a directory, the output path should be a directory.
+            if url.path().ends_with('/') && !output.is_dir() {
+                return Err(Error::ValidationError(format!(
+                    "output path {} is not a directory",
+                    output.to_string_lossy()
+                )));
+            }
+
+            // If the URL is a file, the output path should be a file and the parent directory
+            // should exist.
+            if !url.path().ends_with('/') {
+                let absolute_path = Path::new(output).absolutize()?;
+                match absolute_path.parent() {
+                    Some(parent_path) => {
+                        if !parent_path.is_dir() {
+                            return Err(Error::ValidationError(format!(
+                                "output path {} is not a directory",
+                                parent_path.to_string_lossy()
+                            )));
+                        }
+                    }
+                    None => {
+                        return Err(Error::ValidationError(format!(
+                            "output path {} is not exist",
+                            output.to_string_lossy()
+                        )));
+                    }
+                }
+
+                if !args.overwrite && !args.continue_download && absolute_path.exists() {
                     return Err(Error::ValidationError(format!(
-                        "output path {} is not a directory",
-                        parent_path.to_string_lossy()
+                        "output path {} is already exist",
+                        output.to_string_lossy()
                     )));
                 }
             }
-            None => {
-                return Err(Error::ValidationError(format!(
-                    "output path {} is not exist",
-                    args.output.to_string_lossy()
-                )));
-            }
         }
+    }
 
-        if !args.overwrite && absolute_path.exists() {
+    for range in args.range.iter() {
+        if !is_valid_range(range) {
             return Err(Error::ValidationError(format!(
-                "output path {} is already exist",
-                args.output.to_string_lossy()
+                "invalid range '{}', the format is <start>-<end>, <start>- or -<suffix-length>",
+                range
             )));
         }
     }
@@ -1276,6 +2244,45 @@ fn validate_args(args: &Args) -> Result<()> {
     Ok(())
 }
 
+/// Validates command line arguments for writing the downloading file to stdout.
+///
+/// The content is written to stdout as a stream, so the directory download and the
+/// continued download which need a file system path are rejected, and the console log
+/// is rejected because it is written to stdout too.
+fn validate_stdout_args(args: &Args, url: &Url) -> Result<()> {
+    if url.path().ends_with('/') {
+        return Err(Error::ValidationError(format!(
+            "directory {} can not be downloaded to stdout",
+            url
+        )));
+    }
+
+    if args.continue_download {
+        return Err(Error::ValidationError(
+            "continue download can not be used with stdout".to_string(),
+        ));
+    }
+
+    if args.console {
+        return Err(Error::ValidationError(
+            "console log can not be used with stdout".to_string(),
+        ));
+    }
+
+    if args.output_format == OutputFormat::Json {
+        return Err(Error::ValidationError(
+            "json output format can not be used with stdout".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns whether the output path is stdout.
+fn is_stdout(output: &Path) -> bool {
+    output == Path::new(STDOUT_OUTPUT)
+}
+
 /// Validates that a path string is a normal relative path without unsafe components.
 ///
 /// This function ensures that a given path is both relative (doesn't start with '/')
@@ -1296,7 +2303,6 @@ mod tests {
     use super::*;
     use dragonfly_api::dfdaemon::v2::{Entry, ListTaskEntriesResponse};
     use mocktail::prelude::*;
-    use std::collections::HashMap;
     use tempfile::tempdir;
 
     #[test]
@@ -1351,7 +2357,7 @@ mod tests {
 
         for (args, expected_url) in test_cases {
             let args = convert_args(args);
-            assert!(args.url.to_string() == expected_url);
+            assert!(args.url.unwrap().to_string() == expected_url);
         }
     }
 
@@ -1382,6 +2388,204 @@ mod tests {
 
         let result = validate_args(&args);
         assert!(result.is_ok());
+
+        // Continue the interrupted download into the existing output file.
+        std::fs::File::create(&output_file_path).unwrap();
+        let args = Args::parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--continue",
+            "--output",
+            output_file_path.as_os_str().to_str().unwrap(),
+        ]);
+
+        let result = validate_args(&args);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn should_validate_stdout_args() {
+        let args = convert_args(Args::parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--output",
+            "-",
+        ]));
+        assert!(args.transfer_from_dfdaemon);
+        assert!(validate_args(&args).is_ok());
+
+        let test_cases = vec![
+            vec!["dfget", "http://test.local/test-dir/", "--output", "-"],
+            vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--continue",
+                "--output",
+                "-",
+            ],
+            vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--console",
+                "--output",
+                "-",
+            ],
+            // The arguments which are not related to the output are validated too.
+            vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--piece-length",
+                "1KiB",
+                "--output",
+                "-",
+            ],
+            vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--range=1023-0",
+                "--output",
+                "-",
+            ],
+            vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--include-files",
+                "../test.txt",
+                "--output",
+                "-",
+            ],
+        ];
+
+        for args in test_cases {
+            let args = convert_args(Args::parse_from(args));
+            assert!(validate_args(&args).is_err());
+        }
+    }
+
+    #[test]
+    fn should_parse_sync_args() {
+        let args = Args::parse_from(vec![
+            "dfget",
+            "s3://bucket/dir/",
+            "--output",
+            "/tmp/dir",
+            "--recursive",
+            "--sync",
+            "--delete",
+            "--force-delete",
+            "--sync-state-file",
+            "/tmp/sync.json",
+        ]);
+        assert!(args.sync);
+        assert!(args.delete);
+        assert!(args.force_delete);
+        assert_eq!(args.sync_state_file, Some(PathBuf::from("/tmp/sync.json")));
+
+        let test_cases = vec![
+            vec![
+                "dfget",
+                "s3://bucket/dir/",
+                "--output",
+                "/tmp/dir",
+                "--sync",
+            ],
+            vec![
+                "dfget",
+                "s3://bucket/dir/",
+                "--output",
+                "/tmp/dir",
+                "--recursive",
+                "--delete",
+            ],
+            vec![
+                "dfget",
+                "s3://bucket/dir/",
+                "--output",
+                "/tmp/dir",
+                "--recursive",
+                "--sync",
+                "--force-delete",
+            ],
+            vec![
+                "dfget",
+                "s3://bucket/dir/",
+                "--output",
+                "/tmp/dir",
+                "--recursive",
+                "--sync",
+                "--delete",
+                "--include-files",
+                "a.txt",
+            ],
+        ];
+
+        for args in test_cases {
+            assert!(Args::try_parse_from(args).is_err());
+        }
+    }
+
+    #[test]
+    fn should_make_sync_key() {
+        let output = Path::new("/tmp/dir");
+        let entry_output = make_output_by_entry(
+            Url::parse("s3://bucket/dir/").unwrap(),
+            output,
+            DirEntry {
+                url: "s3://bucket/dir/sub/file%201.txt".to_string(),
+                content_length: 4,
+                is_dir: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            make_sync_key(output, &entry_output).unwrap(),
+            "sub/file 1.txt"
+        );
+        assert!(make_sync_key(output, Path::new("/tmp/other/file.txt")).is_err());
+    }
+
+    #[test]
+    fn should_validate_output_format_args() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let output = tempdir.path().join("test.txt");
+        let output = output.to_str().unwrap();
+
+        let args = convert_args(Args::parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--output",
+            output,
+            "--output-format",
+            "json",
+        ]));
+        assert_eq!(args.output_format, OutputFormat::Json);
+        assert!(args.no_progress);
+        assert!(validate_args(&args).is_ok());
+
+        let test_cases = vec![
+            vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--output",
+                "-",
+                "--output-format",
+                "json",
+            ],
+            vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--output",
+                output,
+                "--output-format",
+                "json",
+                "--console",
+            ],
+        ];
+
+        for args in test_cases {
+            let args = convert_args(Args::parse_from(args));
+            assert!(validate_args(&args).is_err());
+        }
     }
 
     #[test]
@@ -1452,6 +2656,233 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_parse_args_with_input_file() {
+        let args = Args::parse_from(vec!["dfget", "--input-file", "manifest.txt"]);
+        assert!(args.url.is_none());
+        assert!(args.output.is_none());
+        assert_eq!(args.input_file, Some(PathBuf::from("manifest.txt")));
+
+        // The URL and the output path are required without the input file, and conflict
+        // with the input file.
+        assert!(Args::try_parse_from(vec!["dfget", "http://test.local/test.txt"]).is_err());
+        assert!(Args::try_parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--input-file",
+            "manifest.txt",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn should_parse_manifest() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let output_a = tempdir.path().join("a.txt");
+        let output_b = tempdir.path().join("b.txt");
+
+        let content = format!(
+            "# shards\n\nhttp://test.local/a.txt {}\nhttp://test.local/b.txt  {}  sha256:1234\n",
+            output_a.display(),
+            output_b.display()
+        );
+        let entries = parse_manifest(Path::new("manifest.txt"), &content).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].url, "http://test.local/a.txt");
+        assert_eq!(entries[0].output, output_a);
+        assert!(entries[0].digest.is_none());
+        assert_eq!(entries[1].digest, Some("sha256:1234".to_string()));
+
+        let content = format!(
+            "{}\n{}\n",
+            serde_json::json!({"url": "http://test.local/a.txt", "output": output_a}),
+            serde_json::json!({
+                "url": "http://test.local/b.txt",
+                "output": output_b,
+                "header": ["Authorization: Bearer token"],
+                "tag": "shard",
+                "application": "train",
+            })
+        );
+        let entries = parse_manifest(Path::new("manifest.jsonl"), &content).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].header.is_empty());
+        assert_eq!(entries[1].header, vec!["Authorization: Bearer token"]);
+        assert_eq!(entries[1].tag, Some("shard".to_string()));
+        assert_eq!(entries[1].application, Some("train".to_string()));
+
+        // The relative output path is resolved against the current directory.
+        let entries =
+            parse_manifest(Path::new("manifest.txt"), "http://test.local/a.txt a.txt").unwrap();
+        assert!(entries[0].output.is_absolute());
+    }
+
+    #[test]
+    fn should_return_error_when_manifest_is_not_valid() {
+        let test_cases = vec![
+            ("manifest.txt", "http://test.local/a.txt"),
+            (
+                "manifest.txt",
+                "http://test.local/a.txt /tmp/a.txt sha256:1234 extra",
+            ),
+            (
+                "manifest.txt",
+                "http://test.local/a.txt /tmp/a.txt\nhttp://test.local/b.txt /tmp/a.txt",
+            ),
+            ("manifest.jsonl", "{\"url\": \"http://test.local/a.txt\"}"),
+        ];
+
+        for (input_file, content) in test_cases {
+            assert!(parse_manifest(Path::new(input_file), content).is_err());
+        }
+    }
+
+    #[test]
+    fn should_make_args_by_manifest_entry() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let args = Args::parse_from(vec![
+            "dfget",
+            "--input-file",
+            "manifest.jsonl",
+            "--tag",
+            "default",
+            "--header",
+            "X-Request-Id: 1",
+        ]);
+
+        let entry = ManifestEntry {
+            url: "http://test.local/a.txt".to_string(),
+            output: tempdir.path().join("a.txt"),
+            digest: Some("sha256:1234".to_string()),
+            header: vec!["Authorization: Bearer token".to_string()],
+            tag: None,
+            application: Some("train".to_string()),
+        };
+        let entry_args = make_args_by_manifest_entry(&args, &entry).unwrap();
+        assert!(entry_args.input_file.is_none());
+        assert_eq!(entry_args.url.unwrap().as_str(), "http://test.local/a.txt");
+        assert_eq!(entry_args.output, Some(tempdir.path().join("a.txt")));
+        assert_eq!(entry_args.digest, Some("sha256:1234".to_string()));
+        assert_eq!(
+            entry_args.header,
+            Some(vec![
+                "X-Request-Id: 1".to_string(),
+                "Authorization: Bearer token".to_string()
+            ])
+        );
+        assert_eq!(entry_args.tag, "default");
+        assert_eq!(entry_args.application, "train");
+
+        // The entry is validated the same as the single file download.
+        let entry = ManifestEntry {
+            output: tempdir.path().join("non_exist").join("a.txt"),
+            ..entry
+        };
+        assert!(make_args_by_manifest_entry(&args, &entry).is_err());
+
+        let entry = ManifestEntry {
+            url: "http://test.local/dir/".to_string(),
+            output: tempdir.path().join("dir"),
+            ..entry
+        };
+        assert!(make_args_by_manifest_entry(&args, &entry).is_err());
+    }
+
+    #[test]
+    fn should_make_download_with_blob_digest() {
+        let digest = format!("sha256:{}", "a".repeat(64));
+        let url = Url::parse(&format!(
+            "oci://test.local/v2/library/test/blobs/{}",
+            digest
+        ))
+        .unwrap();
+
+        let args = Args::parse_from(vec!["dfget", url.as_str(), "--output", "/tmp/test.txt"]);
+        let download = make_download(&args, &url, None, false).unwrap();
+        assert_eq!(download.digest, Some(digest));
+        assert!(download.enable_task_id_based_blob_digest);
+
+        // The digest of the blob is not used to verify the range of the blob.
+        let args = Args::parse_from(vec![
+            "dfget",
+            url.as_str(),
+            "--output",
+            "/tmp/test.txt",
+            "--range=0-1023",
+        ]);
+        let download = make_download(&args, &url, None, true).unwrap();
+        assert!(download.digest.is_none());
+        assert!(download.enable_task_id_based_blob_digest);
+    }
+
+    #[test]
+    fn should_validate_range_args() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let output = tempdir.path().join("test.txt");
+        let output = output.as_os_str().to_str().unwrap();
+
+        let args = Args::parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--output",
+            output,
+            "--range=0-1023",
+            "--range=-8",
+            "--range=4096-",
+            "--range-output=compact",
+        ]);
+        assert_eq!(args.range, vec!["0-1023", "-8", "4096-"]);
+        assert_eq!(args.range_output, RangeOutput::Compact);
+        assert!(validate_args(&args).is_ok());
+
+        for range in ["1023-0", "-0", "-", "0", "a-b", "0-1,2-3"] {
+            let range = format!("--range={}", range);
+            let args = Args::parse_from(vec![
+                "dfget",
+                "http://test.local/test.txt",
+                "--output",
+                output,
+                range.as_str(),
+            ]);
+            assert!(validate_args(&args).is_err(), "range {}", range);
+        }
+
+        // The range conflicts with the digest of the whole file and the continued download.
+        assert!(Args::try_parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--output",
+            output,
+            "--range=0-1023",
+            "--digest=sha256:1234",
+        ])
+        .is_err());
+        assert!(Args::try_parse_from(vec![
+            "dfget",
+            "http://test.local/test.txt",
+            "--output",
+            output,
+            "--range=0-1023",
+            "--continue",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn should_slice_piece_by_range() {
+        let range = Range {
+            start: 100,
+            length: 200,
+        };
+
+        assert_eq!(slice_piece_by_range(0, 100, &range), None);
+        assert_eq!(slice_piece_by_range(0, 150, &range), Some((100, 150)));
+        assert_eq!(slice_piece_by_range(150, 50, &range), Some((150, 200)));
+        assert_eq!(slice_piece_by_range(0, 400, &range), Some((100, 300)));
+        assert_eq!(slice_piece_by_range(250, 100, &range), Some((250, 300)));
+        assert_eq!(slice_piece_by_range(300, 100, &range), None);
+    }
+
     #[test]
     fn should_make_output_by_entry() {
         let url = Url::parse("http://example.com/root/").unwrap();
@@ -1551,6 +2982,47 @@ mod tests {
         assert_eq!(entries.len(), 0);
     }
 
+    #[tokio::test]
+    async fn should_refuse_to_delete_by_empty_entries() {
+        let mut mocks = MockSet::new();
+        mocks.mock(|when, then| {
+            when.path("/dfdaemon.v2.DfdaemonDownload/ListTaskEntries");
+            then.pb(ListTaskEntriesResponse {
+                content_length: 0,
+                response_header: HashMap::new(),
+                status_code: None,
+                entries: vec![],
+            });
+        });
+
+        let server = MockServer::new_grpc("dfdaemon.v2.DfdaemonDownload").with_mocks(mocks);
+        server.start().await.unwrap();
+
+        let dfdaemon_download_client = DfdaemonDownloadClient::new(
+            Arc::new(dfdaemon::Config::default()),
+            format!("http://0.0.0.0:{}", server.port().unwrap()),
+        )
+        .await
+        .unwrap();
+
+        let temp_dir = tempdir().unwrap();
+        let output = temp_dir.path().to_str().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), b"a").unwrap();
+
+        let args = Args::parse_from(vec![
+            "dfget",
+            "s3://bucket/dir/",
+            "--output",
+            output,
+            "--recursive",
+            "--sync",
+            "--delete",
+        ]);
+        let result = download_dir(args, dfdaemon_download_client).await;
+        assert!(matches!(result, Err(Error::ValidationError(_))));
+        assert!(temp_dir.path().join("a.txt").exists());
+    }
+
     #[tokio::test]
     async fn should_get_all_entries_in_subdir() {
         let mut mocks = MockSet::new();
@@ -0,0 +1,224 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_core::{Error, Result};
+use std::collections::BTreeMap;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, SeekFrom};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// BufferedPiece is the piece which is finished before the pieces in front of it.
+enum BufferedPiece {
+    /// Memory is the content of the piece buffered in memory.
+    Memory(Vec<u8>),
+
+    /// Spilled is the piece spilled to the temporary file when the memory buffer is full.
+    Spilled { position: u64, length: u64 },
+}
+
+/// OrderedWriter writes the pieces to the writer in order of the offset, the pieces are
+/// finished out of order by the concurrent download. The pieces finished before the pieces
+/// in front of them are buffered in memory up to the max buffered size, and the others are
+/// spilled to the temporary file. The download can not be paused to wait for the pieces in
+/// front of them, because the missing piece is sent in the same stream after them.
+pub struct OrderedWriter<W> {
+    /// writer is the writer of the ordered content, e.g. stdout.
+    writer: W,
+
+    /// offset is the offset of the next piece to be written.
+    offset: u64,
+
+    /// pieces are the buffered pieces by the offset.
+    pieces: BTreeMap<u64, BufferedPiece>,
+
+    /// buffered_size is the size of the pieces buffered in memory.
+    buffered_size: u64,
+
+    /// max_buffered_size is the max size of the pieces buffered in memory.
+    max_buffered_size: u64,
+
+    /// spill is the temporary file of the spilled pieces, which is created when the memory
+    /// buffer is full for the first time.
+    spill: Option<File>,
+
+    /// spill_size is the size of the spilled pieces in the temporary file.
+    spill_size: u64,
+}
+
+/// OrderedWriter implements the ordered writer.
+impl<W: AsyncWrite + Unpin> OrderedWriter<W> {
+    /// new creates a new ordered writer.
+    pub fn new(writer: W, max_buffered_size: u64) -> Self {
+        Self {
+            writer,
+            offset: 0,
+            pieces: BTreeMap::new(),
+            buffered_size: 0,
+            max_buffered_size,
+            spill: None,
+            spill_size: 0,
+        }
+    }
+
+    /// write_piece writes the piece if it is the next piece, and the buffered pieces following
+    /// it. Otherwise, the piece is buffered until the pieces in front of it are written.
+    pub async fn write_piece(&mut self, offset: u64, content: Vec<u8>) -> Result<()> {
+        if offset < self.offset || self.pieces.contains_key(&offset) {
+            warn!("piece at offset {} has been received", offset);
+            return Ok(());
+        }
+
+        if offset != self.offset {
+            return self.buffer(offset, content).await;
+        }
+
+        self.writer.write_all(&content).await?;
+        self.offset += content.len() as u64;
+
+        // Write the buffered pieces following the written piece.
+        while let Some(piece) = self.pieces.remove(&self.offset) {
+            let content = match piece {
+                BufferedPiece::Memory(content) => {
+                    self.buffered_size -= content.len() as u64;
+                    content
+                }
+                BufferedPiece::Spilled { position, length } => {
+                    self.read_spilled(position, length).await?
+                }
+            };
+
+            self.writer.write_all(&content).await?;
+            self.offset += content.len() as u64;
+        }
+
+        Ok(())
+    }
+
+    /// finish flushes the writer, and returns an error if any piece is missing.
+    pub async fn finish(mut self) -> Result<()> {
+        if let Some(offset) = self.pieces.keys().next() {
+            error!(
+                "pieces between offset {} and {} are missing",
+                self.offset, offset
+            );
+            return Err(Error::Unknown(format!(
+                "piece at offset {} is missing",
+                self.offset
+            )));
+        }
+
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    /// buffer buffers the piece in memory, or spills it to the temporary file if the memory
+    /// buffer is full.
+    async fn buffer(&mut self, offset: u64, content: Vec<u8>) -> Result<()> {
+        let length = content.len() as u64;
+        if self.buffered_size + length <= self.max_buffered_size {
+            self.buffered_size += length;
+            self.pieces.insert(offset, BufferedPiece::Memory(content));
+            return Ok(());
+        }
+
+        debug!("spill piece at offset {} to temporary file", offset);
+        let position = self.spill_size;
+        let spill = self.spill_file().await?;
+        spill.seek(SeekFrom::Start(position)).await?;
+        spill.write_all(&content).await?;
+        self.spill_size += length;
+        self.pieces
+            .insert(offset, BufferedPiece::Spilled { position, length });
+        Ok(())
+    }
+
+    /// read_spilled reads the spilled piece from the temporary file.
+    async fn read_spilled(&mut self, position: u64, length: u64) -> Result<Vec<u8>> {
+        let spill = self.spill_file().await?;
+        spill.seek(SeekFrom::Start(position)).await?;
+
+        let mut content = vec![0; length as usize];
+        spill.read_exact(&mut content).await?;
+        Ok(content)
+    }
+
+    /// spill_file returns the temporary file of the spilled pieces. The temporary file is
+    /// removed after it is opened, so it is cleaned up when the writer is dropped.
+    async fn spill_file(&mut self) -> Result<&mut File> {
+        if self.spill.is_none() {
+            let path = std::env::temp_dir().join(format!("dfget-{}.spill", Uuid::new_v4()));
+            let file = OpenOptions::new()
+                .create_new(true)
+                .read(true)
+                .write(true)
+                .mode(0o600)
+                .open(&path)
+                .await
+                .inspect_err(|err| {
+                    error!("create temporary file {:?} failed: {}", path, err);
+                })?;
+
+            fs::remove_file(&path).await?;
+            self.spill = Some(file);
+        }
+
+        Ok(self.spill.as_mut().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_write_pieces_in_order() {
+        let mut output = Vec::new();
+        let mut writer = OrderedWriter::new(&mut output, 1024);
+        writer.write_piece(4, b"efgh".to_vec()).await.unwrap();
+        writer.write_piece(8, b"ij".to_vec()).await.unwrap();
+        writer.write_piece(4, b"efgh".to_vec()).await.unwrap();
+        writer.write_piece(0, b"abcd".to_vec()).await.unwrap();
+        writer.finish().await.unwrap();
+        assert_eq!(output, b"abcdefghij");
+    }
+
+    #[tokio::test]
+    async fn should_spill_pieces_when_buffer_is_full() {
+        let mut output = Vec::new();
+        let mut writer = OrderedWriter::new(&mut output, 4);
+        writer.write_piece(4, b"efgh".to_vec()).await.unwrap();
+        writer.write_piece(8, b"ijkl".to_vec()).await.unwrap();
+        writer.write_piece(12, b"mn".to_vec()).await.unwrap();
+        assert_eq!(writer.buffered_size, 4);
+        assert_eq!(writer.spill_size, 6);
+
+        writer.write_piece(0, b"abcd".to_vec()).await.unwrap();
+        assert_eq!(writer.buffered_size, 0);
+        writer.finish().await.unwrap();
+        assert_eq!(output, b"abcdefghijklmn");
+    }
+
+    #[tokio::test]
+    async fn should_return_error_when_piece_is_missing() {
+        let mut output = Vec::new();
+        let mut writer = OrderedWriter::new(&mut output, 1024);
+        writer.write_piece(0, b"abcd".to_vec()).await.unwrap();
+        writer.write_piece(8, b"ij".to_vec()).await.unwrap();
+        assert!(writer.finish().await.is_err());
+        assert_eq!(output, b"abcd");
+    }
+}
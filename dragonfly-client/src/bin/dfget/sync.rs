@@ -0,0 +1,413 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_core::{
+    error::{ErrorType, OrErr},
+    Result,
+};
+use http::header::{HeaderMap, ETAG, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info, warn};
+
+/// DEFAULT_SYNC_STATE_FILE_NAME is the default name of the sync state file in the output directory.
+pub const DEFAULT_SYNC_STATE_FILE_NAME: &str = ".dfget-sync.json";
+
+/// SYNC_STATE_VERSION is the version of the sync state file.
+const SYNC_STATE_VERSION: u32 = 2;
+
+/// RemoteFile is the metadata of the remote file stated by the backend, the ETag and the
+/// Last-Modified are the version of the remote file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteFile {
+    /// content_length is the content length of the remote file.
+    pub content_length: u64,
+
+    /// etag is the ETag of the remote file.
+    pub etag: Option<String>,
+
+    /// last_modified is the Last-Modified of the remote file.
+    pub last_modified: Option<String>,
+}
+
+/// RemoteFile implements the remote file.
+impl RemoteFile {
+    /// new creates the remote file by the content length and the header of the stat response.
+    pub fn new(content_length: u64, header: &HeaderMap) -> Self {
+        let value = |name| {
+            header
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+
+        Self {
+            content_length,
+            etag: value(ETAG),
+            last_modified: value(LAST_MODIFIED),
+        }
+    }
+}
+
+/// SyncedFile is the file synced from the remote directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SyncedFile {
+    /// content_length is the content length of the remote file when it is synced.
+    content_length: u64,
+
+    /// etag is the ETag of the remote file when it is synced.
+    etag: Option<String>,
+
+    /// last_modified is the Last-Modified of the remote file when it is synced.
+    last_modified: Option<String>,
+
+    /// modified is the modification time of the local file after it is synced, which is used
+    /// to detect the local file modified after syncing.
+    modified: Option<SystemTime>,
+}
+
+/// SyncState is the state of the directory sync, it records the synced files by the path
+/// relative to the output directory. The state is saved while syncing, so the interrupted
+/// sync is resumed by skipping the synced files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// version is the version of the sync state file.
+    version: u32,
+
+    /// url is the URL of the synced directory.
+    url: String,
+
+    /// files are the synced files by the relative path.
+    files: HashMap<String, SyncedFile>,
+
+    /// resumed is whether the sync state is loaded from the state file of the previous sync.
+    #[serde(skip)]
+    resumed: bool,
+}
+
+/// SyncState implements the sync state.
+impl SyncState {
+    /// new creates a new empty sync state of the directory.
+    pub fn new(url: &str) -> Self {
+        Self {
+            version: SYNC_STATE_VERSION,
+            url: url.to_string(),
+            files: HashMap::new(),
+            resumed: false,
+        }
+    }
+
+    /// load loads the sync state from the state file. If the state file does not exist, is
+    /// invalid or belongs to another directory, an empty sync state is returned and the files
+    /// are compared by the size only.
+    pub async fn load(path: &Path, url: &str) -> Result<Self> {
+        let content = match fs::read(path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                info!("sync state file {:?} not found, start a new sync", path);
+                return Ok(Self::new(url));
+            }
+            Err(err) => {
+                error!("read sync state file {:?} failed: {}", path, err);
+                return Err(err.into());
+            }
+        };
+
+        match serde_json::from_slice::<Self>(&content) {
+            Ok(state) if state.version == SYNC_STATE_VERSION && state.url == url => Ok(Self {
+                resumed: true,
+                ..state
+            }),
+            Ok(state) => {
+                warn!(
+                    "sync state file {:?} of {} version {} is ignored",
+                    path, state.url, state.version
+                );
+                Ok(Self::new(url))
+            }
+            Err(err) => {
+                warn!("sync state file {:?} is invalid: {}", path, err);
+                Ok(Self::new(url))
+            }
+        }
+    }
+
+    /// save saves the sync state to the state file. The state is written to a temporary file
+    /// and renamed, so the state file is not corrupted if dfget is interrupted.
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let content = serde_json::to_vec(self).or_err(ErrorType::SerializeError)?;
+        let temp_path = temp_path(path);
+        let mut f = fs::File::create(&temp_path).await.inspect_err(|err| {
+            error!("create sync state file {:?} failed: {}", temp_path, err);
+        })?;
+        f.write_all(&content).await?;
+        f.sync_all().await?;
+
+        fs::rename(&temp_path, path).await.inspect_err(|err| {
+            error!("rename sync state file {:?} failed: {}", temp_path, err);
+        })?;
+
+        Ok(())
+    }
+
+    /// is_synced returns whether the local file is synced with the remote file. The file is
+    /// synced if it has the same size as the remote file, the remote file has the same ETag
+    /// and Last-Modified as the last sync, and the local file is not modified after the last
+    /// sync. The file which is not recorded is compared by the size only in the first sync,
+    /// e.g. downloaded without the sync mode. When the sync is resumed, the file which is not
+    /// recorded may be interrupted in downloading with the preallocated size, so it is not
+    /// synced.
+    pub async fn is_synced(&self, key: &str, remote_file: &RemoteFile, path: &Path) -> bool {
+        let Ok(metadata) = fs::metadata(path).await else {
+            return false;
+        };
+
+        if !metadata.is_file() || metadata.len() != remote_file.content_length {
+            return false;
+        }
+
+        match self.files.get(key) {
+            Some(file) => {
+                file.content_length == remote_file.content_length
+                    && file.etag == remote_file.etag
+                    && file.last_modified == remote_file.last_modified
+                    && file.modified == metadata.modified().ok()
+            }
+            None => !self.resumed,
+        }
+    }
+
+    /// record records the synced file with the version of the remote file and the
+    /// modification time of the local file.
+    pub async fn record(&mut self, key: &str, remote_file: &RemoteFile, path: &Path) -> Result<()> {
+        let metadata = fs::metadata(path).await?;
+        self.files.insert(
+            key.to_string(),
+            SyncedFile {
+                content_length: remote_file.content_length,
+                etag: remote_file.etag.clone(),
+                last_modified: remote_file.last_modified.clone(),
+                modified: metadata.modified().ok(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// retain removes the records of the files which no longer exist in the remote directory.
+    pub fn retain(&mut self, keys: &HashSet<String>) {
+        self.files.retain(|key, _| keys.contains(key));
+    }
+}
+
+/// remove_extraneous_files removes the files in the output directory which no longer exist
+/// in the remote directory, the keys are the paths of the remote files relative to the output
+/// directory. The sync state file and its temporary file are kept, and the directories are
+/// not followed if they are symbolic links.
+pub async fn remove_extraneous_files(
+    output: &Path,
+    keys: &HashSet<String>,
+    state_path: &Path,
+) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    let mut dirs = vec![output.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut read_dir = fs::read_dir(&dir).await.inspect_err(|err| {
+            error!("read directory {:?} failed: {}", dir, err);
+        })?;
+
+        while let Some(entry) = read_dir.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+
+            if path == state_path || path == temp_path(state_path) {
+                continue;
+            }
+
+            let Ok(relative_path) = path.strip_prefix(output) else {
+                continue;
+            };
+
+            if keys.contains(relative_path.to_string_lossy().as_ref()) {
+                continue;
+            }
+
+            fs::remove_file(&path).await.inspect_err(|err| {
+                error!("remove extraneous file {:?} failed: {}", path, err);
+            })?;
+
+            info!("remove extraneous file {:?}", path);
+            removed.push(path);
+        }
+    }
+
+    Ok(removed)
+}
+
+/// temp_path returns the path of the temporary file to save the sync state.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_save_and_load_sync_state() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("a.txt");
+        fs::write(&path, b"abcd").await.unwrap();
+
+        let state_path = tempdir.path().join(DEFAULT_SYNC_STATE_FILE_NAME);
+        let mut state = SyncState::load(&state_path, "s3://bucket/dir/")
+            .await
+            .unwrap();
+        assert_eq!(state, SyncState::new("s3://bucket/dir/"));
+
+        state
+            .record("a.txt", &RemoteFile::new(4, &HeaderMap::new()), &path)
+            .await
+            .unwrap();
+        state.save(&state_path).await.unwrap();
+        assert!(!temp_path(&state_path).exists());
+
+        let loaded = SyncState::load(&state_path, "s3://bucket/dir/")
+            .await
+            .unwrap();
+        assert!(loaded.resumed);
+        assert_eq!(loaded.files, state.files);
+
+        let loaded = SyncState::load(&state_path, "s3://bucket/other/")
+            .await
+            .unwrap();
+        assert_eq!(loaded, SyncState::new("s3://bucket/other/"));
+
+        fs::write(&state_path, b"invalid").await.unwrap();
+        let loaded = SyncState::load(&state_path, "s3://bucket/dir/")
+            .await
+            .unwrap();
+        assert_eq!(loaded, SyncState::new("s3://bucket/dir/"));
+    }
+
+    #[tokio::test]
+    async fn should_compare_local_files() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("a.txt");
+        fs::write(&path, b"abcd").await.unwrap();
+
+        let mut header = HeaderMap::new();
+        header.insert(ETAG, "\"v1\"".parse().unwrap());
+        header.insert(
+            LAST_MODIFIED,
+            "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap(),
+        );
+        let remote_file = RemoteFile::new(4, &header);
+        assert_eq!(remote_file.etag, Some("\"v1\"".to_string()));
+        assert_eq!(
+            remote_file.last_modified,
+            Some("Sun, 06 Nov 1994 08:49:37 GMT".to_string())
+        );
+
+        let mut state = SyncState::new("s3://bucket/dir/");
+        assert!(state.is_synced("a.txt", &remote_file, &path).await);
+        assert!(
+            !state
+                .is_synced("a.txt", &RemoteFile::new(5, &header), &path)
+                .await
+        );
+        assert!(
+            !state
+                .is_synced("b.txt", &remote_file, &tempdir.path().join("b.txt"))
+                .await
+        );
+
+        state.record("a.txt", &remote_file, &path).await.unwrap();
+        assert!(state.is_synced("a.txt", &remote_file, &path).await);
+
+        // The remote file is changed with the same size after syncing.
+        header.insert(ETAG, "\"v2\"".parse().unwrap());
+        assert!(
+            !state
+                .is_synced("a.txt", &RemoteFile::new(4, &header), &path)
+                .await
+        );
+
+        header.remove(ETAG);
+        header.insert(
+            LAST_MODIFIED,
+            "Mon, 07 Nov 1994 08:49:37 GMT".parse().unwrap(),
+        );
+        assert!(
+            !state
+                .is_synced("a.txt", &RemoteFile::new(4, &header), &path)
+                .await
+        );
+
+        // The local file is modified after syncing.
+        state.files.get_mut("a.txt").unwrap().modified = Some(SystemTime::UNIX_EPOCH);
+        assert!(!state.is_synced("a.txt", &remote_file, &path).await);
+
+        // The file which is not recorded is not synced when the sync is resumed.
+        state.resumed = true;
+        assert!(!state.is_synced("c.txt", &remote_file, &path).await);
+
+        state.retain(&HashSet::new());
+        assert!(state.files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_remove_extraneous_files() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let output = tempdir.path();
+        fs::create_dir_all(output.join("sub")).await.unwrap();
+        fs::write(output.join("a.txt"), b"a").await.unwrap();
+        fs::write(output.join("b.txt"), b"b").await.unwrap();
+        fs::write(output.join("sub/c.txt"), b"c").await.unwrap();
+        fs::write(output.join("sub/d.txt"), b"d").await.unwrap();
+
+        let state_path = output.join(DEFAULT_SYNC_STATE_FILE_NAME);
+        fs::write(&state_path, b"{}").await.unwrap();
+
+        let keys = HashSet::from(["a.txt".to_string(), "sub/c.txt".to_string()]);
+        let mut removed = remove_extraneous_files(output, &keys, &state_path)
+            .await
+            .unwrap();
+        removed.sort();
+        assert_eq!(
+            removed,
+            vec![output.join("b.txt"), output.join("sub/d.txt")]
+        );
+
+        assert!(output.join("a.txt").exists());
+        assert!(output.join("sub/c.txt").exists());
+        assert!(state_path.exists());
+    }
+}
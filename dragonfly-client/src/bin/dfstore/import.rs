@@ -44,7 +44,7 @@ pub struct ImportCommand {
 
     #[arg(
         long,
-        help = "Specify the URL for copying data to object storage. Format: scheme://<bucket>/<path>. Examples: s3://<bucket>/<path>, abs://<bucket>/<path>"
+        help = "Specify the URL for copying data to object storage, HDFS or HTTP server. Format: scheme://<bucket>/<path>. Examples: s3://<bucket>/<path>, abs://<bucket>/<path>, hdfs://<host>:<port>/<path>, https://<host>:<port>/<path>"
     )]
     url: Url,
 
@@ -139,6 +139,14 @@ pub struct ImportCommand {
 
     #[arg(long, default_value_t = false, help = "Specify whether to print log")]
     console: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Specify the output format, json writes the progress and the result as newline delimited JSON events to stdout"
+    )]
+    output_format: OutputFormat,
 }
 
 /// Implement the execute for ImportCommand.
@@ -157,42 +165,47 @@ impl ImportCommand {
         // Initialize tracing.
         let _guards = init_command_tracing(self.log_level, self.console);
 
+        // Initialize the reporter of the output format.
+        let mut reporter = Reporter::new(self.output_format);
+
         // Validate the command line arguments.
         if let Err(err) = self.validate_args() {
-            println!(
-                "{}{}{}Validating Failed!{}",
-                color::Fg(color::Red),
-                style::Italic,
-                style::Bold,
-                style::Reset
-            );
-
-            println!(
-                "{}{}{}****************************************{}",
-                color::Fg(color::Black),
-                style::Italic,
-                style::Bold,
-                style::Reset
-            );
-
-            println!(
-                "{}{}{}Message:{} {}",
-                color::Fg(color::Cyan),
-                style::Italic,
-                style::Bold,
-                style::Reset,
-                err,
-            );
-
-            println!(
-                "{}{}{}****************************************{}",
-                color::Fg(color::Black),
-                style::Italic,
-                style::Bold,
-                style::Reset
-            );
-
-            std::process::exit(1);
+            if !reporter.is_json() {
+                println!(
+                    "{}{}{}Validating Failed!{}",
+                    color::Fg(color::Red),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                println!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+
+                println!(
+                    "{}{}{}Message:{} {}",
+                    color::Fg(color::Cyan),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset,
+                    err,
+                );
+
+                println!(
+                    "{}{}{}****************************************{}",
+                    color::Fg(color::Black),
+                    style::Italic,
+                    style::Bold,
+                    style::Reset
+                );
+            }
+
+            reporter.fail(ErrorClass::InvalidArgument, &err);
         }
 
         // Get dfdaemon download client.
@@ -200,138 +213,143 @@ impl ImportCommand {
             match get_dfdaemon_download_client(self.endpoint.to_path_buf()).await {
                 Ok(client) => client,
                 Err(err) => {
-                    println!(
-                        "{}{}{}Connect Dfdaemon Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        err,
-                        self.endpoint.to_string_lossy(),
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    std::process::exit(1);
+                    if !reporter.is_json() {
+                        println!(
+                            "{}{}{}Connect Dfdaemon Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}Message:{}, can not connect {}, please check the unix socket {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            err,
+                            self.endpoint.to_string_lossy(),
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
+
+                    reporter.fail(ErrorClass::DfdaemonUnavailable, &err);
                 }
             };
 
         // Run import sub command.
-        if let Err(err) = self.run(dfdaemon_download_client).await {
-            match err {
-                Error::TonicStatus(status) => {
-                    println!(
-                        "{}{}{}Importing Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                    );
-
-                    println!(
-                        "{}{}{}*********************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Bad Code:{} {}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        status.code()
-                    );
-
-                    println!(
-                        "{}{}{}Message:{} {}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        status.message()
-                    );
-
-                    println!(
-                        "{}{}{}Details:{} {}",
-                        color::Fg(color::Cyan),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        std::str::from_utf8(status.details()).unwrap()
-                    );
-
-                    println!(
-                        "{}{}{}*********************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-                }
-                err => {
-                    println!(
-                        "{}{}{}Importing Failed!{}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
-
-                    println!(
-                        "{}{}{}Message:{} {}",
-                        color::Fg(color::Red),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset,
-                        err
-                    );
-
-                    println!(
-                        "{}{}{}****************************************{}",
-                        color::Fg(color::Black),
-                        style::Italic,
-                        style::Bold,
-                        style::Reset
-                    );
+        if let Err(err) = self.run(dfdaemon_download_client, &mut reporter).await {
+            if !reporter.is_json() {
+                match &err {
+                    Error::TonicStatus(status) => {
+                        println!(
+                            "{}{}{}Importing Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                        );
+
+                        println!(
+                            "{}{}{}*********************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}Bad Code:{} {}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            status.code()
+                        );
+
+                        println!(
+                            "{}{}{}Message:{} {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            status.message()
+                        );
+
+                        println!(
+                            "{}{}{}Details:{} {}",
+                            color::Fg(color::Cyan),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            std::str::from_utf8(status.details()).unwrap()
+                        );
+
+                        println!(
+                            "{}{}{}*********************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
+                    err => {
+                        println!(
+                            "{}{}{}Importing Failed!{}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+
+                        println!(
+                            "{}{}{}Message:{} {}",
+                            color::Fg(color::Red),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset,
+                            err
+                        );
+
+                        println!(
+                            "{}{}{}****************************************{}",
+                            color::Fg(color::Black),
+                            style::Italic,
+                            style::Bold,
+                            style::Reset
+                        );
+                    }
                 }
             }
 
-            std::process::exit(1);
+            reporter.fail(ErrorClass::from(&err), &err);
         }
 
+        reporter.succeed();
         Ok(())
     }
 
@@ -343,11 +361,16 @@ impl ImportCommand {
     /// absolute format, and configures the cache task with specified parameters including TTL,
     /// replica count, and piece length. The operation is asynchronous and provides completion
     /// feedback with the generated task ID.
-    async fn run(&self, dfdaemon_download_client: DfdaemonDownloadClient) -> Result<()> {
+    async fn run(
+        &self,
+        dfdaemon_download_client: DfdaemonDownloadClient,
+        reporter: &mut Reporter,
+    ) -> Result<()> {
         let absolute_path = Path::new(&self.path).absolutize()?;
         info!("import file: {}", absolute_path.to_string_lossy());
 
-        let progress_bar = if self.no_progress {
+        reporter.set_source(absolute_path.to_string_lossy());
+        let progress_bar = if self.no_progress || reporter.is_json() {
             ProgressBar::hidden()
         } else {
             ProgressBar::new_spinner()
@@ -361,7 +384,7 @@ impl ImportCommand {
         );
         progress_bar.set_message("Importing...");
 
-        dfdaemon_download_client
+        let persistent_task = dfdaemon_download_client
             .upload_persistent_task(UploadPersistentTaskRequest {
                 url: self.url.to_string(),
                 object_storage: Some(ObjectStorage {
@@ -387,6 +410,8 @@ impl ImportCommand {
             })
             .await?;
 
+        reporter.set_task_id(persistent_task.id);
+        reporter.set_content_length(persistent_task.content_length);
         progress_bar.finish_with_message(format!("Done: {}", self.url));
         Ok(())
     }
@@ -398,6 +423,14 @@ impl ImportCommand {
     /// TTL boundaries, file existence and type, and piece length constraints before allowing the
     /// import operation to proceed.
     fn validate_args(&self) -> Result<()> {
+        // The events in the json output format are written to stdout, which can not be mixed
+        // with the console log.
+        if self.output_format == OutputFormat::Json && self.console {
+            return Err(Error::ValidationError(
+                "console log can not be used with json output format".to_string(),
+            ));
+        }
+
         if self.ttl < Duration::from_secs(5 * 60)
             || self.ttl > Duration::from_secs(7 * 24 * 60 * 60)
         {
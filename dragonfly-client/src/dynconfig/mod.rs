@@ -86,6 +86,25 @@ impl Dynconfig {
         Ok(dc)
     }
 
+    /// new_empty creates a new Dynconfig without available schedulers and does not
+    /// refresh it from the manager, it is used by the tests that run without the manager.
+    #[cfg(test)]
+    pub fn new_empty(
+        config: Arc<Config>,
+        manager_client: Arc<ManagerClient>,
+        shutdown: shutdown::Shutdown,
+        shutdown_complete_tx: mpsc::UnboundedSender<()>,
+    ) -> Self {
+        Dynconfig {
+            config,
+            data: RwLock::new(Data::default()),
+            manager_client,
+            mutex: Mutex::new(()),
+            shutdown,
+            _shutdown_complete: shutdown_complete_tx,
+        }
+    }
+
     /// run starts the dynconfig server.
     pub async fn run(&self) {
         // Clone the shutdown channel.
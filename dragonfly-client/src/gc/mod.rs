@@ -19,11 +19,12 @@ use chrono::Utc;
 use dragonfly_api::scheduler::v2::DeleteTaskRequest;
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::Result;
-use dragonfly_client_storage::{metadata, Storage};
+use dragonfly_client_storage::Storage;
 use dragonfly_client_util::shutdown;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Mutex};
 use tracing::{error, info, instrument};
 
 // DOWNLOAD_TASK_TIMEOUT is the timeout of downloading the task. If the task download timeout, the
@@ -44,6 +45,9 @@ pub struct GC {
     /// scheduler_client is the grpc client of the scheduler.
     scheduler_client: Arc<SchedulerClient>,
 
+    /// scrubbed_task_rx receives the id of the task deleted by the storage scrubber.
+    scrubbed_task_rx: Mutex<mpsc::UnboundedReceiver<String>>,
+
     /// shutdown is used to shutdown the garbage collector.
     shutdown: shutdown::Shutdown,
 
@@ -58,6 +62,7 @@ impl GC {
         host_id: String,
         storage: Arc<Storage>,
         scheduler_client: Arc<SchedulerClient>,
+        scrubbed_task_rx: mpsc::UnboundedReceiver<String>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
     ) -> Self {
@@ -66,6 +71,7 @@ impl GC {
             host_id,
             storage,
             scheduler_client,
+            scrubbed_task_rx: Mutex::new(scrubbed_task_rx),
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
         }
@@ -75,12 +81,16 @@ impl GC {
     pub async fn run(&self) {
         // Clone the shutdown channel.
         let mut shutdown = self.shutdown.clone();
+        let mut scrubbed_task_rx = self.scrubbed_task_rx.lock().await;
 
         // Start the collect loop.
         let mut interval = tokio::time::interval(self.config.gc.interval);
         loop {
             tokio::select! {
                 _ = interval.tick() => {
+                    // Check the disks, take the failing disks out of the rotation.
+                    self.storage.check_disks().await;
+
                     // Evict the persistent cache task by ttl.
                     if let Err(err) = self.evict_persistent_cache_task_by_ttl().await {
                         info!("failed to evict persistent cache task by ttl: {}", err);
@@ -101,6 +111,11 @@ impl GC {
                         info!("failed to evict task by disk usage: {}", err);
                     }
                 }
+                Some(task_id) = scrubbed_task_rx.recv() => {
+                    // Delete the task deleted by the storage scrubber from the scheduler.
+                    self.delete_task_from_scheduler(&task_id).await;
+                    info!("delete scrubbed task {} from scheduler", task_id);
+                }
                 _ = shutdown.recv() => {
                     // Shutdown the garbage collector.
                     info!("garbage collector shutting down");
@@ -120,7 +135,7 @@ impl GC {
                 self.storage.delete_task(&task.id).await;
                 info!("evict task {}", task.id);
 
-                self.delete_task_from_scheduler(task.clone()).await;
+                self.delete_task_from_scheduler(&task.id).await;
                 info!("delete task {} from scheduler", task.id);
             }
         }
@@ -129,39 +144,47 @@ impl GC {
         Ok(())
     }
 
-    /// evict_task_by_disk_usage evicts the task by disk usage.
+    /// evict_task_by_disk_usage evicts the task by the usage of each disk.
     #[instrument(skip_all)]
     async fn evict_task_by_disk_usage(&self) -> Result<()> {
-        let available_space = self.storage.available_space()?;
-        let total_space = self.storage.total_space()?;
+        for disk_usage in self.storage.disk_usages().await {
+            let available_space = disk_usage.available_space;
+            let total_space = disk_usage.total_space;
+            if total_space == 0 {
+                continue;
+            }
 
-        // Calculate the usage percent.
-        let usage_percent = (100 - available_space * 100 / total_space) as u8;
-        if usage_percent >= self.config.gc.policy.dist_high_threshold_percent {
-            info!(
-                "start to evict task by disk usage, disk usage {}% is higher than high threshold {}%",
-                usage_percent, self.config.gc.policy.dist_high_threshold_percent
-            );
+            // Calculate the usage percent.
+            let usage_percent = (100 - available_space * 100 / total_space) as u8;
+            if usage_percent >= self.config.gc.policy.dist_high_threshold_percent {
+                info!(
+                    "start to evict task by disk usage, disk {:?} usage {}% is higher than high threshold {}%",
+                    disk_usage.dir, usage_percent, self.config.gc.policy.dist_high_threshold_percent
+                );
 
-            // Calculate the need evict space.
-            let need_evict_space = total_space as f64
-                * ((usage_percent - self.config.gc.policy.dist_low_threshold_percent) as f64
-                    / 100.0);
+                // Calculate the need evict space.
+                let need_evict_space = total_space as f64
+                    * ((usage_percent - self.config.gc.policy.dist_low_threshold_percent) as f64
+                        / 100.0);
+
+                // Evict the task in the disk by the need evict space.
+                if let Err(err) = self
+                    .evict_task_space(&disk_usage.dir, need_evict_space as u64)
+                    .await
+                {
+                    info!("failed to evict task by disk usage: {}", err);
+                }
 
-            // Evict the task by the need evict space.
-            if let Err(err) = self.evict_task_space(need_evict_space as u64).await {
-                info!("failed to evict task by disk usage: {}", err);
+                info!("evict task by disk usage of {:?} done", disk_usage.dir);
             }
-
-            info!("evict task by disk usage done");
         }
 
         Ok(())
     }
 
-    /// evict_task_space evicts the task by the given space.
+    /// evict_task_space evicts the task stored in the disk by the given space.
     #[instrument(skip_all)]
-    async fn evict_task_space(&self, need_evict_space: u64) -> Result<()> {
+    async fn evict_task_space(&self, disk_dir: &Path, need_evict_space: u64) -> Result<()> {
         let mut tasks = self.storage.get_tasks()?;
         tasks.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
 
@@ -172,6 +195,13 @@ impl GC {
                 break;
             }
 
+            // If the task content is stored in the other disk or is not found in any disk,
+            // skip it, because evicting it does not free the space of the disk.
+            match self.storage.get_task_disk_dir(&task.id).await {
+                Some(task_disk_dir) if task_disk_dir == disk_dir => {}
+                _ => continue,
+            }
+
             // If the task has downloaded finished, task has the content length, evicted space is the
             // content length. If the task has started and did not download the data, and content
             // length is 0, evicted space is 0.
@@ -208,7 +238,7 @@ impl GC {
             evicted_space += task_space;
             info!("evict task {} size {}", task.id, task_space);
 
-            self.delete_task_from_scheduler(task.clone()).await;
+            self.delete_task_from_scheduler(&task.id).await;
             info!("delete task {} from scheduler", task.id);
         }
 
@@ -218,15 +248,15 @@ impl GC {
 
     /// delete_task_from_scheduler deletes the task from the scheduler.
     #[instrument(skip_all)]
-    async fn delete_task_from_scheduler(&self, task: metadata::Task) {
+    async fn delete_task_from_scheduler(&self, task_id: &str) {
         self.scheduler_client
             .delete_task(DeleteTaskRequest {
                 host_id: self.host_id.clone(),
-                task_id: task.id.clone(),
+                task_id: task_id.to_string(),
             })
             .await
             .unwrap_or_else(|err| {
-                error!("failed to delete peer {}: {}", task.id, err);
+                error!("failed to delete peer {}: {}", task_id, err);
             });
     }
 
@@ -246,42 +276,56 @@ impl GC {
         Ok(())
     }
 
-    /// evict_persistent_cache_task_by_disk_usage evicts the persistent cache task by disk usage.
+    /// evict_persistent_cache_task_by_disk_usage evicts the persistent cache task by the usage
+    /// of each disk.
     #[instrument(skip_all)]
     async fn evict_persistent_cache_task_by_disk_usage(&self) -> Result<()> {
-        let available_space = self.storage.available_space()?;
-        let total_space = self.storage.total_space()?;
+        for disk_usage in self.storage.disk_usages().await {
+            let available_space = disk_usage.available_space;
+            let total_space = disk_usage.total_space;
+            if total_space == 0 {
+                continue;
+            }
 
-        // Calculate the usage percent.
-        let usage_percent = (100 - available_space * 100 / total_space) as u8;
-        if usage_percent >= self.config.gc.policy.dist_high_threshold_percent {
-            info!(
-                "start to evict persistent cache task by disk usage, disk usage {}% is higher than high threshold {}%",
-                usage_percent, self.config.gc.policy.dist_high_threshold_percent
-            );
+            // Calculate the usage percent.
+            let usage_percent = (100 - available_space * 100 / total_space) as u8;
+            if usage_percent >= self.config.gc.policy.dist_high_threshold_percent {
+                info!(
+                    "start to evict persistent cache task by disk usage, disk {:?} usage {}% is higher than high threshold {}%",
+                    disk_usage.dir, usage_percent, self.config.gc.policy.dist_high_threshold_percent
+                );
 
-            // Calculate the need evict space.
-            let need_evict_space = total_space as f64
-                * ((usage_percent - self.config.gc.policy.dist_low_threshold_percent) as f64
-                    / 100.0);
+                // Calculate the need evict space.
+                let need_evict_space = total_space as f64
+                    * ((usage_percent - self.config.gc.policy.dist_low_threshold_percent) as f64
+                        / 100.0);
+
+                // Evict the persistent cache task in the disk by the need evict space.
+                if let Err(err) = self
+                    .evict_persistent_cache_task_space(&disk_usage.dir, need_evict_space as u64)
+                    .await
+                {
+                    info!("failed to evict task by disk usage: {}", err);
+                }
 
-            // Evict the persistent cache task by the need evict space.
-            if let Err(err) = self
-                .evict_persistent_cache_task_space(need_evict_space as u64)
-                .await
-            {
-                info!("failed to evict task by disk usage: {}", err);
+                info!(
+                    "evict persistent cache task by disk usage of {:?} done",
+                    disk_usage.dir
+                );
             }
-
-            info!("evict persistent cache task by disk usage done");
         }
 
         Ok(())
     }
 
-    /// evict_persistent_cache_task_space evicts the persistent cache task by the given space.
+    /// evict_persistent_cache_task_space evicts the persistent cache task stored in the disk by
+    /// the given space.
     #[instrument(skip_all)]
-    async fn evict_persistent_cache_task_space(&self, need_evict_space: u64) -> Result<()> {
+    async fn evict_persistent_cache_task_space(
+        &self,
+        disk_dir: &Path,
+        need_evict_space: u64,
+    ) -> Result<()> {
         let mut tasks = self.storage.get_persistent_cache_tasks()?;
         tasks.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
 
@@ -297,6 +341,17 @@ impl GC {
                 continue;
             }
 
+            // If the persistent cache task content is stored in the other disk or is not found
+            // in any disk, skip it, because evicting it does not free the space of the disk.
+            match self
+                .storage
+                .get_persistent_cache_task_disk_dir(&task.id)
+                .await
+            {
+                Some(task_disk_dir) if task_disk_dir == disk_dir => {}
+                _ => continue,
+            }
+
             //  If the task is started and not finished, and the task download is not timeout,
             //  skip it.
             if task.is_started()
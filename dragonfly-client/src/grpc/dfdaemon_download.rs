@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use crate::resource::{persistent_cache_task, persistent_task, task};
+use crate::resource::{cache_task, persistent_cache_task, persistent_task, task};
 use dragonfly_api::common::v2::{
     CacheTask, PersistentCacheTask, PersistentTask, Priority, Task, TaskType,
 };
@@ -51,7 +51,7 @@ use dragonfly_client_metric::{
     collect_upload_task_finished_metrics, collect_upload_task_started_metrics,
 };
 use dragonfly_client_util::{
-    digest::{is_blob_url, verify_file_digest, Digest},
+    digest::{verify_file_digest, Digest},
     http::{get_range, hashmap_to_headermap, headermap_to_hashmap},
     id_generator::{PersistentCacheTaskIDParameter, PersistentTaskIDParameter, TaskIDParameter},
     shutdown,
@@ -97,6 +97,9 @@ pub struct DfdaemonDownloadServer {
     /// persistent_cache_task is the persistent cache task manager.
     persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
 
+    /// cache_task is the cache task manager.
+    cache_task: Arc<cache_task::CacheTask>,
+
     /// shutdown is used to shutdown the grpc server.
     shutdown: shutdown::Shutdown,
 
@@ -113,6 +116,7 @@ impl DfdaemonDownloadServer {
         task: Arc<task::Task>,
         persistent_task: Arc<persistent_task::PersistentTask>,
         persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
+        cache_task: Arc<cache_task::CacheTask>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
     ) -> Self {
@@ -122,6 +126,7 @@ impl DfdaemonDownloadServer {
             task,
             persistent_task,
             persistent_cache_task,
+            cache_task,
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
         }
@@ -137,6 +142,7 @@ impl DfdaemonDownloadServer {
                 task: self.task.clone(),
                 persistent_task: self.persistent_task.clone(),
                 persistent_cache_task: self.persistent_cache_task.clone(),
+                cache_task: self.cache_task.clone(),
             },
             ExtractTracingInterceptor,
         );
@@ -240,6 +246,9 @@ pub struct DfdaemonDownloadServerHandler {
 
     /// persistent_cache_task is the persistent cache task manager.
     persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
+
+    /// cache_task is the cache task manager.
+    cache_task: Arc<cache_task::CacheTask>,
 }
 
 /// DfdaemonDownloadServerHandler implements the dfdaemon download grpc service.
@@ -276,28 +285,10 @@ impl DfdaemonDownload for DfdaemonDownloadServerHandler {
         download.concurrent_piece_count = Some(self.config.download.concurrent_piece_count);
 
         // Generate the task id.
-        let task_id = self
-            .task
-            .id_generator
-            .task_id(
-                if download.enable_task_id_based_blob_digest && is_blob_url(&download.url) {
-                    TaskIDParameter::BlobDigestBased(download.url.clone())
-                } else if let Some(content) = download.content_for_calculating_task_id.clone() {
-                    TaskIDParameter::Content(content)
-                } else {
-                    TaskIDParameter::URLBased {
-                        url: download.url.clone(),
-                        piece_length: download.piece_length,
-                        tag: download.tag.clone(),
-                        application: download.application.clone(),
-                        filtered_query_params: download.filtered_query_params.clone(),
-                    }
-                },
-            )
-            .map_err(|e| {
-                error!("generate task id: {}", e);
-                Status::invalid_argument(e.to_string())
-            })?;
+        let task_id = self.task.task_id(&download).map_err(|e| {
+            error!("generate task id: {}", e);
+            Status::invalid_argument(e.to_string())
+        })?;
 
         // Generate the host id.
         let host_id = self.task.id_generator.host_id();
@@ -402,21 +393,9 @@ impl DfdaemonDownload for DfdaemonDownloadServerHandler {
                 match get_range(&request_header, task.content_length().unwrap_or_default()) {
                     Ok(range) => range,
                     Err(e) => {
-                        // Download task failed.
-                        self.task
-                            .download_failed(task_id.as_str())
-                            .await
-                            .unwrap_or_else(|err| error!("download task failed: {}", err));
-
-                        // Collect download task failure metrics.
-                        collect_download_task_failure_metrics(
-                            download.r#type,
-                            download.tag.clone().unwrap_or_default().as_str(),
-                            download.application.clone().unwrap_or_default().as_str(),
-                            download.priority.to_string().as_str(),
-                        );
-
-                        error!("get range failed: {}", e);
+                        // The range of the request is not satisfiable, which is the error of
+                        // the client, so the task is not failed.
+                        info!("get range failed: {}", e);
                         return Err(Status::failed_precondition(e.to_string()));
                     }
                 };
@@ -1803,27 +1782,453 @@ impl DfdaemonDownload for DfdaemonDownloadServerHandler {
     )]
     async fn download_cache_task(
         &self,
-        _request: Request<DownloadCacheTaskRequest>,
+        request: Request<DownloadCacheTaskRequest>,
     ) -> Result<Response<Self::DownloadCacheTaskStream>, Status> {
-        todo!();
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
+
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // Clone the request.
+        let mut request = request.into_inner();
+
+        // Generate the task id.
+        let task_id = self
+            .cache_task
+            .id_generator
+            .cache_task_id(
+                if let Some(content) = request.content_for_calculating_task_id.clone() {
+                    TaskIDParameter::Content(content)
+                } else {
+                    TaskIDParameter::URLBased {
+                        url: request.url.clone(),
+                        piece_length: request.piece_length,
+                        tag: request.tag.clone(),
+                        application: request.application.clone(),
+                        filtered_query_params: request.filtered_query_params.clone(),
+                    }
+                },
+            )
+            .map_err(|e| {
+                error!("generate cache task id: {}", e);
+                Status::invalid_argument(e.to_string())
+            })?;
+
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
+
+        // Generate the peer id.
+        let peer_id = self.cache_task.id_generator.peer_id();
+
+        // Span record the host id, task id and peer id.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        Span::current().record("peer_id", peer_id.as_str());
+        Span::current().record("url", request.url.clone());
+        Span::current().record(
+            "remote_ip",
+            request.remote_ip.clone().unwrap_or_default().as_str(),
+        );
+
+        // Download cache task started.
+        info!("download cache task started: {:?}", request);
+        let task = match self
+            .cache_task
+            .download_started(task_id.as_str(), request.clone())
+            .await
+        {
+            Err(ClientError::BackendError(err)) => {
+                error!("download started failed by error: {}", err);
+                self.cache_task
+                    .download_failed(task_id.as_str())
+                    .await
+                    .unwrap_or_else(|err| error!("download cache task failed: {}", err));
+
+                match serde_json::to_vec::<Backend>(&Backend {
+                    message: err.message.clone(),
+                    header: headermap_to_hashmap(&err.header.clone().unwrap_or_default()),
+                    status_code: err.status_code.map(|code| code.as_u16() as i32),
+                }) {
+                    Ok(json) => {
+                        return Err(Status::with_details(
+                            Code::Internal,
+                            err.to_string(),
+                            json.into(),
+                        ));
+                    }
+                    Err(err) => {
+                        error!("serialize error: {}", err);
+                        return Err(Status::internal(err.to_string()));
+                    }
+                }
+            }
+            Err(ClientError::NoSpace(err)) => {
+                error!("download started failed: {}", err);
+                return Err(Status::resource_exhausted(err));
+            }
+            Err(err) => {
+                error!("download started failed: {}", err);
+                return Err(Status::internal(err.to_string()));
+            }
+            Ok(task) => {
+                // Collect download task started metrics.
+                collect_download_task_started_metrics(
+                    TaskType::Cache as i32,
+                    request.tag.clone().unwrap_or_default().as_str(),
+                    request.application.clone().unwrap_or_default().as_str(),
+                    request.priority.to_string().as_str(),
+                );
+
+                task
+            }
+        };
+        Span::current().record("content_length", task.content_length().unwrap_or_default());
+
+        // Request's range priority is higher than the request header's range.
+        if request.range.is_none() {
+            // Convert the header.
+            let request_header = match hashmap_to_headermap(&request.request_header) {
+                Ok(header) => header,
+                Err(e) => {
+                    // Download cache task failed.
+                    self.cache_task
+                        .download_failed(task_id.as_str())
+                        .await
+                        .unwrap_or_else(|err| error!("download cache task failed: {}", err));
+
+                    // Collect download task failure metrics.
+                    collect_download_task_failure_metrics(
+                        TaskType::Cache as i32,
+                        request.tag.clone().unwrap_or_default().as_str(),
+                        request.application.clone().unwrap_or_default().as_str(),
+                        request.priority.to_string().as_str(),
+                    );
+
+                    error!("convert header: {}", e);
+                    return Err(Status::invalid_argument(e.to_string()));
+                }
+            };
+
+            request.range =
+                match get_range(&request_header, task.content_length().unwrap_or_default()) {
+                    Ok(range) => range,
+                    Err(e) => {
+                        // The range of the request is not satisfiable, which is the error of
+                        // the client, so the task is not failed.
+                        info!("get range failed: {}", e);
+                        return Err(Status::failed_precondition(e.to_string()));
+                    }
+                };
+        }
+
+        // Initialize stream channel.
+        let request_clone = request.clone();
+        let task_manager_clone = self.cache_task.clone();
+        let task_clone = task.clone();
+        let (out_stream_tx, out_stream_rx) = mpsc::channel(4);
+
+        // Define the error handler to send the error to the stream.
+        async fn handle_error(
+            out_stream_tx: &Sender<Result<DownloadCacheTaskResponse, Status>>,
+            err: impl std::error::Error,
+        ) {
+            out_stream_tx
+                .send_timeout(
+                    Err(Status::internal(err.to_string())),
+                    super::REQUEST_TIMEOUT,
+                )
+                .await
+                .unwrap_or_else(|err| error!("send download progress error: {:?}", err));
+        }
+
+        // Define the backend error handler to send the error to the stream.
+        async fn handle_backend_error(
+            out_stream_tx: &Sender<Result<DownloadCacheTaskResponse, Status>>,
+            err: Status,
+        ) {
+            out_stream_tx
+                .send_timeout(Err(err), super::REQUEST_TIMEOUT)
+                .await
+                .unwrap_or_else(|err| error!("send download progress error: {:?}", err));
+        }
+
+        tokio::spawn(
+            async move {
+                match task_manager_clone
+                    .download(
+                        &task_clone,
+                        host_id.as_str(),
+                        peer_id.as_str(),
+                        request_clone.clone(),
+                        out_stream_tx.clone(),
+                    )
+                    .await
+                {
+                    Ok(_) => {
+                        // Collect download task finished metrics.
+                        collect_download_task_finished_metrics(
+                            TaskType::Cache as i32,
+                            request_clone.tag.clone().unwrap_or_default().as_str(),
+                            request_clone
+                                .application
+                                .clone()
+                                .unwrap_or_default()
+                                .as_str(),
+                            request_clone.priority.to_string().as_str(),
+                            task_clone.content_length().unwrap_or_default(),
+                            request_clone.range,
+                            start_time.elapsed(),
+                        );
+
+                        // Download cache task succeeded.
+                        info!("download cache task succeeded");
+                        if request_clone.range.is_none() {
+                            if let Err(err) =
+                                task_manager_clone.download_finished(task_clone.id.as_str())
+                            {
+                                error!("download cache task finished: {}", err);
+                                handle_error(&out_stream_tx, err).await;
+                                return;
+                            }
+
+                            // The content of the cache task is stored in memory, so copy the content
+                            // to the output path instead of hard linking.
+                            if let Some(output_path) = &request_clone.output_path {
+                                let output_path = Path::new(output_path.as_str());
+                                if output_path.exists() && !request_clone.overwrite {
+                                    error!(
+                                        "output path {} is already exists",
+                                        output_path.display()
+                                    );
+
+                                    handle_error(
+                                        &out_stream_tx,
+                                        Status::internal(format!(
+                                            "output path {} is already exists",
+                                            output_path.display()
+                                        )),
+                                    )
+                                    .await;
+                                    return;
+                                }
+
+                                if let Err(err) = task_manager_clone
+                                    .copy_task(task_clone.id.as_str(), output_path)
+                                    .await
+                                {
+                                    error!("copy cache task: {}", err);
+                                    handle_error(&out_stream_tx, err).await;
+                                    return;
+                                }
+
+                                // Verify the file digest if it is provided.
+                                if let Some(raw_digest) = &request_clone.digest {
+                                    let digest = match raw_digest.parse::<Digest>() {
+                                        Ok(digest) => digest,
+                                        Err(err) => {
+                                            error!("parse digest: {}", err);
+                                            handle_error(
+                                                &out_stream_tx,
+                                                Status::invalid_argument(format!(
+                                                    "invalid digest({}): {}",
+                                                    raw_digest, err
+                                                )),
+                                            )
+                                            .await;
+                                            return;
+                                        }
+                                    };
+
+                                    if let Err(err) = verify_file_digest(digest, output_path) {
+                                        error!("verify file digest: {}", err);
+                                        handle_error(&out_stream_tx, err).await;
+                                        return;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    Err(ClientError::BackendError(err)) => {
+                        // Collect download task failure metrics.
+                        collect_download_task_failure_metrics(
+                            TaskType::Cache as i32,
+                            request_clone.tag.clone().unwrap_or_default().as_str(),
+                            request_clone
+                                .application
+                                .clone()
+                                .unwrap_or_default()
+                                .as_str(),
+                            request_clone.priority.to_string().as_str(),
+                        );
+
+                        task_manager_clone
+                            .download_failed(task_clone.id.as_str())
+                            .await
+                            .unwrap_or_else(|err| error!("download cache task failed: {}", err));
+
+                        match serde_json::to_vec::<Backend>(&Backend {
+                            message: err.message.clone(),
+                            header: headermap_to_hashmap(&err.header.clone().unwrap_or_default()),
+                            status_code: err.status_code.map(|code| code.as_u16() as i32),
+                        }) {
+                            Ok(json) => {
+                                handle_backend_error(
+                                    &out_stream_tx,
+                                    Status::with_details(
+                                        Code::Internal,
+                                        err.to_string(),
+                                        json.into(),
+                                    ),
+                                )
+                                .await;
+                            }
+                            Err(err) => {
+                                error!("serialize error: {}", err);
+                                handle_error(&out_stream_tx, err).await;
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        error!("download failed: {}", err);
+
+                        // Collect download task failure metrics.
+                        collect_download_task_failure_metrics(
+                            TaskType::Cache as i32,
+                            request_clone.tag.clone().unwrap_or_default().as_str(),
+                            request_clone
+                                .application
+                                .clone()
+                                .unwrap_or_default()
+                                .as_str(),
+                            request_clone.priority.to_string().as_str(),
+                        );
+
+                        // Download cache task failed.
+                        task_manager_clone
+                            .download_failed(task_clone.id.as_str())
+                            .await
+                            .unwrap_or_else(|err| error!("download cache task failed: {}", err));
+
+                        handle_error(&out_stream_tx, err).await;
+                    }
+                }
+
+                drop(out_stream_tx);
+            }
+            .in_current_span(),
+        );
+
+        Ok(Response::new(ReceiverStream::new(out_stream_rx)))
     }
 
     /// stat_cache_task gets the status of the cache task.
     #[instrument(skip_all, fields(host_id, task_id, remote_ip))]
     async fn stat_cache_task(
         &self,
-        _request: Request<DfdaemonStatCacheTaskRequest>,
+        request: Request<DfdaemonStatCacheTaskRequest>,
     ) -> Result<Response<CacheTask>, Status> {
-        todo!();
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
+
+        // Clone the request.
+        let request = request.into_inner();
+
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
+
+        // Get the task id from the request.
+        let task_id = request.task_id;
+
+        // Span record the host id and task id.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        Span::current().record(
+            "remote_ip",
+            request.remote_ip.clone().unwrap_or_default().as_str(),
+        );
+        info!("stat cache task in download server");
+
+        // Collect the stat task metrics.
+        collect_stat_task_started_metrics(TaskType::Cache as i32);
+        match self
+            .cache_task
+            .stat(task_id.as_str(), host_id.as_str())
+            .await
+        {
+            Ok(task) => Ok(Response::new(task)),
+            Err(err) => {
+                // Collect the stat task failure metrics.
+                collect_stat_task_failure_metrics(TaskType::Cache as i32);
+
+                // Log the error with detailed context.
+                error!("stat cache task failed: {}", err);
+
+                // Map the error to an appropriate gRPC status.
+                Err(match err {
+                    ClientError::TaskNotFound(id) => {
+                        Status::not_found(format!("cache task not found: {}", id))
+                    }
+                    _ => Status::internal(err.to_string()),
+                })
+            }
+        }
     }
 
     /// delete_cache_task calls the dfdaemon to delete the cache task.
     #[instrument(skip_all, fields(host_id, task_id, remote_ip))]
     async fn delete_cache_task(
         &self,
-        _request: Request<DeleteCacheTaskRequest>,
+        request: Request<DeleteCacheTaskRequest>,
     ) -> Result<Response<()>, Status> {
-        todo!();
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
+
+        // Clone the request.
+        let request = request.into_inner();
+
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
+
+        // Get the task id from the request.
+        let task_id = request.task_id;
+
+        // Span record the host id and task id.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        Span::current().record(
+            "remote_ip",
+            request.remote_ip.clone().unwrap_or_default().as_str(),
+        );
+        info!("delete cache task in download server");
+
+        // Collect the delete task started metrics.
+        collect_delete_task_started_metrics(TaskType::Cache as i32);
+
+        // Delete the cache task from the local cache and the scheduler.
+        self.cache_task
+            .delete(task_id.as_str(), host_id.as_str())
+            .await
+            .map_err(|err| {
+                // Collect the delete task failure metrics.
+                collect_delete_task_failure_metrics(TaskType::Cache as i32);
+
+                error!("delete cache task: {}", err);
+                match err {
+                    ClientError::TaskNotFound(id) => {
+                        Status::not_found(format!("cache task not found: {}", id))
+                    }
+                    _ => Status::internal(err.to_string()),
+                }
+            })?;
+
+        Ok(Response::new(()))
     }
 }
 
@@ -2102,6 +2507,49 @@ impl DfdaemonDownloadClient {
         Ok(response.into_inner())
     }
 
+    /// download_cache_task downloads the cache task.
+    #[instrument(skip_all)]
+    pub async fn download_cache_task(
+        &self,
+        request: DownloadCacheTaskRequest,
+    ) -> ClientResult<tonic::Response<tonic::codec::Streaming<DownloadCacheTaskResponse>>> {
+        // Clone the request.
+        let request_clone = request.clone();
+
+        // Initialize the request.
+        let mut request = tonic::Request::new(request);
+
+        // Set the timeout to the request.
+        if let Some(timeout) = request_clone.timeout {
+            request.set_timeout(
+                Duration::try_from(timeout)
+                    .map_err(|_| tonic::Status::invalid_argument("invalid timeout"))?,
+            );
+        }
+
+        let response = self.client.clone().download_cache_task(request).await?;
+        Ok(response)
+    }
+
+    /// stat_cache_task stats the cache task.
+    #[instrument(skip_all)]
+    pub async fn stat_cache_task(
+        &self,
+        request: DfdaemonStatCacheTaskRequest,
+    ) -> ClientResult<CacheTask> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().stat_cache_task(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// delete_cache_task tells the dfdaemon to delete the cache task.
+    #[instrument(skip_all)]
+    pub async fn delete_cache_task(&self, request: DeleteCacheTaskRequest) -> ClientResult<()> {
+        let request = Self::make_request(request);
+        self.client.clone().delete_cache_task(request).await?;
+        Ok(())
+    }
+
     /// make_request creates a new request with timeout.
     fn make_request<T>(request: T) -> tonic::Request<T> {
         let mut request = tonic::Request::new(request);
@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use crate::resource::{persistent_cache_task, persistent_task, task};
+use crate::resource::{cache_task, persistent_cache_task, persistent_task, task};
 use dragonfly_api::common::v2::{
     CacheTask, Host, Network, PersistentCacheTask, PersistentTask, Piece, Priority, Task, TaskType,
 };
@@ -100,6 +100,9 @@ pub struct DfdaemonUploadServer {
     /// persistent_cache_task is the persistent cache task manager.
     persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
 
+    /// cache_task is the cache task manager.
+    cache_task: Arc<cache_task::CacheTask>,
+
     /// interface is the network interface.
     interface: Arc<Interface>,
 
@@ -120,6 +123,7 @@ impl DfdaemonUploadServer {
         task: Arc<task::Task>,
         persistent_task: Arc<persistent_task::PersistentTask>,
         persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
+        cache_task: Arc<cache_task::CacheTask>,
         interface: Arc<Interface>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
@@ -130,6 +134,7 @@ impl DfdaemonUploadServer {
             task,
             persistent_task,
             persistent_cache_task,
+            cache_task,
             interface,
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
@@ -145,6 +150,7 @@ impl DfdaemonUploadServer {
                 task: self.task.clone(),
                 persistent_task: self.persistent_task.clone(),
                 persistent_cache_task: self.persistent_cache_task.clone(),
+                cache_task: self.cache_task.clone(),
                 interface: self.interface.clone(),
             },
             ExtractTracingInterceptor,
@@ -234,6 +240,9 @@ pub struct DfdaemonUploadServerHandler {
     /// persistent_cache_task is the persistent cache task manager.
     persistent_cache_task: Arc<persistent_cache_task::PersistentCacheTask>,
 
+    /// cache_task is the cache task manager.
+    cache_task: Arc<cache_task::CacheTask>,
+
     /// interface is the network interface.
     interface: Arc<Interface>,
 }
@@ -2700,191 +2709,902 @@ impl DfdaemonUpload for DfdaemonUploadServerHandler {
     )]
     async fn download_cache_task(
         &self,
-        _request: Request<DownloadCacheTaskRequest>,
+        request: Request<DownloadCacheTaskRequest>,
     ) -> Result<Response<Self::DownloadCacheTaskStream>, Status> {
-        todo!();
-    }
-
-    /// stat_cache_task stats the cache task.
-    #[instrument(skip_all, fields(host_id, task_id, remote_ip))]
-    async fn stat_cache_task(
-        &self,
-        _request: Request<StatCacheTaskRequest>,
-    ) -> Result<Response<CacheTask>, Status> {
-        todo!();
-    }
-
-    /// delete_cache_task deletes the cache task.
-    #[instrument(skip_all, fields(host_id, task_id, remote_ip))]
-    async fn delete_cache_task(
-        &self,
-        _request: Request<DeleteCacheTaskRequest>,
-    ) -> Result<Response<()>, Status> {
-        todo!();
-    }
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
 
-    /// SyncCachePiecesStream is the stream of the sync cache pieces response.
-    type SyncCachePiecesStream = ReceiverStream<Result<SyncCachePiecesResponse, Status>>;
+        // Record the start time.
+        let start_time = Instant::now();
 
-    /// sync_cache_pieces provides the cache piece metadata for parent.
-    #[instrument(skip_all, fields(host_id, remote_host_id, task_id))]
-    async fn sync_cache_pieces(
-        &self,
-        _request: Request<SyncCachePiecesRequest>,
-    ) -> Result<Response<Self::SyncCachePiecesStream>, Status> {
-        todo!();
-    }
+        // Clone the request.
+        let mut request = request.into_inner();
 
-    /// download_cache_piece provides the cache piece content for parent.
-    #[instrument(
-        skip_all,
-        fields(host_id, remote_host_id, task_id, piece_id, piece_length)
-    )]
-    async fn download_cache_piece(
-        &self,
-        _request: Request<DownloadCachePieceRequest>,
-    ) -> Result<Response<DownloadCachePieceResponse>, Status> {
-        todo!();
-    }
-}
+        // Generate the task id.
+        let task_id = self
+            .cache_task
+            .id_generator
+            .cache_task_id(
+                if let Some(content) = request.content_for_calculating_task_id.clone() {
+                    TaskIDParameter::Content(content)
+                } else {
+                    TaskIDParameter::URLBased {
+                        url: request.url.clone(),
+                        piece_length: request.piece_length,
+                        tag: request.tag.clone(),
+                        application: request.application.clone(),
+                        filtered_query_params: request.filtered_query_params.clone(),
+                    }
+                },
+            )
+            .map_err(|e| {
+                error!("generate cache task id: {}", e);
+                Status::invalid_argument(e.to_string())
+            })?;
 
-/// DfdaemonUploadClient is a wrapper of DfdaemonUploadGRPCClient.
-#[derive(Clone)]
-pub struct DfdaemonUploadClient {
-    /// client is the grpc client of the dfdaemon upload.
-    pub client: DfdaemonUploadGRPCClient<InterceptedService<Channel, InjectTracingInterceptor>>,
-}
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
 
-/// DfdaemonUploadClient implements the dfdaemon upload grpc client.
-impl DfdaemonUploadClient {
-    /// new creates a new DfdaemonUploadClient.
-    pub async fn new(
-        config: Arc<Config>,
-        addr: String,
-        is_download_piece: bool,
-    ) -> ClientResult<Self> {
-        let domain_name = Url::parse(addr.as_str())?
-            .host_str()
-            .ok_or(ClientError::InvalidParameter)
-            .inspect_err(|_err| {
-                error!("invalid address: {}", addr);
-            })?
-            .to_string();
+        // Generate the peer id.
+        let peer_id = self.cache_task.id_generator.peer_id();
 
-        // If it is download piece, use the download piece timeout, otherwise use the
-        // default request timeout.
-        let timeout = if is_download_piece {
-            config.download.piece_timeout
-        } else {
-            super::REQUEST_TIMEOUT
-        };
+        // Span record the host id, task id and peer id.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        Span::current().record("peer_id", peer_id.as_str());
+        Span::current().record("url", request.url.clone());
+        Span::current().record(
+            "remote_ip",
+            request.remote_ip.clone().unwrap_or_default().as_str(),
+        );
 
-        let channel = match config
-            .upload
-            .client
-            .load_client_tls_config(domain_name.as_str())
-            .await?
+        // Download cache task started.
+        info!("download cache task started: {:?}", request);
+        let task = match self
+            .cache_task
+            .download_started(task_id.as_str(), request.clone())
+            .await
         {
-            Some(client_tls_config) => {
-                Channel::from_static(Box::leak(addr.clone().into_boxed_str()))
-                    .tls_config(client_tls_config)?
-                    .buffer_size(super::BUFFER_SIZE)
-                    .connect_timeout(super::CONNECT_TIMEOUT)
-                    .timeout(timeout)
-                    .tcp_keepalive(Some(super::TCP_KEEPALIVE))
-                    .http2_keep_alive_interval(super::HTTP2_KEEP_ALIVE_INTERVAL)
-                    .keep_alive_timeout(super::HTTP2_KEEP_ALIVE_TIMEOUT)
-                    .connect()
+            Err(ClientError::BackendError(err)) => {
+                error!("download started failed by error: {}", err);
+                self.cache_task
+                    .download_failed(task_id.as_str())
                     .await
-                    .inspect_err(|err| {
-                        error!("connect to {} failed: {}", addr, err);
-                    })
-                    .or_err(ErrorType::ConnectError)?
+                    .unwrap_or_else(|err| error!("download cache task failed: {}", err));
+
+                match serde_json::to_vec::<Backend>(&Backend {
+                    message: err.message.clone(),
+                    header: headermap_to_hashmap(&err.header.clone().unwrap_or_default()),
+                    status_code: err.status_code.map(|code| code.as_u16() as i32),
+                }) {
+                    Ok(json) => {
+                        return Err(Status::with_details(
+                            Code::Internal,
+                            err.to_string(),
+                            json.into(),
+                        ));
+                    }
+                    Err(err) => {
+                        error!("serialize error: {}", err);
+                        return Err(Status::internal(err.to_string()));
+                    }
+                }
             }
-            None => Channel::from_static(Box::leak(addr.clone().into_boxed_str()))
-                .buffer_size(super::BUFFER_SIZE)
-                .connect_timeout(super::CONNECT_TIMEOUT)
-                .timeout(timeout)
-                .tcp_keepalive(Some(super::TCP_KEEPALIVE))
-                .http2_keep_alive_interval(super::HTTP2_KEEP_ALIVE_INTERVAL)
-                .keep_alive_timeout(super::HTTP2_KEEP_ALIVE_TIMEOUT)
-                .connect()
-                .await
-                .inspect_err(|err| {
-                    error!("connect to {} failed: {}", addr, err);
-                })
-                .or_err(ErrorType::ConnectError)?,
-        };
+            Err(ClientError::NoSpace(err)) => {
+                error!("download started failed: {}", err);
+                return Err(Status::resource_exhausted(err));
+            }
+            Err(err) => {
+                error!("download started failed: {}", err);
+                return Err(Status::internal(err.to_string()));
+            }
+            Ok(task) => {
+                // Collect download task started metrics.
+                collect_download_task_started_metrics(
+                    TaskType::Cache as i32,
+                    request.tag.clone().unwrap_or_default().as_str(),
+                    request.application.clone().unwrap_or_default().as_str(),
+                    request.priority.to_string().as_str(),
+                );
 
-        let client = DfdaemonUploadGRPCClient::with_interceptor(channel, InjectTracingInterceptor)
-            .max_decoding_message_size(usize::MAX)
-            .max_encoding_message_size(usize::MAX);
-        Ok(Self { client })
-    }
+                task
+            }
+        };
+        Span::current().record("content_length", task.content_length().unwrap_or_default());
 
-    /// download_task downloads the task.
-    #[instrument(skip_all)]
-    pub async fn download_task(
-        &self,
-        request: DownloadTaskRequest,
-    ) -> ClientResult<tonic::Response<tonic::codec::Streaming<DownloadTaskResponse>>> {
-        // Get the download from the request.
-        let download = request.clone().download.ok_or_else(|| {
-            tonic::Status::invalid_argument("missing download in download task request")
-        })?;
+        // Request's range priority is higher than the request header's range.
+        if request.range.is_none() {
+            // Convert the header.
+            let request_header = match hashmap_to_headermap(&request.request_header) {
+                Ok(header) => header,
+                Err(e) => {
+                    // Download cache task failed.
+                    self.cache_task
+                        .download_failed(task_id.as_str())
+                        .await
+                        .unwrap_or_else(|err| error!("download cache task failed: {}", err));
 
-        // Initialize the request.
-        let mut request = tonic::Request::new(request);
+                    // Collect download task failure metrics.
+                    collect_download_task_failure_metrics(
+                        TaskType::Cache as i32,
+                        request.tag.clone().unwrap_or_default().as_str(),
+                        request.application.clone().unwrap_or_default().as_str(),
+                        request.priority.to_string().as_str(),
+                    );
 
-        // Set the timeout to the request.
-        if let Some(timeout) = download.timeout {
-            request.set_timeout(
-                Duration::try_from(timeout)
-                    .map_err(|_| tonic::Status::invalid_argument("invalid timeout"))?,
-            );
-        }
+                    error!("convert header: {}", e);
+                    return Err(Status::invalid_argument(e.to_string()));
+                }
+            };
 
-        let response = self.client.clone().download_task(request).await?;
-        Ok(response)
-    }
+            request.range =
+                match get_range(&request_header, task.content_length().unwrap_or_default()) {
+                    Ok(range) => range,
+                    Err(e) => {
+                        // Download cache task failed.
+                        self.cache_task
+                            .download_failed(task_id.as_str())
+                            .await
+                            .unwrap_or_else(|err| error!("download cache task failed: {}", err));
 
-    /// stat_task gets the status of the task.
-    #[instrument(skip_all)]
-    pub async fn stat_task(&self, request: DfdaemonStatTaskRequest) -> ClientResult<Task> {
-        let request = Self::make_request(request);
-        let response = self.client.clone().stat_task(request).await?;
-        Ok(response.into_inner())
-    }
+                        // Collect download task failure metrics.
+                        collect_download_task_failure_metrics(
+                            TaskType::Cache as i32,
+                            request.tag.clone().unwrap_or_default().as_str(),
+                            request.application.clone().unwrap_or_default().as_str(),
+                            request.priority.to_string().as_str(),
+                        );
 
-    /// list_task_entries lists the task entries.
-    #[instrument(skip_all)]
-    pub async fn list_task_entries(
-        &self,
-        request: ListTaskEntriesRequest,
-    ) -> ClientResult<ListTaskEntriesResponse> {
-        let request = Self::make_request(request);
-        let response = self.client.clone().list_task_entries(request).await?;
-        Ok(response.into_inner())
-    }
+                        error!("get range failed: {}", e);
+                        return Err(Status::failed_precondition(e.to_string()));
+                    }
+                };
+        }
 
-    /// delete_task tells the dfdaemon to delete the task.
-    #[instrument(skip_all)]
-    pub async fn delete_task(&self, request: DeleteTaskRequest) -> ClientResult<()> {
-        let request = Self::make_request(request);
-        self.client.clone().delete_task(request).await?;
-        Ok(())
-    }
+        // Initialize stream channel.
+        let request_clone = request.clone();
+        let task_manager_clone = self.cache_task.clone();
+        let task_clone = task.clone();
+        let (out_stream_tx, out_stream_rx) = mpsc::channel(4);
 
-    /// sync_pieces provides the piece metadata for parent.
-    #[instrument(skip_all)]
-    pub async fn sync_pieces(
-        &self,
-        request: SyncPiecesRequest,
-    ) -> ClientResult<tonic::Response<tonic::codec::Streaming<SyncPiecesResponse>>> {
-        let request = Self::make_request(request);
-        let response = self.client.clone().sync_pieces(request).await?;
-        Ok(response)
-    }
+        // Define the error handler to send the error to the stream.
+        async fn handle_error(
+            out_stream_tx: &Sender<Result<DownloadCacheTaskResponse, Status>>,
+            err: impl std::error::Error,
+        ) {
+            out_stream_tx
+                .send_timeout(
+                    Err(Status::internal(err.to_string())),
+                    super::REQUEST_TIMEOUT,
+                )
+                .await
+                .unwrap_or_else(|err| error!("send download progress error: {:?}", err));
+        }
+
+        // Define the backend error handler to send the error to the stream.
+        async fn handle_backend_error(
+            out_stream_tx: &Sender<Result<DownloadCacheTaskResponse, Status>>,
+            err: Status,
+        ) {
+            out_stream_tx
+                .send_timeout(Err(err), super::REQUEST_TIMEOUT)
+                .await
+                .unwrap_or_else(|err| error!("send download progress error: {:?}", err));
+        }
+
+        tokio::spawn(
+            async move {
+                match task_manager_clone
+                    .download(
+                        &task_clone,
+                        host_id.as_str(),
+                        peer_id.as_str(),
+                        request_clone.clone(),
+                        out_stream_tx.clone(),
+                    )
+                    .await
+                {
+                    Ok(_) => {
+                        // Collect download task finished metrics.
+                        collect_download_task_finished_metrics(
+                            TaskType::Cache as i32,
+                            request_clone.tag.clone().unwrap_or_default().as_str(),
+                            request_clone
+                                .application
+                                .clone()
+                                .unwrap_or_default()
+                                .as_str(),
+                            request_clone.priority.to_string().as_str(),
+                            task_clone.content_length().unwrap_or_default(),
+                            request_clone.range,
+                            start_time.elapsed(),
+                        );
+
+                        // Download cache task succeeded.
+                        info!("download cache task succeeded");
+                        if request_clone.range.is_none() {
+                            if let Err(err) =
+                                task_manager_clone.download_finished(task_clone.id.as_str())
+                            {
+                                error!("download cache task finished: {}", err);
+                                handle_error(&out_stream_tx, err).await;
+                                return;
+                            }
+
+                            // The content of the cache task is stored in memory, so copy the content
+                            // to the output path instead of hard linking.
+                            if let Some(output_path) = &request_clone.output_path {
+                                let output_path = Path::new(output_path.as_str());
+                                if output_path.exists() && !request_clone.overwrite {
+                                    error!(
+                                        "output path {} is already exists",
+                                        output_path.display()
+                                    );
+
+                                    handle_error(
+                                        &out_stream_tx,
+                                        Status::internal(format!(
+                                            "output path {} is already exists",
+                                            output_path.display()
+                                        )),
+                                    )
+                                    .await;
+                                    return;
+                                }
+
+                                if let Err(err) = task_manager_clone
+                                    .copy_task(task_clone.id.as_str(), output_path)
+                                    .await
+                                {
+                                    error!("copy cache task: {}", err);
+                                    handle_error(&out_stream_tx, err).await;
+                                    return;
+                                }
+
+                                // Verify the file digest if it is provided.
+                                if let Some(raw_digest) = &request_clone.digest {
+                                    let digest = match raw_digest.parse::<Digest>() {
+                                        Ok(digest) => digest,
+                                        Err(err) => {
+                                            error!("parse digest: {}", err);
+                                            handle_error(
+                                                &out_stream_tx,
+                                                Status::invalid_argument(format!(
+                                                    "invalid digest({}): {}",
+                                                    raw_digest, err
+                                                )),
+                                            )
+                                            .await;
+                                            return;
+                                        }
+                                    };
+
+                                    if let Err(err) = verify_file_digest(digest, output_path) {
+                                        error!("verify file digest: {}", err);
+                                        handle_error(&out_stream_tx, err).await;
+                                        return;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    Err(ClientError::BackendError(err)) => {
+                        // Collect download task failure metrics.
+                        collect_download_task_failure_metrics(
+                            TaskType::Cache as i32,
+                            request_clone.tag.clone().unwrap_or_default().as_str(),
+                            request_clone
+                                .application
+                                .clone()
+                                .unwrap_or_default()
+                                .as_str(),
+                            request_clone.priority.to_string().as_str(),
+                        );
+
+                        task_manager_clone
+                            .download_failed(task_clone.id.as_str())
+                            .await
+                            .unwrap_or_else(|err| error!("download cache task failed: {}", err));
+
+                        match serde_json::to_vec::<Backend>(&Backend {
+                            message: err.message.clone(),
+                            header: headermap_to_hashmap(&err.header.clone().unwrap_or_default()),
+                            status_code: err.status_code.map(|code| code.as_u16() as i32),
+                        }) {
+                            Ok(json) => {
+                                handle_backend_error(
+                                    &out_stream_tx,
+                                    Status::with_details(
+                                        Code::Internal,
+                                        err.to_string(),
+                                        json.into(),
+                                    ),
+                                )
+                                .await;
+                            }
+                            Err(err) => {
+                                error!("serialize error: {}", err);
+                                handle_error(&out_stream_tx, err).await;
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        error!("download failed: {}", err);
+
+                        // Collect download task failure metrics.
+                        collect_download_task_failure_metrics(
+                            TaskType::Cache as i32,
+                            request_clone.tag.clone().unwrap_or_default().as_str(),
+                            request_clone
+                                .application
+                                .clone()
+                                .unwrap_or_default()
+                                .as_str(),
+                            request_clone.priority.to_string().as_str(),
+                        );
+
+                        // Download cache task failed.
+                        task_manager_clone
+                            .download_failed(task_clone.id.as_str())
+                            .await
+                            .unwrap_or_else(|err| error!("download cache task failed: {}", err));
+
+                        handle_error(&out_stream_tx, err).await;
+                    }
+                }
+
+                drop(out_stream_tx);
+            }
+            .in_current_span(),
+        );
+
+        Ok(Response::new(ReceiverStream::new(out_stream_rx)))
+    }
+
+    /// stat_cache_task stats the cache task.
+    #[instrument(skip_all, fields(host_id, task_id, remote_ip))]
+    async fn stat_cache_task(
+        &self,
+        request: Request<StatCacheTaskRequest>,
+    ) -> Result<Response<CacheTask>, Status> {
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
+
+        // Clone the request.
+        let request = request.into_inner();
+
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
+
+        // Get the task id from the request.
+        let task_id = request.task_id;
+
+        // Span record the host id and task id.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        Span::current().record(
+            "remote_ip",
+            request.remote_ip.clone().unwrap_or_default().as_str(),
+        );
+        info!("stat cache task in upload server");
+
+        // Collect the stat task metrics.
+        collect_stat_task_started_metrics(TaskType::Cache as i32);
+        match self
+            .cache_task
+            .stat(task_id.as_str(), host_id.as_str())
+            .await
+        {
+            Ok(task) => Ok(Response::new(task)),
+            Err(err) => {
+                // Collect the stat task failure metrics.
+                collect_stat_task_failure_metrics(TaskType::Cache as i32);
+
+                // Log the error with detailed context.
+                error!("stat cache task failed: {}", err);
+
+                // Map the error to an appropriate gRPC status.
+                Err(match err {
+                    ClientError::TaskNotFound(id) => {
+                        Status::not_found(format!("cache task not found: {}", id))
+                    }
+                    _ => Status::internal(err.to_string()),
+                })
+            }
+        }
+    }
+
+    /// delete_cache_task deletes the cache task.
+    #[instrument(skip_all, fields(host_id, task_id, remote_ip))]
+    async fn delete_cache_task(
+        &self,
+        request: Request<DeleteCacheTaskRequest>,
+    ) -> Result<Response<()>, Status> {
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
+
+        // Clone the request.
+        let request = request.into_inner();
+
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
+
+        // Get the task id from the request.
+        let task_id = request.task_id;
+
+        // Span record the host id and task id.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        Span::current().record(
+            "remote_ip",
+            request.remote_ip.clone().unwrap_or_default().as_str(),
+        );
+        info!("delete cache task in upload server");
+
+        // Collect the delete task started metrics.
+        collect_delete_task_started_metrics(TaskType::Cache as i32);
+
+        // Delete the cache task from the local cache and the scheduler.
+        self.cache_task
+            .delete(task_id.as_str(), host_id.as_str())
+            .await
+            .map_err(|err| {
+                // Collect the delete task failure metrics.
+                collect_delete_task_failure_metrics(TaskType::Cache as i32);
+
+                error!("delete cache task: {}", err);
+                match err {
+                    ClientError::TaskNotFound(id) => {
+                        Status::not_found(format!("cache task not found: {}", id))
+                    }
+                    _ => Status::internal(err.to_string()),
+                }
+            })?;
+
+        Ok(Response::new(()))
+    }
+
+    /// SyncCachePiecesStream is the stream of the sync cache pieces response.
+    type SyncCachePiecesStream = ReceiverStream<Result<SyncCachePiecesResponse, Status>>;
+
+    /// sync_cache_pieces provides the cache piece metadata for parent.
+    #[instrument(skip_all, fields(host_id, remote_host_id, task_id))]
+    async fn sync_cache_pieces(
+        &self,
+        request: Request<SyncCachePiecesRequest>,
+    ) -> Result<Response<Self::SyncCachePiecesStream>, Status> {
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
+
+        // Clone the request.
+        let request = request.into_inner();
+
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
+
+        // Get the remote host id from the request.
+        let remote_host_id = request.host_id;
+
+        // Get the task id from tae request.
+        let task_id = request.task_id;
+
+        // Span record the host id and task id.
+        Span::current().record("host_id", host_id.clone());
+        Span::current().record("remote_host_id", remote_host_id.as_str());
+        Span::current().record("task_id", task_id.clone());
+        info!("sync cache pieces in upload server");
+
+        // Get the interested piece numbers from the request.
+        let mut interested_cache_piece_numbers = request.interested_cache_piece_numbers.clone();
+
+        // Clone the cache task.
+        let cache_task_manager = self.cache_task.clone();
+
+        // Initialize stream channel.
+        let (out_stream_tx, out_stream_rx) = mpsc::channel(128);
+        tokio::spawn(
+            async move {
+                match cache_task_manager.get(task_id.as_str()) {
+                    Ok(Some(task)) => {
+                        if task.is_failed() {
+                            error!("get cache task {} failed", task_id);
+                            out_stream_tx
+                                .send_timeout(
+                                    Err(Status::internal(format!("cache task {} failed", task_id))),
+                                    super::REQUEST_TIMEOUT,
+                                )
+                                .await
+                                .unwrap_or_else(|err| {
+                                    error!("send cache task {} failed to stream: {}", task_id, err);
+                                });
+
+                            return;
+                        }
+                    }
+                    Ok(None) => {
+                        error!("get cache task {} not found", task_id);
+                        out_stream_tx
+                            .send_timeout(
+                                Err(Status::not_found(format!(
+                                    "cache task {} not found",
+                                    task_id
+                                ))),
+                                super::REQUEST_TIMEOUT,
+                            )
+                            .await
+                            .unwrap_or_else(|err| {
+                                error!("send cache task {} not found to stream: {}", task_id, err);
+                            });
+
+                        return;
+                    }
+                    Err(err) => {
+                        error!("get cache task {}: {}", task_id, err);
+                        out_stream_tx
+                            .send_timeout(
+                                Err(Status::internal(err.to_string())),
+                                super::REQUEST_TIMEOUT,
+                            )
+                            .await
+                            .unwrap_or_else(|err| {
+                                error!("send cache task {} to stream: {}", task_id, err);
+                            });
+
+                        return;
+                    }
+                }
+
+                loop {
+                    let mut finished_piece_numbers = Vec::new();
+                    for interested_piece_number in interested_cache_piece_numbers.iter() {
+                        let piece = match cache_task_manager.piece.get_cache(
+                            cache_task_manager
+                                .piece
+                                .cache_id(task_id.as_str(), *interested_piece_number)
+                                .as_str(),
+                        ) {
+                            Ok(Some(piece)) => piece,
+                            Ok(None) => continue,
+                            Err(err) => {
+                                error!(
+                                    "send cache piece metadata {}-{}: {}",
+                                    task_id, interested_piece_number, err
+                                );
+                                out_stream_tx
+                                    .send_timeout(
+                                        Err(Status::internal(err.to_string())),
+                                        super::REQUEST_TIMEOUT,
+                                    )
+                                    .await
+                                    .unwrap_or_else(|err| {
+                                        error!(
+                                            "send cache piece metadata {}-{} to stream: {}",
+                                            task_id, interested_piece_number, err
+                                        );
+                                    });
+
+                                drop(out_stream_tx);
+                                return;
+                            }
+                        };
+
+                        // Send the piece metadata to the stream.
+                        if piece.is_finished() {
+                            match out_stream_tx
+                                .send_timeout(
+                                    Ok(SyncCachePiecesResponse {
+                                        number: piece.number,
+                                        offset: piece.offset,
+                                        length: piece.length,
+                                    }),
+                                    super::REQUEST_TIMEOUT,
+                                )
+                                .await
+                            {
+                                Ok(_) => {
+                                    info!("send cache piece metadata {}-{}", task_id, piece.number);
+                                }
+                                Err(err) => {
+                                    error!(
+                                        "send cache piece metadata {}-{} to stream: {}",
+                                        task_id, interested_piece_number, err
+                                    );
+
+                                    drop(out_stream_tx);
+                                    return;
+                                }
+                            }
+
+                            // Add the finished piece number to the finished piece numbers.
+                            finished_piece_numbers.push(piece.number);
+                            continue;
+                        }
+                    }
+
+                    // Remove the finished piece numbers from the interested piece numbers.
+                    interested_cache_piece_numbers
+                        .retain(|number| !finished_piece_numbers.contains(number));
+
+                    // If all the interested pieces are finished, return.
+                    if interested_cache_piece_numbers.is_empty() {
+                        info!("all the interested cache pieces are finished");
+                        drop(out_stream_tx);
+                        return;
+                    }
+
+                    // Wait for the piece to be finished.
+                    tokio::time::sleep(
+                        dragonfly_client_storage::DEFAULT_WAIT_FOR_PIECE_FINISHED_INTERVAL,
+                    )
+                    .await;
+                }
+            }
+            .in_current_span(),
+        );
+
+        Ok(Response::new(ReceiverStream::new(out_stream_rx)))
+    }
+
+    /// download_cache_piece provides the cache piece content for parent.
+    #[instrument(
+        skip_all,
+        fields(host_id, remote_host_id, task_id, piece_id, piece_length)
+    )]
+    async fn download_cache_piece(
+        &self,
+        request: Request<DownloadCachePieceRequest>,
+    ) -> Result<Response<DownloadCachePieceResponse>, Status> {
+        // If the parent context is set, use it as the parent context for the span.
+        if let Some(parent_ctx) = request.extensions().get::<Context>() {
+            Span::current().set_parent(parent_ctx.clone());
+        };
+
+        // Clone the request.
+        let request = request.into_inner();
+
+        // Generate the host id.
+        let host_id = self.cache_task.id_generator.host_id();
+
+        // Get the remote host id from the request.
+        let remote_host_id = request.host_id;
+
+        // Get the task id from the request.
+        let task_id = request.task_id;
+
+        // Get the interested piece number from the request.
+        let piece_number = request.piece_number;
+
+        // Generate the piece id.
+        let piece_id = self
+            .cache_task
+            .piece
+            .cache_id(task_id.as_str(), piece_number);
+
+        // Span record the host id, task id and piece number.
+        Span::current().record("host_id", host_id.as_str());
+        Span::current().record("remote_host_id", remote_host_id.as_str());
+        Span::current().record("task_id", task_id.as_str());
+        Span::current().record("piece_id", piece_id.as_str());
+
+        // Get the piece metadata from the local storage.
+        let piece = self
+            .cache_task
+            .piece
+            .get_cache(piece_id.as_str())
+            .map_err(|err| {
+                error!("upload cache piece metadata from local storage: {}", err);
+                Status::internal(err.to_string())
+            })?
+            .ok_or_else(|| {
+                error!("upload cache piece metadata not found");
+                Status::not_found("cache piece metadata not found")
+            })?;
+        Span::current().record("piece_length", piece.length);
+
+        // Collect upload piece started metrics.
+        collect_upload_piece_started_metrics();
+        info!("start upload cache piece content");
+
+        // Get the piece content from the memory cache.
+        let mut reader = self
+            .cache_task
+            .piece
+            .upload_cache_from_local_into_async_read(
+                piece_id.as_str(),
+                task_id.as_str(),
+                piece.length,
+                None,
+                false,
+            )
+            .await
+            .map_err(|err| {
+                // Collect upload piece failure metrics.
+                collect_upload_piece_failure_metrics();
+
+                error!("upload cache piece content from local storage: {}", err);
+                Status::internal(err.to_string())
+            })?;
+
+        // Read the content of the piece.
+        let mut content = vec![0; piece.length as usize];
+        reader.read_exact(&mut content).await.map_err(|err| {
+            // Collect upload piece failure metrics.
+            collect_upload_piece_failure_metrics();
+
+            error!("upload cache piece content failed: {}", err);
+            Status::internal(err.to_string())
+        })?;
+        drop(reader);
+
+        // Collect upload piece finished metrics.
+        collect_upload_piece_finished_metrics();
+        info!("finished cache upload piece content");
+
+        // Return the piece.
+        Ok(Response::new(DownloadCachePieceResponse {
+            piece: Some(Piece {
+                number: piece.number,
+                parent_id: piece.parent_id.clone(),
+                offset: piece.offset,
+                length: piece.length,
+                digest: piece.digest.clone(),
+                content: Some(content),
+                traffic_type: None,
+                cost: None,
+                created_at: None,
+            }),
+            // Calculate the digest of the piece metadata, including the number, offset, length and
+            // content digest. The digest is used to verify the integrity of the piece metadata.
+            digest: Some(piece.calculate_digest()),
+        }))
+    }
+}
+
+/// DfdaemonUploadClient is a wrapper of DfdaemonUploadGRPCClient.
+#[derive(Clone)]
+pub struct DfdaemonUploadClient {
+    /// client is the grpc client of the dfdaemon upload.
+    pub client: DfdaemonUploadGRPCClient<InterceptedService<Channel, InjectTracingInterceptor>>,
+}
+
+/// DfdaemonUploadClient implements the dfdaemon upload grpc client.
+impl DfdaemonUploadClient {
+    /// new creates a new DfdaemonUploadClient.
+    pub async fn new(
+        config: Arc<Config>,
+        addr: String,
+        is_download_piece: bool,
+    ) -> ClientResult<Self> {
+        let domain_name = Url::parse(addr.as_str())?
+            .host_str()
+            .ok_or(ClientError::InvalidParameter)
+            .inspect_err(|_err| {
+                error!("invalid address: {}", addr);
+            })?
+            .to_string();
+
+        // If it is download piece, use the download piece timeout, otherwise use the
+        // default request timeout.
+        let timeout = if is_download_piece {
+            config.download.piece_timeout
+        } else {
+            super::REQUEST_TIMEOUT
+        };
+
+        let channel = match config
+            .upload
+            .client
+            .load_client_tls_config(domain_name.as_str())
+            .await?
+        {
+            Some(client_tls_config) => {
+                Channel::from_static(Box::leak(addr.clone().into_boxed_str()))
+                    .tls_config(client_tls_config)?
+                    .buffer_size(super::BUFFER_SIZE)
+                    .connect_timeout(super::CONNECT_TIMEOUT)
+                    .timeout(timeout)
+                    .tcp_keepalive(Some(super::TCP_KEEPALIVE))
+                    .http2_keep_alive_interval(super::HTTP2_KEEP_ALIVE_INTERVAL)
+                    .keep_alive_timeout(super::HTTP2_KEEP_ALIVE_TIMEOUT)
+                    .connect()
+                    .await
+                    .inspect_err(|err| {
+                        error!("connect to {} failed: {}", addr, err);
+                    })
+                    .or_err(ErrorType::ConnectError)?
+            }
+            None => Channel::from_static(Box::leak(addr.clone().into_boxed_str()))
+                .buffer_size(super::BUFFER_SIZE)
+                .connect_timeout(super::CONNECT_TIMEOUT)
+                .timeout(timeout)
+                .tcp_keepalive(Some(super::TCP_KEEPALIVE))
+                .http2_keep_alive_interval(super::HTTP2_KEEP_ALIVE_INTERVAL)
+                .keep_alive_timeout(super::HTTP2_KEEP_ALIVE_TIMEOUT)
+                .connect()
+                .await
+                .inspect_err(|err| {
+                    error!("connect to {} failed: {}", addr, err);
+                })
+                .or_err(ErrorType::ConnectError)?,
+        };
+
+        let client = DfdaemonUploadGRPCClient::with_interceptor(channel, InjectTracingInterceptor)
+            .max_decoding_message_size(usize::MAX)
+            .max_encoding_message_size(usize::MAX);
+        Ok(Self { client })
+    }
+
+    /// download_task downloads the task.
+    #[instrument(skip_all)]
+    pub async fn download_task(
+        &self,
+        request: DownloadTaskRequest,
+    ) -> ClientResult<tonic::Response<tonic::codec::Streaming<DownloadTaskResponse>>> {
+        // Get the download from the request.
+        let download = request.clone().download.ok_or_else(|| {
+            tonic::Status::invalid_argument("missing download in download task request")
+        })?;
+
+        // Initialize the request.
+        let mut request = tonic::Request::new(request);
+
+        // Set the timeout to the request.
+        if let Some(timeout) = download.timeout {
+            request.set_timeout(
+                Duration::try_from(timeout)
+                    .map_err(|_| tonic::Status::invalid_argument("invalid timeout"))?,
+            );
+        }
+
+        let response = self.client.clone().download_task(request).await?;
+        Ok(response)
+    }
+
+    /// stat_task gets the status of the task.
+    #[instrument(skip_all)]
+    pub async fn stat_task(&self, request: DfdaemonStatTaskRequest) -> ClientResult<Task> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().stat_task(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// list_task_entries lists the task entries.
+    #[instrument(skip_all)]
+    pub async fn list_task_entries(
+        &self,
+        request: ListTaskEntriesRequest,
+    ) -> ClientResult<ListTaskEntriesResponse> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().list_task_entries(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// delete_task tells the dfdaemon to delete the task.
+    #[instrument(skip_all)]
+    pub async fn delete_task(&self, request: DeleteTaskRequest) -> ClientResult<()> {
+        let request = Self::make_request(request);
+        self.client.clone().delete_task(request).await?;
+        Ok(())
+    }
+
+    /// sync_pieces provides the piece metadata for parent.
+    #[instrument(skip_all)]
+    pub async fn sync_pieces(
+        &self,
+        request: SyncPiecesRequest,
+    ) -> ClientResult<tonic::Response<tonic::codec::Streaming<SyncPiecesResponse>>> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().sync_pieces(request).await?;
+        Ok(response)
+    }
 
     /// download_piece provides the piece content for parent.
     #[instrument(skip_all)]
@@ -3086,6 +3806,71 @@ impl DfdaemonUploadClient {
         Ok(response.into_inner())
     }
 
+    /// download_cache_task downloads the cache task.
+    #[instrument(skip_all)]
+    pub async fn download_cache_task(
+        &self,
+        request: DownloadCacheTaskRequest,
+    ) -> ClientResult<tonic::Response<tonic::codec::Streaming<DownloadCacheTaskResponse>>> {
+        // Clone the request.
+        let request_clone = request.clone();
+
+        // Initialize the request.
+        let mut request = tonic::Request::new(request);
+
+        // Set the timeout to the request.
+        if let Some(timeout) = request_clone.timeout {
+            request.set_timeout(
+                Duration::try_from(timeout)
+                    .map_err(|_| tonic::Status::invalid_argument("invalid timeout"))?,
+            );
+        }
+
+        let response = self.client.clone().download_cache_task(request).await?;
+        Ok(response)
+    }
+
+    /// stat_cache_task stats the cache task.
+    #[instrument(skip_all)]
+    pub async fn stat_cache_task(&self, request: StatCacheTaskRequest) -> ClientResult<CacheTask> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().stat_cache_task(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// delete_cache_task deletes the cache task.
+    #[instrument(skip_all)]
+    pub async fn delete_cache_task(&self, request: DeleteCacheTaskRequest) -> ClientResult<()> {
+        let request = Self::make_request(request);
+        let _response = self.client.clone().delete_cache_task(request).await?;
+        Ok(())
+    }
+
+    /// sync_cache_pieces provides the cache piece metadata for parent.
+    #[instrument(skip_all)]
+    pub async fn sync_cache_pieces(
+        &self,
+        request: SyncCachePiecesRequest,
+    ) -> ClientResult<tonic::Response<tonic::codec::Streaming<SyncCachePiecesResponse>>> {
+        let request = Self::make_request(request);
+        let response = self.client.clone().sync_cache_pieces(request).await?;
+        Ok(response)
+    }
+
+    /// download_cache_piece provides the cache piece content for parent.
+    #[instrument(skip_all)]
+    pub async fn download_cache_piece(
+        &self,
+        request: DownloadCachePieceRequest,
+        timeout: Duration,
+    ) -> ClientResult<DownloadCachePieceResponse> {
+        let mut request = tonic::Request::new(request);
+        request.set_timeout(timeout);
+
+        let response = self.client.clone().download_cache_piece(request).await?;
+        Ok(response.into_inner())
+    }
+
     /// exchange_ib_verbs_queue_pair_endpoint exchanges ib verbs queue pair endpoint.
     #[instrument(skip_all)]
     pub async fn exchange_ib_verbs_queue_pair_endpoint(
@@ -104,6 +104,15 @@ impl ManagerClient {
         Ok(Self { client })
     }
 
+    /// new_lazy creates a new ManagerClient without connecting to the manager, it is
+    /// used by the tests that run without the manager.
+    #[cfg(test)]
+    pub fn new_lazy(addr: &'static str) -> Self {
+        let channel = Channel::from_static(addr).connect_lazy();
+        let client = ManagerGRPCClient::with_interceptor(channel, InjectTracingInterceptor);
+        Self { client }
+    }
+
     /// list_schedulers lists all schedulers that best match the client.
     #[instrument(skip_all)]
     pub async fn list_schedulers(
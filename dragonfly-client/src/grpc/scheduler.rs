@@ -16,16 +16,18 @@
 
 use crate::dynconfig::Dynconfig;
 use dragonfly_api::common::v2::{
-    Peer, PersistentCachePeer, PersistentCacheTask, PersistentPeer, PersistentTask, Task,
+    CachePeer, CacheTask, Peer, PersistentCachePeer, PersistentCacheTask, PersistentPeer,
+    PersistentTask, Task,
 };
 use dragonfly_api::manager::v2::Scheduler;
 use dragonfly_api::scheduler::v2::{
-    scheduler_client::SchedulerClient as SchedulerGRPCClient, AnnounceHostRequest,
-    AnnouncePeerRequest, AnnouncePeerResponse, AnnouncePersistentCachePeerRequest,
-    AnnouncePersistentCachePeerResponse, AnnouncePersistentPeerRequest,
-    AnnouncePersistentPeerResponse, DeleteHostRequest, DeletePeerRequest,
-    DeletePersistentCachePeerRequest, DeletePersistentCacheTaskRequest,
-    DeletePersistentPeerRequest, DeletePersistentTaskRequest, DeleteTaskRequest, StatPeerRequest,
+    scheduler_client::SchedulerClient as SchedulerGRPCClient, AnnounceCachePeerRequest,
+    AnnounceCachePeerResponse, AnnounceHostRequest, AnnouncePeerRequest, AnnouncePeerResponse,
+    AnnouncePersistentCachePeerRequest, AnnouncePersistentCachePeerResponse,
+    AnnouncePersistentPeerRequest, AnnouncePersistentPeerResponse, DeleteCachePeerRequest,
+    DeleteCacheTaskRequest, DeleteHostRequest, DeletePeerRequest, DeletePersistentCachePeerRequest,
+    DeletePersistentCacheTaskRequest, DeletePersistentPeerRequest, DeletePersistentTaskRequest,
+    DeleteTaskRequest, StatCachePeerRequest, StatCacheTaskRequest, StatPeerRequest,
     StatPersistentCachePeerRequest, StatPersistentCacheTaskRequest, StatPersistentPeerRequest,
     StatPersistentTaskRequest, StatTaskRequest, UploadPersistentCacheTaskFailedRequest,
     UploadPersistentCacheTaskFinishedRequest, UploadPersistentCacheTaskStartedRequest,
@@ -98,6 +100,20 @@ impl SchedulerClient {
         Ok(client)
     }
 
+    /// new_empty creates a new SchedulerClient without available schedulers, so every
+    /// scheduler request fails and the download falls back to the source. It is used by
+    /// the tests that run without the scheduler.
+    #[cfg(test)]
+    pub fn new_empty(config: Arc<Config>, dynconfig: Arc<Dynconfig>) -> Self {
+        Self {
+            config,
+            dynconfig,
+            available_schedulers: Arc::new(RwLock::new(Vec::new())),
+            available_scheduler_addrs: Arc::new(RwLock::new(Vec::new())),
+            hashring: Arc::new(RwLock::new(HashRing::new())),
+        }
+    }
+
     /// announce_peer announces the peer to the scheduler.
     #[instrument(skip_all)]
     pub async fn announce_peer(
@@ -164,6 +180,72 @@ impl SchedulerClient {
         Ok(())
     }
 
+    /// announce_cache_peer announces the cache peer to the scheduler.
+    #[instrument(skip_all)]
+    pub async fn announce_cache_peer(
+        &self,
+        task_id: &str,
+        peer_id: &str,
+        request: impl tonic::IntoStreamingRequest<Message = AnnounceCachePeerRequest>,
+    ) -> Result<tonic::Response<tonic::codec::Streaming<AnnounceCachePeerResponse>>> {
+        let response = self
+            .client(task_id, Some(peer_id))
+            .await?
+            .announce_cache_peer(request)
+            .await?;
+        Ok(response)
+    }
+
+    /// stat_cache_peer gets the status of the cache peer.
+    #[instrument(skip(self))]
+    pub async fn stat_cache_peer(&self, request: StatCachePeerRequest) -> Result<CachePeer> {
+        let task_id = request.task_id.clone();
+        let request = Self::make_request(request);
+        let response = self
+            .client(task_id.as_str(), None)
+            .await?
+            .stat_cache_peer(request)
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    /// delete_cache_peer tells the scheduler that the cache peer is deleting.
+    #[instrument(skip(self))]
+    pub async fn delete_cache_peer(&self, request: DeleteCachePeerRequest) -> Result<()> {
+        let task_id = request.task_id.clone();
+        let request = Self::make_request(request);
+        self.client(task_id.as_str(), None)
+            .await?
+            .delete_cache_peer(request)
+            .await?;
+        Ok(())
+    }
+
+    /// stat_cache_task gets the status of the cache task.
+    #[instrument(skip(self))]
+    pub async fn stat_cache_task(&self, request: StatCacheTaskRequest) -> Result<CacheTask> {
+        let task_id = request.task_id.clone();
+        let request = Self::make_request(request);
+        let response = self
+            .client(task_id.as_str(), None)
+            .await?
+            .stat_cache_task(request)
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    /// delete_cache_task tells the scheduler that the cache task is deleting.
+    #[instrument(skip(self))]
+    pub async fn delete_cache_task(&self, request: DeleteCacheTaskRequest) -> Result<()> {
+        let task_id = request.task_id.clone();
+        let request = Self::make_request(request);
+        self.client(task_id.as_str(), None)
+            .await?
+            .delete_cache_task(request)
+            .await?;
+        Ok(())
+    }
+
     /// announce_host announces the host to the scheduler.
     #[instrument(skip(self))]
     pub async fn announce_host(&self, request: AnnounceHostRequest) -> Result<()> {
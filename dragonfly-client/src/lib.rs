@@ -19,6 +19,7 @@ pub mod dynconfig;
 pub mod gc;
 pub mod grpc;
 pub mod health;
+pub mod output;
 pub mod proxy;
 pub mod resource;
 pub mod stats;
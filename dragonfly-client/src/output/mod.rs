@@ -0,0 +1,581 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_api::common::v2::{Piece, TrafficType};
+use dragonfly_api::errordetails::v2::Backend;
+use dragonfly_client_core::{Error, Result};
+use dragonfly_client_util::http::headermap_to_hashmap;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+use tracing::error;
+
+/// EXIT_CODES_HELP is the help of the exit codes, which is appended to the long about of the
+/// commands.
+pub const EXIT_CODES_HELP: &str = r#"
+Exit codes:
+  0  success
+  1  unknown error
+  2  invalid arguments
+  3  dfdaemon is unavailable
+  4  download or upload failed in dfdaemon
+  5  backend returned an error, e.g. the bad status code of the origin
+  6  local I/O error, e.g. no space left on device
+  7  integrity check failed, e.g. digest or content length mismatch
+  8  timeout
+  9  some of the files failed to download in batch
+"#;
+
+/// OutputFormat is the format of the command output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Text is the human readable output with the progress bar.
+    #[default]
+    Text,
+
+    /// Json is the newline delimited JSON events, the last event is the result of the command.
+    Json,
+}
+
+/// ErrorClass is the class of the command error, the exit code of the class is stable and
+/// documented in EXIT_CODES_HELP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Unknown is the error which is not classified.
+    Unknown,
+
+    /// InvalidArgument is the error of the invalid command line arguments.
+    InvalidArgument,
+
+    /// DfdaemonUnavailable is the error when the dfdaemon can not be connected.
+    DfdaemonUnavailable,
+
+    /// Download is the error returned by the dfdaemon.
+    Download,
+
+    /// Backend is the error returned by the backend of the origin.
+    Backend,
+
+    /// IO is the error of the local file system.
+    IO,
+
+    /// Integrity is the error when the digest or the content length mismatches.
+    Integrity,
+
+    /// Timeout is the error when the operation is timeout.
+    Timeout,
+
+    /// PartialFailure is the error when some of the files failed to download in batch.
+    PartialFailure,
+}
+
+/// ErrorClass implements the error class.
+impl ErrorClass {
+    /// exit_code returns the exit code of the error class.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorClass::Unknown => 1,
+            ErrorClass::InvalidArgument => 2,
+            ErrorClass::DfdaemonUnavailable => 3,
+            ErrorClass::Download => 4,
+            ErrorClass::Backend => 5,
+            ErrorClass::IO => 6,
+            ErrorClass::Integrity => 7,
+            ErrorClass::Timeout => 8,
+            ErrorClass::PartialFailure => 9,
+        }
+    }
+}
+
+/// ErrorClass implements the From trait for Error.
+impl From<&Error> for ErrorClass {
+    /// from classifies the error.
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::ValidationError(_)
+            | Error::InvalidURI(_)
+            | Error::URLParseError(_)
+            | Error::Unsupported(_)
+            | Error::MaxDownloadFilesExceeded(_) => ErrorClass::InvalidArgument,
+            Error::TonicTransportError(_) => ErrorClass::DfdaemonUnavailable,
+            Error::BackendError(_) => ErrorClass::Backend,
+            Error::TonicStatus(status) => {
+                if serde_json::from_slice::<Backend>(status.details()).is_ok() {
+                    return ErrorClass::Backend;
+                }
+
+                match status.code() {
+                    tonic::Code::DeadlineExceeded => ErrorClass::Timeout,
+                    _ => ErrorClass::Download,
+                }
+            }
+            Error::IO(_) | Error::NoSpace(_) => ErrorClass::IO,
+            Error::DigestMismatch(_, _) | Error::ContentLengthMismatch(_, _) => {
+                ErrorClass::Integrity
+            }
+            Error::SendTimeout
+            | Error::TokioStreamElapsed(_)
+            | Error::TokioTimeErrorElapsed(_)
+            | Error::DownloadPieceFinishedTimeout(_)
+            | Error::WaitForPieceFinishedTimeout(_) => ErrorClass::Timeout,
+            Error::DownloadFilesFailed(_, _) => ErrorClass::PartialFailure,
+            _ => ErrorClass::Unknown,
+        }
+    }
+}
+
+/// ErrorOutput is the structured error of the command.
+#[derive(Debug, Serialize)]
+pub struct ErrorOutput {
+    /// class is the class of the error.
+    #[serde(rename = "type")]
+    pub class: ErrorClass,
+
+    /// message is the message of the error.
+    pub message: String,
+
+    /// grpc_code is the gRPC code of the error returned by the dfdaemon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grpc_code: Option<String>,
+
+    /// backend is the error returned by the backend of the origin, including the status code
+    /// and the header of the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<Backend>,
+}
+
+/// ErrorOutput implements the error output.
+impl ErrorOutput {
+    /// new creates a new error output by the error class and the error.
+    pub fn new(class: ErrorClass, err: &Error) -> Self {
+        match err {
+            Error::TonicStatus(status) => Self {
+                class,
+                message: status.message().to_string(),
+                grpc_code: Some(format!("{:?}", status.code())),
+                backend: serde_json::from_slice::<Backend>(status.details()).ok(),
+            },
+            Error::BackendError(err) => Self {
+                class,
+                message: err.message.clone(),
+                grpc_code: None,
+                backend: Some(Backend {
+                    message: err.message.clone(),
+                    header: err
+                        .header
+                        .as_ref()
+                        .map(headermap_to_hashmap)
+                        .unwrap_or_default(),
+                    status_code: err
+                        .status_code
+                        .map(|status_code| status_code.as_u16() as i32),
+                }),
+            },
+            err => Self {
+                class,
+                message: err.to_string(),
+                grpc_code: None,
+                backend: None,
+            },
+        }
+    }
+}
+
+/// TaskOutput is the result of the task, which is used by the finished event of the task in batch
+/// and the result event of the command.
+#[derive(Debug, Default, Serialize)]
+pub struct TaskOutput {
+    /// success is whether the task or the command succeeded.
+    pub success: bool,
+
+    /// exit_code is the exit code of the command, it is only set in the result event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+
+    /// task_id is the id of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+
+    /// peer_id is the id of the peer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_id: Option<String>,
+
+    /// source is the source of the task, e.g. the url or the imported path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// output is the output path of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+
+    /// digest is the digest of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+
+    /// content_length is the content length of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+
+    /// bytes_done is the size of the finished pieces.
+    pub bytes_done: u64,
+
+    /// duration_ms is the duration of the task or the command in milliseconds.
+    pub duration_ms: u64,
+
+    /// error is the error of the task or the command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorOutput>,
+
+    /// details are the details of the task, e.g. the task information of the stat command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+/// Event is the event of the command in the json output format, each event is written as a
+/// line of JSON to stdout.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Started is the event when the task is started in dfdaemon.
+    Started {
+        host_id: String,
+        task_id: String,
+        peer_id: String,
+        content_length: u64,
+    },
+
+    /// Progress is the event when a piece of the task is finished.
+    Progress {
+        task_id: String,
+        peer_id: String,
+        piece_number: u32,
+        piece_length: u64,
+        traffic_type: Option<&'static str>,
+        parent_id: Option<String>,
+        bytes_done: u64,
+        pieces_finished: u64,
+        content_length: u64,
+    },
+
+    /// Finished is the event when a task in batch is finished.
+    Finished(TaskOutput),
+
+    /// Result is the last event of the command.
+    Result(TaskOutput),
+}
+
+/// Event implements the event.
+impl Event {
+    /// write writes the event as a line of JSON to stdout.
+    pub fn write(&self) {
+        let line = match serde_json::to_string(self) {
+            Ok(line) => line,
+            Err(err) => {
+                error!("serialize event failed: {}", err);
+                return;
+            }
+        };
+
+        let mut stdout = std::io::stdout().lock();
+        if let Err(err) = writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+            error!("write event failed: {}", err);
+        }
+    }
+}
+
+/// Reporter reports the progress and the result of the task in the output format. In the text
+/// output format, the events are not written and the commands print the progress bar and the
+/// banners themselves.
+pub struct Reporter {
+    /// format is the output format.
+    format: OutputFormat,
+
+    /// started_at is the time when the reporter is created.
+    started_at: Instant,
+
+    /// output is the result of the task.
+    output: TaskOutput,
+
+    /// pieces_finished is the number of the finished pieces.
+    pieces_finished: u64,
+}
+
+/// Reporter implements the reporter.
+impl Reporter {
+    /// new creates a new reporter.
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            started_at: Instant::now(),
+            output: TaskOutput::default(),
+            pieces_finished: 0,
+        }
+    }
+
+    /// is_json returns whether the output format is json.
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// set_source sets the source of the task.
+    pub fn set_source(&mut self, source: impl Into<String>) {
+        self.output.source = Some(source.into());
+    }
+
+    /// set_output sets the output path of the task.
+    pub fn set_output(&mut self, output: &Path) {
+        self.output.output = Some(output.to_string_lossy().to_string());
+    }
+
+    /// set_digest sets the digest of the task.
+    pub fn set_digest(&mut self, digest: Option<String>) {
+        self.output.digest = digest;
+    }
+
+    /// set_task_id sets the id of the task, which is used when the task is not downloaded.
+    pub fn set_task_id(&mut self, task_id: impl Into<String>) {
+        self.output.task_id = Some(task_id.into());
+    }
+
+    /// set_content_length sets the content length of the task.
+    pub fn set_content_length(&mut self, content_length: u64) {
+        self.output.content_length = Some(content_length);
+    }
+
+    /// set_details sets the details of the task.
+    pub fn set_details<T: Serialize>(&mut self, details: &T) {
+        self.output.details = serde_json::to_value(details).ok();
+    }
+
+    /// started records the started task, and writes the started event.
+    pub fn started(&mut self, host_id: &str, task_id: &str, peer_id: &str, content_length: u64) {
+        self.output.task_id = Some(task_id.to_string());
+        self.output.peer_id = Some(peer_id.to_string());
+        self.output.content_length = Some(content_length);
+
+        if self.is_json() {
+            Event::Started {
+                host_id: host_id.to_string(),
+                task_id: task_id.to_string(),
+                peer_id: peer_id.to_string(),
+                content_length,
+            }
+            .write();
+        }
+    }
+
+    /// piece_finished records the finished piece, and writes the progress event.
+    pub fn piece_finished(&mut self, piece: &Piece) {
+        self.output.bytes_done += piece.length;
+        self.pieces_finished += 1;
+
+        if self.is_json() {
+            Event::Progress {
+                task_id: self.output.task_id.clone().unwrap_or_default(),
+                peer_id: self.output.peer_id.clone().unwrap_or_default(),
+                piece_number: piece.number,
+                piece_length: piece.length,
+                traffic_type: piece
+                    .traffic_type
+                    .and_then(|traffic_type| TrafficType::try_from(traffic_type).ok())
+                    .map(traffic_type_name),
+                parent_id: piece.parent_id.clone(),
+                bytes_done: self.output.bytes_done,
+                pieces_finished: self.pieces_finished,
+                content_length: self.output.content_length.unwrap_or_default(),
+            }
+            .write();
+        }
+    }
+
+    /// finished writes the finished event of the task in batch.
+    pub fn finished(mut self, result: &Result<()>) {
+        if !self.is_json() {
+            return;
+        }
+
+        self.output.success = result.is_ok();
+        if let Err(err) = result {
+            self.output.error = Some(ErrorOutput::new(ErrorClass::from(err), err));
+        }
+
+        self.output.duration_ms = self.started_at.elapsed().as_millis() as u64;
+        Event::Finished(self.output).write();
+    }
+
+    /// succeed writes the successful result event of the command.
+    pub fn succeed(mut self) {
+        if !self.is_json() {
+            return;
+        }
+
+        self.output.success = true;
+        self.output.exit_code = Some(0);
+        self.output.duration_ms = self.started_at.elapsed().as_millis() as u64;
+        Event::Result(self.output).write();
+    }
+
+    /// fail writes the failed result event of the command, and exits the process with the exit
+    /// code of the error class.
+    pub fn fail(mut self, class: ErrorClass, err: &Error) -> ! {
+        if self.is_json() {
+            self.output.success = false;
+            self.output.exit_code = Some(class.exit_code());
+            self.output.duration_ms = self.started_at.elapsed().as_millis() as u64;
+            self.output.error = Some(ErrorOutput::new(class, err));
+            Event::Result(self.output).write();
+        }
+
+        std::process::exit(class.exit_code());
+    }
+}
+
+/// traffic_type_name returns the name of the traffic type in the events.
+fn traffic_type_name(traffic_type: TrafficType) -> &'static str {
+    match traffic_type {
+        TrafficType::BackToSource => "back_to_source",
+        TrafficType::RemotePeer => "remote_peer",
+        TrafficType::LocalPeer => "local_peer",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dragonfly_client_core::error::BackendError;
+    use std::collections::HashMap;
+
+    #[test]
+    fn should_classify_errors() {
+        let backend = Backend {
+            message: "not found".to_string(),
+            header: HashMap::new(),
+            status_code: Some(404),
+        };
+        let status = tonic::Status::with_details(
+            tonic::Code::Internal,
+            "backend error",
+            serde_json::to_vec(&backend).unwrap().into(),
+        );
+
+        let test_cases = vec![
+            (
+                Error::ValidationError("invalid".to_string()),
+                ErrorClass::InvalidArgument,
+                2,
+            ),
+            (Error::TonicStatus(status), ErrorClass::Backend, 5),
+            (
+                Error::TonicStatus(tonic::Status::internal("failed")),
+                ErrorClass::Download,
+                4,
+            ),
+            (
+                Error::TonicStatus(tonic::Status::deadline_exceeded("timeout")),
+                ErrorClass::Timeout,
+                8,
+            ),
+            (Error::IO(std::io::Error::other("io")), ErrorClass::IO, 6),
+            (
+                Error::DigestMismatch("a".to_string(), "b".to_string()),
+                ErrorClass::Integrity,
+                7,
+            ),
+            (
+                Error::DownloadFilesFailed(1, 2),
+                ErrorClass::PartialFailure,
+                9,
+            ),
+            (
+                Error::Unknown("unknown".to_string()),
+                ErrorClass::Unknown,
+                1,
+            ),
+        ];
+
+        for (err, class, exit_code) in test_cases {
+            assert_eq!(ErrorClass::from(&err), class);
+            assert_eq!(class.exit_code(), exit_code);
+        }
+
+        assert_eq!(ErrorClass::DfdaemonUnavailable.exit_code(), 3);
+    }
+
+    #[test]
+    fn should_serialize_error_output() {
+        let backend = Backend {
+            message: "forbidden".to_string(),
+            header: HashMap::new(),
+            status_code: Some(403),
+        };
+        let err = Error::TonicStatus(tonic::Status::with_details(
+            tonic::Code::Internal,
+            "backend error",
+            serde_json::to_vec(&backend).unwrap().into(),
+        ));
+
+        let output = serde_json::to_value(ErrorOutput::new(ErrorClass::from(&err), &err)).unwrap();
+        assert_eq!(output["type"], "backend");
+        assert_eq!(output["message"], "backend error");
+        assert_eq!(output["grpc_code"], "Internal");
+        assert_eq!(output["backend"]["status_code"], 403);
+
+        let err = Error::BackendError(Box::new(BackendError {
+            message: "bad gateway".to_string(),
+            status_code: Some(reqwest::StatusCode::BAD_GATEWAY),
+            header: None,
+        }));
+        let output = serde_json::to_value(ErrorOutput::new(ErrorClass::from(&err), &err)).unwrap();
+        assert_eq!(output["type"], "backend");
+        assert_eq!(output["backend"]["status_code"], 502);
+        assert!(output.get("grpc_code").is_none());
+    }
+
+    #[test]
+    fn should_serialize_events() {
+        let event = serde_json::to_value(Event::Progress {
+            task_id: "task".to_string(),
+            peer_id: "peer".to_string(),
+            piece_number: 1,
+            piece_length: 4,
+            traffic_type: Some(traffic_type_name(TrafficType::RemotePeer)),
+            parent_id: Some("parent".to_string()),
+            bytes_done: 8,
+            pieces_finished: 2,
+            content_length: 16,
+        })
+        .unwrap();
+        assert_eq!(event["event"], "progress");
+        assert_eq!(event["traffic_type"], "remote_peer");
+        assert_eq!(event["parent_id"], "parent");
+        assert_eq!(event["bytes_done"], 8);
+
+        let event = serde_json::to_value(Event::Result(TaskOutput {
+            success: true,
+            exit_code: Some(0),
+            task_id: Some("task".to_string()),
+            duration_ms: 10,
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(event["event"], "result");
+        assert_eq!(event["success"], true);
+        assert_eq!(event["task_id"], "task");
+        assert!(event.get("error").is_none());
+    }
+}
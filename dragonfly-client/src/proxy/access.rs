@@ -0,0 +1,551 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::Response;
+use dragonfly_client_config::dfdaemon::{ProxyAccessControl, ProxyRateLimit, ProxyServer};
+use dragonfly_client_core::{Error as ClientError, Result as ClientResult};
+use dragonfly_client_util::http::{basic_auth, htpasswd::Htpasswd};
+use futures::StreamExt;
+use http::header::{HeaderMap, HeaderValue, PROXY_AUTHENTICATE, PROXY_AUTHORIZATION, RETRY_AFTER};
+use http::StatusCode;
+use http_body_util::{combinators::BoxBody, BodyStream, StreamBody};
+use leaky_bucket::RateLimiter;
+use lru::LruCache;
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, instrument};
+
+/// PROXY_AUTHENTICATE_CHALLENGE is the challenge of the Proxy-Authenticate header.
+const PROXY_AUTHENTICATE_CHALLENGE: &str = "Basic realm=\"dragonfly\"";
+
+/// Rejection is the reason why the request of the client is rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// Denied means the remote ip of the client is denied by the access control.
+    Denied,
+
+    /// Unauthorized means the client is not authenticated by the Proxy-Authorization header.
+    Unauthorized,
+
+    /// RateLimited means the requests of the client exceed the request rate, the request can
+    /// be retried after the duration.
+    RateLimited(Duration),
+}
+
+/// Rejection implements the rejection.
+impl Rejection {
+    /// reason returns the reason of the rejection, which is the label of the metrics.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Rejection::Denied => "denied",
+            Rejection::Unauthorized => "unauthorized",
+            Rejection::RateLimited(_) => "rate_limited",
+        }
+    }
+
+    /// status returns the status code of the rejected response.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Rejection::Denied => StatusCode::FORBIDDEN,
+            Rejection::Unauthorized => StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+            Rejection::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    /// header returns the header of the rejected response.
+    pub fn header(&self) -> HeaderMap {
+        let mut header = HeaderMap::new();
+        match self {
+            Rejection::Denied => {}
+            Rejection::Unauthorized => {
+                header.insert(
+                    PROXY_AUTHENTICATE,
+                    HeaderValue::from_static(PROXY_AUTHENTICATE_CHALLENGE),
+                );
+            }
+            Rejection::RateLimited(retry_after) => {
+                header.insert(RETRY_AFTER, HeaderValue::from(retry_after.as_secs().max(1)));
+            }
+        }
+
+        header
+    }
+}
+
+/// ClientLimiter is the rate limiter of the client.
+struct ClientLimiter {
+    /// requests is the rate limiter of the requests, None means the requests are not limited.
+    requests: Option<RateLimiter>,
+
+    /// bytes is the rate limiter of the bytes of the response body, None means the bytes are
+    /// not limited.
+    bytes: Option<Arc<RateLimiter>>,
+}
+
+/// AccessController controls the access of the clients to the proxy by the remote ip, the
+/// credentials and the rate limit of each client.
+pub struct AccessController {
+    /// access_control is the access control of the clients by the remote ip.
+    access_control: ProxyAccessControl,
+
+    /// htpasswd is the credentials of the users, None means the users are not configured.
+    htpasswd: Option<Htpasswd>,
+
+    /// bearer_tokens are the tokens of the clients.
+    bearer_tokens: Vec<String>,
+
+    /// basic_auth is the credentials of the basic auth of the proxy server, None means the
+    /// basic auth is not configured.
+    basic_auth: Option<basic_auth::Credentials>,
+
+    /// rate_limit is the rate limit of each client.
+    rate_limit: ProxyRateLimit,
+
+    /// limiters are the rate limiters by the remote ip of the client, the least recently used
+    /// client is evicted and its rate limiter is reset.
+    limiters: Mutex<LruCache<IpAddr, Arc<ClientLimiter>>>,
+}
+
+/// AccessController implements the access controller.
+impl AccessController {
+    /// new creates a new access controller, the htpasswd file is loaded and the CIDRs of the
+    /// access control are validated.
+    pub fn new(config: &ProxyServer) -> ClientResult<Self> {
+        for cidr in config
+            .access_control
+            .allow
+            .iter()
+            .chain(config.access_control.deny.iter())
+        {
+            let network = cidr
+                .split_once('/')
+                .map_or(cidr.as_str(), |(network, _)| network);
+            if network.trim().parse::<IpAddr>().is_err() {
+                return Err(ClientError::ValidationError(format!(
+                    "invalid proxy access control cidr {}",
+                    cidr
+                )));
+            }
+        }
+
+        let htpasswd = match config.auth.htpasswd.as_ref() {
+            Some(path) => {
+                let htpasswd = Htpasswd::from_file(path)?;
+                info!("load {} users from htpasswd {:?}", htpasswd.len(), path);
+                Some(htpasswd)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            access_control: config.access_control.clone(),
+            htpasswd,
+            bearer_tokens: config.auth.bearer_tokens.clone(),
+            basic_auth: config
+                .basic_auth
+                .as_ref()
+                .map(|basic_auth| basic_auth.credentials()),
+            rate_limit: config.rate_limit.clone(),
+            limiters: Mutex::new(LruCache::new(
+                NonZeroUsize::new(config.rate_limit.capacity).unwrap_or(NonZeroUsize::MIN),
+            )),
+        })
+    }
+
+    /// admit admits the request of the client by the access control, the authentication and
+    /// the request rate. The Proxy-Authorization header is removed from the request, because
+    /// the credentials of the proxy should not be forwarded to the origin. The authentication
+    /// is skipped if the client has been authenticated, e.g. by the CONNECT request of the
+    /// tunnel.
+    #[instrument(skip_all)]
+    pub fn admit(
+        &self,
+        remote_ip: IpAddr,
+        header: &mut HeaderMap,
+        authenticate: bool,
+    ) -> Result<(), Rejection> {
+        if !self.access_control.is_allowed(remote_ip) {
+            info!("request of {} is denied by access control", remote_ip);
+            return Err(Rejection::Denied);
+        }
+
+        let authorization = header.remove(PROXY_AUTHORIZATION);
+        if authenticate && self.is_auth_enabled() && !self.authenticate(authorization.as_ref()) {
+            info!("request of {} is unauthorized", remote_ip);
+            return Err(Rejection::Unauthorized);
+        }
+
+        if let Some(limiter) = self.limiter(remote_ip) {
+            if let Some(requests) = limiter.requests.as_ref() {
+                if !requests.try_acquire(1) {
+                    info!("request of {} is rate limited", remote_ip);
+                    return Err(Rejection::RateLimited(requests.interval()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// throttle throttles the response body by the bandwidth of the client.
+    pub fn throttle(&self, remote_ip: IpAddr, response: Response) -> Response {
+        let Some(bytes) = self
+            .limiter(remote_ip)
+            .and_then(|limiter| limiter.bytes.clone())
+        else {
+            return response;
+        };
+
+        response.map(|body| {
+            let stream = BodyStream::new(body).then(move |frame| {
+                let bytes = bytes.clone();
+                async move {
+                    if let Some(data) = frame.as_ref().ok().and_then(|frame| frame.data_ref()) {
+                        bytes.acquire_owned(data.len()).await;
+                    }
+
+                    frame
+                }
+            });
+
+            BoxBody::new(StreamBody::new(stream))
+        })
+    }
+
+    /// is_auth_enabled returns whether the authentication of the clients is enabled.
+    fn is_auth_enabled(&self) -> bool {
+        self.htpasswd.is_some() || self.basic_auth.is_some() || !self.bearer_tokens.is_empty()
+    }
+
+    /// authenticate authenticates the client by the Proxy-Authorization header, which is
+    /// the basic auth of the proxy server, the basic auth of the htpasswd users or the
+    /// bearer token.
+    fn authenticate(&self, authorization: Option<&HeaderValue>) -> bool {
+        let Some(authorization) =
+            authorization.and_then(|authorization| authorization.to_str().ok())
+        else {
+            return false;
+        };
+
+        if self.htpasswd.is_some() || self.basic_auth.is_some() {
+            match basic_auth::Credentials::decode(authorization) {
+                Ok(credentials) => {
+                    let is_basic_auth_matched =
+                        self.basic_auth.as_ref().is_some_and(|basic_auth| {
+                            constant_time_eq(
+                                basic_auth.username.as_bytes(),
+                                credentials.username.as_bytes(),
+                            ) && constant_time_eq(
+                                basic_auth.password.as_bytes(),
+                                credentials.password.as_bytes(),
+                            )
+                        });
+
+                    return is_basic_auth_matched
+                        || self.htpasswd.as_ref().is_some_and(|htpasswd| {
+                            htpasswd.verify(&credentials.username, &credentials.password)
+                        });
+                }
+                Err(ClientError::Unauthorized) => {}
+                Err(err) => {
+                    debug!("decode basic auth failed: {}", err);
+                    return false;
+                }
+            }
+        }
+
+        match authorization.trim().split_once(' ') {
+            Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => {
+                self.bearer_tokens.iter().any(|bearer_token| {
+                    constant_time_eq(bearer_token.as_bytes(), token.trim().as_bytes())
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// limiter gets the rate limiter of the client, the rate limiter is created if the
+    /// client is not found. None means the client is not limited.
+    fn limiter(&self, remote_ip: IpAddr) -> Option<Arc<ClientLimiter>> {
+        if self.rate_limit.request_rate.is_none() && self.rate_limit.bandwidth.is_none() {
+            return None;
+        }
+
+        let mut limiters = self.limiters.lock().unwrap();
+        Some(
+            limiters
+                .get_or_insert(remote_ip, || Arc::new(self.new_client_limiter()))
+                .clone(),
+        )
+    }
+
+    /// new_client_limiter creates the rate limiter of the client by the rate limit.
+    fn new_client_limiter(&self) -> ClientLimiter {
+        let requests = self.rate_limit.request_rate.map(|request_rate| {
+            let request_rate = request_rate as usize;
+            let request_burst = (self.rate_limit.request_burst as usize).max(request_rate);
+            RateLimiter::builder()
+                .initial(request_burst)
+                .refill(request_rate)
+                .max(request_burst)
+                .interval(Duration::from_secs(1))
+                .fair(false)
+                .build()
+        });
+
+        let bytes = self.rate_limit.bandwidth.map(|bandwidth| {
+            let bandwidth = bandwidth.as_u64() as usize;
+            Arc::new(
+                RateLimiter::builder()
+                    .initial(bandwidth)
+                    .refill(bandwidth)
+                    .max(bandwidth)
+                    .interval(Duration::from_secs(1))
+                    .fair(false)
+                    .build(),
+            )
+        });
+
+        ClientLimiter { requests, bytes }
+    }
+}
+
+/// constant_time_eq compares the bytes in constant time to avoid the timing attack.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use bytesize::ByteSize;
+    use dragonfly_client_config::dfdaemon::{BasicAuth, ProxyAuth};
+    use http_body_util::{BodyExt, Full};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn should_admit_by_access_control() {
+        let access_controller = AccessController::new(&ProxyServer {
+            access_control: ProxyAccessControl {
+                allow: vec!["10.0.0.0/8".to_string()],
+                deny: vec!["10.0.0.1".to_string()],
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut header = HeaderMap::new();
+        assert!(access_controller
+            .admit("10.0.0.2".parse().unwrap(), &mut header, true)
+            .is_ok());
+        assert_eq!(
+            access_controller.admit("10.0.0.1".parse().unwrap(), &mut header, true),
+            Err(Rejection::Denied)
+        );
+        assert_eq!(
+            access_controller.admit("192.168.0.1".parse().unwrap(), &mut header, true),
+            Err(Rejection::Denied)
+        );
+
+        // The invalid CIDR is rejected.
+        assert!(AccessController::new(&ProxyServer {
+            access_control: ProxyAccessControl {
+                allow: vec!["invalid".to_string()],
+                deny: vec![],
+            },
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_admit_by_credentials() {
+        let mut htpasswd = NamedTempFile::new().unwrap();
+        writeln!(htpasswd, "alice:$apr1$saltsalt$LrttParrLPdxvgutaSXWJ0").unwrap();
+
+        let access_controller = AccessController::new(&ProxyServer {
+            auth: ProxyAuth {
+                htpasswd: Some(htpasswd.path().to_path_buf()),
+                bearer_tokens: vec!["token".to_string()],
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let remote_ip = "127.0.0.1".parse().unwrap();
+        let test_cases = vec![
+            (Some("Basic YWxpY2U6c2VjcmV0"), true),
+            (Some("Basic YWxpY2U6d3Jvbmc="), false),
+            (Some("Bearer token"), true),
+            (Some("bearer token"), true),
+            (Some("Bearer wrong"), false),
+            (Some("Digest token"), false),
+            (None, false),
+        ];
+
+        for (authorization, expected) in test_cases {
+            let mut header = HeaderMap::new();
+            if let Some(authorization) = authorization {
+                header.insert(PROXY_AUTHORIZATION, authorization.parse().unwrap());
+            }
+
+            let result = access_controller.admit(remote_ip, &mut header, true);
+            assert!(!header.contains_key(PROXY_AUTHORIZATION));
+            if expected {
+                assert!(result.is_ok());
+            } else {
+                assert_eq!(result, Err(Rejection::Unauthorized));
+            }
+        }
+
+        // The authenticated client is not authenticated again.
+        assert!(access_controller
+            .admit(remote_ip, &mut HeaderMap::new(), false)
+            .is_ok());
+    }
+
+    #[test]
+    fn should_admit_by_basic_auth() {
+        let access_controller = AccessController::new(&ProxyServer {
+            basic_auth: Some(BasicAuth {
+                username: "alice".to_string(),
+                password: "secret".to_string(),
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let remote_ip = "127.0.0.1".parse().unwrap();
+        let test_cases = vec![
+            (Some("Basic YWxpY2U6c2VjcmV0"), true),
+            (Some("Basic YWxpY2U6d3Jvbmc="), false),
+            (Some("Bearer secret"), false),
+            (None, false),
+        ];
+
+        for (authorization, expected) in test_cases {
+            let mut header = HeaderMap::new();
+            if let Some(authorization) = authorization {
+                header.insert(PROXY_AUTHORIZATION, authorization.parse().unwrap());
+            }
+
+            let result = access_controller.admit(remote_ip, &mut header, true);
+            assert!(!header.contains_key(PROXY_AUTHORIZATION));
+            if expected {
+                assert!(result.is_ok());
+            } else {
+                assert_eq!(result, Err(Rejection::Unauthorized));
+            }
+        }
+
+        // The Authorization header is not used to authenticate the client.
+        let mut header = HeaderMap::new();
+        header.insert(
+            http::header::AUTHORIZATION,
+            "Basic YWxpY2U6c2VjcmV0".parse().unwrap(),
+        );
+        assert_eq!(
+            access_controller.admit(remote_ip, &mut header, true),
+            Err(Rejection::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn should_admit_by_request_rate() {
+        let access_controller = AccessController::new(&ProxyServer {
+            rate_limit: ProxyRateLimit {
+                request_rate: Some(1),
+                request_burst: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let alice = "10.0.0.1".parse().unwrap();
+        let bob = "10.0.0.2".parse().unwrap();
+        assert!(access_controller
+            .admit(alice, &mut HeaderMap::new(), true)
+            .is_ok());
+        assert!(access_controller
+            .admit(alice, &mut HeaderMap::new(), true)
+            .is_ok());
+
+        let rejection = access_controller
+            .admit(alice, &mut HeaderMap::new(), true)
+            .unwrap_err();
+        assert_eq!(rejection, Rejection::RateLimited(Duration::from_secs(1)));
+        assert_eq!(rejection.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rejection.header().get(RETRY_AFTER).unwrap(), "1");
+
+        // The rate limit is applied to each client.
+        assert!(access_controller
+            .admit(bob, &mut HeaderMap::new(), true)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_throttle_by_bandwidth() {
+        let access_controller = AccessController::new(&ProxyServer {
+            rate_limit: ProxyRateLimit {
+                bandwidth: Some(ByteSize::b(8)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let response = Response::new(
+            Full::new(Bytes::from_static(b"dragonfly"))
+                .map_err(ClientError::from)
+                .boxed(),
+        );
+
+        // The body exceeds the initial bytes of the bandwidth, so it is throttled until the
+        // bytes are refilled.
+        let started_at = std::time::Instant::now();
+        let body = access_controller
+            .throttle("127.0.0.1".parse().unwrap(), response)
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        assert_eq!(body, Bytes::from_static(b"dragonfly"));
+        assert!(started_at.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn should_make_rejection_header() {
+        assert_eq!(
+            Rejection::Unauthorized
+                .header()
+                .get(PROXY_AUTHENTICATE)
+                .unwrap(),
+            PROXY_AUTHENTICATE_CHALLENGE
+        );
+        assert_eq!(Rejection::Denied.status(), StatusCode::FORBIDDEN);
+        assert_eq!(Rejection::Unauthorized.reason(), "unauthorized");
+    }
+}
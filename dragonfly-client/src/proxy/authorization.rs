@@ -0,0 +1,337 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_config::dfdaemon::{OriginAuthorization, UpstreamProxy};
+use dragonfly_client_core::Result as ClientResult;
+use dragonfly_client_util::digest::is_blob_url;
+use http::header::{HeaderMap, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use http::StatusCode;
+use lru::LruCache;
+use rustls_pki_types::CertificateDer;
+use sha2::{Digest, Sha256};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{debug, error, info, instrument, warn};
+
+/// Denied is the status code and the header of the origin response which denies the request.
+pub type Denied = (StatusCode, HeaderMap);
+
+/// Decision is the cached authorization decision of the credential.
+#[derive(Debug, Clone)]
+struct Decision {
+    /// denied is the origin response if the request is denied, None means the request is
+    /// authorized.
+    denied: Option<Denied>,
+
+    /// expired_at is the time when the decision is expired.
+    expired_at: Instant,
+}
+
+/// OriginAuthorizer authorizes the request of the blob against the origin, because the task of
+/// the blob based on the blob digest can be cached by another client.
+pub struct OriginAuthorizer {
+    /// config is the origin authorization configuration.
+    config: OriginAuthorization,
+
+    /// client is the http client to send the authorization request to the origin, the
+    /// certificate of the origin is verified by the system root certificates.
+    client: reqwest::Client,
+
+    /// proxy is the upstream proxy to send the authorization request.
+    proxy: Option<reqwest::Proxy>,
+
+    /// decisions are the cached authorization decisions by the url and the credential.
+    decisions: Mutex<LruCache<String, Decision>>,
+}
+
+/// OriginAuthorizer implements the origin authorizer.
+impl OriginAuthorizer {
+    /// new creates a new origin authorizer, the authorization request is sent via the upstream
+    /// proxy if configured.
+    pub fn new(
+        config: &OriginAuthorization,
+        upstream_proxy: Option<&UpstreamProxy>,
+    ) -> ClientResult<Self> {
+        let proxy = upstream_proxy
+            .map(|upstream_proxy| upstream_proxy.reqwest_proxy())
+            .transpose()?;
+
+        Ok(Self {
+            config: config.clone(),
+            client: make_client(proxy.clone(), None)?,
+            proxy,
+            decisions: Mutex::new(LruCache::new(
+                NonZeroUsize::new(config.capacity).unwrap_or(NonZeroUsize::MIN),
+            )),
+        })
+    }
+
+    /// authorize authorizes the request of the blob by the HEAD request with the Authorization
+    /// header of the client, and returns the origin response if the request is denied. The
+    /// request is denied if the origin responds the client error, and it is also denied by
+    /// the bad gateway or the service unavailable if the answer of the origin is unknown,
+    /// the decision is not cached if the origin is unavailable.
+    #[instrument(skip_all)]
+    pub async fn authorize(
+        &self,
+        url: &str,
+        header: &HeaderMap,
+        registry_cert: &Option<Vec<CertificateDer<'static>>>,
+    ) -> Option<Denied> {
+        if !self.config.enable || !is_blob_url(url) {
+            return None;
+        }
+
+        let authorization = header.get(AUTHORIZATION);
+        let key = make_decision_key(url, authorization);
+        if let Some(decision) = self.get(&key) {
+            debug!("authorization decision of {} is cached", url);
+            return decision.denied;
+        }
+
+        let client = match self.client(registry_cert) {
+            Ok(client) => client,
+            Err(err) => {
+                error!("make authorization client of {} failed: {}", url, err);
+                return Some((StatusCode::BAD_GATEWAY, HeaderMap::new()));
+            }
+        };
+
+        let mut request = client.head(url).timeout(self.config.timeout);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization.clone());
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => {
+                warn!("authorize request of {} failed: {}", url, err);
+                return Some((StatusCode::BAD_GATEWAY, HeaderMap::new()));
+            }
+        };
+
+        let status = response.status();
+        if status.is_server_error() {
+            warn!("authorize request of {} failed: {}", url, status);
+            return Some((StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new()));
+        }
+
+        let denied = status.is_client_error().then(|| {
+            info!("request of {} is denied by origin: {}", url, status);
+            let mut denied_header = HeaderMap::new();
+            for value in response.headers().get_all(WWW_AUTHENTICATE) {
+                denied_header.append(WWW_AUTHENTICATE, value.clone());
+            }
+
+            (status, denied_header)
+        });
+
+        self.insert(key, denied.clone());
+        denied
+    }
+
+    /// client returns the http client to send the authorization request, the certificate of
+    /// the origin is also verified by the registry certificate if it is set.
+    fn client(
+        &self,
+        registry_cert: &Option<Vec<CertificateDer<'static>>>,
+    ) -> ClientResult<reqwest::Client> {
+        match registry_cert {
+            Some(registry_cert) => make_client(self.proxy.clone(), Some(registry_cert)),
+            None => Ok(self.client.clone()),
+        }
+    }
+
+    /// get gets the unexpired decision from the cache.
+    fn get(&self, key: &str) -> Option<Decision> {
+        let mut decisions = self.decisions.lock().unwrap();
+        match decisions.get(key) {
+            Some(decision) if decision.expired_at > Instant::now() => Some(decision.clone()),
+            Some(_) => {
+                decisions.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// insert inserts the decision into the cache with the ttl.
+    fn insert(&self, key: String, denied: Option<Denied>) {
+        self.decisions.lock().unwrap().put(
+            key,
+            Decision {
+                denied,
+                expired_at: Instant::now() + self.config.ttl,
+            },
+        );
+    }
+}
+
+/// make_client makes the http client to send the authorization request. The certificate of
+/// the origin is verified by the system root certificates and the registry certificate. The
+/// redirect is not followed, because the redirected url may be signed by the GET method and
+/// deny the HEAD request.
+fn make_client(
+    proxy: Option<reqwest::Proxy>,
+    registry_cert: Option<&[CertificateDer<'static>]>,
+) -> ClientResult<reqwest::Client> {
+    let mut client_builder = reqwest::Client::builder().use_rustls_tls();
+    if let Some(proxy) = proxy {
+        client_builder = client_builder.proxy(proxy);
+    }
+
+    for cert in registry_cert.unwrap_or_default() {
+        client_builder = client_builder.add_root_certificate(reqwest::Certificate::from_der(cert)?);
+    }
+
+    Ok(client_builder
+        .hickory_dns(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?)
+}
+
+/// make_decision_key makes the key of the decision by the url and the credential, the
+/// credential is hashed to avoid keeping it in memory.
+fn make_decision_key(url: &str, authorization: Option<&HeaderValue>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    hasher.update(b"\n");
+    if let Some(authorization) = authorization {
+        hasher.update(authorization.as_bytes());
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const BLOB_URL: &str = "https://registry.example.com/v2/library/alpine/blobs/sha256:a3ed95caeb02ffe68cdd9fd84406680ae93d633cb16422d00e8a7c22955b46d4";
+
+    fn new_origin_authorizer(ttl: Duration) -> OriginAuthorizer {
+        OriginAuthorizer::new(
+            &OriginAuthorization {
+                enable: true,
+                ttl,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn should_make_decision_key_by_credential() {
+        let alice = HeaderValue::from_static("Bearer alice");
+        let bob = HeaderValue::from_static("Bearer bob");
+        assert_eq!(
+            make_decision_key(BLOB_URL, Some(&alice)),
+            make_decision_key(BLOB_URL, Some(&alice))
+        );
+        assert_ne!(
+            make_decision_key(BLOB_URL, Some(&alice)),
+            make_decision_key(BLOB_URL, Some(&bob))
+        );
+        assert_ne!(
+            make_decision_key(BLOB_URL, Some(&alice)),
+            make_decision_key(BLOB_URL, None)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_authorize_by_cached_decision() {
+        let origin_authorizer = new_origin_authorizer(Duration::from_secs(60));
+        let mut header = HeaderMap::new();
+        header.insert(AUTHORIZATION, HeaderValue::from_static("Bearer alice"));
+
+        origin_authorizer.insert(
+            make_decision_key(BLOB_URL, header.get(AUTHORIZATION)),
+            Some((StatusCode::UNAUTHORIZED, HeaderMap::new())),
+        );
+        let (status, _) = origin_authorizer
+            .authorize(BLOB_URL, &header, &None)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // The request which is not the blob is not authorized against the origin.
+        assert!(origin_authorizer
+            .authorize("https://registry.example.com/v2/", &header, &None)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn should_deny_if_origin_is_unavailable() {
+        let origin_authorizer = new_origin_authorizer(Duration::from_secs(60));
+
+        // The origin is unreachable, the request is denied by the bad gateway.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let url = format!(
+            "http://{}/v2/library/alpine/blobs/sha256:a3ed95caeb02ffe68cdd9fd84406680ae93d633cb16422d00e8a7c22955b46d4",
+            addr
+        );
+        let (status, _) = origin_authorizer
+            .authorize(&url, &HeaderMap::new(), &None)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert!(origin_authorizer
+            .get(&make_decision_key(&url, None))
+            .is_none());
+
+        // The origin responds the server error, the request is denied by the service
+        // unavailable.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let url = format!(
+            "http://{}/v2/library/alpine/blobs/sha256:a3ed95caeb02ffe68cdd9fd84406680ae93d633cb16422d00e8a7c22955b46d4",
+            addr
+        );
+        let (status, _) = origin_authorizer
+            .authorize(&url, &HeaderMap::new(), &None)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(origin_authorizer
+            .get(&make_decision_key(&url, None))
+            .is_none());
+    }
+
+    #[test]
+    fn should_expire_cached_decision() {
+        let origin_authorizer = new_origin_authorizer(Duration::ZERO);
+        let key = make_decision_key(BLOB_URL, None);
+        origin_authorizer.insert(key.clone(), None);
+        assert!(origin_authorizer.get(&key).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use http::header::{
+    HeaderMap, HeaderName, CACHE_CONTROL, CONTENT_LOCATION, DATE, ETAG, EXPIRES, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, LAST_MODIFIED, VARY,
+};
+
+/// NOT_MODIFIED_HEADERS are the headers of the cached response sent in the 304 response,
+/// refer to https://www.rfc-editor.org/rfc/rfc9110#section-15.4.5.
+static NOT_MODIFIED_HEADERS: [HeaderName; 7] = [
+    CACHE_CONTROL,
+    CONTENT_LOCATION,
+    DATE,
+    ETAG,
+    EXPIRES,
+    LAST_MODIFIED,
+    VARY,
+];
+
+/// is_conditional returns whether the request has the conditional headers, which can be
+/// evaluated by the cached response.
+pub fn is_conditional(request_header: &HeaderMap) -> bool {
+    request_header.contains_key(IF_NONE_MATCH) || request_header.contains_key(IF_MODIFIED_SINCE)
+}
+
+/// is_not_modified evaluates the If-None-Match and If-Modified-Since preconditions of the
+/// request by the cached response header, and returns whether the 304 response should be
+/// returned, refer to https://www.rfc-editor.org/rfc/rfc9110#section-13.2.2.
+pub fn is_not_modified(request_header: &HeaderMap, response_header: &HeaderMap) -> bool {
+    // If-None-Match takes precedence over If-Modified-Since, the If-Modified-Since is ignored
+    // if the If-None-Match is present.
+    if let Some(if_none_match) = request_header.get(IF_NONE_MATCH) {
+        let Ok(if_none_match) = if_none_match.to_str() else {
+            return false;
+        };
+
+        // The "*" matches any current representation of the cached task.
+        if if_none_match.trim() == "*" {
+            return true;
+        }
+
+        let Some(etag) = response_header
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+        else {
+            return false;
+        };
+
+        return if_none_match.split(',').any(|tag| weak_eq(tag, etag));
+    }
+
+    if let Some(if_modified_since) = request_header.get(IF_MODIFIED_SINCE) {
+        let Some(if_modified_since) = if_modified_since
+            .to_str()
+            .ok()
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+        else {
+            return false;
+        };
+
+        let Some(last_modified) = response_header
+            .get(LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+        else {
+            return false;
+        };
+
+        return last_modified <= if_modified_since;
+    }
+
+    false
+}
+
+/// make_not_modified_header makes the header of the 304 response by the cached response
+/// header.
+pub fn make_not_modified_header(response_header: &HeaderMap) -> HeaderMap {
+    let mut header = HeaderMap::new();
+    for name in NOT_MODIFIED_HEADERS.iter() {
+        for value in response_header.get_all(name) {
+            header.append(name.clone(), value.clone());
+        }
+    }
+
+    header
+}
+
+/// weak_eq compares the entity tags by the weak comparison, refer to
+/// https://www.rfc-editor.org/rfc/rfc9110#section-8.8.3.2.
+fn weak_eq(a: &str, b: &str) -> bool {
+    let opaque_tag = |tag: &str| -> String { tag.trim().trim_start_matches("W/").to_string() };
+    opaque_tag(a) == opaque_tag(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::CONTENT_LENGTH;
+
+    fn response_header() -> HeaderMap {
+        let mut header = HeaderMap::new();
+        header.insert(ETAG, "\"v1\"".parse().unwrap());
+        header.insert(
+            LAST_MODIFIED,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        header.insert(CONTENT_LENGTH, "1024".parse().unwrap());
+        header
+    }
+
+    #[test]
+    fn should_evaluate_if_none_match() {
+        let test_cases = vec![
+            ("\"v1\"", true),
+            ("W/\"v1\"", true),
+            ("\"v0\", \"v1\"", true),
+            ("*", true),
+            ("\"v2\"", false),
+        ];
+
+        for (if_none_match, expected) in test_cases {
+            let mut request_header = HeaderMap::new();
+            request_header.insert(IF_NONE_MATCH, if_none_match.parse().unwrap());
+            assert!(is_conditional(&request_header));
+            assert_eq!(
+                is_not_modified(&request_header, &response_header()),
+                expected
+            );
+        }
+
+        // The cached response without the ETag can not be validated.
+        let mut request_header = HeaderMap::new();
+        request_header.insert(IF_NONE_MATCH, "\"v1\"".parse().unwrap());
+        assert!(!is_not_modified(&request_header, &HeaderMap::new()));
+    }
+
+    #[test]
+    fn should_evaluate_if_modified_since() {
+        let test_cases = vec![
+            ("Wed, 21 Oct 2015 07:28:00 GMT", true),
+            ("Thu, 22 Oct 2015 07:28:00 GMT", true),
+            ("Tue, 20 Oct 2015 07:28:00 GMT", false),
+            ("invalid", false),
+        ];
+
+        for (if_modified_since, expected) in test_cases {
+            let mut request_header = HeaderMap::new();
+            request_header.insert(IF_MODIFIED_SINCE, if_modified_since.parse().unwrap());
+            assert!(is_conditional(&request_header));
+            assert_eq!(
+                is_not_modified(&request_header, &response_header()),
+                expected
+            );
+        }
+
+        // If-Modified-Since is ignored if the If-None-Match is present.
+        let mut request_header = HeaderMap::new();
+        request_header.insert(IF_NONE_MATCH, "\"v2\"".parse().unwrap());
+        request_header.insert(
+            IF_MODIFIED_SINCE,
+            "Thu, 22 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert!(!is_not_modified(&request_header, &response_header()));
+
+        assert!(!is_conditional(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn should_make_not_modified_header() {
+        let header = make_not_modified_header(&response_header());
+        assert_eq!(header.get(ETAG).unwrap(), "\"v1\"");
+        assert_eq!(
+            header.get(LAST_MODIFIED).unwrap(),
+            "Wed, 21 Oct 2015 07:28:00 GMT"
+        );
+        assert!(header.get(CONTENT_LENGTH).is_none());
+    }
+}
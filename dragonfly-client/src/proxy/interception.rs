@@ -0,0 +1,369 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_config::dfdaemon::TLSInterception;
+use dragonfly_client_core::Result as ClientResult;
+use dragonfly_client_metric::collect_proxy_tls_cert_metrics;
+use dragonfly_client_util::tls::{
+    generate_self_signed_cert_pem, load_certs_from_pem, load_key_from_pem,
+};
+use lru::LruCache;
+use rcgen::Certificate;
+use rustls::server::Acceptor;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+use tracing::{debug, instrument, warn};
+
+/// MAX_CLIENT_HELLO_SIZE is the max size of the bytes read to parse the TLS ClientHello.
+const MAX_CLIENT_HELLO_SIZE: usize = 64 * 1024;
+
+/// SELF_SIGNED_CERT_CACHE_DIR is the name of the cache directory of the simple self-signed
+/// certificates, which are not signed by the CA certificate.
+const SELF_SIGNED_CERT_CACHE_DIR: &str = "self-signed";
+
+/// CertKeyPair is the type of the certificate and private key pair.
+type CertKeyPair = (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>);
+
+/// TLSInterceptor decides whether the HTTPS request tunneled by the CONNECT method is
+/// intercepted by the server name, and caches the generated leaf certificates in memory and
+/// on disk by the server name.
+pub struct TLSInterceptor {
+    /// config is the tls interception configuration.
+    config: TLSInterception,
+
+    /// ca_cert is the CA certificate to sign the leaf certificates, None means the leaf
+    /// certificates are simple self-signed certificates.
+    ca_cert: Option<Certificate>,
+
+    /// cert_cache_dir is the directory of the leaf certificates signed by the CA certificate,
+    /// which is separated by the fingerprint of the CA key, so the leaf certificates are not
+    /// reused after the CA certificate is changed.
+    cert_cache_dir: Option<PathBuf>,
+
+    /// certs are the leaf certificates cached in memory by the server name.
+    certs: Mutex<LruCache<String, CertKeyPair>>,
+}
+
+/// TLSInterceptor implements the tls interceptor.
+impl TLSInterceptor {
+    /// new creates a new tls interceptor.
+    pub fn new(config: &TLSInterception, ca_cert: Option<Certificate>) -> Self {
+        let cert_cache_dir = config.cert_cache_dir.as_ref().map(|dir| match &ca_cert {
+            Some(ca_cert) => dir.join(hex::encode(Sha256::digest(
+                ca_cert.get_key_pair().public_key_raw(),
+            ))),
+            None => dir.join(SELF_SIGNED_CERT_CACHE_DIR),
+        });
+
+        Self {
+            config: config.clone(),
+            ca_cert,
+            cert_cache_dir,
+            certs: Mutex::new(LruCache::new(
+                NonZeroUsize::new(config.cert_cache_capacity).unwrap_or(NonZeroUsize::MIN),
+            )),
+        }
+    }
+
+    /// is_intercepted returns whether the HTTPS request of the server name is intercepted.
+    pub fn is_intercepted(&self, server_name: &str) -> bool {
+        self.config.is_intercepted(server_name)
+    }
+
+    /// certs returns the leaf certificate and key of the server name. The leaf certificate is
+    /// loaded from the memory cache, then the disk cache, and it is generated if it is not
+    /// cached.
+    #[instrument(skip_all)]
+    pub async fn certs(&self, server_name: &str) -> ClientResult<CertKeyPair> {
+        if let Some((certs, key)) = self.certs.lock().unwrap().get(server_name) {
+            collect_proxy_tls_cert_metrics("memory");
+            return Ok((certs.clone(), key.clone_key()));
+        }
+
+        let cert_path = self.cert_path(server_name);
+        if let Some(cert_path) = cert_path.as_ref() {
+            match fs::read_to_string(cert_path).await {
+                Ok(pem) => match parse_cert_pem(&pem) {
+                    Ok((certs, key)) => {
+                        debug!("load leaf certificate of {} from disk", server_name);
+                        collect_proxy_tls_cert_metrics("disk");
+                        self.insert(server_name, &certs, &key);
+                        return Ok((certs, key));
+                    }
+                    Err(err) => warn!("invalid leaf certificate {:?}: {}", cert_path, err),
+                },
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => warn!("read leaf certificate {:?} failed: {}", cert_path, err),
+            }
+        }
+
+        debug!("generate leaf certificate of {}", server_name);
+        let (cert_pem, key_pem) =
+            generate_self_signed_cert_pem(self.ca_cert.as_ref(), vec![server_name.to_string()])?;
+        collect_proxy_tls_cert_metrics("generated");
+
+        let pem = format!("{}{}", cert_pem, key_pem);
+        let (certs, key) = parse_cert_pem(&pem)?;
+        self.insert(server_name, &certs, &key);
+
+        if let Some(cert_path) = cert_path.as_ref() {
+            if let Err(err) = persist(cert_path, &pem).await {
+                warn!("persist leaf certificate {:?} failed: {}", cert_path, err);
+            }
+        }
+
+        Ok((certs, key))
+    }
+
+    /// insert inserts the leaf certificate into the memory cache.
+    fn insert(
+        &self,
+        server_name: &str,
+        certs: &[CertificateDer<'static>],
+        key: &PrivateKeyDer<'static>,
+    ) {
+        self.certs
+            .lock()
+            .unwrap()
+            .put(server_name.to_string(), (certs.to_vec(), key.clone_key()));
+    }
+
+    /// cert_path returns the path of the leaf certificate on disk by the server name, the file
+    /// name is hashed to avoid the invalid characters of the server name.
+    fn cert_path(&self, server_name: &str) -> Option<PathBuf> {
+        self.cert_cache_dir.as_ref().map(|dir| {
+            dir.join(format!(
+                "{}.pem",
+                hex::encode(Sha256::digest(server_name.to_ascii_lowercase().as_bytes()))
+            ))
+        })
+    }
+}
+
+/// parse_cert_pem parses the leaf certificate and key from the PEM content.
+fn parse_cert_pem(pem: &str) -> ClientResult<CertKeyPair> {
+    Ok((load_certs_from_pem(pem)?, load_key_from_pem(pem)?))
+}
+
+/// persist writes the leaf certificate and key to the file, the file is only readable by the
+/// owner because it contains the private key. The content is written to the unique temporary
+/// file in the same directory and renamed, so the partial file is never read and the concurrent
+/// writers do not share the temporary file.
+async fn persist(path: &Path, pem: &str) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    let pem = pem.to_string();
+    tokio::task::spawn_blocking(move || {
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        std::fs::create_dir_all(dir)?;
+
+        // The temporary file is created with the mode 0600.
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        file.write_all(pem.as_bytes())?;
+        file.as_file().sync_all()?;
+        file.persist(&path).map_err(|err| err.error)?;
+        Ok(())
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// read_client_hello reads the TLS ClientHello from the reader, and returns the server name
+/// by the SNI and the bytes read. The server name is None if the SNI is absent or the bytes
+/// are not the TLS ClientHello, and the bytes should be replayed to the TLS acceptor or the
+/// origin.
+#[instrument(skip_all)]
+pub async fn read_client_hello<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> ClientResult<(Option<String>, Vec<u8>)> {
+    let mut acceptor = Acceptor::default();
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0; 4096];
+    loop {
+        let n = reader.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok((None, buffer));
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+
+        let mut data = &chunk[..n];
+        while !data.is_empty() {
+            if let Err(err) = acceptor.read_tls(&mut data) {
+                debug!("read TLS ClientHello failed: {}", err);
+                return Ok((None, buffer));
+            }
+        }
+
+        match acceptor.accept() {
+            Ok(Some(accepted)) => {
+                let server_name = accepted.client_hello().server_name().map(str::to_string);
+                return Ok((server_name, buffer));
+            }
+            Ok(None) if buffer.len() < MAX_CLIENT_HELLO_SIZE => {}
+            Ok(None) => return Ok((None, buffer)),
+            Err((err, _)) => {
+                debug!("parse TLS ClientHello failed: {}", err);
+                return Ok((None, buffer));
+            }
+        }
+    }
+}
+
+/// Rewind replays the prefix bytes before reading from the inner stream, it is used to
+/// replay the TLS ClientHello read by read_client_hello.
+pub struct Rewind<T> {
+    /// prefix is the bytes to be replayed.
+    prefix: Vec<u8>,
+
+    /// position is the position of the replayed bytes in the prefix.
+    position: usize,
+
+    /// inner is the inner stream.
+    inner: T,
+}
+
+/// Rewind implements the rewind.
+impl<T> Rewind<T> {
+    /// new creates a new rewind.
+    pub fn new(prefix: Vec<u8>, inner: T) -> Self {
+        Self {
+            prefix,
+            position: 0,
+            inner,
+        }
+    }
+}
+
+/// Rewind implements AsyncRead.
+impl<T: AsyncRead + Unpin> AsyncRead for Rewind<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.position < self.prefix.len() {
+            let n = buf.remaining().min(self.prefix.len() - self.position);
+            let position = self.position;
+            buf.put_slice(&self.prefix[position..position + n]);
+            self.position += n;
+            return Poll::Ready(Ok(()));
+        }
+
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+/// Rewind implements AsyncWrite.
+impl<T: AsyncWrite + Unpin> AsyncWrite for Rewind<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dragonfly_client_util::tls::NoVerifier;
+    use regex::Regex;
+    use std::sync::Arc;
+
+    fn make_client_hello(server_name: &str) -> Vec<u8> {
+        let config = rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(NoVerifier::new())
+            .with_no_client_auth();
+        let mut connection = rustls::ClientConnection::new(
+            Arc::new(config),
+            server_name.to_string().try_into().unwrap(),
+        )
+        .unwrap();
+
+        let mut client_hello = Vec::new();
+        connection.write_tls(&mut client_hello).unwrap();
+        client_hello
+    }
+
+    #[tokio::test]
+    async fn should_read_client_hello() {
+        let client_hello = make_client_hello("registry.example.com");
+        let (server_name, buffer) = read_client_hello(&mut client_hello.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(server_name, Some("registry.example.com".to_string()));
+        assert_eq!(buffer, client_hello);
+
+        // The bytes which are not the TLS ClientHello are returned to be replayed.
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec();
+        let (server_name, buffer) = read_client_hello(&mut request.as_slice()).await.unwrap();
+        assert!(server_name.is_none());
+        assert_eq!(buffer, request);
+    }
+
+    #[tokio::test]
+    async fn should_replay_prefix_by_rewind() {
+        let mut rewind = Rewind::new(b"hello ".to_vec(), b"world".as_slice());
+        let mut content = String::new();
+        rewind.read_to_string(&mut content).await.unwrap();
+        assert_eq!(content, "hello world");
+    }
+
+    #[tokio::test]
+    async fn should_cache_certs_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TLSInterception {
+            enable: true,
+            hosts: vec![Regex::new(r"^registry\.example\.com$").unwrap()],
+            cert_cache_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let tls_interceptor = TLSInterceptor::new(&config, None);
+        assert!(tls_interceptor.is_intercepted("registry.example.com"));
+        assert!(!tls_interceptor.is_intercepted("example.com"));
+
+        let (certs, _) = tls_interceptor.certs("registry.example.com").await.unwrap();
+        let (cached_certs, _) = tls_interceptor.certs("registry.example.com").await.unwrap();
+        assert_eq!(certs, cached_certs);
+
+        // The leaf certificate is loaded from disk by the new tls interceptor.
+        let cert_path = tls_interceptor.cert_path("registry.example.com").unwrap();
+        assert!(cert_path.starts_with(dir.path().join(SELF_SIGNED_CERT_CACHE_DIR)));
+        assert!(cert_path.exists());
+
+        let tls_interceptor = TLSInterceptor::new(&config, None);
+        let (persisted_certs, _) = tls_interceptor.certs("registry.example.com").await.unwrap();
+        assert_eq!(certs, persisted_certs);
+    }
+}
@@ -16,23 +16,29 @@
 
 use crate::grpc::{dfdaemon_download::DfdaemonDownloadClient, REQUEST_TIMEOUT};
 use crate::resource::{piece::MIN_PIECE_LENGTH, task::Task};
+use access::{AccessController, Rejection};
+use authorization::OriginAuthorizer;
 use bytes::Bytes;
 use dragonfly_api::common::v2::{Download, TaskType};
 use dragonfly_api::dfdaemon::v2::{
     download_task_response, DownloadTaskRequest, DownloadTaskStartedResponse,
 };
 use dragonfly_api::errordetails::v2::Backend;
-use dragonfly_client_config::dfdaemon::{Config, Rule};
+use dragonfly_client_backend::{StatRequest, StatResponse};
+use dragonfly_client_config::dfdaemon::{Config, DigestPolicy, Rule};
 use dragonfly_client_core::error::{ErrorType, OrErr};
 use dragonfly_client_core::{Error as ClientError, Result as ClientResult};
 use dragonfly_client_metric::{
-    collect_proxy_request_failure_metrics, collect_proxy_request_started_metrics,
-    collect_proxy_request_via_dfdaemon_metrics,
+    collect_proxy_request_failure_metrics, collect_proxy_request_rejected_metrics,
+    collect_proxy_request_started_metrics, collect_proxy_request_via_dfdaemon_metrics,
+    collect_proxy_tls_interception_metrics,
 };
+use dragonfly_client_storage::metadata;
 use dragonfly_client_util::{
-    http::{hashmap_to_headermap, headermap_to_hashmap},
+    digest::{Digest, Hasher},
+    http::{hashmap_to_headermap, headermap_to_hashmap, parse_ranges_header},
     shutdown,
-    tls::{generate_self_signed_certs_by_ca_cert, generate_simple_self_signed_certs, NoVerifier},
+    tls::NoVerifier,
 };
 use futures::TryStreamExt;
 use http_body_util::{combinators::BoxBody, BodyExt, Empty, StreamBody};
@@ -46,31 +52,48 @@ use hyper_util::{
     client::legacy::Client,
     rt::{tokio::TokioIo, TokioExecutor},
 };
+use interception::{read_client_hello, Rewind, TLSInterceptor};
 use lazy_static::lazy_static;
-use rcgen::Certificate;
+use range::{Multipart, RangeRequest};
+use registry_mirror::{Mirror, RegistryMirrors};
 use rustls::{RootCertStore, ServerConfig};
 use rustls_pki_types::CertificateDer;
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use tokio::io::{AsyncWriteExt, BufReader, BufWriter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
 use tokio::net::TcpListener;
-use tokio::net::TcpStream;
 use tokio::sync::{mpsc, Barrier};
 use tokio_rustls::TlsAcceptor;
 use tokio_util::io::ReaderStream;
-use tracing::{debug, error, info, instrument, Instrument, Span};
+use tracing::{debug, error, info, instrument, warn, Instrument, Span};
+use upstream::UpstreamConnector;
 
+pub mod access;
+pub mod authorization;
+pub mod conditional;
 pub mod header;
+pub mod interception;
+pub mod range;
+pub mod registry_mirror;
+pub mod upstream;
 
 lazy_static! {
   /// SUPPORTED_HTTP_PROTOCOLS is the supported HTTP protocols, including http/1.1 and http/1.0.
   static ref SUPPORTED_HTTP_PROTOCOLS: Vec<Vec<u8>> = vec![b"http/1.1".to_vec(), b"http/1.0".to_vec()];
+
+  /// SUPPORTED_HTTP2_PROTOCOLS is the supported HTTP protocols of the proxy server if HTTP/2 is
+  /// enabled, including h2, http/1.1 and http/1.0.
+  static ref SUPPORTED_HTTP2_PROTOCOLS: Vec<Vec<u8>> = vec![b"h2".to_vec(), b"http/1.1".to_vec(), b"http/1.0".to_vec()];
 }
 
 /// Response is the response of the proxy server.
 pub type Response = hyper::Response<BoxBody<Bytes, ClientError>>;
 
+/// Body is the body of the request handled by the proxy, the incoming body is boxed, so the
+/// request without the body can be rebuilt to retry the registry mirrors.
+pub type Body = BoxBody<Bytes, ClientError>;
+
 /// Proxy is the proxy server.
 pub struct Proxy {
     /// config is the configuration of the dfdaemon.
@@ -85,9 +108,22 @@ pub struct Proxy {
     /// registry_cert is the certificate of the client for the registry.
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
 
-    /// server_ca_cert is the CA certificate of the proxy server to
-    /// sign the self-signed certificate.
-    server_ca_cert: Arc<Option<Certificate>>,
+    /// origin_authorizer authorizes the request of the blob against the origin.
+    origin_authorizer: Arc<OriginAuthorizer>,
+
+    /// access_controller controls the access of the clients to the proxy.
+    access_controller: Arc<AccessController>,
+
+    /// registry_mirrors selects the registry mirror by the namespace of the request.
+    registry_mirrors: Arc<RegistryMirrors>,
+
+    /// tls_interceptor decides whether the HTTPS request is intercepted and signs the leaf
+    /// certificate of the intercepted server name.
+    tls_interceptor: Arc<TLSInterceptor>,
+
+    /// server_tls_acceptor accepts the TLS connection of the client, None means the proxy
+    /// server serves plaintext.
+    server_tls_acceptor: Option<TlsAcceptor>,
 
     /// shutdown is used to shutdown the proxy server.
     shutdown: shutdown::Shutdown,
@@ -104,48 +140,67 @@ impl Proxy {
         task: Arc<Task>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
-    ) -> Self {
+    ) -> ClientResult<Self> {
+        // Load and generate the registry certificates of the registry mirrors from the PEM
+        // format files.
+        let registry_mirrors = Arc::new(RegistryMirrors::new(&config.proxy));
+
+        // Generate the CA certificate and key from the PEM format files, the leaf certificates
+        // of the intercepted HTTPS requests are signed by the CA certificate.
+        let server_ca_cert = match config.proxy.server.load_cert() {
+            Ok(server_ca_cert) => {
+                info!("load proxy ca cert and key success");
+                server_ca_cert
+            }
+            Err(err) => {
+                error!("load proxy ca cert and key failed: {}", err);
+                None
+            }
+        };
+
         let mut proxy = Self {
             config: config.clone(),
             task: task.clone(),
             addr: SocketAddr::new(config.proxy.server.ip.unwrap(), config.proxy.server.port),
-            registry_cert: Arc::new(None),
-            server_ca_cert: Arc::new(None),
+            registry_cert: registry_mirrors.default_mirror().cert(),
+            origin_authorizer: Arc::new(OriginAuthorizer::new(
+                &config.proxy.origin_authorization,
+                config.network.upstream_proxy.as_ref(),
+            )?),
+            access_controller: Arc::new(AccessController::new(&config.proxy.server)?),
+            registry_mirrors,
+            tls_interceptor: Arc::new(TLSInterceptor::new(
+                &config.proxy.server.tls_interception,
+                server_ca_cert,
+            )),
+            server_tls_acceptor: None,
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
         };
 
-        // Load and generate the registry certificates from the PEM format file.
-        proxy.registry_cert = match config.proxy.registry_mirror.load_cert_der() {
-            Ok(registry_cert) => {
-                info!("load registry cert success");
-                Arc::new(registry_cert)
-            }
-            Err(err) => {
-                error!("load registry cert failed: {}", err);
-                Arc::new(None)
-            }
-        };
+        // Load the server certificate and key of the listener, and negotiate HTTP/2 by ALPN if
+        // HTTP/2 is enabled.
+        if let Some((server_certs, server_key)) = config.proxy.server.load_server_cert()? {
+            let mut server_config = ServerConfig::builder()
+                .with_no_client_auth()
+                .with_single_cert(server_certs, server_key)
+                .or_err(ErrorType::TLSConfigError)?;
+            server_config.alpn_protocols = if config.proxy.server.enable_http2 {
+                SUPPORTED_HTTP2_PROTOCOLS.clone()
+            } else {
+                SUPPORTED_HTTP_PROTOCOLS.clone()
+            };
 
-        // Generate the CA certificate and key from the PEM format files.
-        proxy.server_ca_cert = match config.proxy.server.load_cert() {
-            Ok(server_ca_cert) => {
-                info!("load proxy ca cert and key success");
-                Arc::new(server_ca_cert)
-            }
-            Err(err) => {
-                error!("load proxy ca cert and key failed: {}", err);
-                Arc::new(None)
-            }
-        };
+            info!("load proxy server cert and key success");
+            proxy.server_tls_acceptor = Some(TlsAcceptor::from(Arc::new(server_config)));
+        }
 
-        proxy
+        Ok(proxy)
     }
 
     /// run starts the proxy server.
     pub async fn run(&self, grpc_server_started_barrier: Arc<Barrier>) -> ClientResult<()> {
         let mut shutdown = self.shutdown.clone();
-        let read_buffer_size = self.config.proxy.read_buffer_size;
 
         // When the grpc server is started, notify the barrier. If the shutdown signal is received
         // before barrier is waited successfully, the server will shutdown immediately.
@@ -165,21 +220,15 @@ impl Proxy {
             DfdaemonDownloadClient::new_unix(self.config.download.server.socket_path.clone())
                 .await?;
 
-        #[derive(Clone)]
-        struct Context {
-            config: Arc<Config>,
-            task: Arc<Task>,
-            dfdaemon_download_client: DfdaemonDownloadClient,
-            registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
-            server_ca_cert: Arc<Option<Certificate>>,
-        }
-
         let context = Context {
             config: self.config.clone(),
             task: self.task.clone(),
             dfdaemon_download_client,
             registry_cert: self.registry_cert.clone(),
-            server_ca_cert: self.server_ca_cert.clone(),
+            origin_authorizer: self.origin_authorizer.clone(),
+            access_controller: self.access_controller.clone(),
+            registry_mirrors: self.registry_mirrors.clone(),
+            tls_interceptor: self.tls_interceptor.clone(),
         };
 
         let listener = TcpListener::bind(self.addr).await?;
@@ -191,30 +240,23 @@ impl Proxy {
                 tcp_accepted = listener.accept() => {
                     // A new client connection has been established.
                     let (tcp, remote_address) = tcp_accepted?;
+                    let local_address = tcp.local_addr()?;
 
                     // Spawn a task to handle the connection.
-                    let io = TokioIo::new(tcp);
                     debug!("accepted connection from {}", remote_address);
 
                     let context = context.clone();
+                    let server_tls_acceptor = self.server_tls_acceptor.clone();
                     tokio::task::spawn(async move {
-                        if let Err(err) = ServerBuilder::new()
-                            .keep_alive(true)
-                            .max_buf_size(read_buffer_size)
-                            .preserve_header_case(true)
-                            .title_case_headers(true)
-                            .serve_connection(
-                                io,
-                                service_fn(move |request|{
-                                    let context = context.clone();
-                                    async move {
-                                        handler(context.config, context.task, request, context.dfdaemon_download_client, context.registry_cert, context.server_ca_cert, remote_address.ip()).await
-                                    }
-                                } ),
-                                )
-                            .with_upgrades()
-                            .await
-                        {
+                        let result = match server_tls_acceptor {
+                            Some(server_tls_acceptor) => match server_tls_acceptor.accept(tcp).await {
+                                Ok(tls_stream) => serve_connection(context, TokioIo::new(tls_stream), remote_address, local_address).await,
+                                Err(err) => Err(err.into()),
+                            },
+                            None => serve_connection(context, TokioIo::new(tcp), remote_address, local_address).await,
+                        };
+
+                        if let Err(err) = result {
                             collect_proxy_request_failure_metrics();
                             error!("failed to serve connection from {}: {}", remote_address, err);
                         }
@@ -230,15 +272,135 @@ impl Proxy {
     }
 }
 
+/// Context is the context of the connection of the proxy server.
+#[derive(Clone)]
+struct Context {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
+    /// task is the task manager.
+    task: Arc<Task>,
+
+    /// dfdaemon_download_client is the client of the dfdaemon download server.
+    dfdaemon_download_client: DfdaemonDownloadClient,
+
+    /// registry_cert is the certificate of the client for the registry.
+    registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+
+    /// origin_authorizer authorizes the request of the blob against the origin.
+    origin_authorizer: Arc<OriginAuthorizer>,
+
+    /// access_controller controls the access of the clients to the proxy.
+    access_controller: Arc<AccessController>,
+
+    /// registry_mirrors selects the registry mirror by the namespace of the request.
+    registry_mirrors: Arc<RegistryMirrors>,
+
+    /// tls_interceptor decides whether the HTTPS request is intercepted and signs the leaf
+    /// certificate of the intercepted server name.
+    tls_interceptor: Arc<TLSInterceptor>,
+}
+
+/// serve_connection serves the connection of the client. If HTTP/2 is enabled, the HTTP
+/// version is detected by the connection preface, so the client can send HTTP/2 negotiated
+/// by ALPN or with prior knowledge (h2c).
+async fn serve_connection<I>(
+    context: Context,
+    io: I,
+    remote_address: SocketAddr,
+    local_address: SocketAddr,
+) -> ClientResult<()>
+where
+    I: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+{
+    let read_buffer_size = context.config.proxy.read_buffer_size;
+    let enable_http2 = context.config.proxy.server.enable_http2;
+    let service = service_fn(move |mut request| {
+        let context = context.clone();
+        async move {
+            // Admit the request by the access control, the authentication and the rate limit
+            // of the client.
+            let access_controller = context.access_controller.clone();
+            if let Err(rejection) =
+                access_controller.admit(remote_address.ip(), request.headers_mut(), true)
+            {
+                return Ok(make_rejected_response(rejection));
+            }
+
+            // The HTTP/2 request has the absolute uri by the :scheme and :authority pseudo
+            // headers, and it is converted to the HTTP/1.1 request to be forwarded. Same as the
+            // HTTP/1 request, it is the mirror request only if the authority is the proxy
+            // itself, then the uri is converted to the origin-form, otherwise the request is
+            // forwarded to the host of the absolute uri.
+            if request.version() == http::Version::HTTP_2 && Method::CONNECT != request.method() {
+                *request.version_mut() = http::Version::HTTP_11;
+                if is_proxy_authority(&context.config, request.uri(), local_address) {
+                    *request.uri_mut() = request
+                        .uri()
+                        .path_and_query()
+                        .map(|path_and_query| path_and_query.as_str())
+                        .unwrap_or("/")
+                        .parse::<http::Uri>()
+                        .or_err(ErrorType::ParseError)?;
+                }
+            }
+
+            handler(
+                context.config,
+                context.task,
+                request.map(|body| body.map_err(ClientError::from).boxed()),
+                context.dfdaemon_download_client,
+                context.registry_cert,
+                context.origin_authorizer,
+                context.access_controller,
+                context.registry_mirrors,
+                context.tls_interceptor,
+                remote_address.ip(),
+            )
+            .await
+            .map(|response| access_controller.throttle(remote_address.ip(), response))
+        }
+    });
+
+    if enable_http2 {
+        let mut builder = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new());
+        builder
+            .http1()
+            .keep_alive(true)
+            .max_buf_size(read_buffer_size)
+            .preserve_header_case(true)
+            .title_case_headers(true);
+
+        return builder
+            .serve_connection_with_upgrades(io, service)
+            .await
+            .map_err(|err| ClientError::Unknown(err.to_string()));
+    }
+
+    ServerBuilder::new()
+        .keep_alive(true)
+        .max_buf_size(read_buffer_size)
+        .preserve_header_case(true)
+        .title_case_headers(true)
+        .serve_connection(io, service)
+        .with_upgrades()
+        .await
+        .map_err(|err| ClientError::Unknown(err.to_string()))
+}
+
 /// handler handles the request from the client.
+#[allow(clippy::too_many_arguments)]
 #[instrument(skip_all, fields(url, method, remote_ip))]
 pub async fn handler(
     config: Arc<Config>,
     task: Arc<Task>,
-    request: Request<hyper::body::Incoming>,
+    mut request: Request<Body>,
     dfdaemon_download_client: DfdaemonDownloadClient,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
-    server_ca_cert: Arc<Option<Certificate>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
+    access_controller: Arc<AccessController>,
+    registry_mirrors: Arc<RegistryMirrors>,
+    tls_interceptor: Arc<TLSInterceptor>,
     remote_ip: std::net::IpAddr,
 ) -> ClientResult<Response> {
     // Span record the url and method.
@@ -261,7 +423,10 @@ pub async fn handler(
                 remote_ip,
                 dfdaemon_download_client,
                 registry_cert,
-                server_ca_cert,
+                origin_authorizer,
+                access_controller,
+                registry_mirrors,
+                tls_interceptor,
             )
             .await;
         }
@@ -273,6 +438,8 @@ pub async fn handler(
             remote_ip,
             dfdaemon_download_client,
             registry_cert,
+            origin_authorizer,
+            registry_mirrors,
         )
         .await;
     }
@@ -286,7 +453,10 @@ pub async fn handler(
             remote_ip,
             dfdaemon_download_client,
             registry_cert,
-            server_ca_cert,
+            origin_authorizer,
+            access_controller,
+            tls_interceptor,
+            None,
         )
         .await;
     }
@@ -298,54 +468,147 @@ pub async fn handler(
         remote_ip,
         dfdaemon_download_client,
         registry_cert,
+        origin_authorizer,
     )
     .await
 }
 
 /// registry_mirror_http_handler handles the http request for the registry mirror by client.
+/// The result of the request is reported to the selected registry mirror, so the unhealthy
+/// registry mirror is failed over by the following requests.
 #[instrument(skip_all)]
 pub async fn registry_mirror_http_handler(
     config: Arc<Config>,
     task: Arc<Task>,
-    request: Request<hyper::body::Incoming>,
+    request: Request<Body>,
     remote_ip: std::net::IpAddr,
     dfdaemon_download_client: DfdaemonDownloadClient,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
+    registry_mirrors: Arc<RegistryMirrors>,
 ) -> ClientResult<Response> {
-    let request = make_registry_mirror_request(config.clone(), request)?;
-    return http_handler(
-        config,
-        task,
-        request,
-        remote_ip,
-        dfdaemon_download_client,
-        registry_cert,
-    )
-    .await;
+    let (request, mirror) = make_registry_mirror_request(&registry_mirrors, request)?;
+    let Some(mirror) = mirror else {
+        return http_handler(
+            config,
+            task,
+            request,
+            remote_ip,
+            dfdaemon_download_client,
+            registry_cert,
+            origin_authorizer,
+        )
+        .await;
+    };
+
+    send_via_registry_mirrors(&registry_mirrors, mirror, request, |request, mirror| {
+        http_handler(
+            config.clone(),
+            task.clone(),
+            request,
+            remote_ip,
+            dfdaemon_download_client.clone(),
+            mirror.cert(),
+            origin_authorizer.clone(),
+        )
+    })
+    .await
 }
 
 /// registry_mirror_https_handler handles the https request for the registry mirror by client.
+/// The requests in the tunnel are reported to the registry mirrors, so the unhealthy registry
+/// mirror is failed over by the following requests.
+#[allow(clippy::too_many_arguments)]
 #[instrument(skip_all)]
 pub async fn registry_mirror_https_handler(
     config: Arc<Config>,
     task: Arc<Task>,
-    request: Request<hyper::body::Incoming>,
+    request: Request<Body>,
     remote_ip: std::net::IpAddr,
     dfdaemon_download_client: DfdaemonDownloadClient,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
-    server_ca_cert: Arc<Option<Certificate>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
+    access_controller: Arc<AccessController>,
+    registry_mirrors: Arc<RegistryMirrors>,
+    tls_interceptor: Arc<TLSInterceptor>,
 ) -> ClientResult<Response> {
-    let request = make_registry_mirror_request(config.clone(), request)?;
-    return https_handler(
+    let (request, mirror) = make_registry_mirror_request(&registry_mirrors, request)?;
+    let registry_cert = mirror
+        .as_ref()
+        .map(|mirror| mirror.cert())
+        .unwrap_or(registry_cert);
+    https_handler(
         config,
         task,
         request,
         remote_ip,
         dfdaemon_download_client,
         registry_cert,
-        server_ca_cert,
+        origin_authorizer,
+        access_controller,
+        tls_interceptor,
+        mirror.map(|mirror| (registry_mirrors, mirror)),
     )
-    .await;
+    .await
+}
+
+/// send_via_registry_mirrors sends the request to the registry mirror and reports the result
+/// to it. If the registry mirror fails by the request error or the server error, the request
+/// is retried by the next healthy registry mirror of the namespace. The request with the body
+/// is not retried, because the body has been consumed.
+async fn send_via_registry_mirrors<F, Fut>(
+    registry_mirrors: &RegistryMirrors,
+    mut mirror: Arc<Mirror>,
+    mut request: Request<Body>,
+    mut send: F,
+) -> ClientResult<Response>
+where
+    F: FnMut(Request<Body>, Arc<Mirror>) -> Fut,
+    Fut: std::future::Future<Output = ClientResult<Response>>,
+{
+    let namespace = registry_mirror::get_namespace(request.uri());
+    let mut tried = Vec::new();
+    loop {
+        let retry_request = clone_request_without_body(&request);
+        let result = send(request, mirror.clone()).await;
+        mirror.report(&result);
+        if !Mirror::is_failure(&result) {
+            return result;
+        }
+
+        tried.push(mirror.clone());
+        let (Some(mut retry_request), Some(next)) = (
+            retry_request,
+            registry_mirrors.select_next(namespace.as_deref(), &tried),
+        ) else {
+            return result;
+        };
+
+        warn!(
+            "registry mirror {} failed, retry by registry mirror {}",
+            mirror.addr(),
+            next.addr()
+        );
+        route_registry_mirror_request(&mut retry_request, next.addr(), Some(&next))?;
+        request = retry_request;
+        mirror = next;
+    }
+}
+
+/// clone_request_without_body clones the request if the request has no body, otherwise
+/// returns None.
+fn clone_request_without_body(request: &Request<Body>) -> Option<Request<Body>> {
+    if !hyper::body::Body::is_end_stream(request.body()) {
+        return None;
+    }
+
+    let mut cloned = Request::new(empty());
+    *cloned.method_mut() = request.method().clone();
+    *cloned.uri_mut() = request.uri().clone();
+    *cloned.version_mut() = request.version();
+    *cloned.headers_mut() = request.headers().clone();
+    *cloned.extensions_mut() = request.extensions().clone();
+    Some(cloned)
 }
 
 /// http_handler handles the http request by client.
@@ -353,40 +616,14 @@ pub async fn registry_mirror_https_handler(
 pub async fn http_handler(
     config: Arc<Config>,
     task: Arc<Task>,
-    request: Request<hyper::body::Incoming>,
+    request: Request<Body>,
     remote_ip: std::net::IpAddr,
     dfdaemon_download_client: DfdaemonDownloadClient,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
 ) -> ClientResult<Response> {
-    // Authenticate the request with the basic auth.
-    if let Some(basic_auth) = config.proxy.server.basic_auth.as_ref() {
-        match basic_auth.credentials().verify(request.headers()) {
-            Ok(_) => {}
-            Err(ClientError::Unauthorized) => {
-                error!("basic auth failed");
-                return Ok(make_error_response(
-                    header::ErrorType::Proxy,
-                    http::StatusCode::UNAUTHORIZED,
-                    None,
-                ));
-            }
-            Err(err) => {
-                error!("verify basic auth failed: {}", err);
-                return Ok(make_error_response(
-                    header::ErrorType::Proxy,
-                    http::StatusCode::BAD_REQUEST,
-                    None,
-                ));
-            }
-        }
-    }
-
     // If find the matching rule, proxy the request via the dfdaemon.
-    let request_uri = request.uri();
-    if let Some(rule) = find_matching_rule(
-        config.proxy.rules.as_deref(),
-        request_uri.to_string().as_str(),
-    ) {
+    if let Some(rule) = find_matching_rule(config.proxy.rules.as_deref(), &request) {
         info!(
             "proxy HTTP request via dfdaemon by rule config: {:?}",
             request
@@ -398,6 +635,8 @@ pub async fn http_handler(
             request,
             remote_ip,
             dfdaemon_download_client,
+            registry_cert,
+            origin_authorizer,
         )
         .await;
     }
@@ -416,6 +655,8 @@ pub async fn http_handler(
             request,
             remote_ip,
             dfdaemon_download_client,
+            registry_cert,
+            origin_authorizer,
         )
         .await;
     }
@@ -425,26 +666,30 @@ pub async fn http_handler(
             "proxy HTTPS request directly to remote server: {:?}",
             request
         );
-        return proxy_via_https(request, registry_cert).await;
+        return proxy_via_https(config, request, registry_cert).await;
     }
 
     info!(
         "proxy HTTP request directly to remote server: {:?}",
         request
     );
-    return proxy_via_http(request).await;
+    return proxy_via_http(config, request).await;
 }
 
 /// https_handler handles the https request by client.
+#[allow(clippy::too_many_arguments)]
 #[instrument(skip_all)]
 pub async fn https_handler(
     config: Arc<Config>,
     task: Arc<Task>,
-    request: Request<hyper::body::Incoming>,
+    request: Request<Body>,
     remote_ip: std::net::IpAddr,
     dfdaemon_download_client: DfdaemonDownloadClient,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
-    server_ca_cert: Arc<Option<Certificate>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
+    access_controller: Arc<AccessController>,
+    tls_interceptor: Arc<TLSInterceptor>,
+    registry_mirror: Option<(Arc<RegistryMirrors>, Arc<Mirror>)>,
 ) -> ClientResult<Response> {
     info!("handle HTTPS request: {:?}", request);
 
@@ -464,7 +709,10 @@ pub async fn https_handler(
                         remote_ip,
                         dfdaemon_download_client,
                         registry_cert,
-                        server_ca_cert,
+                        origin_authorizer,
+                        access_controller,
+                        tls_interceptor,
+                        registry_mirror,
                     )
                     .await
                     {
@@ -485,9 +733,11 @@ pub async fn https_handler(
     }
 }
 
-/// upgraded_tunnel handles the upgraded connection. If the ca_cert is not set, use the
-/// self-signed certificate. Otherwise, use the CA certificate to sign the
-/// self-signed certificate.
+/// upgraded_tunnel handles the upgraded connection. If the server name of the TLS ClientHello
+/// is intercepted, the TLS connection is terminated by the leaf certificate of the server name,
+/// otherwise the connection is tunneled to the origin blindly. If the connection is tunneled
+/// to the registry mirror, the results are reported to the registry mirror, and the
+/// intercepted requests are retried by the next healthy registry mirror if it fails.
 #[allow(clippy::too_many_arguments)]
 #[instrument(skip_all)]
 async fn upgraded_tunnel(
@@ -499,22 +749,51 @@ async fn upgraded_tunnel(
     remote_ip: std::net::IpAddr,
     dfdaemon_download_client: DfdaemonDownloadClient,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
-    server_ca_cert: Arc<Option<Certificate>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
+    access_controller: Arc<AccessController>,
+    tls_interceptor: Arc<TLSInterceptor>,
+    registry_mirror: Option<(Arc<RegistryMirrors>, Arc<Mirror>)>,
 ) -> ClientResult<()> {
-    // Generate the self-signed certificate by the given host. If the ca_cert
-    // is not set, use the self-signed certificate. Otherwise, use the CA
-    // certificate to sign the self-signed certificate.
-    let subject_alt_names = vec![host.to_string()];
-    let (server_certs, server_key) = match server_ca_cert.as_ref() {
-        Some(server_ca_cert) => {
-            info!("generate self-signed certificate by CA certificate");
-            generate_self_signed_certs_by_ca_cert(server_ca_cert, host.as_ref(), subject_alt_names)?
-        }
-        None => {
-            info!("generate simple self-signed certificate");
-            generate_simple_self_signed_certs(host.as_ref(), subject_alt_names)?
+    // Peek the server name by the SNI of the TLS ClientHello, the host of the CONNECT request
+    // is used if the SNI is absent.
+    let mut upgraded = TokioIo::new(upgraded);
+    let (server_name, client_hello) = read_client_hello(&mut upgraded).await?;
+    let server_name = server_name.unwrap_or_else(|| host.clone());
+
+    // The HTTPS request of the server name which is not intercepted is tunneled to the origin
+    // directly, and the TLS ClientHello is replayed to the origin. If the SNI differs from the
+    // host of the CONNECT request, the request is tunneled as well, otherwise the leaf
+    // certificate of the SNI is presented for the requests forwarded to the other host.
+    if !server_name.eq_ignore_ascii_case(&host) || !tls_interceptor.is_intercepted(&server_name) {
+        debug!(
+            "tunnel HTTPS request of {} to {}:{}",
+            server_name, host, port
+        );
+        collect_proxy_tls_interception_metrics("tunneled");
+
+        // The TLS connection is bound to the server name of the registry mirror, so the
+        // tunneled connection is only reported to the registry mirror and not retried.
+        let result = UpstreamConnector::new(config.network.upstream_proxy.clone())
+            .connect(&host, port)
+            .await;
+        if let Some((_, mirror)) = &registry_mirror {
+            match result {
+                Ok(_) => mirror.report_success(),
+                Err(_) => mirror.report_failure(),
+            }
         }
-    };
+
+        let mut origin = result?;
+        origin.write_all(&client_hello).await?;
+        tokio::io::copy_bidirectional(&mut upgraded, &mut origin).await?;
+        return Ok(());
+    }
+
+    // Sign the leaf certificate of the server name by the CA certificate, or generate the
+    // simple self-signed certificate if the CA certificate is not set.
+    debug!("intercept HTTPS request of {}", server_name);
+    collect_proxy_tls_interception_metrics("intercepted");
+    let (server_certs, server_key) = tls_interceptor.certs(&server_name).await?;
 
     // Build TLS configuration.
     let mut server_config = ServerConfig::builder()
@@ -524,7 +803,9 @@ async fn upgraded_tunnel(
     server_config.alpn_protocols = SUPPORTED_HTTP_PROTOCOLS.clone();
 
     let tls_acceptor = TlsAcceptor::from(Arc::new(server_config));
-    let tls_stream = tls_acceptor.accept(TokioIo::new(upgraded)).await?;
+    let tls_stream = tls_acceptor
+        .accept(Rewind::new(client_hello, upgraded))
+        .await?;
 
     // Serve the connection with the TLS stream.
     // Ensure the connection uses HTTP/1 to prevent version mismatch errors, such as:
@@ -538,17 +819,74 @@ async fn upgraded_tunnel(
         .http1_only()
         .serve_connection(
             TokioIo::new(tls_stream),
-            service_fn(move |request| {
-                upgraded_handler(
-                    config.clone(),
-                    task.clone(),
-                    host.clone(),
-                    port,
-                    request,
-                    remote_ip,
-                    dfdaemon_download_client.clone(),
-                    registry_cert.clone(),
-                )
+            service_fn(move |mut request| {
+                // Admit the request in the tunnel by the access control and the rate limit of
+                // the client, the client has been authenticated by the CONNECT request.
+                let access_controller = access_controller.clone();
+                let admitted = access_controller.admit(remote_ip, request.headers_mut(), false);
+                let request = request.map(|body| body.map_err(ClientError::from).boxed());
+                let config = config.clone();
+                let task = task.clone();
+                let host = host.clone();
+                let dfdaemon_download_client = dfdaemon_download_client.clone();
+                let registry_cert = registry_cert.clone();
+                let origin_authorizer = origin_authorizer.clone();
+                let registry_mirror = registry_mirror.clone();
+
+                async move {
+                    if let Err(rejection) = admitted {
+                        return Ok(make_rejected_response(rejection));
+                    }
+
+                    // Select the registry mirror by the namespace of the request in the
+                    // tunnel, so the request can be retried by the other registry mirrors.
+                    let (request, mirror) = match &registry_mirror {
+                        Some((registry_mirrors, _)) => {
+                            make_registry_mirror_request(registry_mirrors, request)?
+                        }
+                        None => (request, None),
+                    };
+
+                    let response = match (registry_mirror, mirror) {
+                        (Some((registry_mirrors, _)), Some(mirror)) => {
+                            send_via_registry_mirrors(
+                                &registry_mirrors,
+                                mirror,
+                                request,
+                                |request, mirror| {
+                                    upgraded_handler(
+                                        config.clone(),
+                                        task.clone(),
+                                        host.clone(),
+                                        port,
+                                        request,
+                                        remote_ip,
+                                        dfdaemon_download_client.clone(),
+                                        mirror.cert(),
+                                        origin_authorizer.clone(),
+                                    )
+                                },
+                            )
+                            .await
+                        }
+                        _ => {
+                            upgraded_handler(
+                                config,
+                                task,
+                                host,
+                                port,
+                                request,
+                                remote_ip,
+                                dfdaemon_download_client,
+                                registry_cert,
+                                origin_authorizer,
+                            )
+                            .await
+                        }
+                    };
+
+                    response.map(|response| access_controller.throttle(remote_ip, response))
+                }
             }),
         )
         .await
@@ -568,37 +906,16 @@ pub async fn upgraded_handler(
     task: Arc<Task>,
     host: String,
     port: u16,
-    mut request: Request<hyper::body::Incoming>,
+    mut request: Request<Body>,
     remote_ip: std::net::IpAddr,
     dfdaemon_download_client: DfdaemonDownloadClient,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
 ) -> ClientResult<Response> {
     // Span record the url and method.
     Span::current().record("url", request.uri().to_string().as_str());
     Span::current().record("method", request.method().as_str());
 
-    // Authenticate the request with the basic auth.
-    if let Some(basic_auth) = config.proxy.server.basic_auth.as_ref() {
-        match basic_auth.credentials().verify(request.headers()) {
-            Ok(_) => {}
-            Err(ClientError::Unauthorized) => {
-                return Ok(make_error_response(
-                    header::ErrorType::Proxy,
-                    http::StatusCode::UNAUTHORIZED,
-                    None,
-                ));
-            }
-            Err(err) => {
-                error!("verify basic auth failed: {}", err);
-                return Ok(make_error_response(
-                    header::ErrorType::Proxy,
-                    http::StatusCode::BAD_REQUEST,
-                    None,
-                ));
-            }
-        }
-    }
-
     // If the scheme is not set, set the scheme to https.
     if request.uri().scheme().is_none() {
         let builder = http::uri::Builder::new();
@@ -617,10 +934,7 @@ pub async fn upgraded_handler(
     }
 
     // If find the matching rule, proxy the request via the dfdaemon.
-    if let Some(rule) = find_matching_rule(
-        config.proxy.rules.as_deref(),
-        request.uri().to_string().as_str(),
-    ) {
+    if let Some(rule) = find_matching_rule(config.proxy.rules.as_deref(), &request) {
         info!(
             "proxy HTTPS request via dfdaemon by rule config: {:?}",
             request,
@@ -632,6 +946,8 @@ pub async fn upgraded_handler(
             request,
             remote_ip,
             dfdaemon_download_client,
+            registry_cert,
+            origin_authorizer,
         )
         .await;
     }
@@ -650,6 +966,8 @@ pub async fn upgraded_handler(
             request,
             remote_ip,
             dfdaemon_download_client,
+            registry_cert,
+            origin_authorizer,
         )
         .await;
     }
@@ -659,14 +977,14 @@ pub async fn upgraded_handler(
             "proxy HTTPS request directly to remote server: {:?}",
             request,
         );
-        return proxy_via_https(request, registry_cert).await;
+        return proxy_via_https(config, request, registry_cert).await;
     }
 
     info!(
         "proxy HTTP request directly to remote server: {:?}",
         request,
     );
-    return proxy_via_http(request).await;
+    return proxy_via_http(config, request).await;
 }
 
 /// proxy_via_dfdaemon proxies the request via the dfdaemon.
@@ -675,16 +993,18 @@ async fn proxy_via_dfdaemon(
     config: Arc<Config>,
     task: Arc<Task>,
     rule: &Rule,
-    request: Request<hyper::body::Incoming>,
+    request: Request<Body>,
     remote_ip: std::net::IpAddr,
     dfdaemon_download_client: DfdaemonDownloadClient,
+    registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+    origin_authorizer: Arc<OriginAuthorizer>,
 ) -> ClientResult<Response> {
     // Collect the metrics for the proxy request via dfdaemon.
     collect_proxy_request_via_dfdaemon_metrics();
 
     // Make the download task request.
-    let download_task_request =
-        match make_download_task_request(config.clone(), rule, request, remote_ip) {
+    let mut download_task_request =
+        match make_download_task_request(config.clone(), rule, &request, remote_ip) {
             Ok(download_task_request) => download_task_request,
             Err(err) => {
                 error!("make download task request failed: {}", err);
@@ -696,6 +1016,139 @@ async fn proxy_via_dfdaemon(
             }
         };
 
+    // Authorize the request against the origin before serving the blob, because the task of
+    // the blob may be cached by another client which has the access to the repository.
+    if let Some(download) = download_task_request.download.as_ref() {
+        if let Some((status, denied_header)) = origin_authorizer
+            .authorize(download.url.as_str(), request.headers(), &registry_cert)
+            .await
+        {
+            return Ok(make_error_response(
+                header::ErrorType::Backend,
+                status,
+                Some(denied_header),
+            ));
+        }
+    }
+
+    // Proxy the request to the origin if the response does not match the rule, the response
+    // conditions of the rule are evaluated before downloading.
+    if let Some(download) = download_task_request.download.as_ref() {
+        match is_response_matched(config.clone(), &task, rule, download, registry_cert.clone())
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("response is not matched by rule, proxy request to origin");
+                return proxy_via_origin(config, request, download.url.as_str(), registry_cert)
+                    .await;
+            }
+            Err(err) => {
+                error!("match response by rule failed: {}", err);
+                return proxy_via_origin(config, request, download.url.as_str(), registry_cert)
+                    .await;
+            }
+        }
+    }
+
+    // Serve the HEAD and conditional requests by the metadata of the cached task, because
+    // they do not need the content of the task. If the task is not cached locally, proxy
+    // the request to the origin instead of downloading the task.
+    let method = request.method().clone();
+    if method == Method::HEAD
+        || (method == Method::GET && conditional::is_conditional(request.headers()))
+    {
+        if let Some(download) = download_task_request.download.as_ref() {
+            let Some(cached_task) = get_cached_task(&task, download) else {
+                info!("task is not cached, proxy {} request to origin", method);
+                return proxy_via_origin(config, request, download.url.as_str(), registry_cert)
+                    .await;
+            };
+
+            let response_header = hashmap_to_headermap(&cached_task.response_header)?;
+            if conditional::is_not_modified(request.headers(), &response_header) {
+                info!("task {} is not modified", cached_task.id);
+                let mut response = Response::new(empty());
+                *response.headers_mut() = make_response_headers(
+                    cached_task.id.as_str(),
+                    config.host.ip.unwrap(),
+                    DownloadTaskStartedResponse {
+                        content_length: cached_task.content_length().unwrap_or_default(),
+                        response_header: headermap_to_hashmap(
+                            &conditional::make_not_modified_header(&response_header),
+                        ),
+                        is_finished: true,
+                        ..Default::default()
+                    },
+                )?;
+                *response.status_mut() = http::StatusCode::NOT_MODIFIED;
+                return Ok(response);
+            }
+
+            if method == Method::HEAD {
+                info!("serve HEAD request by cached task {}", cached_task.id);
+                let content_length = cached_task.content_length().unwrap_or_default();
+                let mut response_header = cached_task.response_header.clone();
+                response_header.insert(
+                    reqwest::header::CONTENT_LENGTH.to_string(),
+                    content_length.to_string(),
+                );
+
+                let mut response = Response::new(empty());
+                *response.headers_mut() = make_response_headers(
+                    cached_task.id.as_str(),
+                    config.host.ip.unwrap(),
+                    DownloadTaskStartedResponse {
+                        content_length,
+                        response_header,
+                        is_finished: true,
+                        ..Default::default()
+                    },
+                )?;
+                *response.status_mut() = http::StatusCode::OK;
+                return Ok(response);
+            }
+
+            // The preconditions of the GET request are passed, download the cached task.
+        }
+    }
+
+    // Evaluate the Range and If-Range headers of the GET request, the Range header sent to
+    // the dfdaemon is replaced by the evaluated range request.
+    let mut range_request = RangeRequest::Full;
+    let mut task_id = None;
+    if method == Method::GET {
+        if let Some(download) = download_task_request.download.as_mut() {
+            if request.headers().contains_key(reqwest::header::RANGE) {
+                let response_header = if request.headers().contains_key(reqwest::header::IF_RANGE) {
+                    get_task(&task, download)
+                        .and_then(|task| hashmap_to_headermap(&task.response_header).ok())
+                } else {
+                    None
+                };
+
+                range_request = range::evaluate(request.headers(), response_header.as_ref());
+                task_id = task.task_id(download).ok();
+            }
+
+            download
+                .request_header
+                .remove(reqwest::header::RANGE.as_str());
+            if let Some(range_header) = range_request.download_range_header() {
+                download
+                    .request_header
+                    .insert(reqwest::header::RANGE.to_string(), range_header);
+            }
+        }
+    }
+
+    // Get the digest of the task before the download task request is sent.
+    let digest = download_task_request
+        .download
+        .as_ref()
+        .and_then(|download| download.digest.as_ref())
+        .and_then(|digest| digest.parse::<Digest>().ok());
+
     // Download the task by the dfdaemon download client.
     let response = match dfdaemon_download_client
         .download_task(download_task_request)
@@ -704,6 +1157,26 @@ async fn proxy_via_dfdaemon(
         Ok(response) => response,
         Err(err) => match err {
             ClientError::TonicStatus(err) => {
+                // The dfdaemon fails with the failed precondition if the range is not
+                // satisfiable by the content length of the started task, and the task is not
+                // failed, respond the 416 with the content length of the task.
+                if err.code() == tonic::Code::FailedPrecondition {
+                    if let Some(content_length) = task_id
+                        .and_then(|task_id| task.get(task_id.as_str()).ok().flatten())
+                        .and_then(|task| task.content_length())
+                        .filter(|content_length| {
+                            is_range_not_satisfiable(&range_request, *content_length)
+                        })
+                    {
+                        info!("range {:?} is not satisfiable", range_request);
+                        return Ok(make_error_response(
+                            header::ErrorType::Proxy,
+                            http::StatusCode::RANGE_NOT_SATISFIABLE,
+                            Some(range::make_range_not_satisfiable_header(content_length)),
+                        ));
+                    }
+                }
+
                 match serde_json::from_slice::<Backend>(err.details()) {
                     Ok(backend) => {
                         error!("download task failed: {:?}", backend);
@@ -766,6 +1239,40 @@ async fn proxy_via_dfdaemon(
         ));
     };
 
+    // Resolve the multiple ranges by the content length of the task, the multiple ranges are
+    // responded by the multipart/byteranges body.
+    let multipart = match range_request {
+        RangeRequest::Multiple(ref value) => {
+            let content_length = download_task_started_response.content_length;
+            match parse_ranges_header(value, content_length) {
+                Ok(ranges) => {
+                    let content_type = download_task_started_response
+                        .response_header
+                        .iter()
+                        .find(|(key, _)| {
+                            key.eq_ignore_ascii_case(reqwest::header::CONTENT_TYPE.as_str())
+                        })
+                        .map(|(_, value)| value.clone());
+
+                    Some(Arc::new(Multipart::new(
+                        ranges,
+                        content_type,
+                        content_length,
+                    )))
+                }
+                Err(err) => {
+                    info!("range {} is not satisfiable: {}", value, err);
+                    return Ok(make_error_response(
+                        header::ErrorType::Proxy,
+                        http::StatusCode::RANGE_NOT_SATISFIABLE,
+                        Some(range::make_range_not_satisfiable_header(content_length)),
+                    ));
+                }
+            }
+        }
+        _ => None,
+    };
+
     // Write the status code to the writer.
     let (sender, mut receiver) = mpsc::channel(4);
 
@@ -781,14 +1288,62 @@ async fn proxy_via_dfdaemon(
     let stream_body = StreamBody::new(reader_stream.map_ok(Frame::data).map_err(ClientError::from));
     let boxed_body = stream_body.boxed();
 
-    // Construct the response.
+    // Construct the response, the partial content is responded with the 206 status code.
     let mut response = Response::new(boxed_body);
-    *response.headers_mut() = make_response_headers(
-        message.task_id.as_str(),
-        config.host.ip.unwrap(),
-        download_task_started_response.clone(),
-    )?;
-    *response.status_mut() = http::StatusCode::OK;
+    match multipart.as_ref() {
+        Some(multipart) => {
+            let mut response_header = make_response_headers(
+                message.task_id.as_str(),
+                config.host.ip.unwrap(),
+                DownloadTaskStartedResponse {
+                    range: None,
+                    ..download_task_started_response.clone()
+                },
+            )?;
+
+            response_header.remove(reqwest::header::CONTENT_RANGE);
+            response_header.insert(
+                reqwest::header::CONTENT_TYPE,
+                multipart
+                    .content_type()
+                    .parse()
+                    .or_err(ErrorType::ParseError)?,
+            );
+            response_header.insert(
+                reqwest::header::CONTENT_LENGTH,
+                multipart.body_length().into(),
+            );
+
+            *response.headers_mut() = response_header;
+            *response.status_mut() = http::StatusCode::PARTIAL_CONTENT;
+        }
+        None => {
+            *response.headers_mut() = make_response_headers(
+                message.task_id.as_str(),
+                config.host.ip.unwrap(),
+                download_task_started_response.clone(),
+            )?;
+
+            *response.status_mut() = match download_task_started_response.range {
+                Some(_) => http::StatusCode::PARTIAL_CONTENT,
+                None => http::StatusCode::OK,
+            };
+        }
+    }
+
+    // Verify the digest of the full task while the pieces are written, the finished task has
+    // been verified by the dfdaemon. The last piece is held back until the digest is verified,
+    // so the client never receives the complete content if the digest is mismatched.
+    let mut verifier = match digest {
+        Some(digest)
+            if multipart.is_none()
+                && download_task_started_response.range.is_none()
+                && !download_task_started_response.is_finished =>
+        {
+            Some((Hasher::new(digest.algorithm()), digest))
+        }
+        _ => None,
+    };
 
     // Return the response if the client return the first piece.
     let mut initialized = false;
@@ -816,6 +1371,11 @@ async fn proxy_via_dfdaemon(
                 return;
             };
             let mut need_piece_number = first_piece.number;
+            let (last_piece_number, last_piece_length) = download_task_started_response
+                .pieces
+                .last()
+                .map(|piece| (piece.number, piece.length))
+                .unwrap_or((first_piece.number, first_piece.length));
 
             // Read piece data from stream and write to pipe. If the piece data is
             // not in order, store it in the hashmap, and write it to the pipe
@@ -848,50 +1408,168 @@ async fn proxy_via_dfdaemon(
                                 return;
                             };
 
-                            let piece_range_reader = match task
-                                .piece
-                                .download_from_local_into_async_read(
-                                    task.piece
-                                        .id(message.task_id.as_str(), piece.number)
-                                        .as_str(),
-                                    message.task_id.as_str(),
-                                    piece.length,
-                                    download_task_started_response.range,
-                                    true,
-                                    false,
-                                )
-                                .await
-                            {
-                                Ok(piece_range_reader) => piece_range_reader,
-                                Err(err) => {
-                                    error!("download piece reader error: {}", err);
-                                    if let Err(err) = writer.shutdown().await {
-                                        error!("writer shutdown error: {}", err);
-                                    }
-
-                                    return;
+                            // Get the ranges of the piece to be written, the part header of
+                            // the multipart/byteranges body is written before the range.
+                            let piece_ranges = match multipart.as_ref() {
+                                Some(multipart) => {
+                                    multipart.piece_ranges(piece.offset, piece.length)
                                 }
+                                None => vec![(None, download_task_started_response.range)],
                             };
 
-                            // Use a buffer to read the piece.
-                            let piece_range_reader =
-                                BufReader::with_capacity(read_buffer_size, piece_range_reader);
+                            let piece_id = task.piece.id(message.task_id.as_str(), piece.number);
+                            let mut piece_range_readers = Vec::with_capacity(piece_ranges.len());
+                            for (part_header, piece_range) in piece_ranges {
+                                let piece_range_reader = match task
+                                    .piece
+                                    .download_from_local_into_async_read(
+                                        piece_id.as_str(),
+                                        message.task_id.as_str(),
+                                        piece.length,
+                                        piece_range,
+                                        true,
+                                        false,
+                                    )
+                                    .await
+                                {
+                                    Ok(piece_range_reader) => piece_range_reader,
+                                    Err(err) => {
+                                        error!("download piece reader error: {}", err);
+                                        if let Err(err) = writer.shutdown().await {
+                                            error!("writer shutdown error: {}", err);
+                                        }
+
+                                        return;
+                                    }
+                                };
+
+                                // Use a buffer to read the piece.
+                                piece_range_readers.push((
+                                    part_header,
+                                    BufReader::with_capacity(read_buffer_size, piece_range_reader),
+                                ));
+                            }
 
                             // Write the piece data to the pipe in order.
-                            finished_piece_readers.insert(piece.number, piece_range_reader);
-                            while let Some(mut piece_range_reader) =
+                            finished_piece_readers.insert(piece.number, piece_range_readers);
+                            while let Some(piece_range_readers) =
                                 finished_piece_readers.remove(&need_piece_number)
                             {
                                 debug!("copy piece {} to stream", need_piece_number);
-                                if let Err(err) =
-                                    tokio::io::copy(&mut piece_range_reader, &mut writer).await
-                                {
-                                    error!("download piece reader error: {}", err);
-                                    if let Err(err) = writer.shutdown().await {
-                                        error!("writer shutdown error: {}", err);
+                                for (part_header, mut piece_range_reader) in piece_range_readers {
+                                    if let Some(part_header) = part_header {
+                                        if let Err(err) = writer.write_all(&part_header).await {
+                                            error!("write part header error: {}", err);
+                                            if let Err(err) = writer.shutdown().await {
+                                                error!("writer shutdown error: {}", err);
+                                            }
+
+                                            return;
+                                        }
+                                    }
+
+                                    let Some((hasher, _)) = verifier.as_mut() else {
+                                        if let Err(err) =
+                                            tokio::io::copy(&mut piece_range_reader, &mut writer)
+                                                .await
+                                        {
+                                            error!("download piece reader error: {}", err);
+                                            if let Err(err) = writer.shutdown().await {
+                                                error!("writer shutdown error: {}", err);
+                                            }
+
+                                            return;
+                                        }
+
+                                        continue;
+                                    };
+
+                                    if need_piece_number != last_piece_number {
+                                        if let Err(err) = copy_and_hash(
+                                            &mut piece_range_reader,
+                                            &mut writer,
+                                            hasher,
+                                        )
+                                        .await
+                                        {
+                                            error!("download piece reader error: {}", err);
+                                            if let Err(err) = writer.shutdown().await {
+                                                error!("writer shutdown error: {}", err);
+                                            }
+
+                                            return;
+                                        }
+
+                                        continue;
+                                    }
+
+                                    // Hash the last piece without writing it, and write it by
+                                    // a new reader if the digest is verified.
+                                    if let Err(err) = copy_and_hash(
+                                        &mut piece_range_reader,
+                                        &mut tokio::io::sink(),
+                                        hasher,
+                                    )
+                                    .await
+                                    {
+                                        error!("download piece reader error: {}", err);
+                                        if let Err(err) = writer.shutdown().await {
+                                            error!("writer shutdown error: {}", err);
+                                        }
+
+                                        return;
                                     }
 
-                                    return;
+                                    let Some((hasher, digest)) = verifier.take() else {
+                                        return;
+                                    };
+
+                                    // The task is failed and evicted by the dfdaemon, abort the
+                                    // response by closing the body before the content length
+                                    // is reached.
+                                    if let Err(err) = hasher.verify(&digest) {
+                                        error!("verify digest failed: {}", err);
+                                        if let Err(err) = writer.shutdown().await {
+                                            error!("writer shutdown error: {}", err);
+                                        }
+
+                                        return;
+                                    }
+
+                                    let last_piece_id =
+                                        task.piece.id(message.task_id.as_str(), need_piece_number);
+                                    let result = match task
+                                        .piece
+                                        .download_from_local_into_async_read(
+                                            last_piece_id.as_str(),
+                                            message.task_id.as_str(),
+                                            last_piece_length,
+                                            None,
+                                            true,
+                                            false,
+                                        )
+                                        .await
+                                    {
+                                        Ok(last_piece_reader) => {
+                                            let mut last_piece_reader = BufReader::with_capacity(
+                                                read_buffer_size,
+                                                last_piece_reader,
+                                            );
+                                            tokio::io::copy(&mut last_piece_reader, &mut writer)
+                                                .await
+                                                .map_err(ClientError::from)
+                                        }
+                                        Err(err) => Err(err),
+                                    };
+
+                                    if let Err(err) = result {
+                                        error!("download piece reader error: {}", err);
+                                        if let Err(err) = writer.shutdown().await {
+                                            error!("writer shutdown error: {}", err);
+                                        }
+
+                                        return;
+                                    }
                                 }
 
                                 need_piece_number += 1;
@@ -907,6 +1585,14 @@ async fn proxy_via_dfdaemon(
                     }
                     Ok(None) => {
                         info!("message is none");
+
+                        // Write the close delimiter of the multipart/byteranges body.
+                        if let Some(multipart) = multipart.as_ref() {
+                            if let Err(err) = writer.write_all(&multipart.end()).await {
+                                error!("write close delimiter error: {}", err);
+                            }
+                        }
+
                         if let Err(err) = writer.flush().await {
                             error!("writer flush error: {}", err);
                         }
@@ -979,9 +1665,33 @@ async fn proxy_via_dfdaemon(
     }
 }
 
+/// proxy_via_origin proxies the request directly to the origin of the download url.
+#[instrument(skip_all)]
+async fn proxy_via_origin(
+    config: Arc<Config>,
+    mut request: Request<Body>,
+    url: &str,
+    registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+) -> ClientResult<Response> {
+    let uri = url.parse::<http::Uri>().or_err(ErrorType::ParseError)?;
+    if let Some(authority) = uri.authority() {
+        request.headers_mut().insert(
+            hyper::header::HOST,
+            authority.as_str().parse().or_err(ErrorType::ParseError)?,
+        );
+    }
+
+    *request.uri_mut() = uri;
+    if request.uri().scheme().cloned() == Some(http::uri::Scheme::HTTPS) {
+        return proxy_via_https(config, request, registry_cert).await;
+    }
+
+    proxy_via_http(config, request).await
+}
+
 /// proxy_via_http proxies the HTTP request directly to the remote server.
 #[instrument(skip_all)]
-async fn proxy_via_http(request: Request<hyper::body::Incoming>) -> ClientResult<Response> {
+async fn proxy_via_http(config: Arc<Config>, request: Request<Body>) -> ClientResult<Response> {
     let Some(host) = request.uri().host() else {
         error!("CONNECT host is not socket addr: {:?}", request.uri());
         return Ok(make_error_response(
@@ -992,7 +1702,10 @@ async fn proxy_via_http(request: Request<hyper::body::Incoming>) -> ClientResult
     };
     let port = request.uri().port_u16().unwrap_or(80);
 
-    let stream = TcpStream::connect((host, port)).await?;
+    // Connect to the remote server via the upstream proxy if configured.
+    let stream = UpstreamConnector::new(config.network.upstream_proxy.clone())
+        .connect(host, port)
+        .await?;
     let io = TokioIo::new(stream);
     let (mut client, conn) = ClientBuilder::new()
         .preserve_header_case(true)
@@ -1013,7 +1726,8 @@ async fn proxy_via_http(request: Request<hyper::body::Incoming>) -> ClientResult
 /// proxy_via_https proxies the HTTPS request directly to the remote server.
 #[instrument(skip_all)]
 async fn proxy_via_https(
-    request: Request<hyper::body::Incoming>,
+    config: Arc<Config>,
+    request: Request<Body>,
     registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
 ) -> ClientResult<Response> {
     let client_config_builder = match registry_cert.as_ref() {
@@ -1037,7 +1751,9 @@ async fn proxy_via_https(
         .with_tls_config(client_config_builder)
         .https_or_http()
         .enable_http1()
-        .build();
+        .wrap_connector(UpstreamConnector::new(
+            config.network.upstream_proxy.clone(),
+        ));
 
     let client = Client::builder(TokioExecutor::new()).build(https);
     let response = client.request(request).await.inspect_err(|err| {
@@ -1047,37 +1763,89 @@ async fn proxy_via_https(
     Ok(response.map(|b| b.map_err(ClientError::from).boxed()))
 }
 
-/// make_registry_mirror_request makes a registry mirror request by the request.
+/// is_proxy_authority returns whether the authority of the uri is the proxy itself. The
+/// authority is the proxy itself if the port is the local port of the connection, and the
+/// host is the local ip of the connection, the loopback address, the ip or the hostname of
+/// the host.
+fn is_proxy_authority(config: &Config, uri: &http::Uri, local_address: SocketAddr) -> bool {
+    let Some(host) = uri.host() else {
+        return true;
+    };
+
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme() == Some(&http::uri::Scheme::HTTPS) {
+            443
+        } else {
+            80
+        });
+    if port != local_address.port() {
+        return false;
+    }
+
+    match host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        Ok(ip) => {
+            let ip = ip.to_canonical();
+            ip.is_loopback()
+                || ip == local_address.ip().to_canonical()
+                || Some(ip) == config.host.ip.map(|ip| ip.to_canonical())
+        }
+        Err(_) => {
+            host.eq_ignore_ascii_case("localhost")
+                || host.eq_ignore_ascii_case(&config.host.hostname)
+        }
+    }
+}
+
+/// make_registry_mirror_request makes a registry mirror request by the request. If the
+/// `X-Dragonfly-Registry` header is set, the request is sent to the registry in the header.
+/// Otherwise, the registry mirror is selected by the namespace of the request, and returned
+/// to report the result of the request.
 fn make_registry_mirror_request(
-    config: Arc<Config>,
-    mut request: Request<hyper::body::Incoming>,
-) -> ClientResult<Request<hyper::body::Incoming>> {
-    let header = request.headers().clone();
-    let registry_mirror_uri = match header::get_registry(&header) {
-        Some(registry) => format!(
-            "{}{}",
-            registry,
-            request
-                .uri()
-                .path_and_query()
-                .map(|v| v.as_str())
-                .unwrap_or("/")
-        )
-        .parse::<http::Uri>()
-        .or_err(ErrorType::ParseError)?,
-        None => format!(
-            "{}{}",
-            config.proxy.registry_mirror.addr,
-            request
-                .uri()
-                .path_and_query()
-                .map(|v| v.as_str())
-                .unwrap_or("/")
-        )
-        .parse::<http::Uri>()
-        .or_err(ErrorType::ParseError)?,
+    registry_mirrors: &RegistryMirrors,
+    mut request: Request<Body>,
+) -> ClientResult<(Request<Body>, Option<Arc<Mirror>>)> {
+    let (registry, mirror) = match header::get_registry(request.headers()) {
+        Some(registry) => (registry, None),
+        None => {
+            let namespace = registry_mirror::get_namespace(request.uri());
+            let mirror = registry_mirrors.select(namespace.as_deref());
+            debug!(
+                "select registry mirror {} for namespace {:?}",
+                mirror.addr(),
+                namespace
+            );
+
+            (mirror.addr().to_string(), Some(mirror))
+        }
     };
-    header::get_registry(&header);
+
+    route_registry_mirror_request(&mut request, &registry, mirror.as_ref())?;
+    Ok((request, mirror))
+}
+
+/// route_registry_mirror_request routes the request to the registry by replacing the uri and
+/// the host header of the request.
+fn route_registry_mirror_request(
+    request: &mut Request<Body>,
+    registry: &str,
+    mirror: Option<&Arc<Mirror>>,
+) -> ClientResult<()> {
+    let registry_mirror_uri = format!(
+        "{}{}",
+        registry,
+        request
+            .uri()
+            .path_and_query()
+            .map(|v| v.as_str())
+            .unwrap_or("/")
+    )
+    .parse::<http::Uri>()
+    .or_err(ErrorType::ParseError)?;
 
     *request.uri_mut() = registry_mirror_uri.clone();
     request.headers_mut().insert(
@@ -1089,14 +1857,20 @@ fn make_registry_mirror_request(
             .or_err(ErrorType::ParseError)?,
     );
 
-    Ok(request)
+    // Store the selected registry mirror in the request extensions, so the download task
+    // request uses the configuration of the registry mirror.
+    if let Some(mirror) = mirror {
+        request.extensions_mut().insert(mirror.clone());
+    }
+
+    Ok(())
 }
 
 /// make_download_task_request makes a download task request by the request.
 fn make_download_task_request(
     config: Arc<Config>,
     rule: &Rule,
-    request: Request<hyper::body::Incoming>,
+    request: &Request<Body>,
     remote_ip: std::net::IpAddr,
 ) -> ClientResult<DownloadTaskRequest> {
     // Convert the Reqwest header to the Hyper header.
@@ -1105,8 +1879,23 @@ fn make_download_task_request(
     // Registry will return the 403 status code if the Host header is set.
     header.remove(reqwest::header::HOST);
 
-    // Validate the request arguments.
-    let piece_length = header::get_piece_length(&header).map(|piece_length| piece_length.as_u64());
+    // Use the configuration of the selected registry mirror if the request is sent to the
+    // registry mirror.
+    let enable_task_id_based_blob_digest = match request.extensions().get::<Arc<Mirror>>() {
+        Some(mirror) => mirror.enable_task_id_based_blob_digest(),
+        None => {
+            config
+                .proxy
+                .registry_mirror
+                .enable_task_id_based_blob_digest
+        }
+    };
+
+    // Validate the request arguments, the X-Dragonfly-Piece-Length header has the higher
+    // priority than the piece length of the rule.
+    let piece_length = header::get_piece_length(&header)
+        .or(rule.piece_length)
+        .map(|piece_length| piece_length.as_u64());
     if let Some(piece_length) = piece_length {
         if piece_length < MIN_PIECE_LENGTH {
             return Err(ClientError::ValidationError(format!(
@@ -1116,16 +1905,31 @@ fn make_download_task_request(
         }
     }
 
+    // The X-Dragonfly-Priority header has the higher priority than the priority of the rule.
+    let priority = match rule.priority {
+        Some(priority) if !header.contains_key(header::DRAGONFLY_PRIORITY_HEADER) => priority,
+        _ => header::get_priority(&header),
+    };
+
+    // Verify the digest of the task by the digest policy of the rule.
+    let url = make_download_url(request.uri(), rule.use_tls, rule.redirect.clone())?;
+    let digest = match rule.digest_policy {
+        DigestPolicy::None => None,
+        DigestPolicy::BlobURL => {
+            Digest::extract_from_blob_url(url.as_str()).map(|digest| digest.to_string())
+        }
+    };
+
     Ok(DownloadTaskRequest {
         download: Some(Download {
-            url: make_download_url(request.uri(), rule.use_tls, rule.redirect.clone())?,
-            digest: None,
+            url,
+            digest,
             // Download range use header range in HTTP protocol.
             range: None,
             r#type: TaskType::Standard as i32,
-            tag: header::get_tag(&header),
-            application: header::get_application(&header),
-            priority: header::get_priority(&header),
+            tag: header::get_tag(&header).or(rule.tag.clone()),
+            application: header::get_application(&header).or(rule.application.clone()),
+            priority,
             filtered_query_params: header::get_filtered_query_params(
                 &header,
                 rule.filtered_query_params.clone(),
@@ -1136,9 +1940,11 @@ fn make_download_task_request(
             output_path: header::get_output_path(&header),
             timeout: None,
             need_back_to_source: false,
-            disable_back_to_source: config.proxy.disable_back_to_source,
+            disable_back_to_source: rule
+                .disable_back_to_source
+                .unwrap_or(config.proxy.disable_back_to_source),
             certificate_chain: Vec::new(),
-            prefetch: need_prefetch(config.clone(), &header),
+            prefetch: need_prefetch(config.clone(), rule, &header),
             object_storage: None,
             hdfs: None,
             is_prefetch: false,
@@ -1151,17 +1957,14 @@ fn make_download_task_request(
             actual_piece_length: None,
             actual_content_length: None,
             actual_piece_count: None,
-            enable_task_id_based_blob_digest: config
-                .proxy
-                .registry_mirror
-                .enable_task_id_based_blob_digest,
+            enable_task_id_based_blob_digest,
         }),
     })
 }
 
-/// need_prefetch returns whether the prefetch is needed by the configuration and the request
-/// header.
-fn need_prefetch(config: Arc<Config>, header: &http::HeaderMap) -> bool {
+/// need_prefetch returns whether the prefetch is needed by the configuration, the rule and the
+/// request header.
+fn need_prefetch(config: Arc<Config>, rule: &Rule, header: &http::HeaderMap) -> bool {
     // If the header not contains the range header, the request does not need prefetch.
     if !header.contains_key(reqwest::header::RANGE) {
         return false;
@@ -1173,8 +1976,8 @@ fn need_prefetch(config: Arc<Config>, header: &http::HeaderMap) -> bool {
         return prefetch;
     }
 
-    // Return the prefetch value from the configuration.
-    config.proxy.prefetch
+    // Return the prefetch value from the rule, then the configuration.
+    rule.prefetch.unwrap_or(config.proxy.prefetch)
 }
 
 /// make_download_url makes a download url by the given uri.
@@ -1245,10 +2048,143 @@ fn make_response_headers(
     hashmap_to_headermap(&download_task_started_response.response_header)
 }
 
+/// get_cached_task gets the finished task of the download from the local storage.
+fn get_cached_task(task: &Task, download: &Download) -> Option<metadata::Task> {
+    get_task(task, download).filter(|cached_task| cached_task.is_finished())
+}
+
+/// get_task gets the task of the download from the local storage, the task may be
+/// downloading.
+fn get_task(task: &Task, download: &Download) -> Option<metadata::Task> {
+    let task_id = match task.task_id(download) {
+        Ok(task_id) => task_id,
+        Err(err) => {
+            error!("generate task id failed: {}", err);
+            return None;
+        }
+    };
+
+    match task.get(task_id.as_str()) {
+        Ok(task) => task,
+        Err(err) => {
+            error!("get task {} failed: {}", task_id, err);
+            None
+        }
+    }
+}
+
+/// is_range_not_satisfiable returns whether the range of the request is not satisfiable by
+/// the content length.
+fn is_range_not_satisfiable(range_request: &RangeRequest, content_length: u64) -> bool {
+    match range_request.value() {
+        Some(value) => matches!(
+            parse_ranges_header(value, content_length),
+            Err(ClientError::EmptyHTTPRangeError)
+        ),
+        None => false,
+    }
+}
+
+/// stat_origin stats the origin of the download by the backend. The Range header is removed,
+/// because the content length of the full content is required.
+async fn stat_origin(
+    config: Arc<Config>,
+    task: &Task,
+    download: &Download,
+    registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+) -> ClientResult<StatResponse> {
+    let mut request_header = hashmap_to_headermap(&download.request_header)?;
+    request_header.remove(reqwest::header::RANGE);
+
+    let backend = task.backend_factory.build(download.url.as_str())?;
+    backend
+        .stat(StatRequest {
+            task_id: task.task_id(download)?,
+            url: download.url.clone(),
+            http_header: Some(request_header),
+            timeout: config.download.piece_timeout,
+            client_cert: registry_cert.as_ref().clone(),
+            object_storage: None,
+            hdfs: None,
+        })
+        .await
+}
+
 /// find_matching_rule returns whether the dfdaemon should be used to download the task.
-/// If the dfdaemon should be used, return the matched rule.
-fn find_matching_rule(rules: Option<&[Rule]>, url: &str) -> Option<Rule> {
-    rules?.iter().find(|rule| rule.regex.is_match(url)).cloned()
+/// If the dfdaemon should be used, return the rule matched by the url, method and headers of
+/// the request.
+fn find_matching_rule(rules: Option<&[Rule]>, request: &Request<Body>) -> Option<Rule> {
+    let url = request.uri().to_string();
+    rules?
+        .iter()
+        .find(|rule| {
+            rule.is_request_match(url.as_str(), request.method().as_str(), request.headers())
+        })
+        .cloned()
+}
+
+/// is_response_matched returns whether the response of the download matches the response
+/// conditions of the rule. The response is got from the cached task, if the task is not
+/// cached, the response is got from the origin by the backend. The rule without the response
+/// conditions matches any response without getting the response.
+async fn is_response_matched(
+    config: Arc<Config>,
+    task: &Task,
+    rule: &Rule,
+    download: &Download,
+    registry_cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+) -> ClientResult<bool> {
+    if !rule.has_response_conditions() {
+        return Ok(true);
+    }
+
+    if let Some(cached_task) = get_task(task, download) {
+        if let Some(content_length) = cached_task.content_length() {
+            let response_header = hashmap_to_headermap(&cached_task.response_header)?;
+            return Ok(rule.is_response_match(
+                response_header
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|content_type| content_type.to_str().ok()),
+                Some(content_length),
+            ));
+        }
+    }
+
+    let response = stat_origin(config, task, download, registry_cert).await?;
+    if !response.success {
+        return Ok(false);
+    }
+
+    let response_header = response.http_header.unwrap_or_default();
+    Ok(rule.is_response_match(
+        response_header
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok()),
+        response.content_length,
+    ))
+}
+
+/// copy_and_hash copies the content from the reader to the writer, and updates the hasher by
+/// the content.
+async fn copy_and_hash<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    hasher: &mut Hasher,
+) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            return Ok(());
+        }
+
+        hasher.update(&buffer[..n]);
+        writer.write_all(&buffer[..n]).await?;
+    }
 }
 
 /// make_error_response makes an error response with the given status and message.
@@ -1274,6 +2210,17 @@ fn make_error_response(
     response
 }
 
+/// make_rejected_response makes the response of the request rejected by the access
+/// controller, and collects the rejected metrics.
+fn make_rejected_response(rejection: Rejection) -> Response {
+    collect_proxy_request_rejected_metrics(rejection.reason());
+    make_error_response(
+        header::ErrorType::Proxy,
+        rejection.status(),
+        Some(rejection.header()),
+    )
+}
+
 /// empty returns an empty body.
 fn empty() -> BoxBody<Bytes, ClientError> {
     Empty::<Bytes>::new()
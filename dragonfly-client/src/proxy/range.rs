@@ -0,0 +1,394 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use bytes::Bytes;
+use dragonfly_api::common::v2::Range;
+use http::header::{HeaderMap, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use http_range_header::{EndPosition, StartPosition};
+
+/// RangeRequest is the range request evaluated by the Range and If-Range headers, refer to
+/// https://www.rfc-editor.org/rfc/rfc9110#section-14.2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// Full means the Range header is absent or ignored, the full content is responded.
+    Full,
+
+    /// Single means a single range is requested, the range is resolved by the dfdaemon and
+    /// responded by the 206 response.
+    Single(String),
+
+    /// Multiple means multiple ranges are requested, the ranges are responded by the
+    /// multipart/byteranges response.
+    Multiple(String),
+}
+
+/// RangeRequest implements the range request.
+impl RangeRequest {
+    /// value returns the value of the Range header.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            RangeRequest::Full => None,
+            RangeRequest::Single(value) | RangeRequest::Multiple(value) => Some(value.as_str()),
+        }
+    }
+
+    /// download_range_header returns the Range header sent to the dfdaemon. For multiple
+    /// ranges, the dfdaemon downloads the range covering all the ranges, if the covering range
+    /// can not be calculated without the content length, the dfdaemon downloads the full
+    /// content.
+    pub fn download_range_header(&self) -> Option<String> {
+        match self {
+            RangeRequest::Full => None,
+            RangeRequest::Single(value) => Some(value.clone()),
+            RangeRequest::Multiple(value) => make_covering_range_header(value),
+        }
+    }
+}
+
+/// evaluate evaluates the Range and If-Range headers of the GET request. The Range header is
+/// ignored if it is invalid, or the If-Range validator does not match the response header of
+/// the task.
+pub fn evaluate(request_header: &HeaderMap, response_header: Option<&HeaderMap>) -> RangeRequest {
+    let Some(value) = request_header
+        .get(RANGE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return RangeRequest::Full;
+    };
+
+    let Ok(parsed_ranges) = http_range_header::parse_range_header(value) else {
+        return RangeRequest::Full;
+    };
+
+    if let Some(if_range) = request_header.get(IF_RANGE) {
+        let Some(response_header) = response_header else {
+            return RangeRequest::Full;
+        };
+
+        let Ok(if_range) = if_range.to_str() else {
+            return RangeRequest::Full;
+        };
+
+        if !is_if_range_matched(if_range, response_header) {
+            return RangeRequest::Full;
+        }
+    }
+
+    if parsed_ranges.ranges.len() > 1 {
+        return RangeRequest::Multiple(value.to_string());
+    }
+
+    RangeRequest::Single(value.to_string())
+}
+
+/// is_if_range_matched returns whether the If-Range validator matches the response header.
+/// The entity tag is matched by the strong comparison, and the HTTP-date is matched if it is
+/// exactly equal to the Last-Modified, refer to
+/// https://www.rfc-editor.org/rfc/rfc9110#section-13.1.5.
+pub fn is_if_range_matched(if_range: &str, response_header: &HeaderMap) -> bool {
+    let if_range = if_range.trim();
+
+    // The weak entity tag never matches.
+    if if_range.starts_with("W/") {
+        return false;
+    }
+
+    if if_range.starts_with('"') {
+        return response_header
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.trim() == if_range)
+            .unwrap_or(false);
+    }
+
+    let Ok(if_range) = httpdate::parse_http_date(if_range) else {
+        return false;
+    };
+
+    response_header
+        .get(LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok())
+        .map(|last_modified| last_modified == if_range)
+        .unwrap_or(false)
+}
+
+/// make_covering_range_header makes the Range header covering all the ranges of the Range
+/// header. If any range is the suffix range, the covering range depends on the content length
+/// and None is returned.
+fn make_covering_range_header(value: &str) -> Option<String> {
+    let parsed_ranges = http_range_header::parse_range_header(value).ok()?;
+
+    let mut start = u64::MAX;
+    let mut end = Some(0);
+    for range in parsed_ranges.ranges.iter() {
+        match range.start {
+            StartPosition::Index(range_start) => start = start.min(range_start),
+            StartPosition::FromLast(_) => return None,
+        }
+
+        end = match (end, range.end) {
+            (Some(end), EndPosition::Index(range_end)) => Some(end.max(range_end)),
+            _ => None,
+        };
+    }
+
+    match end {
+        Some(end) => Some(format!("bytes={}-{}", start, end)),
+        None => Some(format!("bytes={}-", start)),
+    }
+}
+
+/// make_range_not_satisfiable_header makes the header of the 416 response, refer to
+/// https://www.rfc-editor.org/rfc/rfc9110#section-15.5.17.
+pub fn make_range_not_satisfiable_header(content_length: u64) -> HeaderMap {
+    let mut header = HeaderMap::new();
+    header.insert(
+        CONTENT_RANGE,
+        format!("bytes */{}", content_length).parse().unwrap(),
+    );
+
+    header
+}
+
+/// Multipart builds the multipart/byteranges body of the multiple ranges, refer to
+/// https://www.rfc-editor.org/rfc/rfc9110#section-14.6.
+#[derive(Debug)]
+pub struct Multipart {
+    /// boundary is the boundary of the body parts.
+    boundary: String,
+
+    /// ranges are the satisfiable ranges in ascending order.
+    ranges: Vec<Range>,
+
+    /// content_type is the content type of the task.
+    content_type: Option<String>,
+
+    /// content_length is the content length of the task.
+    content_length: u64,
+}
+
+/// Multipart implements the multipart/byteranges body.
+impl Multipart {
+    /// new creates a new multipart/byteranges body.
+    pub fn new(ranges: Vec<Range>, content_type: Option<String>, content_length: u64) -> Self {
+        Self {
+            boundary: uuid::Uuid::new_v4().simple().to_string(),
+            ranges,
+            content_type,
+            content_length,
+        }
+    }
+
+    /// content_type returns the content type of the multipart/byteranges response.
+    pub fn content_type(&self) -> String {
+        format!("multipart/byteranges; boundary={}", self.boundary)
+    }
+
+    /// body_length returns the length of the multipart/byteranges body.
+    pub fn body_length(&self) -> u64 {
+        let parts_length: u64 = (0..self.ranges.len())
+            .map(|index| self.part_header(index).len() as u64 + self.ranges[index].length)
+            .sum();
+
+        parts_length + self.end().len() as u64
+    }
+
+    /// part_header returns the boundary and the header of the body part.
+    pub fn part_header(&self, index: usize) -> Bytes {
+        let range = self.ranges[index];
+        let mut part_header = String::new();
+        if index > 0 {
+            part_header.push_str("\r\n");
+        }
+
+        part_header.push_str(&format!("--{}\r\n", self.boundary));
+        if let Some(content_type) = self.content_type.as_ref() {
+            part_header.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+
+        part_header.push_str(&format!(
+            "Content-Range: bytes {}-{}/{}\r\n\r\n",
+            range.start,
+            range.start + range.length - 1,
+            self.content_length
+        ));
+
+        Bytes::from(part_header)
+    }
+
+    /// end returns the close delimiter of the multipart/byteranges body.
+    pub fn end(&self) -> Bytes {
+        Bytes::from(format!("\r\n--{}--\r\n", self.boundary))
+    }
+
+    /// piece_ranges returns the ranges of the body parts in the piece. The part header is
+    /// returned with the range if the body part starts in the piece.
+    pub fn piece_ranges(&self, offset: u64, length: u64) -> Vec<(Option<Bytes>, Option<Range>)> {
+        self.ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| {
+                range.start < offset + length && offset < range.start + range.length
+            })
+            .map(|(index, range)| {
+                let part_header = (range.start >= offset).then(|| self.part_header(index));
+                (part_header, Some(*range))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response_header() -> HeaderMap {
+        let mut header = HeaderMap::new();
+        header.insert(ETAG, "\"v1\"".parse().unwrap());
+        header.insert(
+            LAST_MODIFIED,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        header
+    }
+
+    #[test]
+    fn should_evaluate_range_request() {
+        let test_cases = vec![
+            (None, None, RangeRequest::Full),
+            (Some("invalid"), None, RangeRequest::Full),
+            (
+                Some("bytes=0-9"),
+                None,
+                RangeRequest::Single("bytes=0-9".to_string()),
+            ),
+            (
+                Some("bytes=0-9,20-29"),
+                None,
+                RangeRequest::Multiple("bytes=0-9,20-29".to_string()),
+            ),
+            (
+                Some("bytes=0-9"),
+                Some("\"v1\""),
+                RangeRequest::Single("bytes=0-9".to_string()),
+            ),
+            (Some("bytes=0-9"), Some("\"v2\""), RangeRequest::Full),
+            (Some("bytes=0-9"), Some("W/\"v1\""), RangeRequest::Full),
+            (
+                Some("bytes=0-9"),
+                Some("Wed, 21 Oct 2015 07:28:00 GMT"),
+                RangeRequest::Single("bytes=0-9".to_string()),
+            ),
+            (
+                Some("bytes=0-9"),
+                Some("Thu, 22 Oct 2015 07:28:00 GMT"),
+                RangeRequest::Full,
+            ),
+        ];
+
+        for (range, if_range, expected) in test_cases {
+            let mut request_header = HeaderMap::new();
+            if let Some(range) = range {
+                request_header.insert(RANGE, range.parse().unwrap());
+            }
+
+            if let Some(if_range) = if_range {
+                request_header.insert(IF_RANGE, if_range.parse().unwrap());
+            }
+
+            assert_eq!(
+                evaluate(&request_header, Some(&response_header())),
+                expected
+            );
+        }
+
+        // The If-Range can not be validated without the response header of the task.
+        let mut request_header = HeaderMap::new();
+        request_header.insert(RANGE, "bytes=0-9".parse().unwrap());
+        request_header.insert(IF_RANGE, "\"v1\"".parse().unwrap());
+        assert_eq!(evaluate(&request_header, None), RangeRequest::Full);
+    }
+
+    #[test]
+    fn should_make_download_range_header() {
+        let test_cases = vec![
+            (RangeRequest::Full, None),
+            (
+                RangeRequest::Single("bytes=-10".to_string()),
+                Some("bytes=-10"),
+            ),
+            (
+                RangeRequest::Multiple("bytes=20-29,0-9".to_string()),
+                Some("bytes=0-29"),
+            ),
+            (
+                RangeRequest::Multiple("bytes=10-19,30-".to_string()),
+                Some("bytes=10-"),
+            ),
+            (RangeRequest::Multiple("bytes=0-9,-10".to_string()), None),
+        ];
+
+        for (range_request, expected) in test_cases {
+            assert_eq!(range_request.download_range_header().as_deref(), expected);
+        }
+    }
+
+    #[test]
+    fn should_build_multipart_body() {
+        let multipart = Multipart::new(
+            vec![
+                Range {
+                    start: 0,
+                    length: 10,
+                },
+                Range {
+                    start: 20,
+                    length: 10,
+                },
+            ],
+            Some("text/plain".to_string()),
+            100,
+        );
+        assert!(multipart
+            .content_type()
+            .starts_with("multipart/byteranges; boundary="));
+
+        let first_part_header = String::from_utf8(multipart.part_header(0).to_vec()).unwrap();
+        assert!(first_part_header.starts_with("--"));
+        assert!(first_part_header.contains("Content-Type: text/plain\r\n"));
+        assert!(first_part_header.ends_with("Content-Range: bytes 0-9/100\r\n\r\n"));
+
+        let second_part_header = String::from_utf8(multipart.part_header(1).to_vec()).unwrap();
+        assert!(second_part_header.starts_with("\r\n--"));
+        assert!(second_part_header.ends_with("Content-Range: bytes 20-29/100\r\n\r\n"));
+
+        assert_eq!(
+            multipart.body_length(),
+            (first_part_header.len() + second_part_header.len() + multipart.end().len() + 20)
+                as u64
+        );
+
+        // The piece contains the end of the first part and the start of the second part.
+        let piece_ranges = multipart.piece_ranges(5, 20);
+        assert_eq!(piece_ranges.len(), 2);
+        assert!(piece_ranges[0].0.is_none());
+        assert!(piece_ranges[1].0.is_some());
+
+        // The piece is in the gap between the parts.
+        assert!(multipart.piece_ranges(10, 10).is_empty());
+    }
+}
@@ -0,0 +1,399 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::Response;
+use dragonfly_client_config::dfdaemon::{Proxy, RegistryMirror, RegistryMirrorHost};
+use dragonfly_client_core::Result as ClientResult;
+use rustls_pki_types::CertificateDer;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+/// NAMESPACE_QUERY_PARAM is the query parameter of the registry namespace, which is appended
+/// by containerd when pulling the image via the mirror.
+const NAMESPACE_QUERY_PARAM: &str = "ns";
+
+/// Health is the health state of the registry mirror.
+#[derive(Debug, Default)]
+struct Health {
+    /// consecutive_failures is the number of the consecutive failures.
+    consecutive_failures: u32,
+
+    /// unhealthy_until is the time until the registry mirror is unhealthy.
+    unhealthy_until: Option<Instant>,
+}
+
+/// Mirror is the registry mirror with the loaded certificate and the health state.
+#[derive(Debug)]
+pub struct Mirror {
+    /// config is the configuration of the registry mirror.
+    config: RegistryMirror,
+
+    /// cert is the client certificate for the registry mirror.
+    cert: Arc<Option<Vec<CertificateDer<'static>>>>,
+
+    /// health is the health state of the registry mirror, which is shared by the registry
+    /// mirrors with the same address.
+    health: Arc<Mutex<Health>>,
+}
+
+/// Mirror implements the registry mirror.
+impl Mirror {
+    /// new creates a new registry mirror and loads the certificate of the registry mirror.
+    fn new(config: RegistryMirror, health: Arc<Mutex<Health>>) -> Self {
+        let cert = match config.load_cert_der() {
+            Ok(cert) => {
+                info!("load registry cert of {} success", config.addr);
+                Arc::new(cert)
+            }
+            Err(err) => {
+                error!("load registry cert of {} failed: {}", config.addr, err);
+                Arc::new(None)
+            }
+        };
+
+        Self {
+            config,
+            cert,
+            health,
+        }
+    }
+
+    /// addr returns the address of the registry mirror.
+    pub fn addr(&self) -> &str {
+        self.config.addr.as_str()
+    }
+
+    /// cert returns the client certificate for the registry mirror.
+    pub fn cert(&self) -> Arc<Option<Vec<CertificateDer<'static>>>> {
+        self.cert.clone()
+    }
+
+    /// enable_task_id_based_blob_digest returns whether to use the blob digest for the task id
+    /// calculation of the registry mirror.
+    pub fn enable_task_id_based_blob_digest(&self) -> bool {
+        self.config.enable_task_id_based_blob_digest
+    }
+
+    /// is_healthy returns whether the registry mirror is healthy.
+    pub fn is_healthy(&self) -> bool {
+        match self.health.lock().unwrap().unhealthy_until {
+            Some(unhealthy_until) => Instant::now() >= unhealthy_until,
+            None => true,
+        }
+    }
+
+    /// is_failure returns whether the result of the request is the failure of the registry
+    /// mirror. The server errors and the request errors are the failures of the registry mirror.
+    pub fn is_failure(result: &ClientResult<Response>) -> bool {
+        match result {
+            Ok(response) => response.status().is_server_error(),
+            Err(_) => true,
+        }
+    }
+
+    /// report reports the result of the request sent to the registry mirror.
+    pub fn report(&self, result: &ClientResult<Response>) {
+        if Self::is_failure(result) {
+            self.report_failure();
+        } else {
+            self.report_success();
+        }
+    }
+
+    /// report_success resets the health state of the registry mirror.
+    pub fn report_success(&self) {
+        let mut health = self.health.lock().unwrap();
+        if health.unhealthy_until.is_some() {
+            info!("registry mirror {} is recovered", self.addr());
+        }
+
+        health.consecutive_failures = 0;
+        health.unhealthy_until = None;
+    }
+
+    /// report_failure records the failure of the registry mirror, and marks the registry
+    /// mirror as unhealthy if the consecutive failures reach the max number.
+    pub fn report_failure(&self) {
+        let mut health = self.health.lock().unwrap();
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= self.config.max_consecutive_failures {
+            warn!(
+                "registry mirror {} is unhealthy after {} consecutive failures",
+                self.addr(),
+                health.consecutive_failures
+            );
+
+            health.unhealthy_until = Some(Instant::now() + self.config.unhealthy_cooldown);
+        }
+    }
+
+    /// unhealthy_until returns the time until the registry mirror is unhealthy.
+    fn unhealthy_until(&self) -> Option<Instant> {
+        self.health.lock().unwrap().unhealthy_until
+    }
+}
+
+/// RegistryMirrors selects the registry mirror by the namespace of the request, like the
+/// `hosts.toml` of containerd.
+#[derive(Debug)]
+pub struct RegistryMirrors {
+    /// hosts are the registry hosts and their mirrors in the order of priority.
+    hosts: Vec<(RegistryMirrorHost, Vec<Arc<Mirror>>)>,
+
+    /// default is the registry mirror used when no registry host matches the namespace.
+    default: Arc<Mirror>,
+}
+
+/// RegistryMirrors implements the registry mirrors.
+impl RegistryMirrors {
+    /// new creates the registry mirrors by the proxy configuration. The registry mirrors with
+    /// the same address share the health state.
+    pub fn new(config: &Proxy) -> Self {
+        let mut healths: HashMap<String, Arc<Mutex<Health>>> = HashMap::new();
+        let mut new_mirror = |mirror: &RegistryMirror| {
+            let health = healths.entry(mirror.addr.clone()).or_default().clone();
+            Arc::new(Mirror::new(mirror.clone(), health))
+        };
+
+        let default = new_mirror(&config.registry_mirror);
+        let hosts = config
+            .registry_mirrors
+            .iter()
+            .map(|host| {
+                let host_mirrors = host.mirrors.iter().map(&mut new_mirror).collect();
+
+                (host.clone(), host_mirrors)
+            })
+            .collect();
+
+        Self { hosts, default }
+    }
+
+    /// default_mirror returns the registry mirror used when no registry host matches the
+    /// namespace.
+    pub fn default_mirror(&self) -> Arc<Mirror> {
+        self.default.clone()
+    }
+
+    /// select selects the registry mirror by the namespace. The registry host matching the
+    /// namespace is used first, then the `_default` registry host, and the default registry
+    /// mirror at last. The first healthy mirror of the registry host is selected, if all
+    /// mirrors are unhealthy, the mirror which recovers first is selected.
+    pub fn select(&self, namespace: Option<&str>) -> Arc<Mirror> {
+        let Some(mirrors) = self.mirrors(namespace) else {
+            return self.default.clone();
+        };
+
+        if let Some(mirror) = mirrors.iter().find(|mirror| mirror.is_healthy()) {
+            return mirror.clone();
+        }
+
+        mirrors
+            .iter()
+            .min_by_key(|mirror| mirror.unhealthy_until())
+            .cloned()
+            .unwrap_or_else(|| self.default.clone())
+    }
+
+    /// select_next selects the next healthy registry mirror of the namespace to retry the
+    /// request, the registry mirrors which have been tried are skipped.
+    pub fn select_next(
+        &self,
+        namespace: Option<&str>,
+        tried: &[Arc<Mirror>],
+    ) -> Option<Arc<Mirror>> {
+        self.mirrors(namespace)?
+            .iter()
+            .filter(|mirror| !tried.iter().any(|tried| tried.addr() == mirror.addr()))
+            .find(|mirror| mirror.is_healthy())
+            .cloned()
+    }
+
+    /// mirrors returns the registry mirrors of the registry host matching the namespace, the
+    /// `_default` registry host is used if no registry host matches the namespace.
+    fn mirrors(&self, namespace: Option<&str>) -> Option<&[Arc<Mirror>]> {
+        self.hosts
+            .iter()
+            .find(|(host, _)| host.is_match(namespace))
+            .or_else(|| self.hosts.iter().find(|(host, _)| host.is_default()))
+            .map(|(_, mirrors)| mirrors.as_slice())
+    }
+}
+
+/// get_namespace gets the registry namespace from the `ns` query parameter of the uri.
+pub fn get_namespace(uri: &http::Uri) -> Option<String> {
+    url::form_urlencoded::parse(uri.query()?.as_bytes())
+        .find(|(key, _)| key == NAMESPACE_QUERY_PARAM)
+        .map(|(_, value)| value.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_registry_mirrors() -> RegistryMirrors {
+        RegistryMirrors::new(&Proxy {
+            registry_mirror: RegistryMirror {
+                addr: "https://index.docker.io".to_string(),
+                ..Default::default()
+            },
+            registry_mirrors: vec![
+                RegistryMirrorHost {
+                    host: "docker.io".to_string(),
+                    mirrors: vec![
+                        RegistryMirror {
+                            addr: "https://harbor.example.com".to_string(),
+                            enable_task_id_based_blob_digest: true,
+                            ..Default::default()
+                        },
+                        RegistryMirror {
+                            addr: "https://registry-1.docker.io".to_string(),
+                            ..Default::default()
+                        },
+                    ],
+                },
+                RegistryMirrorHost {
+                    host: "_default".to_string(),
+                    mirrors: vec![RegistryMirror {
+                        addr: "https://harbor.example.com".to_string(),
+                        ..Default::default()
+                    }],
+                },
+            ],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn should_get_namespace() {
+        let uri: http::Uri = "/v2/library/alpine/manifests/latest?ns=docker.io"
+            .parse()
+            .unwrap();
+        assert_eq!(get_namespace(&uri), Some("docker.io".to_string()));
+
+        let uri: http::Uri = "/v2/library/alpine/manifests/latest".parse().unwrap();
+        assert_eq!(get_namespace(&uri), None);
+    }
+
+    #[test]
+    fn should_select_registry_mirror_by_namespace() {
+        let registry_mirrors = new_registry_mirrors();
+
+        let mirror = registry_mirrors.select(Some("docker.io"));
+        assert_eq!(mirror.addr(), "https://harbor.example.com");
+        assert!(mirror.enable_task_id_based_blob_digest());
+
+        // The namespace is not matched, use the `_default` registry host. The registry mirrors
+        // with the same address share the health state, but not the configuration.
+        let mirror = registry_mirrors.select(Some("ghcr.io"));
+        assert_eq!(mirror.addr(), "https://harbor.example.com");
+        assert!(!mirror.enable_task_id_based_blob_digest());
+        assert!(Arc::ptr_eq(
+            &mirror.health,
+            &registry_mirrors.select(Some("docker.io")).health
+        ));
+
+        // The registry mirrors without any registry host use the default registry mirror.
+        let registry_mirrors = RegistryMirrors::new(&Proxy::default());
+        let mirror = registry_mirrors.select(Some("docker.io"));
+        assert_eq!(mirror.addr(), "https://index.docker.io");
+    }
+
+    #[test]
+    fn should_fail_over_unhealthy_registry_mirror() {
+        let registry_mirrors = new_registry_mirrors();
+        let mirror = registry_mirrors.select(Some("docker.io"));
+        let max_consecutive_failures = mirror.config.max_consecutive_failures;
+        for _ in 0..max_consecutive_failures - 1 {
+            mirror.report_failure();
+        }
+        assert!(mirror.is_healthy());
+
+        mirror.report_failure();
+        assert!(!mirror.is_healthy());
+        assert_eq!(
+            registry_mirrors.select(Some("docker.io")).addr(),
+            "https://registry-1.docker.io"
+        );
+
+        // If all mirrors are unhealthy, the mirror which recovers first is selected.
+        let fallback = registry_mirrors.select(Some("docker.io"));
+        for _ in 0..max_consecutive_failures {
+            fallback.report_failure();
+        }
+        assert_eq!(
+            registry_mirrors.select(Some("docker.io")).addr(),
+            "https://harbor.example.com"
+        );
+
+        mirror.report_success();
+        assert!(mirror.is_healthy());
+        assert_eq!(
+            registry_mirrors.select(Some("docker.io")).addr(),
+            "https://harbor.example.com"
+        );
+    }
+
+    #[test]
+    fn should_select_next_registry_mirror() {
+        let registry_mirrors = new_registry_mirrors();
+        let mirror = registry_mirrors.select(Some("docker.io"));
+
+        let next = registry_mirrors
+            .select_next(Some("docker.io"), &[mirror.clone()])
+            .unwrap();
+        assert_eq!(next.addr(), "https://registry-1.docker.io");
+        assert!(registry_mirrors
+            .select_next(Some("docker.io"), &[mirror.clone(), next.clone()])
+            .is_none());
+
+        // The unhealthy registry mirror is not selected to retry.
+        for _ in 0..next.config.max_consecutive_failures {
+            next.report_failure();
+        }
+        assert!(registry_mirrors
+            .select_next(Some("docker.io"), &[mirror])
+            .is_none());
+
+        // The registry mirrors without any registry host have no registry mirror to retry.
+        let registry_mirrors = RegistryMirrors::new(&Proxy::default());
+        let mirror = registry_mirrors.select(Some("docker.io"));
+        assert!(registry_mirrors
+            .select_next(Some("docker.io"), &[mirror])
+            .is_none());
+    }
+
+    #[test]
+    fn should_use_configured_unhealthy_threshold() {
+        let registry_mirrors = RegistryMirrors::new(&Proxy {
+            registry_mirror: RegistryMirror {
+                max_consecutive_failures: 1,
+                unhealthy_cooldown: std::time::Duration::ZERO,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mirror = registry_mirrors.default_mirror();
+        mirror.report_failure();
+        assert!(mirror.unhealthy_until().is_some());
+
+        // The registry mirror is tried again after the cooldown.
+        assert!(mirror.is_healthy());
+    }
+}
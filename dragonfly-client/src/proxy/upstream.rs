@@ -0,0 +1,217 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dragonfly_client_config::dfdaemon::UpstreamProxy;
+use dragonfly_client_core::error::{ErrorType, OrErr};
+use dragonfly_client_core::{Error as ClientError, Result as ClientResult};
+use dragonfly_client_util::http::basic_auth;
+use http::Uri;
+use hyper_util::client::legacy::connect::{
+    proxy::{SocksV5, Tunnel},
+    HttpConnector,
+};
+use hyper_util::rt::TokioIo;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tower::Service;
+use tracing::debug;
+
+/// UpstreamConnector connects to the origin via the upstream proxy by the HTTP CONNECT method
+/// or the SOCKS5 protocol, the hosts in the no proxy list are connected directly.
+#[derive(Clone)]
+pub struct UpstreamConnector {
+    /// upstream_proxy is the upstream proxy configuration, None means the origin is
+    /// connected directly.
+    upstream_proxy: Option<UpstreamProxy>,
+
+    /// http is the connector to connect to the origin or the upstream proxy.
+    http: HttpConnector,
+}
+
+/// UpstreamConnector implements the upstream connector.
+impl UpstreamConnector {
+    /// new creates a new upstream connector.
+    pub fn new(upstream_proxy: Option<UpstreamProxy>) -> Self {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_nodelay(true);
+
+        Self {
+            upstream_proxy,
+            http,
+        }
+    }
+
+    /// connect connects to the host and the port of the origin.
+    pub async fn connect(&self, host: &str, port: u16) -> ClientResult<TcpStream> {
+        let dst = format!("http://{}:{}", host, port)
+            .parse::<Uri>()
+            .or_err(ErrorType::ParseError)?;
+
+        Ok(self.connect_uri(dst).await?.into_inner())
+    }
+
+    /// connect_uri connects to the origin by the uri, the port of the uri is required.
+    async fn connect_uri(&self, dst: Uri) -> ClientResult<TokioIo<TcpStream>> {
+        let mut http = self.http.clone();
+        let host = dst.host().unwrap_or_default();
+        let upstream_proxy = match self.upstream_proxy.as_ref() {
+            Some(upstream_proxy) if !upstream_proxy.is_bypassed(host) => upstream_proxy,
+            _ => {
+                return http
+                    .call(dst)
+                    .await
+                    .map_err(|err| ClientError::Unknown(err.to_string()))
+            }
+        };
+
+        let url = upstream_proxy.url()?;
+        let proxy_dst = format!(
+            "http://{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or(1080)
+        )
+        .parse::<Uri>()
+        .or_err(ErrorType::ParseError)?;
+        debug!("connect to {} via upstream proxy {}", dst, proxy_dst);
+
+        let credentials = upstream_proxy.username.as_ref().map(|username| {
+            (
+                username.clone(),
+                upstream_proxy.password.clone().unwrap_or_default(),
+            )
+        });
+
+        match url.scheme() {
+            "socks5" | "socks5h" => {
+                let mut socks = SocksV5::new(proxy_dst, http).local_dns(url.scheme() == "socks5");
+                if let Some((username, password)) = credentials {
+                    socks = socks.with_auth(username, password);
+                }
+
+                socks
+                    .call(dst)
+                    .await
+                    .map_err(|err| ClientError::Unknown(err.to_string()))
+            }
+            _ => {
+                let mut tunnel = Tunnel::new(proxy_dst, http);
+                if let Some((username, password)) = credentials {
+                    tunnel = tunnel.with_auth(
+                        basic_auth::Credentials::new(&username, &password)
+                            .encode()
+                            .parse()
+                            .or_err(ErrorType::ParseError)?,
+                    );
+                }
+
+                tunnel
+                    .call(dst)
+                    .await
+                    .map_err(|err| ClientError::Unknown(err.to_string()))
+            }
+        }
+    }
+}
+
+/// UpstreamConnector implements the connector of the hyper client.
+impl Service<Uri> for UpstreamConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = ClientError;
+    type Future = Pin<Box<dyn Future<Output = ClientResult<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<ClientResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let connector = self.clone();
+        Box::pin(async move {
+            // The port of the destination is required by the upstream proxy.
+            let port = dst.port_u16().unwrap_or(match dst.scheme_str() {
+                Some("https") => 443,
+                _ => 80,
+            });
+
+            let dst = format!(
+                "{}://{}:{}",
+                dst.scheme_str().unwrap_or("http"),
+                dst.host().unwrap_or_default(),
+                port
+            )
+            .parse::<Uri>()
+            .or_err(ErrorType::ParseError)?;
+
+            connector.connect_uri(dst).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn should_connect_via_http_connect_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            stream
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await
+                .unwrap();
+            request
+        });
+
+        let connector = UpstreamConnector::new(Some(UpstreamProxy {
+            addr: format!("http://{}", addr),
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+            no_proxy: vec![],
+        }));
+        connector.connect("example.com", 443).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+        assert!(request
+            .to_lowercase()
+            .contains("proxy-authorization: basic dxnlcjpwyxnz"));
+    }
+
+    #[tokio::test]
+    async fn should_connect_directly_by_no_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // The upstream proxy is unreachable, so the connection succeeds only if the host is
+        // connected directly.
+        let connector = UpstreamConnector::new(Some(UpstreamProxy {
+            addr: "http://127.0.0.1:1".to_string(),
+            no_proxy: vec!["127.0.0.0/8".to_string()],
+            ..Default::default()
+        }));
+        assert!(connector.connect("127.0.0.1", addr.port()).await.is_ok());
+        assert!(connector.connect("example.com", 443).await.is_err());
+    }
+}
@@ -0,0 +1,2178 @@
+/*
+ *     Copyright 2025 The Dragonfly Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::grpc::{scheduler::SchedulerClient, REQUEST_TIMEOUT};
+use crate::resource::parent_selector::CacheParentSelector;
+use dragonfly_api::common::v2::{
+    CachePeer, CacheTask as CommonCacheTask, Hdfs, ObjectStorage, Piece, TrafficType,
+};
+use dragonfly_api::dfdaemon::{
+    self,
+    v2::{download_cache_task_response, DownloadCacheTaskRequest, DownloadCacheTaskResponse},
+};
+use dragonfly_api::errordetails::v2::{Backend, Unknown};
+use dragonfly_api::scheduler::v2::{
+    announce_cache_peer_request, announce_cache_peer_response,
+    download_piece_back_to_source_failed_request, AnnounceCachePeerRequest, DeleteCacheTaskRequest,
+    DownloadCachePeerBackToSourceFailedRequest, DownloadCachePeerBackToSourceFinishedRequest,
+    DownloadCachePeerBackToSourceStartedRequest, DownloadCachePeerFailedRequest,
+    DownloadCachePeerFinishedRequest, DownloadCachePeerStartedRequest,
+    DownloadPieceBackToSourceFailedRequest, DownloadPieceBackToSourceFinishedRequest,
+    DownloadPieceFailedRequest, DownloadPieceFinishedRequest, RegisterCachePeerRequest,
+    RescheduleCachePeerRequest, StatCacheTaskRequest,
+};
+use dragonfly_client_backend::{BackendFactory, StatRequest};
+use dragonfly_client_config::dfdaemon::Config;
+use dragonfly_client_core::{
+    error::{BackendError, DownloadFromParentFailed, ErrorType, OrErr},
+    Error, Result as ClientResult,
+};
+use dragonfly_client_metric::{
+    collect_backend_request_failure_metrics, collect_backend_request_finished_metrics,
+    collect_backend_request_started_metrics,
+};
+use dragonfly_client_storage::{metadata, Storage};
+use dragonfly_client_util::{
+    http::{hashmap_to_headermap, headermap_to_hashmap},
+    id_generator::IDGenerator,
+    shutdown,
+};
+use leaky_bucket::RateLimiter;
+use reqwest::header::HeaderMap;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Instant;
+use tokio::io::AsyncReadExt;
+use tokio::sync::{
+    mpsc::{self, Sender},
+    Mutex, Semaphore,
+};
+use tokio::task::JoinSet;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::{Request, Status};
+use tracing::{debug, error, info, instrument, Instrument};
+
+use super::*;
+
+/// CacheTask represents a cache task manager. The content of the cache task is stored
+/// in the memory cache of the storage, and the metadata is stored in the metadata storage.
+pub struct CacheTask {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
+    /// id_generator is the id generator.
+    pub id_generator: Arc<IDGenerator>,
+
+    /// storage is the local storage.
+    storage: Arc<Storage>,
+
+    /// scheduler_client is the grpc client of the scheduler.
+    pub scheduler_client: Arc<SchedulerClient>,
+
+    /// backend_factory is the backend factory.
+    pub backend_factory: Arc<BackendFactory>,
+
+    /// piece is the piece manager.
+    pub piece: Arc<piece::Piece>,
+
+    /// parent_selector is the cache parent selector.
+    pub parent_selector: Arc<CacheParentSelector<CachePeer>>,
+}
+
+/// CacheTask implements the cache task manager.
+impl CacheTask {
+    /// new returns a new CacheTask.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: Arc<Config>,
+        id_generator: Arc<IDGenerator>,
+        storage: Arc<Storage>,
+        scheduler_client: Arc<SchedulerClient>,
+        backend_factory: Arc<BackendFactory>,
+        download_rate_limiter: Arc<RateLimiter>,
+        upload_rate_limiter: Arc<RateLimiter>,
+        prefetch_rate_limiter: Arc<RateLimiter>,
+        shutdown: shutdown::Shutdown,
+        shutdown_complete_tx: mpsc::UnboundedSender<()>,
+    ) -> ClientResult<Self> {
+        Ok(Self {
+            config: config.clone(),
+            id_generator: id_generator.clone(),
+            storage: storage.clone(),
+            scheduler_client: scheduler_client.clone(),
+            backend_factory: backend_factory.clone(),
+            piece: Arc::new(piece::Piece::new(
+                config.clone(),
+                id_generator.clone(),
+                storage.clone(),
+                backend_factory.clone(),
+                download_rate_limiter,
+                upload_rate_limiter,
+                prefetch_rate_limiter,
+            )?),
+            parent_selector: Arc::new(CacheParentSelector::new(
+                config.clone(),
+                id_generator.clone(),
+                shutdown.clone(),
+                shutdown_complete_tx.clone(),
+            )),
+        })
+    }
+
+    /// get gets the metadata of the cache task.
+    #[instrument(skip_all)]
+    pub fn get(&self, id: &str) -> ClientResult<Option<metadata::CacheTask>> {
+        self.storage.get_cache_task(id)
+    }
+
+    /// download_started updates the metadata of the cache task when the cache task downloads started.
+    #[instrument(skip_all)]
+    pub async fn download_started(
+        &self,
+        id: &str,
+        request: DownloadCacheTaskRequest,
+    ) -> ClientResult<metadata::CacheTask> {
+        // If the cache task is finished and the content is still in the cache, reuse it.
+        if let Some(task) = self.storage.prepare_download_cache_task_started(id).await? {
+            return Ok(task);
+        }
+
+        // Handle the request header.
+        let mut request_header =
+            hashmap_to_headermap(&request.request_header).inspect_err(|err| {
+                error!("convert header: {}", err);
+            })?;
+
+        // Remove the range header to prevent the server from
+        // returning a 206 partial content and returning
+        // a 200 full content.
+        request_header.remove(reqwest::header::RANGE);
+
+        // Head the url to get the content length.
+        let backend = self.backend_factory.build(request.url.as_str())?;
+
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // Collect the backend request started metrics.
+        collect_backend_request_started_metrics(
+            backend.scheme().as_str(),
+            http::Method::HEAD.as_str(),
+        );
+        let response = backend
+            .stat(StatRequest {
+                task_id: id.to_string(),
+                url: request.url,
+                http_header: Some(request_header),
+                timeout: self.config.download.piece_timeout,
+                client_cert: None,
+                object_storage: request.object_storage,
+                hdfs: request.hdfs,
+            })
+            .await
+            .inspect_err(|_err| {
+                // Collect the backend request failure metrics.
+                collect_backend_request_failure_metrics(
+                    backend.scheme().as_str(),
+                    http::Method::HEAD.as_str(),
+                );
+            })?;
+
+        // Check if the status code is success.
+        if !response.success {
+            // Collect the backend request failure metrics.
+            collect_backend_request_failure_metrics(
+                backend.scheme().as_str(),
+                http::Method::HEAD.as_str(),
+            );
+
+            return Err(Error::BackendError(Box::new(BackendError {
+                message: response.error_message.unwrap_or_default(),
+                status_code: response.http_status_code,
+                header: response.http_header,
+            })));
+        }
+
+        // Collect the backend request finished metrics.
+        collect_backend_request_finished_metrics(
+            backend.scheme().as_str(),
+            http::Method::HEAD.as_str(),
+            start_time.elapsed(),
+        );
+
+        let content_length = match response.content_length {
+            Some(content_length) => content_length,
+            None => return Err(Error::InvalidContentLength),
+        };
+
+        let piece_length = match request.piece_length {
+            Some(piece_length) => self
+                .piece
+                .calculate_piece_length(piece::PieceLengthStrategy::FixedPieceLength(piece_length)),
+            None => {
+                self.piece
+                    .calculate_piece_length(piece::PieceLengthStrategy::OptimizeByFileLength(
+                        content_length,
+                    ))
+            }
+        };
+
+        self.storage
+            .download_cache_task_started(id, piece_length, content_length, response.http_header)
+            .await
+    }
+
+    /// download_finished updates the metadata of the cache task when the cache task downloads finished.
+    #[instrument(skip_all)]
+    pub fn download_finished(&self, id: &str) -> ClientResult<metadata::CacheTask> {
+        self.storage.download_cache_task_finished(id)
+    }
+
+    /// download_failed updates the metadata of the cache task when the cache task downloads failed.
+    #[instrument(skip_all)]
+    pub async fn download_failed(&self, id: &str) -> ClientResult<()> {
+        self.storage
+            .download_cache_task_failed(id)
+            .await
+            .map(|_| ())
+    }
+
+    /// copy_task copies the cache task content from the memory to the destination.
+    #[instrument(skip_all)]
+    pub async fn copy_task(&self, id: &str, to: &Path) -> ClientResult<()> {
+        self.storage.copy_cache_task(id, to).await
+    }
+
+    /// download downloads a cache task.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    pub async fn download(
+        &self,
+        task: &metadata::CacheTask,
+        host_id: &str,
+        peer_id: &str,
+        request: DownloadCacheTaskRequest,
+        download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+    ) -> ClientResult<()> {
+        // Get the id of the cache task.
+        let task_id = task.id.as_str();
+
+        // Get the content length from the cache task.
+        let Some(content_length) = task.content_length() else {
+            error!("content length not found");
+            return Err(Error::InvalidContentLength);
+        };
+
+        // Get the piece length from the cache task.
+        let Some(piece_length) = task.piece_length() else {
+            error!("piece length not found");
+            return Err(Error::InvalidPieceLength);
+        };
+
+        // Calculate the interested pieces to download.
+        let interested_pieces =
+            match self
+                .piece
+                .calculate_interested(piece_length, content_length, request.range)
+            {
+                Ok(interested_pieces) => interested_pieces,
+                Err(err) => {
+                    error!("calculate interested pieces error: {:?}", err);
+                    return Err(err);
+                }
+            };
+        debug!(
+            "interested pieces: {:?}",
+            interested_pieces
+                .iter()
+                .map(|p| p.number)
+                .collect::<Vec<u32>>()
+        );
+
+        // Construct the pieces for the download cache task started response.
+        let mut pieces = Vec::new();
+        for interested_piece in interested_pieces.clone() {
+            pieces.push(Piece {
+                number: interested_piece.number,
+                parent_id: interested_piece.parent_id.clone(),
+                offset: interested_piece.offset,
+                length: interested_piece.length,
+                digest: interested_piece.digest.clone(),
+                content: None,
+                traffic_type: None,
+                cost: interested_piece.prost_cost(),
+                created_at: Some(prost_wkt_types::Timestamp::from(
+                    interested_piece.created_at,
+                )),
+            });
+        }
+
+        // Send the download cache task started request.
+        download_progress_tx
+            .send_timeout(
+                Ok(DownloadCacheTaskResponse {
+                    host_id: host_id.to_string(),
+                    task_id: task_id.to_string(),
+                    peer_id: peer_id.to_string(),
+                    response: Some(
+                        download_cache_task_response::Response::DownloadCacheTaskStartedResponse(
+                            dfdaemon::v2::DownloadCacheTaskStartedResponse {
+                                content_length,
+                                range: request.range,
+                                response_header: task.response_header.clone(),
+                                pieces,
+                                is_finished: task.is_finished(),
+                            },
+                        ),
+                    ),
+                }),
+                REQUEST_TIMEOUT,
+            )
+            .await
+            .inspect_err(|err| {
+                error!("send DownloadCacheTaskStartedResponse failed: {:?}", err);
+            })?;
+
+        // Download the pieces from the local.
+        debug!("download the cache pieces from local");
+        let finished_pieces = match self
+            .download_partial_from_local(
+                task,
+                host_id,
+                peer_id,
+                request.need_piece_content,
+                interested_pieces.clone(),
+                download_progress_tx.clone(),
+            )
+            .await
+        {
+            Ok(finished_pieces) => finished_pieces,
+            Err(err) => {
+                error!("download from local error: {:?}", err);
+                return Err(err);
+            }
+        };
+
+        // Remove the finished pieces from the pieces.
+        let interested_pieces = self
+            .piece
+            .remove_finished_from_interested(finished_pieces, interested_pieces);
+        info!(
+            "interested pieces after downloading from local: {:?}",
+            interested_pieces
+                .iter()
+                .map(|p| p.number)
+                .collect::<Vec<u32>>()
+        );
+
+        // Check if all pieces are downloaded.
+        if interested_pieces.is_empty() {
+            info!("all cache pieces are downloaded from local");
+            return Ok(());
+        };
+        debug!("download the cache pieces with scheduler");
+
+        // Download the pieces with scheduler.
+        let finished_pieces = match self
+            .download_partial_with_scheduler(
+                task,
+                host_id,
+                peer_id,
+                interested_pieces.clone(),
+                request.clone(),
+                download_progress_tx.clone(),
+            )
+            .await
+        {
+            Ok(finished_pieces) => finished_pieces,
+            Err(err) => {
+                error!("download with scheduler error: {:?}", err);
+
+                // If disable back-to-source is true, return an error directly.
+                if request.disable_back_to_source {
+                    error!(
+                        "download back-to-source is disabled, download with scheduler error: {:?}",
+                        err
+                    );
+                    return Err(Error::Unknown("download failed".to_string()));
+                };
+
+                // Download the pieces from the source.
+                if let Err(err) = self
+                    .download_partial_from_source(
+                        task,
+                        host_id,
+                        peer_id,
+                        interested_pieces.clone(),
+                        request.clone(),
+                        download_progress_tx.clone(),
+                    )
+                    .await
+                {
+                    error!("download from source error: {:?}", err);
+                    return Err(err);
+                }
+
+                info!("all cache pieces are downloaded from source");
+                return Ok(());
+            }
+        };
+
+        // Remove the finished pieces from the pieces.
+        let interested_pieces = self
+            .piece
+            .remove_finished_from_interested(finished_pieces, interested_pieces);
+        info!(
+            "interested pieces after downloading from scheduler: {:?}",
+            interested_pieces
+                .iter()
+                .map(|p| p.number)
+                .collect::<Vec<u32>>()
+        );
+
+        // Check if all pieces are downloaded.
+        if interested_pieces.is_empty() {
+            info!("all cache pieces are downloaded with scheduler");
+            return Ok(());
+        };
+
+        // If disable back-to-source is true, return an error directly.
+        if request.disable_back_to_source {
+            error!("download back-to-source is disabled");
+            return Err(Error::Unknown("download failed".to_string()));
+        };
+
+        // Download the pieces from the source.
+        if let Err(err) = self
+            .download_partial_from_source(
+                task,
+                host_id,
+                peer_id,
+                interested_pieces.clone(),
+                request.clone(),
+                download_progress_tx.clone(),
+            )
+            .await
+        {
+            error!("download from source error: {:?}", err);
+            return Err(err);
+        }
+
+        info!("all cache pieces are downloaded from source");
+        Ok(())
+    }
+
+    /// download_partial_with_scheduler downloads a partial cache task with scheduler.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    async fn download_partial_with_scheduler(
+        &self,
+        task: &metadata::CacheTask,
+        host_id: &str,
+        peer_id: &str,
+        interested_pieces: Vec<metadata::Piece>,
+        request: DownloadCacheTaskRequest,
+        download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+    ) -> ClientResult<Vec<metadata::Piece>> {
+        // Get the id of the cache task.
+        let task_id = task.id.as_str();
+
+        // Initialize the schedule count.
+        let mut schedule_count = 0;
+
+        // Initialize the finished pieces.
+        let mut finished_pieces: Vec<metadata::Piece> = Vec::new();
+
+        // Initialize stream channel.
+        let (in_stream_tx, in_stream_rx) = mpsc::channel(4);
+
+        // Send the register cache peer request.
+        in_stream_tx
+            .send_timeout(
+                AnnounceCachePeerRequest {
+                    host_id: host_id.to_string(),
+                    task_id: task_id.to_string(),
+                    peer_id: peer_id.to_string(),
+                    request: Some(
+                        announce_cache_peer_request::Request::RegisterCachePeerRequest(
+                            RegisterCachePeerRequest {
+                                url: request.url.clone(),
+                                digest: request.digest.clone(),
+                                range: request.range,
+                                r#type: request.r#type,
+                                tag: request.tag.clone(),
+                                application: request.application.clone(),
+                                priority: request.priority,
+                                filtered_query_params: request.filtered_query_params.clone(),
+                                request_header: request.request_header.clone(),
+                                piece_length: request.piece_length,
+                                output_path: request.output_path.clone(),
+                                timeout: request.timeout,
+                                disable_back_to_source: request.disable_back_to_source,
+                                need_back_to_source: request.need_back_to_source,
+                                certificate_chain: request.certificate_chain.clone(),
+                                prefetch: request.prefetch,
+                                object_storage: request.object_storage.clone(),
+                                hdfs: request.hdfs.clone(),
+                                is_prefetch: request.is_prefetch,
+                                need_piece_content: request.need_piece_content,
+                                content_for_calculating_task_id: request
+                                    .content_for_calculating_task_id
+                                    .clone(),
+                                remote_ip: request.remote_ip.clone(),
+                                concurrent_piece_count: Some(
+                                    self.config.download.concurrent_piece_count,
+                                ),
+                                actual_piece_length: task.piece_length(),
+                                actual_content_length: task.content_length(),
+                                actual_piece_count: task.piece_count(),
+                            },
+                        ),
+                    ),
+                },
+                REQUEST_TIMEOUT,
+            )
+            .await
+            .inspect_err(|err| {
+                error!("send RegisterCachePeerRequest failed: {:?}", err);
+            })?;
+        debug!("sent RegisterCachePeerRequest");
+
+        // Initialize the stream.
+        let in_stream = ReceiverStream::new(in_stream_rx);
+        let request_stream = Request::new(in_stream);
+        let response = self
+            .scheduler_client
+            .announce_cache_peer(task_id, peer_id, request_stream)
+            .await
+            .inspect_err(|err| {
+                error!("announce cache peer failed: {:?}", err);
+            })?;
+        debug!("announced cache peer has been connected");
+
+        let out_stream = response
+            .into_inner()
+            .timeout(self.config.scheduler.schedule_timeout);
+        tokio::pin!(out_stream);
+
+        while let Some(message) = out_stream.try_next().await.inspect_err(|err| {
+            error!("receive message from scheduler failed: {:?}", err);
+        })? {
+            // Check if the schedule count is exceeded.
+            schedule_count += 1;
+            if schedule_count > self.config.scheduler.max_schedule_count {
+                in_stream_tx
+                    .send_timeout(
+                        AnnounceCachePeerRequest {
+                            host_id: host_id.to_string(),
+                            task_id: task_id.to_string(),
+                            peer_id: peer_id.to_string(),
+                            request: Some(
+                                announce_cache_peer_request::Request::DownloadCachePeerFailedRequest(
+                                    DownloadCachePeerFailedRequest {
+                                        description: Some(
+                                            "max schedule count exceeded".to_string(),
+                                        ),
+                                    },
+                                ),
+                            ),
+                        },
+                        REQUEST_TIMEOUT,
+                    )
+                    .await
+                    .unwrap_or_else(|err| {
+                        error!("send DownloadCachePeerFailedRequest failed: {:?}", err)
+                    });
+                debug!("sent DownloadCachePeerFailedRequest");
+
+                // Wait for the latest message to be sent.
+                in_stream_tx.closed().await;
+                return Ok(finished_pieces);
+            }
+
+            let response = message?.response.ok_or(Error::UnexpectedResponse)?;
+            match response {
+                announce_cache_peer_response::Response::EmptyCacheTaskResponse(response) => {
+                    // If the cache task is empty, return an empty vector.
+                    info!("empty cache task response: {:?}", response);
+
+                    // Send the download cache peer started request.
+                    in_stream_tx
+                        .send_timeout(
+                            AnnounceCachePeerRequest {
+                                host_id: host_id.to_string(),
+                                task_id: task_id.to_string(),
+                                peer_id: peer_id.to_string(),
+                                request: Some(
+                                    announce_cache_peer_request::Request::DownloadCachePeerStartedRequest(
+                                        DownloadCachePeerStartedRequest {},
+                                    ),
+                                ),
+                            },
+                            REQUEST_TIMEOUT,
+                        )
+                        .await
+                        .inspect_err(|err| {
+                            error!("send DownloadCachePeerStartedRequest failed: {:?}", err);
+                        })?;
+                    debug!("sent DownloadCachePeerStartedRequest");
+
+                    // Send the download cache peer finished request.
+                    in_stream_tx
+                        .send_timeout(
+                            AnnounceCachePeerRequest {
+                                host_id: host_id.to_string(),
+                                task_id: task_id.to_string(),
+                                peer_id: peer_id.to_string(),
+                                request: Some(
+                                    announce_cache_peer_request::Request::DownloadCachePeerFinishedRequest(
+                                        DownloadCachePeerFinishedRequest {},
+                                    ),
+                                ),
+                            },
+                            REQUEST_TIMEOUT,
+                        )
+                        .await
+                        .inspect_err(|err| {
+                            error!("send DownloadCachePeerFinishedRequest failed: {:?}", err);
+                        })?;
+                    debug!("sent DownloadCachePeerFinishedRequest");
+
+                    // Wait for the latest message to be sent.
+                    in_stream_tx.closed().await;
+                    return Ok(Vec::new());
+                }
+                announce_cache_peer_response::Response::NormalCacheTaskResponse(response) => {
+                    // If the cache task is normal, download the pieces from the parent.
+                    info!(
+                        "normal cache task response: {:?}",
+                        response
+                            .candidate_parents
+                            .iter()
+                            .map(|p| p.id.clone())
+                            .collect::<Vec<String>>()
+                    );
+
+                    // Send the download cache peer started request.
+                    match in_stream_tx
+                        .send_timeout(
+                            AnnounceCachePeerRequest {
+                                host_id: host_id.to_string(),
+                                task_id: task_id.to_string(),
+                                peer_id: peer_id.to_string(),
+                                request: Some(
+                                    announce_cache_peer_request::Request::DownloadCachePeerStartedRequest(
+                                        DownloadCachePeerStartedRequest {},
+                                    ),
+                                ),
+                            },
+                            REQUEST_TIMEOUT,
+                        )
+                        .await
+                    {
+                        Ok(_) => debug!("sent DownloadCachePeerStartedRequest"),
+                        Err(err) => {
+                            error!("send DownloadCachePeerStartedRequest failed: {:?}", err);
+                            return Ok(finished_pieces);
+                        }
+                    };
+
+                    // Remove the finished pieces from the pieces.
+                    let remaining_interested_pieces = self.piece.remove_finished_from_interested(
+                        finished_pieces.clone(),
+                        interested_pieces.clone(),
+                    );
+
+                    // Download the pieces from the parent.
+                    let partial_finished_pieces = match self
+                        .download_partial_with_scheduler_from_parent(
+                            task,
+                            host_id,
+                            peer_id,
+                            response.candidate_parents.clone(),
+                            remaining_interested_pieces.clone(),
+                            request.is_prefetch,
+                            request.need_piece_content,
+                            download_progress_tx.clone(),
+                            in_stream_tx.clone(),
+                        )
+                        .await
+                    {
+                        Ok(partial_finished_pieces) => {
+                            debug!(
+                                "schedule {} finished {} cache pieces from parent",
+                                schedule_count,
+                                partial_finished_pieces.len()
+                            );
+
+                            partial_finished_pieces
+                        }
+                        Err(err) => {
+                            error!("download from parent error: {:?}", err);
+                            Vec::new()
+                        }
+                    };
+
+                    // Merge the finished pieces.
+                    finished_pieces = self.piece.merge_finished_pieces(
+                        finished_pieces.clone(),
+                        partial_finished_pieces.clone(),
+                    );
+
+                    // Check if all pieces are downloaded.
+                    if finished_pieces.len() == interested_pieces.len() {
+                        // Send the download cache peer finished request.
+                        match in_stream_tx
+                            .send_timeout(
+                                AnnounceCachePeerRequest {
+                                    host_id: host_id.to_string(),
+                                    task_id: task_id.to_string(),
+                                    peer_id: peer_id.to_string(),
+                                    request: Some(
+                                        announce_cache_peer_request::Request::DownloadCachePeerFinishedRequest(
+                                            DownloadCachePeerFinishedRequest {},
+                                        ),
+                                    ),
+                                },
+                                REQUEST_TIMEOUT,
+                            )
+                            .await
+                        {
+                            Ok(_) => debug!("sent DownloadCachePeerFinishedRequest"),
+                            Err(err) => {
+                                error!("send DownloadCachePeerFinishedRequest failed: {:?}", err);
+                            }
+                        }
+
+                        // Wait for the latest message to be sent.
+                        in_stream_tx.closed().await;
+                        return Ok(finished_pieces);
+                    }
+
+                    // If not all pieces are downloaded, send the reschedule request.
+                    match in_stream_tx
+                        .send_timeout(
+                            AnnounceCachePeerRequest {
+                                host_id: host_id.to_string(),
+                                task_id: task_id.to_string(),
+                                peer_id: peer_id.to_string(),
+                                request: Some(
+                                    announce_cache_peer_request::Request::RescheduleCachePeerRequest(
+                                        RescheduleCachePeerRequest {
+                                            candidate_parents: response.candidate_parents,
+                                            description: Some(
+                                                "not all cache pieces are downloaded from parent"
+                                                    .to_string(),
+                                            ),
+                                        },
+                                    ),
+                                ),
+                            },
+                            REQUEST_TIMEOUT,
+                        )
+                        .await
+                    {
+                        Ok(_) => debug!("sent RescheduleCachePeerRequest"),
+                        Err(err) => {
+                            error!("send RescheduleCachePeerRequest failed: {:?}", err);
+                            return Ok(finished_pieces);
+                        }
+                    };
+                }
+                announce_cache_peer_response::Response::NeedBackToSourceResponse(response) => {
+                    // If the cache task need back to source, download the pieces from the source.
+                    info!("need back to source response: {:?}", response);
+
+                    // Send the download cache peer back-to-source request.
+                    match in_stream_tx
+                        .send_timeout(AnnounceCachePeerRequest {
+                            host_id: host_id.to_string(),
+                            task_id: task_id.to_string(),
+                            peer_id: peer_id.to_string(),
+                            request: Some(
+                                announce_cache_peer_request::Request::DownloadCachePeerBackToSourceStartedRequest(
+                                    DownloadCachePeerBackToSourceStartedRequest {
+                                        description: None,
+                                    },
+                                ),
+                            ),
+                        }, REQUEST_TIMEOUT)
+                    .await {
+                        Ok(_) => debug!("sent DownloadCachePeerBackToSourceStartedRequest"),
+                        Err(err) => {
+                            error!("send DownloadCachePeerBackToSourceStartedRequest failed: {:?}", err);
+                            return Ok(finished_pieces);
+                        }
+                    };
+
+                    // Remove the finished pieces from the pieces.
+                    let remaining_interested_pieces = self.piece.remove_finished_from_interested(
+                        finished_pieces.clone(),
+                        interested_pieces.clone(),
+                    );
+
+                    // Download the pieces from the source.
+                    let partial_finished_pieces = match self
+                        .download_partial_with_scheduler_from_source(
+                            task,
+                            host_id,
+                            peer_id,
+                            remaining_interested_pieces.clone(),
+                            request.clone(),
+                            download_progress_tx.clone(),
+                            in_stream_tx.clone(),
+                        )
+                        .await
+                    {
+                        Ok(finished_pieces) => finished_pieces,
+                        Err(err) => {
+                            in_stream_tx
+                                .send_timeout(AnnounceCachePeerRequest {
+                                    host_id: host_id.to_string(),
+                                    task_id: task_id.to_string(),
+                                    peer_id: peer_id.to_string(),
+                                    request: Some(
+                                        announce_cache_peer_request::Request::DownloadCachePeerBackToSourceFailedRequest(
+                                            DownloadCachePeerBackToSourceFailedRequest {
+                                                description: Some(err.to_string()),
+                                            },
+                                        ),
+                                    ),
+                                }, REQUEST_TIMEOUT)
+                                .await
+                                .unwrap_or_else(|err| {
+                                    error!("send DownloadCachePeerBackToSourceFailedRequest failed: {:?}", err)
+                                });
+                            debug!("sent DownloadCachePeerBackToSourceFailedRequest");
+
+                            // Wait for the latest message to be sent.
+                            in_stream_tx.closed().await;
+                            return Ok(finished_pieces);
+                        }
+                    };
+
+                    // Merge the finished pieces.
+                    finished_pieces = self.piece.merge_finished_pieces(
+                        finished_pieces.clone(),
+                        partial_finished_pieces.clone(),
+                    );
+
+                    if partial_finished_pieces.len() == remaining_interested_pieces.len() {
+                        // Send the download cache peer finished request.
+                        match in_stream_tx
+                            .send_timeout(
+                                AnnounceCachePeerRequest {
+                                    host_id: host_id.to_string(),
+                                    task_id: task_id.to_string(),
+                                    peer_id: peer_id.to_string(),
+                                    request: Some(
+                                        announce_cache_peer_request::Request::DownloadCachePeerBackToSourceFinishedRequest(
+                                            DownloadCachePeerBackToSourceFinishedRequest {},
+                                        ),
+                                    ),
+                                },
+                                REQUEST_TIMEOUT,
+                            )
+                            .await
+                        {
+                            Ok(_) => debug!("sent DownloadCachePeerBackToSourceFinishedRequest"),
+                            Err(err) => {
+                                error!("send DownloadCachePeerBackToSourceFinishedRequest failed: {:?}", err);
+                            }
+                        }
+
+                        // Wait for the latest message to be sent.
+                        in_stream_tx.closed().await;
+                        return Ok(finished_pieces);
+                    }
+
+                    match in_stream_tx
+                        .send_timeout(AnnounceCachePeerRequest {
+                            host_id: host_id.to_string(),
+                            task_id: task_id.to_string(),
+                            peer_id: peer_id.to_string(),
+                            request: Some(
+                                announce_cache_peer_request::Request::DownloadCachePeerBackToSourceFailedRequest(
+                                    DownloadCachePeerBackToSourceFailedRequest {
+                                        description: Some("not all cache pieces are downloaded from source".to_string()),
+                                    },
+                                ),
+                            ),
+                        }, REQUEST_TIMEOUT)
+                    .await {
+                        Ok(_) => debug!("sent DownloadCachePeerBackToSourceFailedRequest"),
+                        Err(err) => {
+                            error!("send DownloadCachePeerBackToSourceFailedRequest failed: {:?}", err);
+                        }
+                    }
+
+                    // Wait for the latest message to be sent.
+                    in_stream_tx.closed().await;
+                    return Ok(finished_pieces);
+                }
+            }
+        }
+
+        // If the stream is finished abnormally, return an error.
+        error!("stream is finished abnormally");
+        Ok(finished_pieces)
+    }
+
+    /// download_partial_with_scheduler_from_parent downloads a partial cache task with scheduler from a parent.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    async fn download_partial_with_scheduler_from_parent(
+        &self,
+        task: &metadata::CacheTask,
+        host_id: &str,
+        peer_id: &str,
+        parents: Vec<CachePeer>,
+        interested_pieces: Vec<metadata::Piece>,
+        is_prefetch: bool,
+        need_piece_content: bool,
+        download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+        in_stream_tx: Sender<AnnounceCachePeerRequest>,
+    ) -> ClientResult<Vec<metadata::Piece>> {
+        // Get the id of the cache task.
+        let task_id = task.id.as_str();
+
+        // Register the parents for syncing host info.
+        self.parent_selector
+            .register(&parents)
+            .await
+            .inspect_err(|err| {
+                error!("register parents for syncing host info failed: {:?}", err);
+            })?;
+
+        // Clean up the parents in parent selector when the function returns.
+        let parents_clone = parents.clone();
+        let _guard = scopeguard::guard((), |_| {
+            self.parent_selector.unregister(&parents_clone);
+        });
+
+        // Initialize the piece collector.
+        let piece_collector = piece_collector::CachePieceCollector::new(
+            self.config.clone(),
+            host_id,
+            task_id,
+            interested_pieces.clone(),
+            parents
+                .into_iter()
+                .map(|peer| piece_collector::CollectedParent {
+                    id: peer.id,
+                    host: peer.host,
+                    download_ip: None,
+                    download_tcp_port: None,
+                    download_quic_port: None,
+                })
+                .collect(),
+        )
+        .await;
+        let mut piece_collector_rx = piece_collector.run().await;
+
+        // Initialize the interrupt. If download from parent failed with scheduler or download
+        // progress, interrupt the collector and return the finished pieces.
+        let interrupt = Arc::new(AtomicBool::new(false));
+
+        // Initialize the finished pieces.
+        let finished_pieces = Arc::new(Mutex::new(Vec::new()));
+
+        // Initialize the join set.
+        let mut join_set = JoinSet::new();
+        let semaphore = Arc::new(Semaphore::new(
+            self.config.download.concurrent_piece_count as usize,
+        ));
+        while let Some(collect_piece) = piece_collector_rx.recv().await {
+            if interrupt.load(Ordering::SeqCst) {
+                // If the interrupt is true, break the collector loop.
+                debug!("interrupt the cache piece collector");
+                drop(piece_collector_rx);
+                break;
+            }
+
+            async fn download_from_parent(
+                task_id: String,
+                host_id: String,
+                peer_id: String,
+                number: u32,
+                length: u64,
+                parents: Vec<piece_collector::CollectedParent>,
+                piece_manager: Arc<piece::Piece>,
+                download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+                in_stream_tx: Sender<AnnounceCachePeerRequest>,
+                interrupt: Arc<AtomicBool>,
+                finished_pieces: Arc<Mutex<Vec<metadata::Piece>>>,
+                is_prefetch: bool,
+                need_piece_content: bool,
+                parent_selector: Arc<CacheParentSelector<CachePeer>>,
+            ) -> ClientResult<metadata::Piece> {
+                let piece_id = piece_manager.cache_id(task_id.as_str(), number);
+                let parent = parent_selector.select(parents);
+
+                info!(
+                    "start to download cache piece {} from parent {:?}",
+                    piece_id,
+                    parent.id.clone()
+                );
+
+                let metadata = piece_manager
+                    .download_cache_from_parent(
+                        piece_id.as_str(),
+                        host_id.as_str(),
+                        task_id.as_str(),
+                        number,
+                        length,
+                        parent.clone(),
+                        is_prefetch,
+                    )
+                    .await
+                    .map_err(|err| {
+                        error!(
+                            "download cache piece {} from parent {:?} error: {:?}",
+                            piece_id,
+                            parent.id.clone(),
+                            err
+                        );
+                        Error::DownloadFromParentFailed(DownloadFromParentFailed {
+                            piece_number: number,
+                            parent_id: parent.id.clone(),
+                        })
+                    })?;
+
+                // Construct the piece.
+                let piece = Piece {
+                    number: metadata.number,
+                    parent_id: metadata.parent_id.clone(),
+                    offset: metadata.offset,
+                    length: metadata.length,
+                    digest: metadata.digest.clone(),
+                    content: None,
+                    traffic_type: Some(TrafficType::RemotePeer as i32),
+                    cost: metadata.prost_cost(),
+                    created_at: Some(prost_wkt_types::Timestamp::from(metadata.created_at)),
+                };
+
+                // If need_piece_content is true, read the piece content from the local.
+                let mut response_piece = piece.clone();
+                if need_piece_content {
+                    let mut reader = piece_manager
+                        .download_cache_from_local_into_async_read(
+                            piece_id.as_str(),
+                            task_id.as_str(),
+                            metadata.length,
+                            None,
+                            true,
+                            false,
+                        )
+                        .await
+                        .inspect_err(|err| {
+                            error!("read cache piece {} failed: {:?}", piece_id, err);
+                            interrupt.store(true, Ordering::SeqCst);
+                        })?;
+
+                    let mut content = vec![0; metadata.length as usize];
+                    reader.read_exact(&mut content).await.inspect_err(|err| {
+                        error!("read cache piece {} failed: {:?}", piece_id, err);
+                        interrupt.store(true, Ordering::SeqCst);
+                    })?;
+
+                    response_piece.content = Some(content);
+                }
+
+                // Send the download progress.
+                download_progress_tx
+                    .send_timeout(
+                        Ok(DownloadCacheTaskResponse {
+                            host_id: host_id.to_string(),
+                            task_id: task_id.to_string(),
+                            peer_id: peer_id.to_string(),
+                            response: Some(
+                                download_cache_task_response::Response::DownloadPieceFinishedResponse(
+                                    dfdaemon::v2::DownloadPieceFinishedResponse {
+                                        piece: Some(response_piece),
+                                    },
+                                ),
+                            ),
+                        }),
+                        REQUEST_TIMEOUT,
+                    )
+                    .await
+                    .unwrap_or_else(|err| {
+                        error!(
+                            "send DownloadPieceFinishedResponse for cache piece {} failed: {:?}",
+                            piece_id, err
+                        );
+                        interrupt.store(true, Ordering::SeqCst);
+                    });
+
+                // Send the download piece finished request.
+                in_stream_tx
+                    .send_timeout(
+                        AnnounceCachePeerRequest {
+                            host_id: host_id.to_string(),
+                            task_id: task_id.to_string(),
+                            peer_id: peer_id.to_string(),
+                            request: Some(
+                                announce_cache_peer_request::Request::DownloadPieceFinishedRequest(
+                                    DownloadPieceFinishedRequest { piece: Some(piece) },
+                                ),
+                            ),
+                        },
+                        REQUEST_TIMEOUT,
+                    )
+                    .await
+                    .unwrap_or_else(|err| {
+                        error!(
+                            "send DownloadPieceFinishedRequest for cache piece {} failed: {:?}",
+                            piece_id, err
+                        );
+                        interrupt.store(true, Ordering::SeqCst);
+                    });
+
+                info!(
+                    "finished cache piece {} from parent {:?}",
+                    piece_id, metadata.parent_id,
+                );
+
+                let mut finished_pieces = finished_pieces.lock().await;
+                finished_pieces.push(metadata.clone());
+
+                Ok(metadata)
+            }
+
+            let task_id = task_id.to_string();
+            let host_id = host_id.to_string();
+            let peer_id = peer_id.to_string();
+            let piece_manager = self.piece.clone();
+            let download_progress_tx = download_progress_tx.clone();
+            let in_stream_tx = in_stream_tx.clone();
+            let interrupt = interrupt.clone();
+            let finished_pieces = finished_pieces.clone();
+            let parent_selector = self.parent_selector.clone();
+            let permit = semaphore.clone().acquire_owned().await.unwrap();
+            join_set.spawn(
+                async move {
+                    let _permit = permit;
+                    download_from_parent(
+                        task_id,
+                        host_id,
+                        peer_id,
+                        collect_piece.number,
+                        collect_piece.length,
+                        collect_piece.parents,
+                        piece_manager,
+                        download_progress_tx,
+                        in_stream_tx,
+                        interrupt,
+                        finished_pieces,
+                        is_prefetch,
+                        need_piece_content,
+                        parent_selector,
+                    )
+                    .await
+                }
+                .in_current_span(),
+            );
+        }
+
+        // Wait for the pieces to be downloaded.
+        while let Some(message) = join_set
+            .join_next()
+            .await
+            .transpose()
+            .or_err(ErrorType::AsyncRuntimeError)?
+        {
+            match message {
+                Ok(_) => {}
+                Err(Error::DownloadFromParentFailed(err)) => {
+                    let (piece_number, parent_id) = (err.piece_number, err.parent_id);
+
+                    // Send the download piece failed request.
+                    in_stream_tx
+                        .send_timeout(
+                            AnnounceCachePeerRequest {
+                                host_id: host_id.to_string(),
+                                task_id: task_id.to_string(),
+                                peer_id: peer_id.to_string(),
+                                request: Some(
+                                    announce_cache_peer_request::Request::DownloadPieceFailedRequest(
+                                        DownloadPieceFailedRequest {
+                                            piece_number: Some(err.piece_number),
+                                            parent_id,
+                                            temporary: true,
+                                        },
+                                    ),
+                                ),
+                            },
+                            REQUEST_TIMEOUT,
+                        )
+                        .await
+                        .unwrap_or_else(|err| {
+                            error!(
+                                "send DownloadPieceFailedRequest for cache piece {} failed: {:?}",
+                                self.piece.cache_id(task_id, piece_number),
+                                err
+                            )
+                        });
+
+                    // If the download failed from the parent, continue to download the next
+                    // piece and ignore the error.
+                    continue;
+                }
+                Err(Error::SendTimeout) => {
+                    join_set.shutdown().await;
+
+                    // If the send timeout with scheduler or download progress, return the finished pieces.
+                    // It will stop the download from the parent with scheduler
+                    // and download from the source directly from middle.
+                    let finished_pieces = finished_pieces.lock().await.clone();
+                    return Ok(finished_pieces);
+                }
+                Err(err) => {
+                    error!("download from parent error: {:?}", err);
+
+                    // If the unknown error occurred, continue to download the next piece and
+                    // ignore the error.
+                    continue;
+                }
+            }
+        }
+
+        let finished_pieces = finished_pieces.lock().await.clone();
+        Ok(finished_pieces)
+    }
+
+    /// download_partial_with_scheduler_from_source downloads a partial cache task with scheduler from the source.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    async fn download_partial_with_scheduler_from_source(
+        &self,
+        task: &metadata::CacheTask,
+        host_id: &str,
+        peer_id: &str,
+        interested_pieces: Vec<metadata::Piece>,
+        request: DownloadCacheTaskRequest,
+        download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+        in_stream_tx: Sender<AnnounceCachePeerRequest>,
+    ) -> ClientResult<Vec<metadata::Piece>> {
+        // Get the id of the cache task.
+        let task_id = task.id.as_str();
+
+        // Convert the header.
+        let request_header: HeaderMap = (&request.request_header)
+            .try_into()
+            .or_err(ErrorType::ParseError)?;
+
+        // Initialize the finished pieces.
+        let mut finished_pieces: Vec<metadata::Piece> = Vec::new();
+
+        // Download the pieces from the source.
+        let mut join_set = JoinSet::new();
+        let semaphore = Arc::new(Semaphore::new(
+            self.config.download.concurrent_piece_count as usize,
+        ));
+        for interested_piece in interested_pieces {
+            async fn download_from_source(
+                task_id: String,
+                host_id: String,
+                peer_id: String,
+                number: u32,
+                url: String,
+                offset: u64,
+                length: u64,
+                request_header: HeaderMap,
+                is_prefetch: bool,
+                need_piece_content: bool,
+                piece_manager: Arc<piece::Piece>,
+                download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+                in_stream_tx: Sender<AnnounceCachePeerRequest>,
+                object_storage: Option<ObjectStorage>,
+                hdfs: Option<Hdfs>,
+            ) -> ClientResult<metadata::Piece> {
+                let piece_id = piece_manager.cache_id(task_id.as_str(), number);
+                info!("start to download cache piece {} from source", piece_id);
+
+                let metadata = piece_manager
+                    .download_cache_from_source(
+                        piece_id.as_str(),
+                        task_id.as_str(),
+                        number,
+                        url.as_str(),
+                        offset,
+                        length,
+                        request_header,
+                        is_prefetch,
+                        object_storage,
+                        hdfs,
+                    )
+                    .await?;
+
+                // Construct the piece.
+                let piece = Piece {
+                    number: metadata.number,
+                    parent_id: metadata.parent_id.clone(),
+                    offset: metadata.offset,
+                    length: metadata.length,
+                    digest: metadata.digest.clone(),
+                    content: None,
+                    traffic_type: Some(TrafficType::BackToSource as i32),
+                    cost: metadata.prost_cost(),
+                    created_at: Some(prost_wkt_types::Timestamp::from(metadata.created_at)),
+                };
+
+                // If need_piece_content is true, read the piece content from the local.
+                let mut response_piece = piece.clone();
+                if need_piece_content {
+                    let mut reader = piece_manager
+                        .download_cache_from_local_into_async_read(
+                            piece_id.as_str(),
+                            task_id.as_str(),
+                            metadata.length,
+                            None,
+                            true,
+                            false,
+                        )
+                        .await
+                        .inspect_err(|err| {
+                            error!("read cache piece {} failed: {:?}", piece_id, err);
+                        })?;
+
+                    let mut content = vec![0; metadata.length as usize];
+                    reader.read_exact(&mut content).await.inspect_err(|err| {
+                        error!("read cache piece {} failed: {:?}", piece_id, err);
+                    })?;
+
+                    response_piece.content = Some(content);
+                }
+
+                // Send the download progress.
+                download_progress_tx
+                    .send_timeout(
+                        Ok(DownloadCacheTaskResponse {
+                            host_id: host_id.to_string(),
+                            task_id: task_id.to_string(),
+                            peer_id: peer_id.to_string(),
+                            response: Some(
+                                download_cache_task_response::Response::DownloadPieceFinishedResponse(
+                                    dfdaemon::v2::DownloadPieceFinishedResponse {
+                                        piece: Some(response_piece),
+                                    },
+                                ),
+                            ),
+                        }),
+                        REQUEST_TIMEOUT,
+                    )
+                    .await
+                    .unwrap_or_else(|err| {
+                        error!(
+                            "send DownloadPieceFinishedResponse for cache piece {} failed: {:?}",
+                            piece_id, err
+                        );
+                    });
+
+                // Send the download piece finished request.
+                in_stream_tx
+                        .send_timeout(
+                            AnnounceCachePeerRequest {
+                                host_id: host_id.to_string(),
+                                task_id: task_id.to_string(),
+                                peer_id: peer_id.to_string(),
+                                request: Some(
+                                    announce_cache_peer_request::Request::DownloadPieceBackToSourceFinishedRequest(
+                                        DownloadPieceBackToSourceFinishedRequest {
+                                            piece: Some(piece),
+                                        },
+                                    ),
+                                ),
+                            },
+                            REQUEST_TIMEOUT,
+                        )
+                        .await.unwrap_or_else(|err| {
+                            error!("send DownloadPieceBackToSourceFinishedRequest for cache piece {} failed: {:?}", piece_id, err);
+                        });
+
+                info!("finished cache piece {} from source", piece_id);
+                Ok(metadata)
+            }
+
+            let task_id = task_id.to_string();
+            let host_id = host_id.to_string();
+            let peer_id = peer_id.to_string();
+            let url = request.url.clone();
+            let request_header = request_header.clone();
+            let piece_manager = self.piece.clone();
+            let download_progress_tx = download_progress_tx.clone();
+            let in_stream_tx = in_stream_tx.clone();
+            let object_storage = request.object_storage.clone();
+            let hdfs = request.hdfs.clone();
+            let permit = semaphore.clone().acquire_owned().await.unwrap();
+            join_set.spawn(
+                async move {
+                    let _permit = permit;
+                    download_from_source(
+                        task_id,
+                        host_id,
+                        peer_id,
+                        interested_piece.number,
+                        url,
+                        interested_piece.offset,
+                        interested_piece.length,
+                        request_header,
+                        request.is_prefetch,
+                        request.need_piece_content,
+                        piece_manager,
+                        download_progress_tx,
+                        in_stream_tx,
+                        object_storage,
+                        hdfs,
+                    )
+                    .await
+                }
+                .in_current_span(),
+            );
+        }
+
+        // Wait for the pieces to be downloaded.
+        while let Some(message) = join_set
+            .join_next()
+            .await
+            .transpose()
+            .or_err(ErrorType::AsyncRuntimeError)?
+        {
+            match message {
+                Ok(metadata) => {
+                    // Store the finished piece.
+                    finished_pieces.push(metadata.clone());
+                }
+                Err(Error::BackendError(err)) => {
+                    join_set.shutdown().await;
+
+                    // Send the download piece http failed request.
+                    in_stream_tx.send_timeout(AnnounceCachePeerRequest {
+                                    host_id: host_id.to_string(),
+                                    task_id: task_id.to_string(),
+                                    peer_id: peer_id.to_string(),
+                                    request: Some(announce_cache_peer_request::Request::DownloadPieceBackToSourceFailedRequest(
+                                            DownloadPieceBackToSourceFailedRequest{
+                                                piece_number: None,
+                                                response: Some(download_piece_back_to_source_failed_request::Response::Backend(
+                                                        Backend{
+                                                            message: err.message.clone(),
+                                                            header: headermap_to_hashmap(&err.header.clone().unwrap_or_default()),
+                                                            status_code: err.status_code.map(|code| code.as_u16() as i32),
+                                                        }
+                                                )),
+                                            }
+                                    )),
+                                }, REQUEST_TIMEOUT)
+                                .await
+                                .unwrap_or_else(|err| error!("send DownloadPieceBackToSourceFailedRequest error: {:?}", err));
+
+                    // If the backend error with source, return the error.
+                    // It will stop the download from the source with scheduler
+                    // and download from the source directly from beginning.
+                    return Err(Error::BackendError(err));
+                }
+                Err(Error::SendTimeout) => {
+                    join_set.shutdown().await;
+
+                    // Send the download piece failed request.
+                    in_stream_tx.send_timeout(AnnounceCachePeerRequest {
+                                    host_id: host_id.to_string(),
+                                    task_id: task_id.to_string(),
+                                    peer_id: peer_id.to_string(),
+                                    request: Some(announce_cache_peer_request::Request::DownloadPieceBackToSourceFailedRequest(
+                                            DownloadPieceBackToSourceFailedRequest{
+                                                piece_number: None,
+                                                response: Some(download_piece_back_to_source_failed_request::Response::Unknown(
+                                                        Unknown{
+                                                            message: Some("send timeout".to_string()),
+                                                        }
+                                                )),
+                                            }
+                                    )),
+                                }, REQUEST_TIMEOUT)
+                                .await
+                                .unwrap_or_else(|err| error!("send DownloadPieceBackToSourceFailedRequest error: {:?}", err));
+
+                    // If the send timeout with scheduler or download progress, return
+                    // the finished pieces. It will stop the download from the source with
+                    // scheduler and download from the source directly from middle.
+                    return Ok(finished_pieces);
+                }
+                Err(err) => {
+                    join_set.shutdown().await;
+
+                    // Send the download piece failed request.
+                    in_stream_tx.send_timeout(AnnounceCachePeerRequest {
+                                    host_id: host_id.to_string(),
+                                    task_id: task_id.to_string(),
+                                    peer_id: peer_id.to_string(),
+                                    request: Some(announce_cache_peer_request::Request::DownloadPieceBackToSourceFailedRequest(
+                                            DownloadPieceBackToSourceFailedRequest{
+                                                piece_number: None,
+                                                response: Some(download_piece_back_to_source_failed_request::Response::Unknown(
+                                                        Unknown{
+                                                            message: Some(err.to_string()),
+                                                        }
+                                                )),
+                                            }
+                                    )),
+                                }, REQUEST_TIMEOUT)
+                                .await
+                                .unwrap_or_else(|err| error!("send DownloadPieceBackToSourceFailedRequest error: {:?}", err));
+
+                    // If the unknown error, return the error.
+                    // It will stop the download from the source with scheduler
+                    // and download from the source directly from beginning.
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(finished_pieces)
+    }
+
+    /// download_partial_from_local downloads a partial cache task from a local.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    async fn download_partial_from_local(
+        &self,
+        task: &metadata::CacheTask,
+        host_id: &str,
+        peer_id: &str,
+        need_piece_content: bool,
+        interested_pieces: Vec<metadata::Piece>,
+        download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+    ) -> ClientResult<Vec<metadata::Piece>> {
+        // Get the id of the cache task.
+        let task_id = task.id.as_str();
+
+        // Initialize the finished pieces.
+        let mut finished_pieces: Vec<metadata::Piece> = Vec::new();
+
+        // Download the piece from the local.
+        for interested_piece in interested_pieces {
+            let piece_id = self.piece.cache_id(task_id, interested_piece.number);
+
+            // Get the piece metadata from the local storage.
+            let piece = match self.piece.get_cache(piece_id.as_str()) {
+                Ok(Some(piece)) => piece,
+                Ok(None) => {
+                    debug!("cache piece {} not found in local storage", piece_id);
+                    continue;
+                }
+                Err(err) => {
+                    error!(
+                        "get cache piece {} from local storage error: {:?}",
+                        piece_id, err
+                    );
+                    continue;
+                }
+            };
+
+            if !piece.is_finished() {
+                debug!("cache piece {} is not finished, skip it", piece_id);
+                continue;
+            }
+
+            // Fake the download from the local.
+            self.piece.download_cache_from_local(piece.length);
+            info!("finished cache piece {} from local", piece_id);
+
+            // Construct the piece.
+            let mut piece = Piece {
+                number: piece.number,
+                parent_id: None,
+                offset: piece.offset,
+                length: piece.length,
+                digest: piece.digest.clone(),
+                content: None,
+                traffic_type: Some(TrafficType::LocalPeer as i32),
+                cost: piece.prost_cost(),
+                created_at: Some(prost_wkt_types::Timestamp::from(piece.created_at)),
+            };
+
+            // If need_piece_content is true, read the piece content from the local.
+            if need_piece_content {
+                let mut reader = self
+                    .piece
+                    .download_cache_from_local_into_async_read(
+                        piece_id.as_str(),
+                        task_id,
+                        piece.length,
+                        None,
+                        true,
+                        false,
+                    )
+                    .await
+                    .inspect_err(|err| {
+                        error!("read cache piece {} failed: {:?}", piece_id, err);
+                    })?;
+
+                let mut content = vec![0; piece.length as usize];
+                reader.read_exact(&mut content).await.inspect_err(|err| {
+                    error!("read cache piece {} failed: {:?}", piece_id, err);
+                })?;
+
+                piece.content = Some(content);
+            }
+
+            // Send the download progress.
+            download_progress_tx
+                .send_timeout(
+                    Ok(DownloadCacheTaskResponse {
+                        host_id: host_id.to_string(),
+                        task_id: task_id.to_string(),
+                        peer_id: peer_id.to_string(),
+                        response: Some(
+                            download_cache_task_response::Response::DownloadPieceFinishedResponse(
+                                dfdaemon::v2::DownloadPieceFinishedResponse { piece: Some(piece) },
+                            ),
+                        ),
+                    }),
+                    REQUEST_TIMEOUT,
+                )
+                .await
+                .unwrap_or_else(|err| {
+                    error!(
+                        "send DownloadPieceFinishedResponse for cache piece {} failed: {:?}",
+                        piece_id, err
+                    );
+                });
+
+            // Store the finished piece.
+            finished_pieces.push(interested_piece.clone());
+        }
+
+        Ok(finished_pieces)
+    }
+
+    /// download_partial_from_source downloads a partial cache task from the source.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    async fn download_partial_from_source(
+        &self,
+        task: &metadata::CacheTask,
+        host_id: &str,
+        peer_id: &str,
+        interested_pieces: Vec<metadata::Piece>,
+        request: DownloadCacheTaskRequest,
+        download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+    ) -> ClientResult<Vec<metadata::Piece>> {
+        // Get the id of the cache task.
+        let task_id = task.id.as_str();
+
+        // Convert the header.
+        let request_header: HeaderMap = (&request.request_header)
+            .try_into()
+            .or_err(ErrorType::ParseError)?;
+
+        // Initialize the finished pieces.
+        let mut finished_pieces: Vec<metadata::Piece> = Vec::new();
+
+        // Download the pieces.
+        let mut join_set = JoinSet::new();
+        let semaphore = Arc::new(Semaphore::new(
+            self.config.download.concurrent_piece_count as usize,
+        ));
+        for interested_piece in interested_pieces.clone() {
+            async fn download_from_source(
+                task_id: String,
+                host_id: String,
+                peer_id: String,
+                number: u32,
+                url: String,
+                offset: u64,
+                length: u64,
+                request_header: HeaderMap,
+                is_prefetch: bool,
+                need_piece_content: bool,
+                piece_manager: Arc<piece::Piece>,
+                download_progress_tx: Sender<Result<DownloadCacheTaskResponse, Status>>,
+                object_storage: Option<ObjectStorage>,
+                hdfs: Option<Hdfs>,
+            ) -> ClientResult<metadata::Piece> {
+                let piece_id = piece_manager.cache_id(task_id.as_str(), number);
+                info!("start to download cache piece {} from source", piece_id);
+
+                let metadata = piece_manager
+                    .download_cache_from_source(
+                        piece_id.as_str(),
+                        task_id.as_str(),
+                        number,
+                        url.as_str(),
+                        offset,
+                        length,
+                        request_header,
+                        is_prefetch,
+                        object_storage,
+                        hdfs,
+                    )
+                    .await?;
+
+                // Construct the piece.
+                let mut piece = Piece {
+                    number: metadata.number,
+                    parent_id: None,
+                    offset: metadata.offset,
+                    length: metadata.length,
+                    digest: metadata.digest.clone(),
+                    content: None,
+                    traffic_type: Some(TrafficType::BackToSource as i32),
+                    cost: metadata.prost_cost(),
+                    created_at: Some(prost_wkt_types::Timestamp::from(metadata.created_at)),
+                };
+
+                // If need_piece_content is true, read the piece content from the local.
+                if need_piece_content {
+                    let mut reader = piece_manager
+                        .download_cache_from_local_into_async_read(
+                            piece_id.as_str(),
+                            task_id.as_str(),
+                            piece.length,
+                            None,
+                            true,
+                            false,
+                        )
+                        .await
+                        .inspect_err(|err| {
+                            error!("read cache piece {} failed: {:?}", piece_id, err);
+                        })?;
+
+                    let mut content = vec![0; piece.length as usize];
+                    reader.read_exact(&mut content).await.inspect_err(|err| {
+                        error!("read cache piece {} failed: {:?}", piece_id, err);
+                    })?;
+
+                    piece.content = Some(content);
+                }
+
+                // Send the download progress.
+                download_progress_tx
+                    .send_timeout(
+                        Ok(DownloadCacheTaskResponse {
+                            host_id: host_id.to_string(),
+                            task_id: task_id.to_string(),
+                            peer_id: peer_id.to_string(),
+                            response: Some(
+                                download_cache_task_response::Response::DownloadPieceFinishedResponse(
+                                    dfdaemon::v2::DownloadPieceFinishedResponse {
+                                        piece: Some(piece),
+                                    },
+                                ),
+                            ),
+                        }),
+                        REQUEST_TIMEOUT,
+                    )
+                    .await
+                    .unwrap_or_else(|err| {
+                        error!(
+                            "send DownloadPieceFinishedResponse for cache piece {} failed: {:?}",
+                            piece_id, err
+                        );
+                    });
+
+                info!("finished cache piece {} from source", piece_id);
+                Ok(metadata)
+            }
+
+            let task_id = task_id.to_string();
+            let host_id = host_id.to_string();
+            let peer_id = peer_id.to_string();
+            let url = request.url.clone();
+            let request_header = request_header.clone();
+            let piece_manager = self.piece.clone();
+            let download_progress_tx = download_progress_tx.clone();
+            let object_storage = request.object_storage.clone();
+            let hdfs = request.hdfs.clone();
+            let permit = semaphore.clone().acquire_owned().await.unwrap();
+            join_set.spawn(
+                async move {
+                    let _permit = permit;
+                    download_from_source(
+                        task_id,
+                        host_id,
+                        peer_id,
+                        interested_piece.number,
+                        url,
+                        interested_piece.offset,
+                        interested_piece.length,
+                        request_header,
+                        request.is_prefetch,
+                        request.need_piece_content,
+                        piece_manager,
+                        download_progress_tx,
+                        object_storage,
+                        hdfs,
+                    )
+                    .await
+                }
+                .in_current_span(),
+            );
+        }
+
+        // Wait for the pieces to be downloaded.
+        while let Some(message) = join_set
+            .join_next()
+            .await
+            .transpose()
+            .or_err(ErrorType::AsyncRuntimeError)?
+        {
+            match message {
+                Ok(metadata) => {
+                    // Store the finished piece.
+                    finished_pieces.push(metadata.clone());
+                }
+                Err(err) => {
+                    join_set.shutdown().await;
+
+                    // If the download failed from the source, return the error.
+                    // It will stop the download from the source.
+                    return Err(err);
+                }
+            }
+        }
+
+        // Check if all pieces are downloaded.
+        if finished_pieces.len() != interested_pieces.len() {
+            // If not all pieces are downloaded, return an error.
+            return Err(Error::Unknown(
+                "not all cache pieces are downloaded from source".to_string(),
+            ));
+        }
+
+        Ok(finished_pieces)
+    }
+
+    /// stat returns the cache task metadata from scheduler.
+    #[instrument(skip_all)]
+    pub async fn stat(&self, task_id: &str, host_id: &str) -> ClientResult<CommonCacheTask> {
+        self.scheduler_client
+            .stat_cache_task(StatCacheTaskRequest {
+                host_id: host_id.to_string(),
+                task_id: task_id.to_string(),
+            })
+            .await
+            .inspect_err(|err| {
+                error!("stat cache task failed: {}", err);
+            })
+    }
+
+    /// delete deletes a cache task and reclaims the memory cache.
+    #[instrument(skip_all)]
+    pub async fn delete(&self, task_id: &str, host_id: &str) -> ClientResult<()> {
+        let task = self.storage.get_cache_task(task_id).inspect_err(|err| {
+            error!(
+                "get cache task {} from local storage error: {:?}",
+                task_id, err
+            );
+        })?;
+
+        match task {
+            Some(task) => {
+                self.storage.delete_cache_task(task.id.as_str()).await;
+
+                self.scheduler_client
+                    .delete_cache_task(DeleteCacheTaskRequest {
+                        host_id: host_id.to_string(),
+                        task_id: task_id.to_string(),
+                    })
+                    .await
+                    .inspect_err(|err| {
+                        error!(
+                            "delete cache task {} failed from scheduler: {:?}",
+                            task_id, err
+                        );
+                    })?;
+
+                info!("delete cache task {} from local storage", task.id);
+                Ok(())
+            }
+            None => {
+                error!("delete_cache_task cache task {} not found", task_id);
+                Err(Error::TaskNotFound(task_id.to_owned()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynconfig::Dynconfig;
+    use crate::grpc::manager::ManagerClient;
+    use bytesize::ByteSize;
+    use dragonfly_client_config::dfdaemon::Storage as StorageConfig;
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    // test_copy_cache_task tests the cache pieces are stored in memory
+    // and can be copied to the output path.
+    #[tokio::test]
+    async fn test_copy_cache_task() {
+        let temp_dir = tempdir().unwrap();
+        let log_dir = temp_dir.path().join("log");
+        std::fs::create_dir_all(&log_dir).unwrap();
+
+        let config = Arc::new(Config {
+            storage: StorageConfig {
+                cache_capacity: ByteSize::mib(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let storage = Storage::new(config.clone(), temp_dir.path(), log_dir)
+            .await
+            .unwrap();
+
+        let task_id = "test-cache-task-id";
+        storage
+            .download_cache_task_started(task_id, 5, 10, None)
+            .await
+            .unwrap();
+
+        for (number, content) in [(0_u32, b"hello"), (1_u32, b"world")] {
+            let piece_id = storage.cache_piece_id(task_id, number);
+            storage
+                .download_cache_piece_started(piece_id.as_str(), number)
+                .await
+                .unwrap();
+            storage
+                .download_cache_piece_from_source_finished(
+                    piece_id.as_str(),
+                    task_id,
+                    number as u64 * 5,
+                    5,
+                    &mut Cursor::new(content.to_vec()),
+                    config.storage.write_piece_timeout,
+                )
+                .await
+                .unwrap();
+        }
+        storage.download_cache_task_finished(task_id).unwrap();
+
+        // The finished cache task should be reused.
+        let task = storage
+            .prepare_download_cache_task_started(task_id)
+            .await
+            .unwrap();
+        assert!(task.is_some());
+
+        let output_path = temp_dir.path().join("output");
+        storage
+            .copy_cache_task(task_id, output_path.as_path())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"helloworld");
+
+        // The cache task should not be reused after it is deleted.
+        storage.delete_cache_task(task_id).await;
+        let task = storage
+            .prepare_download_cache_task_started(task_id)
+            .await
+            .unwrap();
+        assert!(task.is_none());
+    }
+
+    // test_download_cache_task_from_source tests the cache task is downloaded from the source
+    // through CacheTask.download when no scheduler is available, and the downloaded pieces
+    // can be copied to the output path.
+    #[tokio::test]
+    async fn test_download_cache_task_from_source() {
+        let temp_dir = tempdir().unwrap();
+        let log_dir = temp_dir.path().join("log");
+        std::fs::create_dir_all(&log_dir).unwrap();
+
+        let mut config = Config {
+            storage: StorageConfig {
+                cache_capacity: ByteSize::mib(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.backend.file.allowed_roots = vec![temp_dir.path().to_path_buf()];
+        let config = Arc::new(config);
+
+        let source_path = temp_dir.path().join("source");
+        let content: Vec<u8> = (0..10240).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&source_path, &content).unwrap();
+
+        let storage = Arc::new(
+            Storage::new(config.clone(), temp_dir.path(), log_dir)
+                .await
+                .unwrap(),
+        );
+        let id_generator = Arc::new(IDGenerator::new(
+            "127.0.0.1".to_string(),
+            "localhost".to_string(),
+            false,
+        ));
+
+        let shutdown = shutdown::Shutdown::default();
+        let (shutdown_complete_tx, _shutdown_complete_rx) = mpsc::unbounded_channel();
+        let dynconfig = Arc::new(Dynconfig::new_empty(
+            config.clone(),
+            Arc::new(ManagerClient::new_lazy("http://127.0.0.1:65004")),
+            shutdown.clone(),
+            shutdown_complete_tx.clone(),
+        ));
+
+        let cache_task = CacheTask::new(
+            config.clone(),
+            id_generator.clone(),
+            storage.clone(),
+            Arc::new(SchedulerClient::new_empty(config.clone(), dynconfig)),
+            Arc::new(BackendFactory::new(config.clone(), None).unwrap()),
+            Arc::new(RateLimiter::builder().build()),
+            Arc::new(RateLimiter::builder().build()),
+            Arc::new(RateLimiter::builder().build()),
+            shutdown,
+            shutdown_complete_tx,
+        )
+        .unwrap();
+
+        let request = DownloadCacheTaskRequest {
+            url: url::Url::from_file_path(&source_path).unwrap().to_string(),
+            piece_length: Some(4096),
+            ..Default::default()
+        };
+
+        let task_id = "test-cache-task-id";
+        let task = cache_task
+            .download_started(task_id, request.clone())
+            .await
+            .unwrap();
+        assert_eq!(task.content_length(), Some(content.len() as u64));
+
+        let (download_progress_tx, mut download_progress_rx) = mpsc::channel(16);
+        cache_task
+            .download(
+                &task,
+                "test-host-id",
+                "test-peer-id",
+                request,
+                download_progress_tx,
+            )
+            .await
+            .unwrap();
+        cache_task.download_finished(task_id).unwrap();
+
+        let mut finished_pieces = 0;
+        while let Some(Ok(response)) = download_progress_rx.recv().await {
+            if let Some(download_cache_task_response::Response::DownloadPieceFinishedResponse(_)) =
+                response.response
+            {
+                finished_pieces += 1;
+            }
+        }
+        assert_eq!(finished_pieces, 3);
+
+        let output_path = temp_dir.path().join("output");
+        cache_task
+            .copy_task(task_id, output_path.as_path())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), content);
+    }
+
+    // test_copy_unfinished_cache_task tests the cache task with unfinished pieces
+    // can not be copied to the output path.
+    #[tokio::test]
+    async fn test_copy_unfinished_cache_task() {
+        let temp_dir = tempdir().unwrap();
+        let log_dir = temp_dir.path().join("log");
+        std::fs::create_dir_all(&log_dir).unwrap();
+
+        let config = Arc::new(Config {
+            storage: StorageConfig {
+                cache_capacity: ByteSize::mib(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let storage = Storage::new(config.clone(), temp_dir.path(), log_dir)
+            .await
+            .unwrap();
+
+        let task_id = "test-cache-task-id";
+        storage
+            .download_cache_task_started(task_id, 5, 10, None)
+            .await
+            .unwrap();
+
+        let piece_id = storage.cache_piece_id(task_id, 0);
+        storage
+            .download_cache_piece_started(piece_id.as_str(), 0)
+            .await
+            .unwrap();
+        storage
+            .download_cache_piece_from_source_finished(
+                piece_id.as_str(),
+                task_id,
+                0,
+                5,
+                &mut Cursor::new(b"hello".to_vec()),
+                config.storage.write_piece_timeout,
+            )
+            .await
+            .unwrap();
+
+        let piece_id = storage.cache_piece_id(task_id, 1);
+        storage
+            .download_cache_piece_started(piece_id.as_str(), 1)
+            .await
+            .unwrap();
+
+        let output_path = temp_dir.path().join("output");
+        let result = storage
+            .copy_cache_task(task_id, output_path.as_path())
+            .await;
+        assert!(matches!(result, Err(Error::InvalidState(_))));
+        assert!(!output_path.exists());
+    }
+
+    // test_download_cache_task_too_large tests the cache task exceeding the cache capacity
+    // can not be downloaded.
+    #[tokio::test]
+    async fn test_download_cache_task_too_large() {
+        let temp_dir = tempdir().unwrap();
+        let log_dir = temp_dir.path().join("log");
+        std::fs::create_dir_all(&log_dir).unwrap();
+
+        let config = Arc::new(Config {
+            storage: StorageConfig {
+                cache_capacity: ByteSize::kib(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let storage = Storage::new(config.clone(), temp_dir.path(), log_dir)
+            .await
+            .unwrap();
+
+        let result = storage
+            .download_cache_task_started("test-cache-task-id", 1024, 4096, None)
+            .await;
+        assert!(matches!(result, Err(Error::NoSpace(_))));
+    }
+}
@@ -17,7 +17,7 @@
 use crate::grpc::dfdaemon_upload::DfdaemonUploadClient;
 use crate::resource::piece_collector::CollectedParent;
 use dashmap::DashMap;
-use dragonfly_api::common::v2::{Host, Peer, PersistentCachePeer, PersistentPeer};
+use dragonfly_api::common::v2::{CachePeer, Host, Peer, PersistentCachePeer, PersistentPeer};
 use dragonfly_api::dfdaemon::v2::SyncHostRequest;
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::Result;
@@ -25,6 +25,7 @@ use dragonfly_client_util::id_generator::IDGenerator;
 use dragonfly_client_util::shutdown::{self, Shutdown};
 use rand::distr::weighted::WeightedIndex;
 use rand::distr::Distribution;
+use std::marker::PhantomData;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -674,8 +675,51 @@ impl PersistentParentSelector {
     }
 }
 
-/// PersistentCacheParentSelector is the download persistent cache parent selector configuration for dfdaemon. It will synchronize
-/// the host info in real-time from the parents and then select the parents for downloading.
+/// CacheParent is implemented by the parent peers of the cache task kinds, so that a single
+/// CacheParentSelector can be shared by the cache task and the persistent cache task.
+pub trait CacheParent {
+    /// KIND is the task kind used in the log messages, such as `cache` or `persistent cache`.
+    const KIND: &'static str;
+
+    /// id returns the id of the parent peer.
+    fn id(&self) -> &str;
+
+    /// host returns the host info of the parent peer.
+    fn host(&self) -> Option<&Host>;
+}
+
+/// Implements CacheParent for the parent of the cache task.
+impl CacheParent for CachePeer {
+    const KIND: &'static str = "cache";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn host(&self) -> Option<&Host> {
+        self.host.as_ref()
+    }
+}
+
+/// Implements CacheParent for the parent of the persistent cache task.
+impl CacheParent for PersistentCachePeer {
+    const KIND: &'static str = "persistent cache";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn host(&self) -> Option<&Host> {
+        self.host.as_ref()
+    }
+}
+
+/// PersistentCacheParentSelector is the parent selector of the persistent cache task.
+pub type PersistentCacheParentSelector = CacheParentSelector<PersistentCachePeer>;
+
+/// CacheParentSelector is the download cache parent selector configuration for dfdaemon. It is generic
+/// over the task kind, and it will synchronize the host info in real-time from the parents and then
+/// select the parents for downloading.
 ///
 /// The workflow diagram is as follows:
 ///
@@ -701,7 +745,7 @@ impl PersistentParentSelector {
 /// |                                                +------------+     |
 /// +-------------------------------------------------------------------+
 /// ```
-pub struct PersistentCacheParentSelector {
+pub struct CacheParentSelector<P: CacheParent> {
     /// Config is the configuration of the dfdaemon.
     config: Arc<Config>,
 
@@ -719,18 +763,21 @@ pub struct PersistentCacheParentSelector {
 
     /// _shutdown_complete is used to notify the garbage collector is shutdown.
     _shutdown_complete: mpsc::UnboundedSender<()>,
+
+    /// _parent is the marker of the parent peer type.
+    _parent: PhantomData<fn(&P)>,
 }
 
-/// Implements persistent cache parent peer selection and connection management logic.
-impl PersistentCacheParentSelector {
-    /// Creates a new persistent cache parent selector instance.
+/// Implements cache parent peer selection and connection management logic.
+impl<P: CacheParent> CacheParentSelector<P> {
+    /// Creates a new cache parent selector instance.
     #[instrument(skip_all)]
     pub fn new(
         config: Arc<Config>,
         id_generator: Arc<IDGenerator>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
-    ) -> PersistentCacheParentSelector {
+    ) -> CacheParentSelector<P> {
         Self {
             config,
             id_generator,
@@ -738,10 +785,11 @@ impl PersistentCacheParentSelector {
             connections: Arc::new(DashMap::new()),
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
+            _parent: PhantomData,
         }
     }
 
-    /// Selects the best persistent cache parent from a list of candidates based on their load quality weights.
+    /// Selects the best cache parent from a list of candidates based on their load quality weights.
     ///
     /// This function performs weighted random selection where parents with higher weights
     /// (better idle bandwidth) have a higher probability of being selected. If weight
@@ -753,7 +801,8 @@ impl PersistentCacheParentSelector {
             .map(|parent| {
                 let Some(parent_host) = parent.host.as_ref() else {
                     warn!(
-                        "persistent cache parent {} has no host info, defaulting weight to 0",
+                        "{} parent {} has no host info, defaulting weight to 0",
+                        P::KIND,
                         parent.id
                     );
 
@@ -766,8 +815,10 @@ impl PersistentCacheParentSelector {
                     .map(|w| *w)
                     .unwrap_or_else(|| {
                         debug!(
-                            "no weight info for persistent cache parent {} {}, defaulting weight to 0",
-                            parent.id, parent_host_id
+                            "no weight info for {} parent {} {}, defaulting weight to 0",
+                            P::KIND,
+                            parent.id,
+                            parent_host_id
                         );
 
                         0
@@ -780,7 +831,7 @@ impl PersistentCacheParentSelector {
                 let mut rng = rand::rng();
                 let index = dist.sample(&mut rng);
                 let selected_parent = &parents[index];
-                debug!("selected persistent cache parent {}", selected_parent.id);
+                debug!("selected {} parent {}", P::KIND, selected_parent.id);
 
                 selected_parent.clone()
             }
@@ -788,23 +839,24 @@ impl PersistentCacheParentSelector {
         }
     }
 
-    /// Registers multiple persistent cache parents for host information synchronization.
+    /// Registers multiple cache parents for host information synchronization.
     ///
     /// For each parent, this function:
     /// - Creates a new gRPC connection if one doesn't exist.
     /// - Spawns a background task to continuously sync host metrics (bandwidth, load).
     /// - Updates the connection's request counter.
     #[instrument(skip_all)]
-    pub async fn register(&self, parents: &[PersistentCachePeer]) -> Result<()> {
+    pub async fn register(&self, parents: &[P]) -> Result<()> {
         let dfdaemon_shutdown = self.shutdown.clone();
         let mut join_set = JoinSet::new();
         for parent in parents {
-            debug!("register persistent cache parent {}", parent.id);
+            debug!("register {} parent {}", P::KIND, parent.id());
 
-            let Some(parent_host) = parent.host.as_ref() else {
+            let Some(parent_host) = parent.host() else {
                 warn!(
-                    "persistent cache parent {} has no host info, skipping",
-                    parent.id
+                    "{} parent {} has no host info, skipping",
+                    P::KIND,
+                    parent.id()
                 );
                 continue;
             };
@@ -861,7 +913,7 @@ impl PersistentCacheParentSelector {
         Ok(())
     }
 
-    /// Unregisters multiple persistent cache parents and cleans up their connections.
+    /// Unregisters multiple cache parents and cleans up their connections.
     ///
     /// Decrements the request counter for each parent's connection. When a connection's
     /// active request count reaches zero, it:
@@ -869,14 +921,15 @@ impl PersistentCacheParentSelector {
     /// - Removes the weight entry.
     /// - Removes the connection from the pool.
     #[instrument(skip_all)]
-    pub fn unregister(&self, parents: &[PersistentCachePeer]) {
+    pub fn unregister(&self, parents: &[P]) {
         for parent in parents {
-            debug!("unregister persistent cache parent {}", parent.id);
+            debug!("unregister {} parent {}", P::KIND, parent.id());
 
-            let Some(parent_host) = parent.host.as_ref() else {
+            let Some(parent_host) = parent.host() else {
                 warn!(
-                    "persistent cache parent {} has no host info, skipping",
-                    parent.id
+                    "{} parent {} has no host info, skipping",
+                    P::KIND,
+                    parent.id()
                 );
                 continue;
             };
@@ -898,7 +951,7 @@ impl PersistentCacheParentSelector {
         }
     }
 
-    /// Continuously synchronizes host metrics from a persistent cache parent peer.
+    /// Continuously synchronizes host metrics from a cache parent peer.
     ///
     /// This is a long-running background task that:
     /// - Establishes a streaming gRPC connection to the parent.
@@ -916,17 +969,16 @@ impl PersistentCacheParentSelector {
         mut shutdown: Shutdown,
         mut dfdaemon_shutdown: Shutdown,
     ) -> Result<()> {
-        debug!(
-            "sync host info from persistent cache parent {}",
-            parent_host_id
-        );
+        debug!("sync host info from {} parent {}", P::KIND, parent_host_id);
         let response = dfdaemon_upload_client
             .sync_host(SyncHostRequest { host_id, peer_id })
             .await
             .inspect_err(|err| {
                 error!(
-                    "sync host info from persistent cache parent {} failed: {}",
-                    parent_host_id, err
+                    "sync host info from {} parent {} failed: {}",
+                    P::KIND,
+                    parent_host_id,
+                    err
                 );
             })?;
 
@@ -936,7 +988,7 @@ impl PersistentCacheParentSelector {
             tokio::select! {
                 result = out_stream.try_next() => {
                     match result.inspect_err(|err| {
-                        error!("sync host info from persistent cache parent {} failed: {}", parent_host_id, err);
+                        error!("sync host info from {} parent {} failed: {}", P::KIND, parent_host_id, err);
                     })? {
                         Some(message) => {
                             let idle_tx_bandwidth = Self::get_idle_tx_bandwidth(&message);
@@ -948,11 +1000,11 @@ impl PersistentCacheParentSelector {
                     }
                 }
                 _ = shutdown.recv() => {
-                    debug!("sync host info from persistent cache parent {} shutting down", parent_host_id);
+                    debug!("sync host info from {} parent {} shutting down", P::KIND, parent_host_id);
                     break;
                 }
                 _ = dfdaemon_shutdown.recv() => {
-                    debug!("persistent cache parent selector shutting down");
+                    debug!("{} parent selector shutting down", P::KIND);
                     break;
                 }
             }
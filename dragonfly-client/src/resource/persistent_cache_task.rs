@@ -187,7 +187,7 @@ impl PersistentCacheTask {
             .inspect_err(|err| error!("upload persistent cache task started: {}", err))?;
 
         // Check if the storage has enough space to store the persistent cache task.
-        let has_enough_space = self.storage.has_enough_space(content_length)?;
+        let has_enough_space = self.storage.has_enough_space(content_length).await?;
         if !has_enough_space {
             return Err(Error::NoSpace(format!(
                 "not enough space to store the persistent cache task: content_length={}",
@@ -571,7 +571,10 @@ impl PersistentCacheTask {
         // If the persistent cache task is not found, check if the storage has enough space to
         // store the persistent cache task.
         if let Ok(None) = self.get(task_id) {
-            let has_enough_space = self.storage.has_enough_space(response.content_length)?;
+            let has_enough_space = self
+                .storage
+                .has_enough_space(response.content_length)
+                .await?;
             if !has_enough_space {
                 return Err(Error::NoSpace(format!(
                     "not enough space to store the persistent cache task: content_length={}",
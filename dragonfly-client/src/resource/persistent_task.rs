@@ -194,7 +194,7 @@ impl PersistentTask {
             .inspect_err(|err| error!("upload persistent task started: {}", err))?;
 
         // Check if the storage has enough space to store the persistent task.
-        let has_enough_space = self.storage.has_enough_space(content_length)?;
+        let has_enough_space = self.storage.has_enough_space(content_length).await?;
         if !has_enough_space {
             return Err(Error::NoSpace(format!(
                 "not enough space to store the persistent task: content_length={}",
@@ -579,7 +579,7 @@ impl PersistentTask {
                 task_id: task_id.to_string(),
                 url: url.to_string(),
                 path,
-                http_header: None,
+                http_header: Some(HeaderMap::new()),
                 timeout: self.config.backend.put_timeout,
                 client_cert: None,
                 object_storage,
@@ -689,7 +689,7 @@ impl PersistentTask {
         // If the persistent task is not found, check if the storage has enough space to
         // store the persistent task.
         if let Ok(None) = self.get(task_id) {
-            let has_enough_space = self.storage.has_enough_space(content_length)?;
+            let has_enough_space = self.storage.has_enough_space(content_length).await?;
             if !has_enough_space {
                 return Err(Error::NoSpace(format!(
                     "not enough space to store the persistent task: content_length={}",
@@ -2311,7 +2311,7 @@ impl PersistentTask {
             .exists(ExistsRequest {
                 task_id: task_id.to_string(),
                 url: url.to_string(),
-                http_header: None,
+                http_header: Some(HeaderMap::new()),
                 timeout: self.config.backend.put_timeout,
                 client_cert: None,
                 object_storage,
@@ -2336,7 +2336,7 @@ impl PersistentTask {
             .stat(StatRequest {
                 task_id: task_id.to_string(),
                 url: url.to_string(),
-                http_header: None,
+                http_header: Some(HeaderMap::new()),
                 timeout: self.config.backend.put_timeout,
                 client_cert: None,
                 object_storage,
@@ -16,7 +16,7 @@
 
 use super::*;
 use chrono::Utc;
-use dragonfly_api::common::v2::{Hdfs, ObjectStorage, Range, TrafficType};
+use dragonfly_api::common::v2::{Hdfs, ObjectStorage, Range, TaskType, TrafficType};
 use dragonfly_client_backend::{BackendFactory, GetRequest};
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::{error::BackendError, Error, Result};
@@ -1266,6 +1266,337 @@ impl Piece {
             }
         }
     }
+
+    /// cache_id generates a new cache piece id.
+    #[inline]
+    pub fn cache_id(&self, task_id: &str, number: u32) -> String {
+        self.storage.cache_piece_id(task_id, number)
+    }
+
+    /// get_cache gets a cache piece from the local storage.
+    #[instrument(skip_all)]
+    pub fn get_cache(&self, piece_id: &str) -> Result<Option<metadata::Piece>> {
+        self.storage.get_cache_piece(piece_id)
+    }
+
+    /// upload_cache_from_local_into_async_read uploads a cache piece from local cache.
+    #[instrument(skip_all, fields(piece_id))]
+    pub async fn upload_cache_from_local_into_async_read(
+        &self,
+        piece_id: &str,
+        task_id: &str,
+        length: u64,
+        range: Option<Range>,
+        disable_rate_limit: bool,
+    ) -> Result<impl AsyncRead> {
+        // Span record the piece_id.
+        Span::current().record("piece_id", piece_id);
+        Span::current().record("piece_length", length);
+
+        // Acquire the upload rate limiter.
+        if !disable_rate_limit {
+            self.upload_rate_limiter.acquire(length as usize).await;
+        }
+
+        // Upload the cache piece content.
+        self.storage
+            .upload_cache_piece(piece_id, task_id, range)
+            .await
+            .inspect(|_| {
+                collect_upload_piece_traffic_metrics(TaskType::Cache as i32, length);
+            })
+    }
+
+    /// download_cache_from_local_into_async_read downloads a cache piece from local cache.
+    #[instrument(skip_all, fields(piece_id))]
+    pub async fn download_cache_from_local_into_async_read(
+        &self,
+        piece_id: &str,
+        task_id: &str,
+        length: u64,
+        range: Option<Range>,
+        disable_rate_limit: bool,
+        is_prefetch: bool,
+    ) -> Result<impl AsyncRead> {
+        // Span record the piece_id.
+        Span::current().record("piece_id", piece_id);
+        Span::current().record("piece_length", length);
+
+        // Acquire the download rate limiter.
+        if !disable_rate_limit {
+            if is_prefetch {
+                // Acquire the prefetch rate limiter.
+                self.prefetch_rate_limiter.acquire(length as usize).await;
+            } else {
+                // Acquire the download rate limiter.
+                self.download_rate_limiter.acquire(length as usize).await;
+            }
+        }
+
+        // Upload the cache piece content.
+        self.storage
+            .upload_cache_piece(piece_id, task_id, range)
+            .await
+    }
+
+    /// download_cache_from_local downloads a cache piece from local cache. Fake the download
+    /// cache piece from the local cache, just collect the metrics.
+    #[instrument(skip_all)]
+    pub fn download_cache_from_local(&self, length: u64) {
+        collect_download_piece_traffic_metrics(
+            &TrafficType::LocalPeer,
+            TaskType::Cache as i32,
+            length,
+        );
+    }
+
+    /// download_cache_from_parent downloads a cache piece from a parent. The cache piece
+    /// is only served by the gRPC server of the parent, because the content is stored in memory.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all, fields(piece_id))]
+    pub async fn download_cache_from_parent(
+        &self,
+        piece_id: &str,
+        host_id: &str,
+        task_id: &str,
+        number: u32,
+        length: u64,
+        parent: piece_collector::CollectedParent,
+        is_prefetch: bool,
+    ) -> Result<metadata::Piece> {
+        // Span record the piece_id.
+        Span::current().record("piece_id", piece_id);
+        Span::current().record("piece_length", length);
+
+        // Clean up residual cache piece metadata if error occurred.
+        let guard = scopeguard::guard((), |_| {
+            if let Some(err) = self.storage.download_cache_piece_failed(piece_id).err() {
+                error!("set cache piece metadata failed: {}", err)
+            };
+        });
+
+        // Record the start of downloading cache piece.
+        let piece = self
+            .storage
+            .download_cache_piece_started(piece_id, number)
+            .await?;
+
+        // If the cache piece is downloaded by the other thread,
+        // return the cache piece directly.
+        if piece.is_finished() {
+            info!("finished cache piece {} from local", piece_id);
+            scopeguard::ScopeGuard::into_inner(guard);
+            return Ok(piece);
+        }
+
+        if is_prefetch {
+            // Acquire the prefetch rate limiter.
+            self.prefetch_rate_limiter.acquire(length as usize).await;
+        } else {
+            // Acquire the download rate limiter.
+            self.download_rate_limiter.acquire(length as usize).await;
+        }
+
+        let host = parent.host.clone().ok_or_else(|| {
+            error!("parent host is empty");
+            Error::InvalidPeer(parent.id.clone())
+        })?;
+
+        let (mut reader, offset, digest) = self
+            .grpc_downloader
+            .download_cache_piece(
+                format!("{}:{}", host.ip, host.port).as_str(),
+                number,
+                host_id,
+                task_id,
+            )
+            .await
+            .inspect_err(|err| {
+                error!("download cache piece failed: {}", err);
+            })?;
+
+        // Record the finish of downloading cache piece.
+        match self
+            .storage
+            .download_cache_piece_from_parent_finished(
+                piece_id,
+                task_id,
+                offset,
+                length,
+                digest.as_str(),
+                parent.id.as_str(),
+                &mut reader,
+                self.config.storage.write_piece_timeout,
+            )
+            .await
+        {
+            Ok(piece) => {
+                collect_download_piece_traffic_metrics(
+                    &TrafficType::RemotePeer,
+                    TaskType::Cache as i32,
+                    length,
+                );
+
+                scopeguard::ScopeGuard::into_inner(guard);
+                Ok(piece)
+            }
+            Err(err) => {
+                error!("download cache piece finished: {}", err);
+                Err(err)
+            }
+        }
+    }
+
+    /// download_cache_from_source downloads a cache piece from the source.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all, fields(piece_id))]
+    pub async fn download_cache_from_source(
+        &self,
+        piece_id: &str,
+        task_id: &str,
+        number: u32,
+        url: &str,
+        offset: u64,
+        length: u64,
+        request_header: HeaderMap,
+        is_prefetch: bool,
+        object_storage: Option<ObjectStorage>,
+        hdfs: Option<Hdfs>,
+    ) -> Result<metadata::Piece> {
+        // Span record the piece_id.
+        Span::current().record("piece_id", piece_id);
+        Span::current().record("piece_length", length);
+
+        // Clean up residual cache piece metadata if error occurred.
+        let guard = scopeguard::guard((), |_| {
+            if let Some(err) = self.storage.download_cache_piece_failed(piece_id).err() {
+                error!("set cache piece metadata failed: {}", err)
+            };
+        });
+
+        // Record the start of downloading cache piece.
+        let piece = self
+            .storage
+            .download_cache_piece_started(piece_id, number)
+            .await?;
+
+        // If the cache piece is downloaded by the other thread,
+        // return the cache piece directly.
+        if piece.is_finished() {
+            info!("finished cache piece {} from local", piece_id);
+            scopeguard::ScopeGuard::into_inner(guard);
+            return Ok(piece);
+        }
+
+        if is_prefetch {
+            // Acquire the prefetch rate limiter.
+            self.prefetch_rate_limiter.acquire(length as usize).await;
+        } else {
+            // Acquire the download rate limiter.
+            self.download_rate_limiter.acquire(length as usize).await;
+        }
+
+        // Download the cache piece from the source.
+        let backend = self.backend_factory.build(url).inspect_err(|err| {
+            error!("build backend failed: {}", err);
+        })?;
+
+        // Record the start time.
+        let start_time = Instant::now();
+
+        // Collect the backend request started metrics.
+        collect_backend_request_started_metrics(
+            backend.scheme().as_str(),
+            http::Method::GET.as_str(),
+        );
+        let mut response = backend
+            .get(GetRequest {
+                task_id: task_id.to_string(),
+                piece_id: piece_id.to_string(),
+                url: url.to_string(),
+                range: Some(Range {
+                    start: offset,
+                    length,
+                }),
+                http_header: Some(request_header),
+                timeout: self.config.download.piece_timeout,
+                client_cert: None,
+                object_storage,
+                hdfs,
+            })
+            .await
+            .inspect_err(|err| {
+                // Collect the backend request failure metrics.
+                collect_backend_request_failure_metrics(
+                    backend.scheme().as_str(),
+                    http::Method::GET.as_str(),
+                );
+
+                // if the request is failed.
+                error!("backend get failed: {}", err);
+            })?;
+
+        if !response.success {
+            // Collect the backend request failure metrics.
+            collect_backend_request_failure_metrics(
+                backend.scheme().as_str(),
+                http::Method::GET.as_str(),
+            );
+
+            // if the status code is not OK.
+            let mut buffer = String::new();
+            response
+                .reader
+                .read_to_string(&mut buffer)
+                .await
+                .unwrap_or_default();
+
+            let error_message = response.error_message.unwrap_or_default();
+            error!("backend get failed: {} {}", error_message, buffer.as_str());
+
+            return Err(Error::BackendError(Box::new(BackendError {
+                message: error_message,
+                status_code: Some(response.http_status_code.unwrap_or_default()),
+                header: Some(response.http_header.unwrap_or_default()),
+            })));
+        }
+
+        // Collect the backend request finished metrics.
+        collect_backend_request_finished_metrics(
+            backend.scheme().as_str(),
+            http::Method::GET.as_str(),
+            start_time.elapsed(),
+        );
+
+        // Record the finish of downloading cache piece.
+        match self
+            .storage
+            .download_cache_piece_from_source_finished(
+                piece_id,
+                task_id,
+                offset,
+                length,
+                &mut response.reader,
+                self.config.storage.write_piece_timeout,
+            )
+            .await
+        {
+            Ok(piece) => {
+                collect_download_piece_traffic_metrics(
+                    &TrafficType::BackToSource,
+                    TaskType::Cache as i32,
+                    length,
+                );
+
+                scopeguard::ScopeGuard::into_inner(guard);
+                Ok(piece)
+            }
+            Err(err) => {
+                error!("download cache piece finished: {}", err);
+                Err(err)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -18,7 +18,8 @@ use crate::grpc::dfdaemon_upload::DfdaemonUploadClient;
 use dashmap::DashMap;
 use dragonfly_api::common::v2::Host;
 use dragonfly_api::dfdaemon::v2::{
-    SyncPersistentCachePiecesRequest, SyncPersistentPiecesRequest, SyncPiecesRequest,
+    SyncCachePiecesRequest, SyncPersistentCachePiecesRequest, SyncPersistentPiecesRequest,
+    SyncPiecesRequest,
 };
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::{Error, Result};
@@ -808,3 +809,235 @@ impl PersistentCachePieceCollector {
         Ok(())
     }
 }
+
+/// CachePieceCollector is used to collect cache pieces from peers.
+pub struct CachePieceCollector {
+    /// config is the configuration of the dfdaemon.
+    config: Arc<Config>,
+
+    /// host_id is the id of the host.
+    host_id: String,
+
+    /// task_id is the id of the cache task.
+    task_id: String,
+
+    /// parents is the parent peers.
+    parents: Vec<CollectedParent>,
+
+    /// interested_pieces is the pieces interested by the collector.
+    interested_pieces: Vec<metadata::Piece>,
+
+    /// collected_pieces is a map to store the collected pieces from different parents.
+    collected_pieces: Arc<DashMap<u32, Vec<CollectedParent>>>,
+}
+
+/// CachePieceCollector is used to collect cache pieces from peers.
+impl CachePieceCollector {
+    /// new creates a new CachePieceCollector.
+    pub async fn new(
+        config: Arc<Config>,
+        host_id: &str,
+        task_id: &str,
+        interested_pieces: Vec<metadata::Piece>,
+        parents: Vec<CollectedParent>,
+    ) -> Self {
+        let collected_pieces = Arc::new(DashMap::with_capacity(interested_pieces.len()));
+        for interested_piece in &interested_pieces {
+            collected_pieces.insert(interested_piece.number, Vec::new());
+        }
+
+        Self {
+            config,
+            task_id: task_id.to_string(),
+            host_id: host_id.to_string(),
+            parents,
+            interested_pieces,
+            collected_pieces,
+        }
+    }
+
+    /// run runs the piece collector.
+    #[instrument(skip_all)]
+    pub async fn run(&self) -> Receiver<CollectedPiece> {
+        let config = self.config.clone();
+        let host_id = self.host_id.clone();
+        let task_id = self.task_id.clone();
+        let parents = self.parents.clone();
+        let interested_pieces = self.interested_pieces.clone();
+        let collected_pieces = self.collected_pieces.clone();
+        let collected_piece_timeout = self.config.download.collected_piece_timeout;
+        let (collected_piece_tx, collected_piece_rx) = mpsc::channel(1024);
+        tokio::spawn(
+            async move {
+                Self::collect_from_parents(
+                    config,
+                    &host_id,
+                    &task_id,
+                    parents,
+                    interested_pieces,
+                    collected_pieces,
+                    collected_piece_tx,
+                    collected_piece_timeout,
+                )
+                .await
+                .unwrap_or_else(|err| {
+                    error!("collect cache pieces failed: {}", err);
+                });
+            }
+            .in_current_span(),
+        );
+
+        collected_piece_rx
+    }
+
+    /// collect_from_parents collects cache pieces from multiple parents with load balancing strategy,
+    /// refer to CachePieceCollector::collect_from_parents.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    async fn collect_from_parents(
+        config: Arc<Config>,
+        host_id: &str,
+        task_id: &str,
+        parents: Vec<CollectedParent>,
+        interested_pieces: Vec<metadata::Piece>,
+        collected_pieces: Arc<DashMap<u32, Vec<CollectedParent>>>,
+        collected_piece_tx: Sender<CollectedPiece>,
+        collected_piece_timeout: Duration,
+    ) -> Result<()> {
+        // Create a task to collect pieces from peers.
+        let mut join_set = JoinSet::new();
+        for parent in parents.iter() {
+            #[allow(clippy::too_many_arguments)]
+            async fn sync_pieces(
+                config: Arc<Config>,
+                host_id: String,
+                task_id: String,
+                parent: CollectedParent,
+                interested_pieces: Vec<metadata::Piece>,
+                collected_pieces: Arc<DashMap<u32, Vec<CollectedParent>>>,
+                collected_piece_tx: Sender<CollectedPiece>,
+                collected_piece_timeout: Duration,
+            ) -> Result<CollectedParent> {
+                debug!("sync cache pieces from parent {}", parent.id);
+
+                // If candidate_parent.host is None, skip it.
+                let host = parent.host.clone().ok_or_else(|| {
+                    error!("cache peer {:?} host is empty", parent);
+                    Error::InvalidPeer(parent.id.clone())
+                })?;
+
+                // Create a dfdaemon client.
+                let dfdaemon_upload_client = DfdaemonUploadClient::new(
+                    config,
+                    format!("http://{}:{}", host.ip, host.port),
+                    false,
+                )
+                .await
+                .inspect_err(|err| {
+                    error!(
+                        "create dfdaemon upload client from parent {} failed: {}",
+                        parent.id, err
+                    );
+                })?;
+
+                let response = dfdaemon_upload_client
+                    .sync_cache_pieces(SyncCachePiecesRequest {
+                        host_id: host_id.to_string(),
+                        task_id: task_id.to_string(),
+                        interested_cache_piece_numbers: interested_pieces
+                            .iter()
+                            .map(|piece| piece.number)
+                            .collect(),
+                    })
+                    .await
+                    .inspect_err(|err| {
+                        error!(
+                            "sync cache pieces from parent {} failed: {}",
+                            parent.id, err
+                        );
+                    })?;
+
+                // If the response repeating timeout exceeds the piece download timeout, the stream will return error.
+                let out_stream = response.into_inner().timeout(collected_piece_timeout);
+                tokio::pin!(out_stream);
+
+                while let Some(message) = out_stream.try_next().await.inspect_err(|err| {
+                    error!(
+                        "sync cache pieces from parent {} failed: {}",
+                        parent.id, err
+                    );
+                })? {
+                    let message = message?;
+                    if let Some(mut parents) = collected_pieces.get_mut(&message.number) {
+                        // The cache piece is only served by the gRPC server of the parent,
+                        // so the download ip and ports are not required.
+                        parents.push(parent.clone());
+                    } else {
+                        continue;
+                    }
+
+                    // Wait for collecting the piece from different parents when the first
+                    // piece is collected.
+                    tokio::time::sleep(DEFAULT_WAIT_FOR_PIECE_FROM_DIFFERENT_PARENTS).await;
+                    let parents = match collected_pieces.remove(&message.number) {
+                        Some((_, parents)) => parents,
+                        None => continue,
+                    };
+
+                    debug!(
+                        "receive piece {}-{} metadata from parents {:?}",
+                        task_id,
+                        message.number,
+                        parents.iter().map(|p| &p.id).collect::<Vec<&String>>()
+                    );
+
+                    collected_piece_tx
+                        .send(CollectedPiece {
+                            number: message.number,
+                            length: message.length,
+                            parents,
+                        })
+                        .await
+                        .inspect_err(|err| {
+                            error!("send CollectedPiece failed: {}", err);
+                        })?;
+                }
+
+                Ok(parent)
+            }
+
+            join_set.spawn(
+                sync_pieces(
+                    config.clone(),
+                    host_id.to_string(),
+                    task_id.to_string(),
+                    parent.clone(),
+                    interested_pieces.clone(),
+                    collected_pieces.clone(),
+                    collected_piece_tx.clone(),
+                    collected_piece_timeout,
+                )
+                .in_current_span(),
+            );
+        }
+
+        // Wait for all tasks to finish.
+        while let Some(message) = join_set.join_next().await {
+            match message {
+                Ok(Ok(peer)) => {
+                    debug!("peer {} sync cache pieces finished", peer.id);
+
+                    // If all pieces are collected, abort all tasks.
+                    if collected_pieces.is_empty() {
+                        info!("all cache pieces are collected, abort all tasks");
+                        join_set.shutdown().await;
+                    }
+                }
+                Ok(Err(err)) => error!("sync cache pieces failed: {}", err),
+                Err(err) => error!("task join error: {}", err),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -16,7 +16,8 @@
 
 use crate::grpc::dfdaemon_upload::DfdaemonUploadClient;
 use dragonfly_api::dfdaemon::v2::{
-    DownloadPersistentCachePieceRequest, DownloadPersistentPieceRequest, DownloadPieceRequest,
+    DownloadCachePieceRequest, DownloadPersistentCachePieceRequest, DownloadPersistentPieceRequest,
+    DownloadPieceRequest,
 };
 use dragonfly_client_config::dfdaemon::Config;
 use dragonfly_client_core::{Error, Result};
@@ -66,6 +67,16 @@ pub trait Downloader: Send + Sync {
         host_id: &str,
         task_id: &str,
     ) -> Result<(Box<dyn AsyncRead + Send + Unpin>, u64, String)>;
+
+    /// download_cache_piece downloads a cache piece from the other peer by different
+    /// protocols.
+    async fn download_cache_piece(
+        &self,
+        addr: &str,
+        number: u32,
+        host_id: &str,
+        task_id: &str,
+    ) -> Result<(Box<dyn AsyncRead + Send + Unpin>, u64, String)>;
 }
 
 /// DownloaderFactory is the factory for creating different downloaders by different protocols.
@@ -387,6 +398,74 @@ impl Downloader for GRPCDownloader {
 
         Ok((Box::new(Cursor::new(content)), piece.offset, piece.digest))
     }
+
+    /// download_cache_piece downloads a cache piece from the other peer by the gRPC protocol.
+    #[instrument(skip_all)]
+    async fn download_cache_piece(
+        &self,
+        addr: &str,
+        number: u32,
+        host_id: &str,
+        task_id: &str,
+    ) -> Result<(Box<dyn AsyncRead + Send + Unpin>, u64, String)> {
+        let key = self.get_entry_key(addr);
+        let entry = self.get_client_entry(key.clone(), addr.to_string()).await?;
+        let request_guard = entry.request_guard();
+
+        let response = match entry
+            .client
+            .download_cache_piece(
+                DownloadCachePieceRequest {
+                    host_id: host_id.to_string(),
+                    task_id: task_id.to_string(),
+                    piece_number: number,
+                },
+                self.config.download.piece_timeout,
+            )
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                // If the request fails, it will drop the request guard and remove the client
+                // entry to avoid using the invalid client.
+                drop(request_guard);
+                self.remove_client_entry(key).await;
+                return Err(err);
+            }
+        };
+
+        let Some(piece) = response.piece else {
+            error!("cache piece is missing");
+            return Err(Error::InvalidParameter);
+        };
+
+        let Some(content) = piece.content else {
+            error!("cache piece content is missing");
+            return Err(Error::InvalidParameter);
+        };
+
+        // Calculate the digest of the piece metadata and compare it with the expected digest,
+        // it verifies the integrity of the piece metadata.
+        let piece_metadata = metadata::Piece {
+            number,
+            length: piece.length,
+            offset: piece.offset,
+            digest: piece.digest.clone(),
+            ..Default::default()
+        };
+
+        if let Some(expected_digest) = response.digest {
+            let digest = piece_metadata.calculate_digest();
+            if expected_digest != digest {
+                return Err(Error::DigestMismatch(
+                    expected_digest.to_string(),
+                    digest.to_string(),
+                ));
+            }
+        }
+
+        Ok((Box::new(Cursor::new(content)), piece.offset, piece.digest))
+    }
 }
 
 /// QUICDownloader is the downloader for downloading pieces by the QUIC protocol.
@@ -538,6 +617,22 @@ impl Downloader for QUICDownloader {
             }
         }
     }
+
+    /// download_cache_piece downloads a cache piece from the other peer by the QUIC protocol.
+    /// The cache piece is stored in memory and only served by the gRPC server, so it is
+    /// unsupported by the QUIC protocol.
+    #[instrument(skip_all)]
+    async fn download_cache_piece(
+        &self,
+        _addr: &str,
+        _number: u32,
+        _host_id: &str,
+        _task_id: &str,
+    ) -> Result<(Box<dyn AsyncRead + Send + Unpin>, u64, String)> {
+        Err(Error::Unsupported(
+            "download cache piece by QUIC protocol".to_string(),
+        ))
+    }
 }
 
 /// TCPDownloader is the downloader for downloading pieces by the TCP protocol.
@@ -690,4 +785,20 @@ impl Downloader for TCPDownloader {
             }
         }
     }
+
+    /// download_cache_piece downloads a cache piece from the other peer by the TCP protocol.
+    /// The cache piece is stored in memory and only served by the gRPC server, so it is
+    /// unsupported by the TCP protocol.
+    #[instrument(skip_all)]
+    async fn download_cache_piece(
+        &self,
+        _addr: &str,
+        _number: u32,
+        _host_id: &str,
+        _task_id: &str,
+    ) -> Result<(Box<dyn AsyncRead + Send + Unpin>, u64, String)> {
+        Err(Error::Unsupported(
+            "download cache piece by TCP protocol".to_string(),
+        ))
+    }
 }
@@ -43,10 +43,11 @@ use dragonfly_client_metric::{
     collect_backend_request_failure_metrics, collect_backend_request_finished_metrics,
     collect_backend_request_started_metrics,
 };
-use dragonfly_client_storage::{metadata, Storage};
+use dragonfly_client_storage::{metadata, Storage, DEFAULT_WAIT_FOR_PIECE_FINISHED_INTERVAL};
 use dragonfly_client_util::{
+    digest::{is_blob_url, Digest, Hasher},
     http::{hashmap_to_headermap, headermap_to_hashmap},
-    id_generator::IDGenerator,
+    id_generator::{IDGenerator, TaskIDParameter},
     shutdown,
 };
 use leaky_bucket::RateLimiter;
@@ -69,6 +70,10 @@ use tracing::{debug, error, info, instrument, warn, Instrument};
 
 use super::*;
 
+/// DEFAULT_VERIFY_DIGEST_BUFFER_SIZE is the buffer size to read the pieces when verifying the
+/// digest of the task.
+const DEFAULT_VERIFY_DIGEST_BUFFER_SIZE: usize = 64 * 1024;
+
 /// Task represents a task manager.
 pub struct Task {
     /// config is the configuration of the dfdaemon.
@@ -133,6 +138,28 @@ impl Task {
         })
     }
 
+    /// task_id generates the task id of the download. If the task id based blob digest is
+    /// enabled for the blob url, the task id is generated by the blob digest. If the content
+    /// for calculating task id is set, the task id is generated by the content. Otherwise, the
+    /// task id is generated by the url, piece length, tag, application and filtered query params.
+    pub fn task_id(&self, download: &Download) -> ClientResult<String> {
+        self.id_generator.task_id(
+            if download.enable_task_id_based_blob_digest && is_blob_url(&download.url) {
+                TaskIDParameter::BlobDigestBased(download.url.clone())
+            } else if let Some(content) = download.content_for_calculating_task_id.clone() {
+                TaskIDParameter::Content(content)
+            } else {
+                TaskIDParameter::URLBased {
+                    url: download.url.clone(),
+                    piece_length: download.piece_length,
+                    tag: download.tag.clone(),
+                    application: download.application.clone(),
+                    filtered_query_params: download.filtered_query_params.clone(),
+                }
+            },
+        )
+    }
+
     /// get gets the metadata of the task.
     #[instrument(skip_all)]
     pub fn get(&self, id: &str) -> ClientResult<Option<metadata::Task>> {
@@ -148,6 +175,12 @@ impl Task {
     ) -> ClientResult<metadata::Task> {
         let (task, reused) = self.storage.prepare_download_task(id)?;
         if reused {
+            // If the task is not finished, the task is resumed from the finished pieces,
+            // e.g. the download is interrupted by the restart of the dfdaemon.
+            if !task.is_finished() {
+                info!("resume task {} from the finished pieces", id);
+            }
+
             // Attempt to create a hard link from the task file to the output path.
             //
             // Behavior based on force_hard_link setting:
@@ -254,7 +287,7 @@ impl Task {
 
         // If the task is not finished, check if the storage has enough space to
         // store the task.
-        if !task.is_finished() && !self.storage.has_enough_space(content_length)? {
+        if !task.is_finished() && !self.storage.has_enough_space(content_length).await? {
             return Err(Error::NoSpace(format!(
                 "not enough space to store the task: content_length={}",
                 content_length
@@ -325,7 +358,9 @@ impl Task {
         self.storage.copy_task(id, to).await
     }
 
-    /// download downloads a task.
+    /// download downloads a task. If the digest of the full task is provided, the content is
+    /// hashed while the pieces are downloaded, and the task is evicted if the digest is
+    /// mismatched, so the corrupted content is never committed as a finished task.
     #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all)]
     pub async fn download(
@@ -335,6 +370,130 @@ impl Task {
         peer_id: &str,
         request: Download,
         download_progress_tx: Sender<Result<DownloadTaskResponse, Status>>,
+    ) -> ClientResult<()> {
+        // The finished task has been verified, and the range request can not be verified by
+        // the digest of the full task.
+        let digest = match &request.digest {
+            Some(digest) if request.range.is_none() && !task.is_finished() => {
+                Some(digest.parse::<Digest>().map_err(|err| {
+                    error!("parse digest {} failed: {}", digest, err);
+                    Error::ValidationError(format!("invalid digest({}): {}", digest, err))
+                })?)
+            }
+            _ => None,
+        };
+
+        let (Some(digest), Some(content_length), Some(piece_length)) =
+            (digest, task.content_length(), task.piece_length())
+        else {
+            return self
+                .download_pieces(task, host_id, peer_id, request, download_progress_tx)
+                .await;
+        };
+
+        // Verify the digest concurrently with the download, the verification stops if the
+        // download fails. The verification waits for the pieces which are not started yet,
+        // until the download is finished.
+        let pieces = self
+            .piece
+            .calculate_interested(piece_length, content_length, None)?;
+        let downloaded = AtomicBool::new(false);
+        let result = tokio::try_join!(
+            async {
+                let result = self
+                    .download_pieces(task, host_id, peer_id, request, download_progress_tx)
+                    .await;
+                downloaded.store(true, Ordering::SeqCst);
+                result
+            },
+            self.verify_digest(task.id.as_str(), pieces, &digest, &downloaded),
+        );
+
+        if let Err(Error::DigestMismatch(expected, actual)) = &result {
+            error!(
+                "digest of task {} mismatch, expected: {}, actual: {}",
+                task.id, expected, actual
+            );
+
+            // Evict the corrupted task from the local storage and the scheduler.
+            if let Err(err) = self.delete(task.id.as_str(), host_id).await {
+                error!("evict task {} failed: {}", task.id, err);
+            }
+        }
+
+        result.map(|_| ())
+    }
+
+    /// verify_digest reads the pieces of the task in order when they are finished, and
+    /// verifies the digest of the content incrementally.
+    #[instrument(skip_all)]
+    async fn verify_digest(
+        &self,
+        task_id: &str,
+        pieces: Vec<metadata::Piece>,
+        expected_digest: &Digest,
+        downloaded: &AtomicBool,
+    ) -> ClientResult<()> {
+        let mut hasher = Hasher::new(expected_digest.algorithm());
+        let mut buffer = vec![0; DEFAULT_VERIFY_DIGEST_BUFFER_SIZE];
+        for piece in pieces {
+            let piece_id = self.piece.id(task_id, piece.number);
+            let piece = self
+                .wait_for_piece_finished(piece_id.as_str(), downloaded)
+                .await?;
+            let mut reader = self
+                .storage
+                .read_piece(piece_id.as_str(), task_id, piece)
+                .await?;
+            loop {
+                let n = reader.read(&mut buffer).await?;
+                if n == 0 {
+                    break;
+                }
+
+                hasher.update(&buffer[..n]);
+            }
+        }
+
+        hasher.verify(expected_digest)?;
+        info!("digest of task {} is verified", task_id);
+        Ok(())
+    }
+
+    /// wait_for_piece_finished waits for the piece to be finished while the task is
+    /// downloading. The piece metadata is created when the piece download is started, so the
+    /// missing piece is waited until the download is finished.
+    async fn wait_for_piece_finished(
+        &self,
+        piece_id: &str,
+        downloaded: &AtomicBool,
+    ) -> ClientResult<metadata::Piece> {
+        let mut interval = tokio::time::interval(DEFAULT_WAIT_FOR_PIECE_FINISHED_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            // Load the download state before getting the piece, so the piece finished before
+            // the download is finished is not missed.
+            let downloaded = downloaded.load(Ordering::SeqCst);
+            match self.storage.get_piece(piece_id)? {
+                Some(piece) if piece.is_finished() => return Ok(piece),
+                _ if downloaded => return Err(Error::PieceNotFound(piece_id.to_string())),
+                _ => {}
+            }
+        }
+    }
+
+    /// download_pieces downloads the interested pieces of the task from the local, the
+    /// scheduler and the source.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    async fn download_pieces(
+        &self,
+        task: &metadata::Task,
+        host_id: &str,
+        peer_id: &str,
+        request: Download,
+        download_progress_tx: Sender<Result<DownloadTaskResponse, Status>>,
     ) -> ClientResult<()> {
         // Get the id of the task.
         let task_id = task.id.as_str();
@@ -1970,9 +2129,236 @@ impl Task {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dynconfig::Dynconfig;
+    use crate::grpc::manager::ManagerClient;
+    use dragonfly_client_util::digest::Algorithm;
     use std::sync::Arc;
     use tempfile::tempdir;
 
+    /// new_task creates the task manager for testing, the scheduler is not available, so the
+    /// task is downloaded from the source. The storage is kept when the task manager is
+    /// created again if keep is true.
+    async fn new_task(temp_dir: &Path, keep: bool) -> (Task, Arc<Storage>) {
+        let log_dir = temp_dir.join("log");
+        std::fs::create_dir_all(&log_dir).unwrap();
+
+        let mut config = Config::default();
+        config.storage.keep = keep;
+        config.backend.file.allowed_roots = vec![temp_dir.to_path_buf()];
+        let config = Arc::new(config);
+        let storage = Arc::new(
+            Storage::new(config.clone(), temp_dir, log_dir)
+                .await
+                .unwrap(),
+        );
+        let id_generator = Arc::new(IDGenerator::new(
+            "127.0.0.1".to_string(),
+            "localhost".to_string(),
+            false,
+        ));
+
+        let shutdown = shutdown::Shutdown::default();
+        let (shutdown_complete_tx, _shutdown_complete_rx) = mpsc::unbounded_channel();
+        let dynconfig = Arc::new(Dynconfig::new_empty(
+            config.clone(),
+            Arc::new(ManagerClient::new_lazy("http://127.0.0.1:65004")),
+            shutdown.clone(),
+            shutdown_complete_tx.clone(),
+        ));
+
+        let task = Task::new(
+            config.clone(),
+            id_generator,
+            storage.clone(),
+            Arc::new(SchedulerClient::new_empty(config.clone(), dynconfig)),
+            Arc::new(BackendFactory::new(config, None).unwrap()),
+            Arc::new(RateLimiter::builder().build()),
+            Arc::new(RateLimiter::builder().build()),
+            Arc::new(RateLimiter::builder().build()),
+            shutdown,
+            shutdown_complete_tx,
+        )
+        .unwrap();
+
+        (task, storage)
+    }
+
+    // test_download_task_with_digest tests the digest of the multi-piece task is verified
+    // while the pieces are downloaded, the pieces are not started before the download.
+    #[tokio::test]
+    async fn test_download_task_with_digest() {
+        let temp_dir = tempdir().unwrap();
+        let (task_manager, storage) = new_task(temp_dir.path(), false).await;
+
+        let source_path = temp_dir.path().join("source");
+        let content: Vec<u8> = (0..10240).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&source_path, &content).unwrap();
+
+        let mut hasher = Hasher::new(Algorithm::Sha256);
+        hasher.update(&content);
+        let request = Download {
+            url: url::Url::from_file_path(&source_path).unwrap().to_string(),
+            digest: Some(hasher.finalize().to_string()),
+            piece_length: Some(4096),
+            ..Default::default()
+        };
+
+        let task_id = "test-task-id";
+        let task = task_manager
+            .download_started(task_id, request.clone())
+            .await
+            .unwrap();
+        assert_eq!(task.piece_count(), Some(3));
+        assert!(storage.get_pieces(task_id).unwrap().is_empty());
+
+        let (download_progress_tx, mut download_progress_rx) = mpsc::channel(16);
+        task_manager
+            .download(
+                &task,
+                "test-host-id",
+                "test-peer-id",
+                request,
+                download_progress_tx,
+            )
+            .await
+            .unwrap();
+
+        let mut finished_pieces = 0;
+        while let Some(Ok(response)) = download_progress_rx.recv().await {
+            if let Some(download_task_response::Response::DownloadPieceFinishedResponse(_)) =
+                response.response
+            {
+                finished_pieces += 1;
+            }
+        }
+        assert_eq!(finished_pieces, 3);
+    }
+
+    // test_resume_task_after_restart tests the task interrupted by the restart is resumed from
+    // the finished pieces, and the started pieces are downloaded again.
+    #[tokio::test]
+    async fn test_resume_task_after_restart() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let content: Vec<u8> = (0..10240).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&source_path, &content).unwrap();
+
+        let request = Download {
+            url: url::Url::from_file_path(&source_path).unwrap().to_string(),
+            piece_length: Some(4096),
+            ..Default::default()
+        };
+
+        // Finish the first piece and leave the second piece started, like the download is
+        // interrupted by the restart.
+        let task_id = "test-task-id";
+        let finished_piece = {
+            let (task_manager, storage) = new_task(temp_dir.path(), true).await;
+            task_manager
+                .download_started(task_id, request.clone())
+                .await
+                .unwrap();
+
+            let piece_id = storage.piece_id(task_id, 0);
+            storage.download_piece_started(&piece_id, 0).await.unwrap();
+            let piece = storage
+                .download_piece_from_source_finished(
+                    &piece_id,
+                    task_id,
+                    0,
+                    4096,
+                    &mut std::io::Cursor::new(&content[..4096]),
+                    std::time::Duration::from_secs(5),
+                )
+                .await
+                .unwrap();
+
+            let piece_id = storage.piece_id(task_id, 1);
+            storage.download_piece_started(&piece_id, 1).await.unwrap();
+            piece
+        };
+
+        // Restart with the kept storage, the started piece is reset.
+        let (task_manager, storage) = new_task(temp_dir.path(), true).await;
+        assert_eq!(
+            storage.get_pieces(task_id).unwrap(),
+            vec![finished_piece.clone()]
+        );
+
+        let task = task_manager
+            .download_started(task_id, request.clone())
+            .await
+            .unwrap();
+        assert!(!task.is_finished());
+
+        let (download_progress_tx, mut download_progress_rx) = mpsc::channel(16);
+        task_manager
+            .download(
+                &task,
+                "test-host-id",
+                "test-peer-id",
+                request,
+                download_progress_tx,
+            )
+            .await
+            .unwrap();
+
+        let mut finished_pieces = 0;
+        while let Some(Ok(response)) = download_progress_rx.recv().await {
+            if let Some(download_task_response::Response::DownloadPieceFinishedResponse(_)) =
+                response.response
+            {
+                finished_pieces += 1;
+            }
+        }
+        assert_eq!(finished_pieces, 3);
+
+        // The finished piece is reused instead of being downloaded again.
+        let pieces = storage.get_pieces(task_id).unwrap();
+        assert_eq!(pieces.len(), 3);
+        assert!(pieces.iter().all(|piece| piece.is_finished()));
+        assert!(pieces.contains(&finished_piece));
+    }
+
+    // test_download_task_with_mismatched_digest tests the task is evicted if the digest of
+    // the task is mismatched.
+    #[tokio::test]
+    async fn test_download_task_with_mismatched_digest() {
+        let temp_dir = tempdir().unwrap();
+        let (task_manager, storage) = new_task(temp_dir.path(), false).await;
+
+        let source_path = temp_dir.path().join("source");
+        std::fs::write(&source_path, vec![1u8; 10240]).unwrap();
+
+        let mut hasher = Hasher::new(Algorithm::Sha256);
+        hasher.update(b"other content");
+        let request = Download {
+            url: url::Url::from_file_path(&source_path).unwrap().to_string(),
+            digest: Some(hasher.finalize().to_string()),
+            piece_length: Some(4096),
+            ..Default::default()
+        };
+
+        let task_id = "test-task-id";
+        let task = task_manager
+            .download_started(task_id, request.clone())
+            .await
+            .unwrap();
+
+        let (download_progress_tx, _download_progress_rx) = mpsc::channel(16);
+        let result = task_manager
+            .download(
+                &task,
+                "test-host-id",
+                "test-peer-id",
+                request,
+                download_progress_tx,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::DigestMismatch(_, _))));
+        assert!(storage.get_task(task_id).unwrap().is_none());
+    }
+
     // test_delete_task_not_found tests the Task.delete method when the task does not exist.
     #[tokio::test]
     async fn test_delete_task_not_found() {
@@ -14,11 +14,13 @@
  * limitations under the License.
  */
 
+use dragonfly_client_storage::scrub::Scrubber;
 use dragonfly_client_util::shutdown;
 use pprof::protos::Message;
 use pprof::ProfilerGuard;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tracing::{error, info, instrument};
@@ -52,11 +54,13 @@ impl Default for PProfProfileQueryParams {
 }
 
 /// Stats is the stats server.
-#[derive(Debug)]
 pub struct Stats {
     /// addr is the address of the stats server.
     addr: SocketAddr,
 
+    /// scrubber is the scrubber of the storage.
+    scrubber: Arc<Scrubber>,
+
     /// shutdown is used to shutdown the stats server.
     shutdown: shutdown::Shutdown,
 
@@ -69,11 +73,13 @@ impl Stats {
     /// new creates a new Stats.
     pub fn new(
         addr: SocketAddr,
+        scrubber: Arc<Scrubber>,
         shutdown: shutdown::Shutdown,
         shutdown_complete_tx: mpsc::UnboundedSender<()>,
     ) -> Self {
         Self {
             addr,
+            scrubber,
             shutdown,
             _shutdown_complete: shutdown_complete_tx,
         }
@@ -98,10 +104,17 @@ impl Stats {
         // Create the pprof routes.
         let pprof_routes = pprof_profile_route.or(pprof_heap_route);
 
+        // Create the storage scrub route.
+        let scrubber = self.scrubber.clone();
+        let storage_scrub_route = warp::path!("debug" / "storage" / "scrub")
+            .and(warp::get())
+            .and(warp::any().map(move || scrubber.clone()))
+            .and_then(Self::storage_scrub_handler);
+
         // Start the stats server and wait for it to finish.
         info!("stats server listening on {}", self.addr);
         tokio::select! {
-            _ = warp::serve(pprof_routes).run(self.addr) => {
+            _ = warp::serve(pprof_routes.or(storage_scrub_route)).run(self.addr) => {
                 // Stats server ended.
                 info!("stats server ended");
             }
@@ -147,6 +160,16 @@ impl Stats {
         Ok(body)
     }
 
+    /// storage_scrub_handler handles the storage scrub request, and returns the report of the
+    /// last finished scrub.
+    #[instrument(skip_all)]
+    async fn storage_scrub_handler(scrubber: Arc<Scrubber>) -> Result<impl Reply, Rejection> {
+        match scrubber.last_report() {
+            Some(report) => Ok(warp::reply::json(&report)),
+            None => Err(warp::reject::not_found()),
+        }
+    }
+
     /// pprof_heap_handler handles the pprof heap request.
     #[instrument(skip_all)]
     async fn pprof_heap_handler() -> Result<impl Reply, Rejection> {